@@ -0,0 +1,106 @@
+use std::collections::VecDeque;
+
+/// How many memory accesses the access log remembers.
+pub const ACCESS_LOG_LENGTH: usize = 256;
+
+/// Whether an instruction read or wrote memory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum AccessKind {
+    /// Read by `Dxyn` (sprite data) or `Fx65`.
+    Read,
+    /// Written by `Fx33` or `Fx55`.
+    Write,
+}
+
+/// A memory read or write made by an instruction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct MemoryAccess {
+    pub kind: AccessKind,
+    pub address: u16,
+    /// The value that was read or written.
+    pub value: u8,
+    /// The address of the instruction that made the access.
+    pub program_counter: u16,
+    /// The instruction that made the access.
+    pub opcode: u16,
+}
+
+/// A ring buffer of the last memory accesses made by instructions, and how many times each address
+/// was accessed. Fetching instructions is not logged.
+#[derive(Debug, Default, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct AccessLog {
+    entries: VecDeque<MemoryAccess>,
+    /// The number of reads of each address, up to the highest address accessed.
+    reads: Vec<u32>,
+    /// The number of writes to each address, up to the highest address accessed.
+    writes: Vec<u32>,
+}
+
+impl AccessLog {
+    /// Record a memory access, forgetting the oldest one if the log is full.
+    #[inline]
+    pub(crate) fn push(&mut self, access: MemoryAccess) {
+        if self.entries.len() == ACCESS_LOG_LENGTH {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(access);
+
+        let counts = match access.kind {
+            AccessKind::Read => &mut self.reads,
+            AccessKind::Write => &mut self.writes,
+        };
+        let address = access.address as usize;
+        if counts.len() <= address {
+            counts.resize(address + 1, 0);
+        }
+        counts[address] = counts[address].saturating_add(1);
+    }
+
+    /// Forget all accesses.
+    #[inline]
+    pub(crate) fn clear(&mut self) {
+        self.entries.clear();
+        self.reads.clear();
+        self.writes.clear();
+    }
+
+    /// Get how many times an address was read and written, including accesses that are no longer
+    /// in the log.
+    #[inline]
+    pub fn access_counts(&self, address: u16) -> (u32, u32) {
+        let address = address as usize;
+        (
+            self.reads.get(address).copied().unwrap_or(0),
+            self.writes.get(address).copied().unwrap_or(0),
+        )
+    }
+
+    /// Get the highest number of accesses (reads and writes) to a single address.
+    pub fn max_access_count(&self) -> u32 {
+        (0..self.reads.len().max(self.writes.len()) as u16)
+            .map(|address| {
+                let (reads, writes) = self.access_counts(address);
+                reads.saturating_add(writes)
+            })
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Iterate over the recorded accesses, from the oldest to the most recent.
+    #[inline]
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &MemoryAccess> {
+        self.entries.iter()
+    }
+
+    /// Get the number of recorded accesses.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Check if no accesses were recorded.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
@@ -0,0 +1,60 @@
+use crate::Chip8;
+
+/// What a bot sees of the interpreter. See [`Chip8::act`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[allow(non_snake_case)]
+pub struct Observation {
+    /// The display pixels, row by row. `true` if the pixel is on in either plane.
+    pub pixels: Vec<bool>,
+    /// The width of the display: 64 in lowres mode, 128 in highres mode.
+    pub width: usize,
+    /// The height of the display: 32 in lowres mode (48 on the ETI-660), 64 in highres mode.
+    pub height: usize,
+    /// The general purpose registers.
+    pub V: [u8; 16],
+    /// The address register.
+    pub I: u16,
+    pub program_counter: u16,
+    pub delay: u8,
+    pub sound: u8,
+    /// True if the interpreter is not running, for example because the program crashed or ended.
+    pub halted: bool,
+}
+
+impl Chip8 {
+    /// Observe the display and registers without changing anything.
+    pub fn observe(&self) -> Observation {
+        let (width, height) = self.display.size(self.highres);
+        Observation {
+            pixels: (0..width * height)
+                .map(|i| self.display.planes_at(i) != 0)
+                .collect(),
+            width,
+            height,
+            V: self.V,
+            I: self.I,
+            program_counter: self.program_counter,
+            delay: self.delay,
+            sound: self.sound,
+            halted: !self.running,
+        }
+    }
+
+    /// One step of a bot's step-observe-act loop: hold down `keys`, run one frame and observe the
+    /// result. Starts the interpreter if it is stopped, so reset it once an observation says it
+    /// halted.
+    ///
+    /// Keys that were held in the last step and are released now resolve the `Fx0A` instruction, or
+    /// keys that are pressed now with the `key_wait_on_press` quirk.
+    pub fn act(&mut self, keys: [bool; 16]) -> Observation {
+        let released = (0..16).find(|&key| self.keypad[key] && !keys[key]);
+        if let Some(key) = released {
+            self.key_released(key as u8);
+        }
+        self.set_keys(keys);
+
+        self.start();
+        self.run_frame();
+        self.observe()
+    }
+}
@@ -0,0 +1,196 @@
+use std::{
+    io::Read,
+    sync::mpsc::{self, Receiver, TryRecvError},
+    thread,
+};
+
+use e_chip::{Chip8, IndexIncrement, Quirks, Variant, VblankWait, MAX_EXECUTION_SPEED};
+use serde_json::Value;
+
+/// The list of programs in the chip8Archive, with their metadata.
+const PROGRAMS_URL: &str =
+    "https://raw.githubusercontent.com/JohnEarnest/chip8Archive/master/programs.json";
+/// Where the ROMs of the chip8Archive are stored.
+const ROMS_URL: &str = "https://raw.githubusercontent.com/JohnEarnest/chip8Archive/master/roms";
+
+/// A program of the chip8Archive.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArchiveProgram {
+    /// The file name of the ROM, without the extension.
+    pub key: String,
+    pub title: String,
+    pub authors: String,
+    pub description: String,
+    /// The variant the program was made for. `None` for variants E-CHIP does not support.
+    pub variant: Option<Variant>,
+    /// The program's options: its speed and quirks, named like in Octo.
+    options: Value,
+}
+
+impl ArchiveProgram {
+    fn from_json(key: &str, json: &Value) -> ArchiveProgram {
+        let text = |field: &str| json[field].as_str().unwrap_or_default().to_string();
+        ArchiveProgram {
+            key: key.to_string(),
+            title: text("title"),
+            authors: json["authors"]
+                .as_array()
+                .map(|authors| {
+                    let names: Vec<_> = authors.iter().filter_map(Value::as_str).collect();
+                    names.join(", ")
+                })
+                .unwrap_or_default(),
+            description: text("desc"),
+            variant: match json["platform"].as_str() {
+                Some("originalChip8" | "modernChip8" | "chip8") => Some(Variant::CHIP8),
+                Some("hybridVIP") => Some(Variant::VIPHYBRID),
+                Some("chip48") => Some(Variant::CHIP48),
+                Some("superchip1") => Some(Variant::SCHIP10),
+                Some("superchip" | "schip" | "superchip1.1") => Some(Variant::SCHIP11),
+                Some("xochip") => Some(Variant::XOCHIP),
+                _ => None,
+            },
+            options: json["options"].clone(),
+        }
+    }
+
+    /// Create an interpreter with the variant, quirks and speed from the program's metadata.
+    pub fn configure(&self) -> Chip8 {
+        let mut chip8 = match self.variant {
+            Some(Variant::VIPHYBRID) => Chip8::vip_hybrid(),
+            Some(Variant::CHIP48) => Chip8::chip48(),
+            Some(Variant::SCHIP10) => Chip8::super_chip1_0(),
+            Some(Variant::SCHIP11) => Chip8::super_chip1_1(),
+            Some(Variant::XOCHIP) => Chip8::xo_chip(),
+            _ => Chip8::chip8(),
+        };
+        // The archive uses Octo's option names, and Octo's quirks when an option is missing
+        let flag = |name: &str| self.options[name].as_bool().unwrap_or(false);
+        chip8.quirks = Quirks {
+            bitwise_reset_vf: flag("logicQuirks"),
+            direct_shifting: flag("shiftQuirks"),
+            save_load_increment: if flag("loadStoreQuirk") {
+                IndexIncrement::Unchanged
+            } else {
+                IndexIncrement::XPlusOne
+            },
+            jump_to_x: flag("jumpQuirks"),
+            wait_for_vblank: if flag("vBlankQuirks") {
+                VblankWait::Always
+            } else {
+                VblankWait::Never
+            },
+            edge_clipping: flag("clipQuirks"),
+            lowres_scroll: false,
+            // Octo has no option for it, so keep the one of the variant
+            lowres_tall_sprites: chip8.quirks.lowres_tall_sprites,
+            // Octo always clears the display when switching resolution
+            resolution_clear: true,
+            deterministic_random: false,
+            memory_wrap: false,
+            key_wait_on_press: false,
+        };
+        if let Some(tickrate) = self.options["tickrate"].as_u64() {
+            chip8.execution_speed = tickrate.clamp(1, MAX_EXECUTION_SPEED as u64) as u32;
+        }
+        chip8
+    }
+}
+
+/// The programs of the archive, or why they could not be listed.
+type ListResult = Result<Vec<ArchiveProgram>, String>;
+/// A program and its ROM, or why the ROM could not be downloaded.
+type DownloadResult = Result<(ArchiveProgram, Vec<u8>), String>;
+
+/// Lists and downloads programs from the chip8Archive in the background.
+#[derive(Debug, Default)]
+pub struct ArchiveBrowser {
+    /// The programs of the archive, or why they could not be listed.
+    pub programs: Option<ListResult>,
+    /// Only programs containing this text in their title or authors are shown.
+    pub filter: String,
+    /// Why the last download failed.
+    pub download_error: Option<String>,
+    /// Receives the program list while it is downloading.
+    listing: Option<Receiver<ListResult>>,
+    /// Receives the ROM that is downloading.
+    downloading: Option<Receiver<DownloadResult>>,
+}
+
+impl ArchiveBrowser {
+    /// Start downloading the program list if it was not downloaded yet.
+    pub fn list(&mut self) {
+        if self.programs.is_some() || self.listing.is_some() {
+            return;
+        }
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            let _ = sender.send(fetch_programs());
+        });
+        self.listing = Some(receiver);
+    }
+
+    /// Start downloading a program's ROM.
+    pub fn download(&mut self, program: ArchiveProgram) {
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            let rom = fetch(&format!("{}/{}.ch8", ROMS_URL, program.key));
+            let _ = sender.send(rom.map(|rom| (program, rom)));
+        });
+        self.download_error = None;
+        self.downloading = Some(receiver);
+    }
+
+    /// Check if a download is in progress.
+    pub fn is_busy(&self) -> bool {
+        self.listing.is_some() || self.downloading.is_some()
+    }
+
+    /// Collect finished downloads. Returns a downloaded ROM and the program it belongs to.
+    pub fn poll(&mut self) -> Option<(ArchiveProgram, Vec<u8>)> {
+        if let Some(listing) = &self.listing {
+            match listing.try_recv() {
+                Ok(programs) => self.programs = Some(programs),
+                Err(TryRecvError::Empty) => {}
+                Err(TryRecvError::Disconnected) => {
+                    self.programs = Some(Err("the download failed".to_string()))
+                }
+            }
+            if self.programs.is_some() {
+                self.listing = None;
+            }
+        }
+
+        let result = match self.downloading.as_ref()?.try_recv() {
+            Ok(result) => result,
+            Err(TryRecvError::Empty) => return None,
+            Err(TryRecvError::Disconnected) => Err("the download failed".to_string()),
+        };
+        self.downloading = None;
+        result.map_err(|e| self.download_error = Some(e)).ok()
+    }
+}
+
+/// Download a file.
+fn fetch(url: &str) -> Result<Vec<u8>, String> {
+    let response = ureq::get(url).call().map_err(|e| e.to_string())?;
+    let mut bytes = Vec::new();
+    response
+        .into_reader()
+        .read_to_end(&mut bytes)
+        .map_err(|e| e.to_string())?;
+    Ok(bytes)
+}
+
+/// Download the program list, sorted by title.
+fn fetch_programs() -> ListResult {
+    let json: Value = serde_json::from_slice(&fetch(PROGRAMS_URL)?).map_err(|e| e.to_string())?;
+    let mut programs: Vec<_> = json
+        .as_object()
+        .ok_or("the program list is invalid")?
+        .iter()
+        .map(|(key, program)| ArchiveProgram::from_json(key, program))
+        .collect();
+    programs.sort_by_key(|program| program.title.to_lowercase());
+    Ok(programs)
+}
@@ -0,0 +1,154 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use e_chip::{Chip8, Replay};
+
+/// The extensions of the files in a playlist folder that are loaded as ROMs.
+const ROM_EXTENSIONS: [&str; 3] = ["ch8", "c8", "sc8"];
+/// The extension of a replay that is played back with the ROM of the same name.
+const REPLAY_EXTENSION: &str = "replay";
+
+/// A ROM of the attract mode playlist.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlaylistEntry {
+    pub rom: PathBuf,
+    /// The replay next to the ROM, if there is one.
+    pub replay: Option<PathBuf>,
+}
+
+/// Cycles through the ROMs of a folder, running each one for a number of frames, for exhibitions
+/// and for soak-testing the interpreter with a whole library.
+///
+/// A ROM with a replay of the same name next to it (`game.ch8` and `game.replay`) plays the
+/// replay's input. ROMs that halt or can't be loaded are skipped and remembered.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AttractMode {
+    playlist: Vec<PlaylistEntry>,
+    /// How many frames each ROM runs.
+    frames_per_rom: u64,
+    /// The index of the ROM that is running.
+    current: usize,
+    /// The frame count of the interpreter when the current ROM started.
+    started_at: u64,
+    /// How many times a ROM was started.
+    runs: u64,
+    /// The ROMs that halted or could not be loaded, and why.
+    failures: Vec<(String, String)>,
+}
+
+impl AttractMode {
+    /// Make a playlist of the ROMs in `folder`, in alphabetical order, that run for `seconds` of
+    /// emulated time each.
+    pub fn open(folder: &Path, seconds: u32) -> io::Result<AttractMode> {
+        let mut roms: Vec<PathBuf> = fs::read_dir(folder)?
+            .filter_map(|entry| Some(entry.ok()?.path()))
+            .filter(|path| {
+                path.extension()
+                    .is_some_and(|extension| ROM_EXTENSIONS.iter().any(|e| extension == *e))
+            })
+            .collect();
+        if roms.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                "the folder has no .ch8, .c8 or .sc8 files",
+            ));
+        }
+        roms.sort();
+
+        let playlist = roms
+            .into_iter()
+            .map(|rom| {
+                let replay = Some(rom.with_extension(REPLAY_EXTENSION)).filter(|r| r.is_file());
+                PlaylistEntry { rom, replay }
+            })
+            .collect();
+        Ok(AttractMode {
+            playlist,
+            frames_per_rom: seconds as u64 * 60,
+            current: 0,
+            started_at: 0,
+            runs: 0,
+            failures: Vec::new(),
+        })
+    }
+
+    /// Get the ROM that is running.
+    #[inline]
+    pub fn current(&self) -> &PlaylistEntry {
+        &self.playlist[self.current]
+    }
+    /// Get the position of the running ROM in the playlist and the length of the playlist.
+    #[inline]
+    pub fn position(&self) -> (usize, usize) {
+        (self.current, self.playlist.len())
+    }
+    /// Get how many times a ROM was started.
+    #[inline]
+    pub fn runs(&self) -> u64 {
+        self.runs
+    }
+    /// Get the ROMs that halted or could not be loaded, and why.
+    #[inline]
+    pub fn failures(&self) -> &[(String, String)] {
+        &self.failures
+    }
+
+    /// Load the current ROM into the interpreter and start it, moving on to the next ROM if it can't
+    /// be loaded. Returns the loaded ROM, or `None` if no ROM of the playlist could be loaded.
+    pub fn start(&mut self, interpreter: &mut Chip8) -> Option<Vec<u8>> {
+        for _ in 0..self.playlist.len() {
+            match self.load(interpreter) {
+                Ok(rom) => {
+                    self.runs += 1;
+                    self.started_at = interpreter.get_frame_count();
+                    interpreter.start();
+                    return Some(rom);
+                }
+                Err(e) => {
+                    self.fail(e);
+                    self.current = (self.current + 1) % self.playlist.len();
+                }
+            }
+        }
+        None
+    }
+
+    /// Move on to the next ROM when the current one stopped or ran for long enough. Returns the ROM
+    /// that was started, if any.
+    pub fn update(&mut self, interpreter: &mut Chip8) -> Option<Vec<u8>> {
+        if let Some(reason) = &interpreter.halt_reason {
+            self.fail(reason.to_string());
+        } else if interpreter.is_running()
+            && interpreter.get_frame_count() < self.started_at + self.frames_per_rom
+        {
+            return None;
+        }
+        self.current = (self.current + 1) % self.playlist.len();
+        self.start(interpreter)
+    }
+
+    /// Reset the interpreter and load the current ROM, playing back its replay if it has one.
+    fn load(&self, interpreter: &mut Chip8) -> Result<Vec<u8>, String> {
+        let entry = self.current();
+        let rom = fs::read(&entry.rom).map_err(|e| e.to_string())?;
+        interpreter.stop_replay();
+        interpreter.reset();
+        interpreter.load_program(&rom).map_err(|e| e.to_string())?;
+        if let Some(path) = &entry.replay {
+            let replay = fs::read(path).map_err(|e| e.to_string())?;
+            let replay = Replay::from_bytes(&replay).map_err(|e| e.to_string())?;
+            interpreter
+                .start_replay_playback(replay)
+                .map_err(|e| e.to_string())?;
+        }
+        Ok(rom)
+    }
+
+    /// Remember that the current ROM failed.
+    fn fail(&mut self, reason: String) {
+        let name = self.current().rom.display().to_string();
+        self.failures.push((name, reason));
+    }
+}
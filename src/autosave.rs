@@ -0,0 +1,105 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+/// The name of the file the machine is saved to when the app is closed.
+const AUTOSAVE_FILE: &str = "autosave.dat";
+
+/// The machine as it was when the app was closed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Autosave {
+    /// The path of the ROM that was running.
+    pub rom_path: String,
+    /// The save state of the interpreter.
+    pub state: Vec<u8>,
+}
+
+impl Autosave {
+    /// Read the autosave file, if there is one.
+    pub fn load() -> Option<Autosave> {
+        Autosave::read(&autosave_file()?)
+    }
+
+    /// Write the autosave file.
+    pub fn save(&self) -> io::Result<()> {
+        let path = autosave_file().ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                "the platform has no config directory",
+            )
+        })?;
+        self.write(&path)
+    }
+
+    /// Delete the autosave file so it is not offered again.
+    pub fn delete() {
+        if let Some(path) = autosave_file() {
+            let _ = fs::remove_file(path);
+        }
+    }
+
+    /// Read an autosave from `path`: the length of the ROM path as 4 bytes, the ROM path and the
+    /// save state.
+    fn read(path: &Path) -> Option<Autosave> {
+        let data = fs::read(path).ok()?;
+        let len = u32::from_le_bytes(data.get(0..4)?.try_into().unwrap()) as usize;
+        let rom_path = String::from_utf8(data.get(4..4 + len)?.to_vec()).ok()?;
+        Some(Autosave {
+            rom_path,
+            state: data[4 + len..].to_vec(),
+        })
+    }
+
+    /// Write the autosave to `path`, creating its folder if needed.
+    fn write(&self, path: &Path) -> io::Result<()> {
+        let mut data = Vec::with_capacity(4 + self.rom_path.len() + self.state.len());
+        data.extend((self.rom_path.len() as u32).to_le_bytes());
+        data.extend(self.rom_path.as_bytes());
+        data.extend(&self.state);
+        if let Some(folder) = path.parent() {
+            fs::create_dir_all(folder)?;
+        }
+        fs::write(path, data)
+    }
+}
+
+/// Get the autosave file: `e-chip/autosave.dat` in the platform config directory, next to the
+/// recent ROMs, so it is found wherever the app is started from. `None` if the platform has no
+/// config directory.
+fn autosave_file() -> Option<PathBuf> {
+    Some(dirs::config_dir()?.join("e-chip").join(AUTOSAVE_FILE))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn file_location() {
+        assert_eq!(
+            autosave_file(),
+            dirs::config_dir().map(|config| config.join("e-chip").join("autosave.dat"))
+        );
+    }
+
+    #[test]
+    fn write_and_read() {
+        let folder = std::env::temp_dir().join(format!("e-chip-autosave-{}", std::process::id()));
+        let path = folder.join("e-chip").join(AUTOSAVE_FILE);
+        let autosave = Autosave {
+            rom_path: "/roms/Brix [Andreas Gustafsson].ch8".to_string(),
+            state: vec![0x45, 0x43, 0x48, 0x38, 1, 2, 3],
+        };
+        // The folder is created
+        autosave.write(&path).unwrap();
+        assert_eq!(Autosave::read(&path), Some(autosave));
+
+        // A file cut off in the ROM path is not offered
+        let data = fs::read(&path).unwrap();
+        fs::write(&path, &data[..10]).unwrap();
+        assert_eq!(Autosave::read(&path), None);
+        fs::remove_dir_all(&folder).unwrap();
+        assert_eq!(Autosave::read(&path), None);
+    }
+}
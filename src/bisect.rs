@@ -0,0 +1,168 @@
+use std::{
+    collections::HashSet,
+    hash::{DefaultHasher, Hash, Hasher},
+};
+
+use crate::{Breakpoints, Chip8, IndexIncrement, Quirks, VblankWait};
+
+/// The quirks tried by [`bisect_quirks`] and how many settings each of them has.
+pub(crate) const QUIRKS: [(&str, u8); 8] = [
+    ("Bitwise operations reset VF", 2),
+    ("Shift Vx directly", 2),
+    ("Memory access index register increment", 3),
+    ("Jump with offset Vx", 2),
+    ("Wait for vblank interrupt", 3),
+    ("Clip sprites at edges", 2),
+    ("Legacy scrolling", 2),
+    ("Clear display on resolution change", 2),
+];
+
+/// How a run of a ROM ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Outcome {
+    /// A hash of the display pixels.
+    pub display_hash: u64,
+    /// Whether the interpreter stopped before all frames were executed.
+    pub halted: bool,
+}
+
+/// Whether a quirk changes how a ROM runs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct QuirkEffect {
+    /// The name of the quirk, as shown in the quirks menu.
+    pub quirk: &'static str,
+    /// True if changing only this quirk changed the outcome in at least one combination.
+    pub changes_outcome: bool,
+}
+
+/// The result of [`bisect_quirks`].
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct BisectReport {
+    /// The effect of each quirk.
+    pub effects: Vec<QuirkEffect>,
+    /// The outcome of every quirk combination.
+    pub outcomes: Vec<(Quirks, Outcome)>,
+    /// The number of different outcomes.
+    pub distinct_outcomes: usize,
+}
+
+/// Set the `quirk`th quirk of [`QUIRKS`] to its `setting`th setting.
+fn set_quirk(quirks: &mut Quirks, quirk: usize, setting: u8) {
+    let on = setting != 0;
+    match quirk {
+        0 => quirks.bitwise_reset_vf = on,
+        1 => quirks.direct_shifting = on,
+        2 => {
+            quirks.save_load_increment = match setting {
+                0 => IndexIncrement::XPlusOne,
+                1 => IndexIncrement::X,
+                _ => IndexIncrement::Unchanged,
+            }
+        }
+        3 => quirks.jump_to_x = on,
+        4 => {
+            quirks.wait_for_vblank = match setting {
+                0 => VblankWait::Never,
+                1 => VblankWait::LowresOnly,
+                _ => VblankWait::Always,
+            }
+        }
+        5 => quirks.edge_clipping = on,
+        6 => quirks.lowres_scroll = on,
+        _ => quirks.resolution_clear = on,
+    }
+}
+
+/// Get the quirks of a combination, which has one setting per quirk of [`QUIRKS`].
+fn combination_quirks(settings: &[u8]) -> Quirks {
+    let mut quirks = Quirks::default();
+    for (quirk, &setting) in settings.iter().enumerate() {
+        set_quirk(&mut quirks, quirk, setting);
+    }
+    quirks
+}
+
+/// Run a ROM with the variant and speed of `base` under every quirk combination for a number of
+/// frames and find out which quirks change the outcome (the final display and whether it halted).
+///
+/// Input is not simulated, so ROMs that wait for key presses should be bisected at a point where
+/// they already show something.
+pub fn bisect_quirks(base: &Chip8, rom: &[u8], frames: u32) -> BisectReport {
+    // Enumerate all combinations like a mixed-radix number
+    let count: usize = QUIRKS
+        .iter()
+        .map(|&(_, settings)| settings as usize)
+        .product();
+    let combinations: Vec<Vec<u8>> = (0..count)
+        .map(|mut n| {
+            QUIRKS
+                .iter()
+                .map(|&(_, settings)| {
+                    let setting = (n % settings as usize) as u8;
+                    n /= settings as usize;
+                    setting
+                })
+                .collect()
+        })
+        .collect();
+
+    let outcomes: Vec<Outcome> = combinations
+        .iter()
+        .map(|settings| {
+            let mut chip8 = base.clone();
+            chip8.stop_chrome_trace();
+            chip8.stop_frame_dump();
+            chip8.stop_replay();
+            chip8.breakpoints = Breakpoints::default();
+            chip8.quirks = combination_quirks(settings);
+            run(&mut chip8, rom, frames)
+        })
+        .collect();
+
+    // A quirk matters if two combinations that only differ in it have different outcomes
+    let effects = QUIRKS
+        .iter()
+        .enumerate()
+        .map(|(quirk, &(name, _))| QuirkEffect {
+            quirk: name,
+            changes_outcome: combinations.iter().zip(&outcomes).any(|(a, outcome_a)| {
+                combinations.iter().zip(&outcomes).any(|(b, outcome_b)| {
+                    outcome_a != outcome_b
+                        && (0..QUIRKS.len()).all(|other| other == quirk || a[other] == b[other])
+                })
+            }),
+        })
+        .collect();
+
+    let distinct_outcomes = outcomes.iter().collect::<HashSet<_>>().len();
+    BisectReport {
+        effects,
+        outcomes: combinations
+            .iter()
+            .map(|settings| combination_quirks(settings))
+            .zip(outcomes)
+            .collect(),
+        distinct_outcomes,
+    }
+}
+
+/// Run a ROM from the start for a number of frames.
+fn run(chip8: &mut Chip8, rom: &[u8], frames: u32) -> Outcome {
+    chip8.reset_and_load(rom);
+    if chip8.halt_reason.is_none() {
+        chip8.start();
+    }
+    for _ in 0..frames {
+        chip8.run_frame();
+        if !chip8.is_running() {
+            break;
+        }
+    }
+
+    let mut hasher = DefaultHasher::new();
+    chip8.observe().pixels.hash(&mut hasher);
+    Outcome {
+        display_hash: hasher.finish(),
+        halted: !chip8.is_running(),
+    }
+}
@@ -0,0 +1,316 @@
+use std::collections::BTreeSet;
+
+use crate::{AccessKind, Chip8, Expression, MemoryAccess};
+
+/// Addresses where the running interpreter stops before executing the instruction there,
+/// conditions like `V3 == 0x1F` or `I >= 0xE00` that stop it before the cycle where they become
+/// true (not 0), and watchpoints that stop it after an instruction reads or writes a watched byte.
+///
+/// Starting the interpreter on a breakpoint executes its instruction instead of stopping again, so
+/// a program can be resumed from where it stopped. Conditions are checked before every cycle, also
+/// while stepping, so a condition that became true while stepping does not stop the interpreter
+/// when it is started.
+#[derive(Debug, Default, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Breakpoints {
+    addresses: BTreeSet<u16>,
+    conditions: Vec<Expression>,
+    /// The watched addresses and the kind of access that stops at each.
+    watchpoints: BTreeSet<(u16, AccessKind)>,
+    /// The first access to a watched byte made by the instruction being executed.
+    pending_access: Option<MemoryAccess>,
+    /// Whether each condition was true before the last cycle.
+    held: Vec<bool>,
+    /// The breakpoint the interpreter stopped at last, until it is started again.
+    hit: Option<BreakpointHit>,
+    /// The address the interpreter was started at, which does not stop it before its first cycle.
+    resumed_at: Option<u16>,
+}
+
+impl Breakpoints {
+    /// Stop at `address`.
+    #[inline]
+    pub fn set(&mut self, address: u16) {
+        self.addresses.insert(address);
+    }
+    /// Do not stop at `address` anymore.
+    #[inline]
+    pub fn clear(&mut self, address: u16) {
+        self.addresses.remove(&address);
+    }
+    /// Set the breakpoint at `address` if there is none, clear it otherwise.
+    #[inline]
+    pub fn toggle(&mut self, address: u16) {
+        if !self.addresses.remove(&address) {
+            self.addresses.insert(address);
+        }
+    }
+    /// Clear every breakpoint.
+    #[inline]
+    pub fn clear_all(&mut self) {
+        self.addresses.clear();
+    }
+    /// Check if there is a breakpoint at `address`.
+    #[inline]
+    pub fn contains(&self, address: u16) -> bool {
+        self.addresses.contains(&address)
+    }
+    /// Get the addresses of the breakpoints in ascending order.
+    #[inline]
+    pub fn list(&self) -> impl Iterator<Item = u16> + '_ {
+        self.addresses.iter().copied()
+    }
+
+    /// Stop after an instruction makes a `kind` access to the byte at `address`.
+    #[inline]
+    pub fn watch(&mut self, address: u16, kind: AccessKind) {
+        self.watchpoints.insert((address, kind));
+    }
+    /// Do not stop after `kind` accesses to the byte at `address` anymore.
+    #[inline]
+    pub fn unwatch(&mut self, address: u16, kind: AccessKind) {
+        self.watchpoints.remove(&(address, kind));
+    }
+    /// Check if `kind` accesses to the byte at `address` stop the interpreter.
+    #[inline]
+    pub fn is_watched(&self, address: u16, kind: AccessKind) -> bool {
+        self.watchpoints.contains(&(address, kind))
+    }
+    /// Remove every watchpoint.
+    #[inline]
+    pub fn clear_watchpoints(&mut self) {
+        self.watchpoints.clear();
+    }
+    /// Get the watchpoints in ascending order of address.
+    #[inline]
+    pub fn watchpoints(&self) -> impl Iterator<Item = (u16, AccessKind)> + '_ {
+        self.watchpoints.iter().copied()
+    }
+
+    /// Remember `access` if it touches a watched byte and is the first one of the instruction.
+    #[inline]
+    pub(crate) fn notice_access(&mut self, access: MemoryAccess) {
+        if self.pending_access.is_none() && self.is_watched(access.address, access.kind) {
+            self.pending_access = Some(access);
+        }
+    }
+
+    /// Stop when `condition` becomes true.
+    #[inline]
+    pub fn add_condition(&mut self, condition: Expression) {
+        self.conditions.push(condition);
+        self.held.push(false);
+    }
+    /// Do not stop when `condition` becomes true anymore.
+    #[inline]
+    pub fn remove_condition(&mut self, condition: &Expression) {
+        if let Some(i) = self.conditions.iter().position(|c| c == condition) {
+            self.conditions.remove(i);
+            self.held.remove(i);
+        }
+    }
+    /// Replace every condition. Conditions that are already true stop the interpreter before the
+    /// next cycle.
+    #[inline]
+    pub fn set_conditions(&mut self, conditions: Vec<Expression>) {
+        self.held = vec![false; conditions.len()];
+        self.conditions = conditions;
+    }
+    /// Get the conditions in the order they were added.
+    #[inline]
+    pub fn conditions(&self) -> &[Expression] {
+        &self.conditions
+    }
+
+    /// Get the breakpoint or condition the interpreter stopped at, if it stopped at one and was not
+    /// started since.
+    #[inline]
+    pub fn hit(&self) -> Option<&BreakpointHit> {
+        self.hit.as_ref()
+    }
+
+    /// Remember that the interpreter is started at `address`.
+    #[inline]
+    pub(crate) fn resume(&mut self, address: u16) {
+        self.hit = None;
+        self.resumed_at = Some(address);
+    }
+}
+
+/// What stopped the interpreter. See [`Breakpoints::hit`].
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum BreakpointHit {
+    /// The breakpoint at an address.
+    Address(u16),
+    /// A condition that became true.
+    Condition(Expression),
+    /// An access to a watched byte, stopped at after the instruction that made it.
+    Access(MemoryAccess),
+}
+
+impl Chip8 {
+    /// Check if the running interpreter has to stop before the next cycle because of a breakpoint
+    /// or a condition, and remember which if so.
+    pub(crate) fn check_breakpoints(&mut self) -> bool {
+        let address = self.program_counter;
+        let resumed = self.breakpoints.resumed_at.take() == Some(address);
+
+        let held: Vec<bool> = self
+            .breakpoints
+            .conditions
+            .iter()
+            .map(|condition| condition.evaluate(self).is_some_and(|value| value != 0))
+            .collect();
+        let became_true = held
+            .iter()
+            .zip(&self.breakpoints.held)
+            .position(|(now, before)| *now && !before);
+        self.breakpoints.held = held;
+        if !self.running {
+            return false;
+        }
+
+        let hit = if !resumed && self.breakpoints.addresses.contains(&address) {
+            Some(BreakpointHit::Address(address))
+        } else {
+            became_true.map(|i| BreakpointHit::Condition(self.breakpoints.conditions[i].clone()))
+        };
+        let stop = hit.is_some();
+        if stop {
+            self.breakpoints.hit = hit;
+        }
+        stop
+    }
+
+    /// Check if the last instruction accessed a watched byte, and remember the access if the
+    /// interpreter is running and has to stop.
+    pub(crate) fn check_watchpoints(&mut self) -> bool {
+        let Some(access) = self.breakpoints.pending_access.take() else {
+            return false;
+        };
+        if self.running {
+            self.breakpoints.hit = Some(BreakpointHit::Access(access));
+        }
+        self.running
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Writes V1 = 7 as BCD to 300, reads it back into V0 to V2 and counts V1 up in a loop.
+    const PROGRAM: &[u8] = &[
+        0x61, 0x07, 0xA3, 0x00, 0xF1, 0x33, 0xF2, 0x65, 0x71, 0x01, 0x12, 0x08,
+    ];
+
+    fn machine(setup: impl FnOnce(&mut Breakpoints)) -> Chip8 {
+        let mut chip8 = Chip8::chip8();
+        setup(&mut chip8.breakpoints);
+        chip8.reset_and_load(PROGRAM);
+        chip8.start();
+        chip8
+    }
+
+    /// Run until the interpreter stops, for at most 10 frames.
+    fn run(chip8: &mut Chip8) {
+        for _ in 0..10 {
+            chip8.run_frame();
+            if !chip8.is_running() {
+                return;
+            }
+        }
+        panic!("the interpreter did not stop");
+    }
+
+    #[test]
+    fn address() {
+        let mut chip8 = machine(|breakpoints| breakpoints.set(0x204));
+        run(&mut chip8);
+        // Stopped before the instruction
+        assert_eq!(chip8.program_counter, 0x204);
+        assert_eq!(
+            chip8.breakpoints.hit(),
+            Some(&BreakpointHit::Address(0x204))
+        );
+        assert_eq!(chip8.memory.ram[0x302], 0);
+
+        // Starting again executes the instruction
+        chip8.start();
+        assert_eq!(chip8.breakpoints.hit(), None);
+        chip8.execute_cycle();
+        assert!(chip8.is_running());
+        assert_eq!(chip8.program_counter, 0x206);
+        assert_eq!(chip8.memory.ram[0x302], 7);
+    }
+
+    #[test]
+    fn condition() {
+        let condition = Expression::parse("V1 == 3").unwrap();
+        let mut chip8 = machine(|breakpoints| breakpoints.add_condition(condition.clone()));
+        run(&mut chip8);
+        // Stopped before the cycle after the one that made it true
+        assert_eq!(chip8.V[1], 3);
+        assert_eq!(chip8.program_counter, 0x20A);
+        assert_eq!(
+            chip8.breakpoints.hit(),
+            Some(&BreakpointHit::Condition(condition))
+        );
+    }
+
+    #[test]
+    fn write_watchpoint() {
+        let mut chip8 = machine(|breakpoints| breakpoints.watch(0x302, AccessKind::Write));
+        run(&mut chip8);
+        // Stopped after the instruction
+        assert_eq!(chip8.program_counter, 0x206);
+        assert_eq!(chip8.memory.ram[0x302], 7);
+        assert_eq!(
+            chip8.breakpoints.hit(),
+            Some(&BreakpointHit::Access(MemoryAccess {
+                kind: AccessKind::Write,
+                address: 0x302,
+                value: 7,
+                program_counter: 0x204,
+                opcode: 0xF133,
+            }))
+        );
+    }
+
+    #[test]
+    fn read_watchpoint() {
+        let mut chip8 = machine(|breakpoints| {
+            breakpoints.watch(0x301, AccessKind::Read);
+            // Writes to the byte do not stop the interpreter
+            breakpoints.watch(0x302, AccessKind::Read);
+        });
+        run(&mut chip8);
+        // Stopped after the instruction, at the first watched byte it read
+        assert_eq!(chip8.program_counter, 0x208);
+        assert_eq!(chip8.V[..3], [0, 0, 7]);
+        assert_eq!(
+            chip8.breakpoints.hit(),
+            Some(&BreakpointHit::Access(MemoryAccess {
+                kind: AccessKind::Read,
+                address: 0x301,
+                value: 0,
+                program_counter: 0x206,
+                opcode: 0xF265,
+            }))
+        );
+    }
+
+    #[test]
+    fn stepping() {
+        let mut chip8 = machine(|breakpoints| {
+            breakpoints.set(0x204);
+            breakpoints.watch(0x302, AccessKind::Write);
+        });
+        chip8.stop();
+        // Breakpoints do not stop stepping
+        for _ in 0..4 {
+            chip8.step_cycle();
+        }
+        assert_eq!(chip8.program_counter, 0x208);
+        assert_eq!(chip8.breakpoints.hit(), None);
+    }
+}
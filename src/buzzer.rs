@@ -0,0 +1,242 @@
+#[cfg(target_arch = "wasm32")]
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::Duration;
+
+use e_chip::Chip8;
+#[cfg(not(target_arch = "wasm32"))]
+use rodio::Source;
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen::JsValue;
+#[cfg(target_arch = "wasm32")]
+use web_sys::{
+    AudioBufferSourceNode, AudioContext, AudioContextState, AudioScheduledSourceNode, GainNode,
+};
+
+use crate::BUZZER_FREQUENCY;
+#[cfg(target_arch = "wasm32")]
+use crate::BUZZER_VOLUME;
+
+/// The sample rate of the buzzer's output.
+#[cfg(not(target_arch = "wasm32"))]
+const SAMPLE_RATE: u32 = 48000;
+/// How many samples the buzzer plays before it looks at the tone again: a hundredth of a second.
+#[cfg(not(target_arch = "wasm32"))]
+const TONE_UPDATE_SAMPLES: u32 = SAMPLE_RATE / 100;
+
+/// What the buzzer plays.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Tone {
+    /// A square wave of [`BUZZER_FREQUENCY`].
+    Square,
+    /// An XO-CHIP audio pattern: 128 1-bit samples, played `sample_rate` samples per second.
+    Pattern {
+        samples: [bool; 128],
+        sample_rate: f32,
+    },
+}
+
+impl Tone {
+    /// Get the tone the interpreter plays while its sound timer runs.
+    pub fn of(interpreter: &Chip8) -> Tone {
+        match interpreter.get_audio_samples() {
+            Some(samples) => Tone::Pattern {
+                samples,
+                sample_rate: interpreter.get_audio_sample_rate(),
+            },
+            None => Tone::Square,
+        }
+    }
+
+    /// Get how many periods of the tone play per second: waves of the square wave or samples of the
+    /// pattern.
+    pub fn frequency(&self) -> f32 {
+        match self {
+            Tone::Square => BUZZER_FREQUENCY,
+            Tone::Pattern { sample_rate, .. } => *sample_rate,
+        }
+    }
+
+    /// Check if the tone is high after `position` periods.
+    pub fn is_high(&self, position: f32) -> bool {
+        match self {
+            Tone::Square => position.fract() < 0.5,
+            Tone::Pattern { samples, .. } => samples[position as usize % 128],
+        }
+    }
+}
+
+/// An endless sound source that plays the tone shared with the frontend.
+#[cfg(not(target_arch = "wasm32"))]
+pub struct Buzzer {
+    /// The tone to play, set by the frontend.
+    tone: Arc<Mutex<Tone>>,
+    /// The tone being played.
+    current: Tone,
+    /// How many periods of the tone were played, wrapped around after a whole pattern.
+    position: f32,
+    /// The samples left until the tone is looked at again.
+    until_update: u32,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Buzzer {
+    /// Create a buzzer playing a square wave and get the tone that controls it.
+    pub fn new() -> (Buzzer, Arc<Mutex<Tone>>) {
+        let tone = Arc::new(Mutex::new(Tone::Square));
+        let buzzer = Buzzer {
+            tone: Arc::clone(&tone),
+            current: Tone::Square,
+            position: 0.0,
+            until_update: 0,
+        };
+        (buzzer, tone)
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Iterator for Buzzer {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        // Don't hold up the audio thread if the frontend is setting the tone
+        if self.until_update == 0 {
+            if let Ok(tone) = self.tone.try_lock() {
+                self.current = *tone;
+            }
+            self.until_update = TONE_UPDATE_SAMPLES;
+        }
+        self.until_update -= 1;
+
+        let sample = if self.current.is_high(self.position) {
+            1.0
+        } else {
+            -1.0
+        };
+        self.position = (self.position + self.current.frequency() / SAMPLE_RATE as f32) % 128.0;
+        Some(sample)
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Source for Buzzer {
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> u16 {
+        1
+    }
+
+    fn sample_rate(&self) -> u32 {
+        SAMPLE_RATE
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+}
+
+/// Plays the tone shared with the frontend with the Web Audio API, in the browser.
+///
+/// The tone is rendered into a looping buffer whenever it changes, and muted while the buzzer is
+/// off.
+#[cfg(target_arch = "wasm32")]
+pub struct WebBuzzer {
+    /// The audio context and the volume control the tone is played through. `None` if the
+    /// browser has no audio.
+    output: Option<(AudioContext, GainNode)>,
+    /// Plays the rendered tone.
+    source: Option<AudioBufferSourceNode>,
+    /// The tone to play, set by the frontend.
+    tone: Arc<Mutex<Tone>>,
+    /// Whether the buzzer should play, set by the driver.
+    playing: Arc<AtomicBool>,
+    /// The tone that was rendered.
+    current: Option<Tone>,
+    /// Whether the buzzer is audible.
+    audible: bool,
+}
+
+#[cfg(target_arch = "wasm32")]
+impl WebBuzzer {
+    /// Create a silent buzzer, and get the tone and the switch that control it.
+    pub fn new() -> (WebBuzzer, Arc<Mutex<Tone>>, Arc<AtomicBool>) {
+        let output = AudioContext::new()
+            .and_then(|context| {
+                let gain = context.create_gain()?;
+                gain.gain().set_value(0.0);
+                gain.connect_with_audio_node(&context.destination())?;
+                Ok((context, gain))
+            })
+            .ok();
+        let tone = Arc::new(Mutex::new(Tone::Square));
+        let playing = Arc::new(AtomicBool::new(false));
+        let buzzer = WebBuzzer {
+            output,
+            source: None,
+            tone: Arc::clone(&tone),
+            playing: Arc::clone(&playing),
+            current: None,
+            audible: false,
+        };
+        (buzzer, tone, playing)
+    }
+
+    /// Follow the tone and the switch. Call it every frame.
+    pub fn update(&mut self) {
+        let Some((context, gain)) = &self.output else {
+            return;
+        };
+
+        let tone = *self.tone.lock().unwrap();
+        if self.current != Some(tone) {
+            self.current = Some(tone);
+            if let Some(source) = self.source.take() {
+                let _ = AudioScheduledSourceNode::stop(&source);
+            }
+            self.source = WebBuzzer::play(context, gain, &tone).ok();
+        }
+
+        let playing = self.playing.load(Ordering::Relaxed);
+        if playing != self.audible {
+            self.audible = playing;
+            // Browsers only start the audio once the page was interacted with
+            if playing && context.state() != AudioContextState::Running {
+                let _ = context.resume();
+            }
+            gain.gain()
+                .set_value(if playing { BUZZER_VOLUME } else { 0.0 });
+        }
+    }
+
+    /// Render a whole pattern of a tone (128 periods) into a buffer and loop it.
+    fn play(
+        context: &AudioContext,
+        gain: &GainNode,
+        tone: &Tone,
+    ) -> Result<AudioBufferSourceNode, JsValue> {
+        let sample_rate = context.sample_rate();
+        let step = tone.frequency() / sample_rate;
+        let length = ((128.0 / step).round() as u32).max(1);
+        let samples: Vec<f32> = (0..length)
+            .map(|i| {
+                if tone.is_high(i as f32 * step) {
+                    1.0
+                } else {
+                    -1.0
+                }
+            })
+            .collect();
+
+        let buffer = context.create_buffer(1, length, sample_rate)?;
+        buffer.copy_to_channel(&samples, 0)?;
+        let source = context.create_buffer_source()?;
+        source.set_buffer(Some(&buffer));
+        source.set_loop(true);
+        source.connect_with_audio_node(gain)?;
+        source.start()?;
+        Ok(source)
+    }
+}
@@ -0,0 +1,590 @@
+use crate::{Chip8, FrameEventKind, HaltReason};
+
+/// Where the VIP interpreter keeps V0 to VF in a 4KB machine.
+const V_ADDRESS: usize = 0x0EF0;
+/// Where the VIP interpreter keeps the 64x32 display, one bit per pixel.
+const DISPLAY_ADDRESS: usize = 0x0F00;
+/// The VIP interpreter's stack pointer while the CHIP-8 stack is empty.
+const STACK_ADDRESS: u16 = 0x0ECF;
+/// How many instructions a machine code routine may execute before it is considered stuck.
+const MAX_INSTRUCTIONS: u32 = 1_000_000;
+
+/// An RCA CDP1802, the CPU of the COSMAC VIP, for running `0nnn` machine code routines.
+///
+/// Only what a routine called from CHIP-8 can see is emulated: interrupts and DMA are not, `IDL`
+/// does nothing and `INP` reads 0. `EF3` is the key latched with `OUT 2`, like on the VIP.
+#[derive(Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+struct Cdp1802 {
+    r: [u16; 16],
+    /// The register used as the program counter.
+    p: usize,
+    /// The register used as the data pointer.
+    x: usize,
+    d: u8,
+    df: bool,
+    t: u8,
+    ie: bool,
+    q: bool,
+    /// The key selected with `OUT 2`, read through `EF3`.
+    key: u8,
+}
+
+impl Cdp1802 {
+    /// Read a byte of memory. Addresses wrap around the RAM.
+    #[inline]
+    fn read(ram: &[u8], address: u16) -> u8 {
+        ram[address as usize % ram.len()]
+    }
+
+    /// Read the byte at the program counter and advance it.
+    #[inline]
+    fn fetch(&mut self, ram: &[u8]) -> u8 {
+        let byte = Cdp1802::read(ram, self.r[self.p]);
+        self.r[self.p] = self.r[self.p].wrapping_add(1);
+        byte
+    }
+
+    /// Add `a`, `b` and the carry, setting DF to the carry out.
+    #[inline]
+    fn add(&mut self, a: u8, b: u8, carry: bool) -> u8 {
+        let sum = a as u16 + b as u16 + carry as u16;
+        self.df = sum > 0xFF;
+        sum as u8
+    }
+
+    /// Execute one instruction. `keypad` gives the state of the keys for `EF3`.
+    fn step(&mut self, ram: &mut [u8], keypad: &[bool; 16]) {
+        let opcode = self.fetch(ram);
+        let n = (opcode & 0x0F) as usize;
+        match opcode >> 4 {
+            // IDL
+            0x0 if n == 0 => {}
+            // LDN
+            0x0 => self.d = Cdp1802::read(ram, self.r[n]),
+            // INC, DEC
+            0x1 => self.r[n] = self.r[n].wrapping_add(1),
+            0x2 => self.r[n] = self.r[n].wrapping_sub(1),
+            // Short branches
+            0x3 => {
+                let ef3 = keypad[self.key as usize & 0xF];
+                let condition = match n & 0x7 {
+                    0 => true,
+                    1 => self.q,
+                    2 => self.d == 0,
+                    3 => self.df,
+                    4 | 5 | 7 => false,
+                    _ => ef3,
+                };
+                // The second half inverts the condition, and 38 is SKP
+                let taken = if n < 8 {
+                    condition
+                } else {
+                    !condition && n != 8
+                };
+                let target = Cdp1802::read(ram, self.r[self.p]);
+                if taken {
+                    self.r[self.p] = self.r[self.p] & 0xFF00 | target as u16;
+                } else {
+                    self.r[self.p] = self.r[self.p].wrapping_add(1);
+                }
+            }
+            // LDA
+            0x4 => {
+                self.d = Cdp1802::read(ram, self.r[n]);
+                self.r[n] = self.r[n].wrapping_add(1);
+            }
+            // STR
+            0x5 => ram[self.r[n] as usize % ram.len()] = self.d,
+            // IRX
+            0x6 if n == 0 => self.r[self.x] = self.r[self.x].wrapping_add(1),
+            // OUT 1 to 7, only OUT 2 (latch a key) does something here
+            0x6 if n < 8 => {
+                if n == 2 {
+                    self.key = Cdp1802::read(ram, self.r[self.x]) & 0xF;
+                }
+                self.r[self.x] = self.r[self.x].wrapping_add(1);
+            }
+            // INP 1 to 7
+            0x6 if n > 8 => {
+                self.d = 0;
+                ram[self.r[self.x] as usize % ram.len()] = 0;
+            }
+            0x6 => {}
+            0x7 => {
+                let m = Cdp1802::read(ram, self.r[self.x]);
+                match n {
+                    // RET, DIS
+                    0x0 | 0x1 => {
+                        self.r[self.x] = self.r[self.x].wrapping_add(1);
+                        self.x = (m >> 4) as usize;
+                        self.p = (m & 0xF) as usize;
+                        self.ie = n == 0;
+                    }
+                    // LDXA
+                    0x2 => {
+                        self.d = m;
+                        self.r[self.x] = self.r[self.x].wrapping_add(1);
+                    }
+                    // STXD
+                    0x3 => {
+                        ram[self.r[self.x] as usize % ram.len()] = self.d;
+                        self.r[self.x] = self.r[self.x].wrapping_sub(1);
+                    }
+                    // ADC
+                    0x4 => self.d = self.add(m, self.d, self.df),
+                    // SDB
+                    0x5 => self.d = self.add(m, !self.d, self.df),
+                    // SHRC
+                    0x6 => {
+                        let carry = self.df;
+                        self.df = self.d & 1 != 0;
+                        self.d = self.d >> 1 | (carry as u8) << 7;
+                    }
+                    // SMB
+                    0x7 => self.d = self.add(self.d, !m, self.df),
+                    // SAV
+                    0x8 => ram[self.r[self.x] as usize % ram.len()] = self.t,
+                    // MARK
+                    0x9 => {
+                        self.t = (self.x as u8) << 4 | self.p as u8;
+                        ram[self.r[2] as usize % ram.len()] = self.t;
+                        self.x = self.p;
+                        self.r[2] = self.r[2].wrapping_sub(1);
+                    }
+                    // REQ, SEQ
+                    0xA => self.q = false,
+                    0xB => self.q = true,
+                    // ADCI
+                    0xC => {
+                        let m = self.fetch(ram);
+                        self.d = self.add(m, self.d, self.df);
+                    }
+                    // SDBI
+                    0xD => {
+                        let m = self.fetch(ram);
+                        self.d = self.add(m, !self.d, self.df);
+                    }
+                    // SHLC
+                    0xE => {
+                        let carry = self.df;
+                        self.df = self.d & 0x80 != 0;
+                        self.d = self.d << 1 | carry as u8;
+                    }
+                    // SMBI
+                    _ => {
+                        let m = self.fetch(ram);
+                        self.d = self.add(self.d, !m, self.df);
+                    }
+                }
+            }
+            // GLO, GHI, PLO, PHI
+            0x8 => self.d = self.r[n] as u8,
+            0x9 => self.d = (self.r[n] >> 8) as u8,
+            0xA => self.r[n] = self.r[n] & 0xFF00 | self.d as u16,
+            0xB => self.r[n] = self.r[n] & 0x00FF | (self.d as u16) << 8,
+            // Long branches and skips
+            0xC => {
+                let condition = match n & 0x3 {
+                    0 => true,
+                    1 => self.q,
+                    2 => self.d == 0,
+                    _ => self.df,
+                };
+                match n {
+                    // LBR, LBQ, LBZ, LBDF, LBNQ, LBNZ, LBNF
+                    0x0..=0x3 | 0x9..=0xB => {
+                        let taken = if n < 8 { condition } else { !condition };
+                        if taken {
+                            let high = Cdp1802::read(ram, self.r[self.p]);
+                            let low = Cdp1802::read(ram, self.r[self.p].wrapping_add(1));
+                            self.r[self.p] = (high as u16) << 8 | low as u16;
+                        } else {
+                            self.r[self.p] = self.r[self.p].wrapping_add(2);
+                        }
+                    }
+                    // NOP
+                    0x4 => {}
+                    // LSNQ, LSNZ, LSNF, LSKP, LSIE, LSQ, LSZ, LSDF
+                    _ => {
+                        let skip = match n {
+                            0x5 => !self.q,
+                            0x6 => self.d != 0,
+                            0x7 => !self.df,
+                            0x8 => true,
+                            0xC => self.ie,
+                            _ => condition,
+                        };
+                        if skip {
+                            self.r[self.p] = self.r[self.p].wrapping_add(2);
+                        }
+                    }
+                }
+            }
+            // SEP, SEX
+            0xD => self.p = n,
+            0xE => self.x = n,
+            _ => {
+                // The immediate forms read the byte after the opcode instead of M(R(X))
+                let m = if n >= 8 && n != 0xE {
+                    self.fetch(ram)
+                } else {
+                    Cdp1802::read(ram, self.r[self.x])
+                };
+                match n & 0x7 {
+                    // LDX, LDI
+                    0 => self.d = m,
+                    // OR, ORI
+                    1 => self.d |= m,
+                    // AND, ANI
+                    2 => self.d &= m,
+                    // XOR, XRI
+                    3 => self.d ^= m,
+                    // ADD, ADI
+                    4 => self.d = self.add(m, self.d, false),
+                    // SD, SDI
+                    5 => self.d = self.add(m, !self.d, true),
+                    // SHR, SHL
+                    6 if n == 0x6 => {
+                        self.df = self.d & 1 != 0;
+                        self.d >>= 1;
+                    }
+                    6 => {
+                        self.df = self.d & 0x80 != 0;
+                        self.d <<= 1;
+                    }
+                    // SM, SMI
+                    _ => self.d = self.add(self.d, !m, true),
+                }
+            }
+        }
+    }
+}
+
+impl Chip8 {
+    /// `0nnn` - Run the machine code routine at nnn on the CDP1802 until it returns to the
+    /// interpreter with `SEP 4` (`D4`), like the COSMAC VIP does, and move on to the next
+    /// instruction.
+    ///
+    /// The routine sees the machine the way the VIP interpreter keeps it: V0 to VF at 0x0EF0, the
+    /// display at 0x0F00, I in RA, the address of the next instruction in R5, Vx and Vy pointed to by R6 and R7 and
+    /// the delay and sound timers in R8. Whatever the routine changes there is copied back. The
+    /// interpreter itself is not in memory, so routines that call into it do not work.
+    pub(crate) fn call_machine_code(&mut self, address: u16) {
+        let keypad = std::array::from_fn(|key| self.is_key_down(key));
+        let ram = &mut self.memory.ram;
+        ram[V_ADDRESS..V_ADDRESS + 16].copy_from_slice(&self.V);
+        for (i, byte) in ram[DISPLAY_ADDRESS..DISPLAY_ADDRESS + 256]
+            .iter_mut()
+            .enumerate()
+        {
+            *byte = (0..8).fold(0, |byte, bit| {
+                byte << 1 | self.display.planes[0][i * 8 + bit] as u8
+            });
+        }
+
+        let x = (address >> 8) as usize & 0xF;
+        let y = (address >> 4) as usize & 0xF;
+        let mut cpu = Cdp1802 {
+            p: 3,
+            x: 2,
+            ..Default::default()
+        };
+        cpu.r[2] = STACK_ADDRESS;
+        cpu.r[3] = address;
+        cpu.r[5] = self.program_counter.wrapping_add(2);
+        cpu.r[6] = (V_ADDRESS + x) as u16;
+        cpu.r[7] = (V_ADDRESS + y) as u16;
+        cpu.r[8] = (self.delay as u16) << 8 | self.sound as u16;
+        cpu.r[0xA] = self.I;
+        cpu.r[0xB] = DISPLAY_ADDRESS as u16;
+
+        let mut instructions = 0;
+        while cpu.p != 4 {
+            if instructions == MAX_INSTRUCTIONS {
+                self.halt(HaltReason::MachineCodeDidNotReturn { address });
+                return;
+            }
+            cpu.step(ram, &keypad);
+            instructions += 1;
+        }
+
+        self.V.copy_from_slice(&ram[V_ADDRESS..V_ADDRESS + 16]);
+        for (i, byte) in ram[DISPLAY_ADDRESS..DISPLAY_ADDRESS + 256]
+            .iter()
+            .enumerate()
+        {
+            for bit in 0..8 {
+                self.display.planes[0][i * 8 + bit] = byte & (0x80 >> bit) != 0;
+            }
+        }
+        self.I = cpu.r[0xA];
+        self.jump(cpu.r[5]);
+        self.delay = (cpu.r[8] >> 8) as u8;
+        self.sound = cpu.r[8] as u8;
+        self.record_event(FrameEventKind::Draw);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A CPU with R0 as the program counter and R2, pointing at 0x100, as the data pointer.
+    fn setup() -> Cdp1802 {
+        let mut cpu = Cdp1802 {
+            x: 2,
+            ..Default::default()
+        };
+        cpu.r[2] = 0x100;
+        cpu
+    }
+
+    /// Run `program` from 0x000 until the program counter leaves it, with `data` at 0x100.
+    fn run_with(
+        mut cpu: Cdp1802,
+        program: &[u8],
+        data: &[u8],
+        keypad: &[bool; 16],
+    ) -> (Cdp1802, Vec<u8>) {
+        let mut ram = vec![0; 0x1000];
+        ram[..program.len()].copy_from_slice(program);
+        ram[0x100..0x100 + data.len()].copy_from_slice(data);
+        for _ in 0..100 {
+            if cpu.r[cpu.p] as usize >= program.len() {
+                break;
+            }
+            cpu.step(&mut ram, keypad);
+        }
+        (cpu, ram)
+    }
+
+    fn run(program: &[u8], data: &[u8]) -> Cdp1802 {
+        run_with(setup(), program, data, &[false; 16]).0
+    }
+
+    /// Get D and DF after running `program` with 0x30 at M(R2).
+    fn arithmetic(program: &[u8]) -> (u8, bool) {
+        let cpu = run(program, &[0x30]);
+        (cpu.d, cpu.df)
+    }
+
+    /// Get where the program counter ends up after running `program`.
+    fn branch(program: &[u8]) -> u16 {
+        run(program, &[]).r[0]
+    }
+
+    #[test]
+    fn registers() {
+        // LDI, PLO, PHI, INC, DEC, GLO, GHI
+        let cpu = run(&[0xF8, 0x12, 0xA3, 0xF8, 0x34, 0xB3, 0x13, 0x24, 0x83], &[]);
+        assert_eq!(cpu.r[3], 0x3413);
+        assert_eq!(cpu.r[4], 0xFFFF);
+        assert_eq!(cpu.d, 0x13);
+        assert_eq!(run(&[0xF8, 0x34, 0xB3, 0x93], &[]).d, 0x34);
+        // SEX, SEP
+        assert_eq!(run(&[0xE5], &[]).x, 5);
+        let mut start = setup();
+        start.r[3] = 0x80;
+        assert_eq!(run_with(start, &[0xD3], &[], &[false; 16]).0.p, 3);
+    }
+
+    #[test]
+    fn memory() {
+        // LDN, LDA, LDXA, IRX, LDI, STR, STXD, LDX
+        let (cpu, ram) = run_with(
+            setup(),
+            &[0x02, 0x42, 0x72, 0x60, 0xF8, 0x55, 0x52, 0x73, 0xF0],
+            &[0xAA, 0xBB, 0xCC],
+            &[false; 16],
+        );
+        assert_eq!(cpu.d, 0xCC);
+        assert_eq!(cpu.r[2], 0x102);
+        assert_eq!(ram[0x103], 0x55);
+        assert_eq!(run(&[0x02, 0x42], &[0xAA]).r[2], 0x101);
+        // INP reads 0 into D and memory
+        let (cpu, ram) = run_with(setup(), &[0xF8, 0x12, 0x6A], &[0x30], &[false; 16]);
+        assert_eq!((cpu.d, ram[0x100]), (0, 0));
+    }
+
+    #[test]
+    fn logic() {
+        assert_eq!(arithmetic(&[0xF8, 0x3C, 0xF9, 0x03]), (0x3F, false));
+        assert_eq!(arithmetic(&[0xF8, 0x3C, 0xFA, 0x0F]), (0x0C, false));
+        assert_eq!(arithmetic(&[0xF8, 0x3C, 0xFB, 0xFF]), (0xC3, false));
+        assert_eq!(arithmetic(&[0xF8, 0x0C, 0xF1]), (0x3C, false));
+        assert_eq!(arithmetic(&[0xF8, 0x3C, 0xF2]), (0x30, false));
+        assert_eq!(arithmetic(&[0xF8, 0x3C, 0xF3]), (0x0C, false));
+        assert_eq!(arithmetic(&[0xF0]), (0x30, false));
+    }
+
+    #[test]
+    fn addition() {
+        // ADD, ADI
+        assert_eq!(arithmetic(&[0xF8, 0x10, 0xF4]), (0x40, false));
+        assert_eq!(arithmetic(&[0xF8, 0xF0, 0xFC, 0x20]), (0x10, true));
+        // ADC, ADCI add the carry of the ADI before them
+        assert_eq!(arithmetic(&[0xF8, 0xFF, 0xFC, 0x01, 0x74]), (0x31, false));
+        assert_eq!(
+            arithmetic(&[0xF8, 0xFF, 0xFC, 0x01, 0x7C, 0x05]),
+            (0x06, false)
+        );
+        assert_eq!(arithmetic(&[0xF8, 0xFF, 0x74]), (0x2F, true));
+    }
+
+    #[test]
+    fn subtraction() {
+        // DF is 1 when there is no borrow. SD, SDI subtract D from the operand
+        assert_eq!(arithmetic(&[0xF8, 0x10, 0xF5]), (0x20, true));
+        assert_eq!(arithmetic(&[0xF8, 0x40, 0xF5]), (0xF0, false));
+        assert_eq!(arithmetic(&[0xF8, 0x10, 0xFD, 0x05]), (0xF5, false));
+        // SM, SMI subtract the operand from D
+        assert_eq!(arithmetic(&[0xF8, 0x40, 0xF7]), (0x10, true));
+        assert_eq!(arithmetic(&[0xF8, 0x05, 0xFF, 0x06]), (0xFF, false));
+        // SDB, SMB subtract the borrow of the SD before them
+        assert_eq!(arithmetic(&[0xF8, 0x40, 0xF5, 0x75]), (0x3F, false));
+        assert_eq!(arithmetic(&[0xF8, 0x40, 0xF5, 0x77]), (0xBF, true));
+        // SDBI, SMBI without a borrow before them
+        assert_eq!(arithmetic(&[0xF8, 0x10, 0xF5, 0x7D, 0x25]), (0x05, true));
+        assert_eq!(arithmetic(&[0xF8, 0x10, 0xF5, 0x7F, 0x25]), (0xFB, false));
+    }
+
+    #[test]
+    fn shifts() {
+        // SHR, SHL shift into DF
+        assert_eq!(arithmetic(&[0xF8, 0x03, 0xF6]), (0x01, true));
+        assert_eq!(arithmetic(&[0xF8, 0x81, 0xFE]), (0x02, true));
+        // SHRC, SHLC also shift DF in
+        assert_eq!(arithmetic(&[0xF8, 0x81, 0xFE, 0x76]), (0x81, false));
+        assert_eq!(arithmetic(&[0xF8, 0x81, 0xFE, 0x7E]), (0x05, false));
+    }
+
+    #[test]
+    fn short_branches() {
+        // BR, BZ, BNZ
+        assert_eq!(branch(&[0x30, 0x40]), 0x40);
+        assert_eq!(branch(&[0x32, 0x40]), 0x40);
+        assert_eq!(branch(&[0xF8, 0x01, 0x32, 0x40]), 4);
+        assert_eq!(branch(&[0xF8, 0x01, 0x3A, 0x40]), 0x40);
+        assert_eq!(branch(&[0x3A, 0x40]), 2);
+        // BDF, BNF
+        assert_eq!(branch(&[0xF8, 0xFF, 0xFC, 0x01, 0x33, 0x40]), 0x40);
+        assert_eq!(branch(&[0x33, 0x40]), 2);
+        assert_eq!(branch(&[0x3B, 0x40]), 0x40);
+        // SEQ, REQ, BQ, BNQ
+        assert_eq!(branch(&[0x7B, 0x31, 0x40]), 0x40);
+        assert_eq!(branch(&[0x7B, 0x7A, 0x31, 0x40]), 4);
+        assert_eq!(branch(&[0x39, 0x40]), 0x40);
+        // EF1, EF2 and EF4 are never set
+        assert_eq!(branch(&[0x34, 0x40]), 2);
+        assert_eq!(branch(&[0x3C, 0x40]), 0x40);
+        // SKP skips the next byte
+        let cpu = run(&[0x38, 0xF8, 0x11], &[]);
+        assert_eq!((cpu.d, cpu.r[1]), (0, 1));
+    }
+
+    #[test]
+    fn key_latch() {
+        // OUT 2 latches the key at M(R(X)), which EF3 reads
+        let mut keypad = [false; 16];
+        let b3 = [0x62, 0x36, 0x40];
+        let bn3 = [0x62, 0x3E, 0x40];
+        let pc = |program: &[u8], keypad: &[bool; 16]| {
+            run_with(setup(), program, &[0x05], keypad).0.r[0]
+        };
+        assert_eq!(pc(&b3, &keypad), 3);
+        assert_eq!(pc(&bn3, &keypad), 0x40);
+        keypad[5] = true;
+        assert_eq!(pc(&b3, &keypad), 0x40);
+        assert_eq!(pc(&bn3, &keypad), 3);
+        assert_eq!(run_with(setup(), &b3, &[0x05], &keypad).0.r[2], 0x101);
+    }
+
+    #[test]
+    fn long_branches_and_skips() {
+        // LBR, LBZ, LBNZ, LBDF, LBNF, LBQ, LBNQ
+        assert_eq!(branch(&[0xC0, 0x01, 0x23]), 0x123);
+        assert_eq!(branch(&[0xC2, 0x01, 0x23]), 0x123);
+        assert_eq!(branch(&[0xF8, 0x01, 0xC2, 0x01, 0x23]), 5);
+        assert_eq!(branch(&[0xF8, 0x01, 0xCA, 0x01, 0x23]), 0x123);
+        assert_eq!(branch(&[0xC3, 0x01, 0x23]), 3);
+        assert_eq!(branch(&[0xCB, 0x01, 0x23]), 0x123);
+        assert_eq!(branch(&[0x7B, 0xC1, 0x01, 0x23]), 0x123);
+        assert_eq!(branch(&[0xC9, 0x01, 0x23]), 0x123);
+        // NOP, LSKP, LSZ, LSNZ, LSNQ, LSNF, LSIE, LSQ, LSDF
+        let skipped = |skip: u8| run(&[skip, 0xF8, 0x07, 0x11], &[]).d == 0;
+        assert!(!skipped(0xC4));
+        assert!(skipped(0xC8));
+        assert!(skipped(0xCE));
+        assert!(!skipped(0xC6));
+        assert!(skipped(0xC5));
+        assert!(skipped(0xC7));
+        assert!(!skipped(0xCC));
+        assert!(!skipped(0xCD));
+        assert!(!skipped(0xCF));
+        assert_eq!(run(&[0xC8, 0xF8, 0x07, 0x11], &[]).r[1], 1);
+    }
+
+    #[test]
+    fn subroutines() {
+        // MARK saves X and P in T and at M(R2), then SAV stores T at M(R(X))
+        let (cpu, ram) = run_with(setup(), &[0x79, 0xE2, 0x78], &[], &[false; 16]);
+        assert_eq!((cpu.t, cpu.x, cpu.r[2]), (0x20, 2, 0xFF));
+        assert_eq!((ram[0xFF], ram[0x100]), (0x20, 0x20));
+        // RET and DIS load X and P from M(R(X)) and enable or disable interrupts
+        let mut start = setup();
+        start.r[3] = 0x80;
+        let ret = run_with(start.clone(), &[0x70], &[0x23], &[false; 16]).0;
+        assert_eq!((ret.x, ret.p, ret.r[2], ret.ie), (2, 3, 0x101, true));
+        start.ie = true;
+        let dis = run_with(start, &[0x71], &[0x23], &[false; 16]).0;
+        assert_eq!((dis.x, dis.p, dis.ie), (2, 3, false));
+        // IDL does nothing
+        let idle = run(&[0x00], &[]);
+        assert_eq!((idle.d, idle.r[0], idle.r[2]), (0, 1, 0x100));
+    }
+
+    /// A VIP running `routine` at 0x210 from `0210` at 0x200, followed by `6042`.
+    fn vip(routine: &[u8]) -> Chip8 {
+        let mut program = vec![0x02, 0x10, 0x60, 0x42];
+        program.resize(0x10, 0);
+        program.extend_from_slice(routine);
+        let mut chip8 = Chip8::vip_hybrid();
+        chip8.reset_and_load(&program);
+        chip8.start();
+        chip8
+    }
+
+    #[test]
+    fn return_to_interpreter() {
+        // Set Vx (V2) through R6, the low byte of I through RA, the delay timer through R8 and
+        // the first display byte through RB, then return with SEP 4
+        let mut chip8 = vip(&[
+            0xF8, 0x99, 0x56, 0xF8, 0x77, 0xAA, 0xF8, 0x09, 0xB8, 0xF8, 0xFF, 0x5B, 0xD4,
+        ]);
+        chip8.I = 0x0300;
+        chip8.execute_cycle();
+        assert_eq!(chip8.halt_reason, None);
+        assert!(chip8.is_running());
+        assert_eq!(chip8.V[2], 0x99);
+        assert_eq!(chip8.I, 0x0377);
+        assert_eq!(chip8.delay, 0x09);
+        assert_eq!(
+            chip8.display.planes[0][..9],
+            [true, true, true, true, true, true, true, true, false]
+        );
+        assert_eq!(chip8.program_counter, 0x202);
+        chip8.execute_cycle();
+        assert_eq!(chip8.V[0], 0x42);
+    }
+
+    #[test]
+    fn routine_that_does_not_return() {
+        let mut chip8 = vip(&[0x30, 0x10]);
+        chip8.execute_cycle();
+        assert_eq!(
+            chip8.halt_reason,
+            Some(HaltReason::MachineCodeDidNotReturn { address: 0x210 })
+        );
+        assert!(!chip8.is_running());
+    }
+}
@@ -0,0 +1,111 @@
+use crate::Chip8;
+
+/// The colors of the VP-590 color board in RGBA, by their 3-bit code: bit 0 is red, bit 1 blue
+/// and bit 2 green.
+const COLORS: [[u8; 4]; 8] = [
+    [0, 0, 0, 255],
+    [255, 0, 0, 255],
+    [0, 0, 255, 255],
+    [255, 0, 255, 255],
+    [0, 255, 0, 255],
+    [255, 255, 0, 255],
+    [0, 255, 255, 255],
+    [255, 255, 255, 255],
+];
+
+/// The background colors in RGBA that the `02A0` instruction steps through, in order.
+const BACKGROUNDS: [[u8; 4]; 4] = [
+    [0, 0, 128, 255],
+    [0, 0, 0, 255],
+    [0, 128, 0, 255],
+    [128, 0, 0, 255],
+];
+
+/// The color RAM of the CHIP-8X: the background color and the foreground color of every zone of
+/// the display. Zones are 8 pixels wide and 1 pixel high.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub(crate) struct ColorMap {
+    /// The index of the background color in [`BACKGROUNDS`].
+    pub(crate) background: u8,
+    /// The color code of each zone, row by row, 8 zones per row.
+    pub(crate) zones: Vec<u8>,
+}
+
+impl Default for ColorMap {
+    /// Blue background and red foreground, like the VP-590 after a reset.
+    fn default() -> Self {
+        ColorMap {
+            background: 0,
+            zones: vec![1; 8 * 32],
+        }
+    }
+}
+
+impl ColorMap {
+    /// Get the RGBA color of the pixel at `x`, `y`.
+    #[inline]
+    pub(crate) fn color(&self, x: usize, y: usize, on: bool) -> [u8; 4] {
+        if on {
+            COLORS[self.zones[x / 8 + y * 8] as usize & 0b111]
+        } else {
+            BACKGROUNDS[self.background as usize % BACKGROUNDS.len()]
+        }
+    }
+
+    /// Set the color of the zones from column `x` to `x + width` and row `y` to `y + height`,
+    /// wrapping around the edges.
+    fn fill(&mut self, x: usize, width: usize, y: usize, height: usize, color: u8) {
+        for row in y..=y + height {
+            for column in x..=x + width {
+                self.zones[column % 8 + row % 32 * 8] = color & 0b111;
+            }
+        }
+    }
+}
+
+impl Chip8 {
+    /// Set the state of the second keypad of the CHIP-8X, read by the `ExF2` and `ExF5`
+    /// instructions. Ignored while a replay is played back.
+    #[inline]
+    pub fn set_keys2(&mut self, keys: [bool; 16]) {
+        if !self.is_playing_replay() {
+            self.keypad2 = keys;
+        }
+    }
+    /// Get the state of key `i` on the second keypad. For the inspector.
+    #[inline]
+    pub fn get_key2_state(&self, key: usize) -> bool {
+        self.keypad2[key]
+    }
+
+    /// `02A0` - Step the background color.
+    pub(crate) fn step_background(&mut self) {
+        self.color_map.background = (self.color_map.background + 1) % BACKGROUNDS.len() as u8;
+    }
+
+    /// `Bxy0` and `Bxyn` - Set the color of an area to Vy.
+    ///
+    /// With n = 0, the area is given in zones of 8x4 pixels: the low nibble of Vx is the first column
+    /// and the high nibble how many more columns it covers, and V(x + 1) is the same for rows. With
+    /// n > 0, the area is that of an 8xn sprite drawn at Vx, V(x + 1).
+    pub(crate) fn set_zone_color(&mut self, x: usize, y: usize, n: u8) {
+        let horizontal = self.V[x] as usize;
+        let vertical = self.V[(x + 1) & 0xF] as usize;
+        let color = self.V[y];
+        if n == 0 {
+            let first_row = (vertical & 0xF) * 4;
+            let extra_rows = (vertical >> 4) * 4 + 3;
+            self.color_map.fill(
+                horizontal & 0xF,
+                horizontal >> 4,
+                first_row,
+                extra_rows,
+                color,
+            );
+        } else {
+            self.color_map
+                .fill(horizontal / 8, 0, vertical, n as usize - 1, color);
+        }
+    }
+}
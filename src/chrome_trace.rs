@@ -0,0 +1,160 @@
+use std::fmt::Write;
+
+use crate::{FrameEventKind, FRAME_DURATION};
+
+/// An event recorded by a [`ChromeTrace`], timestamped in microseconds of emulated time.
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+enum RecordedEvent {
+    /// A frame was completed.
+    Frame { ts: f64, number: u64 },
+    /// An instruction was executed.
+    Instruction {
+        ts: f64,
+        dur: f64,
+        address: u16,
+        opcode: u16,
+    },
+    /// A draw, key read or sound change.
+    Marker { ts: f64, kind: FrameEventKind },
+    /// A subroutine was entered.
+    Call { ts: f64, target: u16 },
+    /// A subroutine returned.
+    Return { ts: f64 },
+}
+
+/// Records executed instructions, frames, draws, sound and subroutine calls for export in the
+/// Chrome trace event format, which can be explored in Perfetto or `chrome://tracing`.
+///
+/// Time is emulated time: every frame lasts 1/60 of a second and its cycles are spread evenly over it.
+#[derive(Debug, Default, Clone, PartialEq, PartialOrd)]
+pub struct ChromeTrace {
+    events: Vec<RecordedEvent>,
+    /// The number of frames completed since recording started.
+    frames: u64,
+}
+
+impl ChromeTrace {
+    /// The duration of a frame in microseconds.
+    fn frame_us() -> f64 {
+        FRAME_DURATION.as_nanos() as f64 / 1000.0
+    }
+
+    /// The start of a cycle of the current frame. `cycle` starts at 1.
+    fn timestamp(&self, cycle: u32, cycles_per_frame: u32) -> f64 {
+        let cycle_us = Self::frame_us() / cycles_per_frame.max(1) as f64;
+        self.frames as f64 * Self::frame_us() + cycle.saturating_sub(1) as f64 * cycle_us
+    }
+
+    /// Record an executed instruction and the subroutine call or return it made.
+    pub(crate) fn instruction(
+        &mut self,
+        cycle: u32,
+        cycles_per_frame: u32,
+        address: u16,
+        opcode: u16,
+    ) {
+        let ts = self.timestamp(cycle, cycles_per_frame);
+        let dur = Self::frame_us() / cycles_per_frame.max(1) as f64;
+        self.events.push(RecordedEvent::Instruction {
+            ts,
+            dur,
+            address,
+            opcode,
+        });
+
+        if opcode & 0xF000 == 0x2000 {
+            self.events.push(RecordedEvent::Call {
+                ts: ts + dur,
+                target: opcode & 0x0FFF,
+            });
+        } else if opcode == 0x00EE {
+            self.events.push(RecordedEvent::Return { ts: ts + dur });
+        }
+    }
+
+    /// Record a draw, key read or sound change.
+    pub(crate) fn marker(&mut self, cycle: u32, cycles_per_frame: u32, kind: FrameEventKind) {
+        let ts = self.timestamp(cycle, cycles_per_frame);
+        self.events.push(RecordedEvent::Marker { ts, kind });
+    }
+
+    /// Record the end of a frame.
+    pub(crate) fn end_frame(&mut self) {
+        self.events.push(RecordedEvent::Frame {
+            ts: self.frames as f64 * Self::frame_us(),
+            number: self.frames,
+        });
+        self.frames += 1;
+    }
+
+    /// Get the number of frames recorded.
+    #[inline]
+    pub fn frames(&self) -> u64 {
+        self.frames
+    }
+
+    /// Write the recording as Chrome trace event JSON.
+    ///
+    /// Frames, instructions with subroutine spans and draw/key/sound markers each get their own track.
+    pub fn to_json(&self) -> String {
+        const FRAMES: u32 = 1;
+        const INSTRUCTIONS: u32 = 2;
+        const MARKERS: u32 = 3;
+
+        let mut json = String::from("{\"traceEvents\":[\n");
+        for (tid, name) in [
+            (FRAMES, "Frames"),
+            (INSTRUCTIONS, "Instructions"),
+            (MARKERS, "Draws, key reads and sound"),
+        ] {
+            let _ = writeln!(
+                json,
+                "{{\"name\":\"thread_name\",\"ph\":\"M\",\"pid\":1,\"tid\":{tid},\"args\":{{\"name\":\"{name}\"}}}},"
+            );
+        }
+
+        for event in &self.events {
+            let _ = match *event {
+                RecordedEvent::Frame { ts, number } => writeln!(
+                    json,
+                    "{{\"name\":\"Frame {number}\",\"ph\":\"X\",\"ts\":{ts:.3},\"dur\":{:.3},\"pid\":1,\"tid\":{FRAMES}}},",
+                    Self::frame_us()
+                ),
+                RecordedEvent::Instruction {
+                    ts,
+                    dur,
+                    address,
+                    opcode,
+                } => writeln!(
+                    json,
+                    "{{\"name\":\"{opcode:04X}\",\"ph\":\"X\",\"ts\":{ts:.3},\"dur\":{dur:.3},\"pid\":1,\"tid\":{INSTRUCTIONS},\"args\":{{\"address\":\"{address:04X}\"}}}},"
+                ),
+                RecordedEvent::Marker { ts, kind } => writeln!(
+                    json,
+                    "{{\"name\":\"{}\",\"ph\":\"i\",\"s\":\"t\",\"ts\":{ts:.3},\"pid\":1,\"tid\":{MARKERS}}},",
+                    match kind {
+                        FrameEventKind::Draw => "Draw",
+                        FrameEventKind::KeyRead => "Key read",
+                        FrameEventKind::Sound => "Sound",
+                    }
+                ),
+                RecordedEvent::Call { ts, target } => writeln!(
+                    json,
+                    "{{\"name\":\"sub_{target:03X}\",\"ph\":\"B\",\"ts\":{ts:.3},\"pid\":1,\"tid\":{INSTRUCTIONS}}},"
+                ),
+                RecordedEvent::Return { ts } => writeln!(
+                    json,
+                    "{{\"ph\":\"E\",\"ts\":{ts:.3},\"pid\":1,\"tid\":{INSTRUCTIONS}}},"
+                ),
+            };
+        }
+
+        // JSON does not allow a trailing comma
+        if json.ends_with(",\n") {
+            json.truncate(json.len() - 2);
+            json.push('\n');
+        }
+        json += "],\"displayTimeUnit\":\"ms\"}\n";
+        json
+    }
+}
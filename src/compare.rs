@@ -0,0 +1,104 @@
+use crate::Chip8;
+
+/// A second interpreter that runs the same ROM with the same input as the main one, usually with a
+/// different variant or quirks, to find which configuration a misbehaving ROM depends on.
+///
+/// Controlled by the [`EmulatorDriver`](crate::EmulatorDriver), which runs it in lockstep with the
+/// main interpreter.
+#[derive(Debug, Clone, PartialEq, PartialOrd)]
+pub struct Comparison {
+    /// The second interpreter.
+    interpreter: Chip8,
+    /// The number of frames compared since the comparison was (re)started.
+    frames: u64,
+    /// The first frame in which the displays or halt states of the interpreters differed.
+    divergence: Option<u64>,
+}
+
+impl Comparison {
+    /// Compare the main interpreter against `interpreter`. Both are restarted with the last loaded
+    /// ROM when the driver resets.
+    pub fn new(interpreter: Chip8) -> Comparison {
+        Comparison {
+            interpreter,
+            frames: 0,
+            divergence: None,
+        }
+    }
+
+    /// Get the second interpreter.
+    #[inline]
+    pub const fn interpreter(&self) -> &Chip8 {
+        &self.interpreter
+    }
+    /// Get the number of frames compared.
+    #[inline]
+    pub const fn frames(&self) -> u64 {
+        self.frames
+    }
+    /// Get the first frame in which the interpreters differed, starting at 1.
+    #[inline]
+    pub const fn divergence(&self) -> Option<u64> {
+        self.divergence
+    }
+
+    /// Reset the second interpreter and load a ROM, forgetting the divergence.
+    pub(crate) fn restart(&mut self, rom: &[u8]) {
+        self.interpreter.reset_and_load(rom);
+        if self.interpreter.halt_reason.is_none() {
+            self.interpreter.start();
+        }
+        self.frames = 0;
+        self.divergence = None;
+    }
+
+    /// Give the second interpreter the same input as `main`.
+    fn copy_input(&mut self, main: &Chip8) {
+        self.interpreter.set_keys(main.keypad);
+        self.interpreter.set_keys2(main.keypad2);
+    }
+
+    /// Run the rest of the second interpreter's frame and compare it to `main`, which just completed
+    /// a frame.
+    pub(crate) fn run_frame(&mut self, main: &Chip8) {
+        self.copy_input(main);
+        let chip8 = &mut self.interpreter;
+        while chip8.is_running() && !chip8.is_frame_complete() {
+            chip8.execute_cycle();
+        }
+        if chip8.is_running() {
+            chip8.tick_frame();
+        }
+        self.compare(main);
+    }
+
+    /// Execute one cycle of the second interpreter. If `main` completed a frame, they are compared.
+    pub(crate) fn step_cycle(&mut self, main: &Chip8, frame_completed: bool) {
+        self.copy_input(main);
+        if self.interpreter.is_running() {
+            self.interpreter.step_cycle();
+        }
+        if frame_completed {
+            self.compare(main);
+        }
+    }
+
+    /// Report a released key to the second interpreter, which may resolve the `Fx0A` instruction.
+    pub(crate) fn release_key(&mut self, key: u8) {
+        self.interpreter.key_released(key);
+    }
+
+    /// Count a frame and remember it if the interpreters differ.
+    fn compare(&mut self, main: &Chip8) {
+        self.frames += 1;
+        if self.divergence.is_none() {
+            let a = main.observe();
+            let b = self.interpreter.observe();
+            if a.pixels != b.pixels
+                || main.halt_reason.is_some() != self.interpreter.halt_reason.is_some()
+            {
+                self.divergence = Some(self.frames);
+            }
+        }
+    }
+}
@@ -0,0 +1,136 @@
+use std::fmt::Write;
+
+use crate::{Chip8, StateError};
+
+/// The first line of every crash dump.
+const HEADER: &str = "E-CHIP crash dump";
+/// The line before the hex encoded save state.
+const STATE_HEADER: &str = "State:";
+
+impl Chip8 {
+    /// Describe the machine in a single text file that can be attached to bug reports: the halt
+    /// message, the SHA-1 hash of `rom`, the variant, quirks and speed, the last executed
+    /// instructions and the full save state, which [`Chip8::load_crash_dump`] restores.
+    pub fn crash_dump(&self, rom: &[u8]) -> String {
+        let mut dump = format!("{HEADER}\n");
+        // Writing to a String can't fail
+        let _ = writeln!(
+            dump,
+            "Halted: {}",
+            self.halt_reason
+                .as_ref()
+                .map_or("no".to_string(), ToString::to_string)
+        );
+        let _ = writeln!(dump, "ROM: {} bytes, SHA-1 {}", rom.len(), sha1_hex(rom));
+        let _ = writeln!(dump, "Variant: {:?}", self.variant);
+        let _ = writeln!(dump, "Quirks: {:?}", self.quirks);
+        if self.vip_speed {
+            let _ = writeln!(dump, "Speed: COSMAC VIP");
+        } else {
+            let _ = writeln!(
+                dump,
+                "Speed: {} instructions per frame",
+                self.execution_speed
+            );
+        }
+        let _ = writeln!(dump, "Frame: {}", self.frame_count);
+
+        // The instruction that halted is the last one
+        dump += "Trace:\n";
+        for entry in self.trace.iter() {
+            let _ = write!(dump, "{:04X}  {:04X} ", entry.address, entry.opcode);
+            for (register, before, after) in entry.changed_registers() {
+                let _ = write!(dump, " V{:X}:{:02X}->{:02X}", register, before, after);
+            }
+            if entry.I_before != entry.I_after {
+                let _ = write!(dump, " I:{:04X}->{:04X}", entry.I_before, entry.I_after);
+            }
+            dump += "\n";
+        }
+
+        let _ = writeln!(dump, "{STATE_HEADER}");
+        for line in self.save_state().chunks(32) {
+            for byte in line {
+                let _ = write!(dump, "{:02X}", byte);
+            }
+            dump += "\n";
+        }
+        dump
+    }
+
+    /// Restore the machine from the save state in a crash dump made by [`Chip8::crash_dump`]. See
+    /// [`Chip8::load_state`].
+    pub fn load_crash_dump(&mut self, dump: &str) -> Result<(), StateError> {
+        if !dump.starts_with(HEADER) {
+            return Err(StateError::NotAState);
+        }
+        let hex: String = dump
+            .lines()
+            .skip_while(|&line| line != STATE_HEADER)
+            .skip(1)
+            .collect();
+        if !hex.len().is_multiple_of(2) {
+            return Err(StateError::Truncated);
+        }
+        let state = hex
+            .as_bytes()
+            .chunks(2)
+            .map(|pair| {
+                std::str::from_utf8(pair)
+                    .ok()
+                    .and_then(|pair| u8::from_str_radix(pair, 16).ok())
+            })
+            .collect::<Option<Vec<_>>>()
+            .ok_or(StateError::Invalid("crash dump state"))?;
+        self.load_state(&state)
+    }
+}
+
+/// Hash data with SHA-1 in lowercase hex, the hash ROM databases identify ROMs by.
+pub fn sha1_hex(data: &[u8]) -> String {
+    let mut h: [u32; 5] = [0x67452301, 0xEFCDAB89, 0x98BADCFE, 0x10325476, 0xC3D2E1F0];
+
+    // Pad with a 1 bit, zeros and the length in bits to a multiple of 64 bytes
+    let mut message = data.to_vec();
+    message.push(0x80);
+    while message.len() % 64 != 56 {
+        message.push(0);
+    }
+    message.extend((data.len() as u64 * 8).to_be_bytes());
+
+    for block in message.chunks(64) {
+        let mut w = [0u32; 80];
+        for (i, word) in block.chunks(4).enumerate() {
+            w[i] = u32::from_be_bytes(word.try_into().unwrap());
+        }
+        for i in 16..80 {
+            w[i] = (w[i - 3] ^ w[i - 8] ^ w[i - 14] ^ w[i - 16]).rotate_left(1);
+        }
+
+        let [mut a, mut b, mut c, mut d, mut e] = h;
+        for (i, &word) in w.iter().enumerate() {
+            let (f, k) = match i {
+                0..=19 => ((b & c) | (!b & d), 0x5A827999),
+                20..=39 => (b ^ c ^ d, 0x6ED9EBA1),
+                40..=59 => ((b & c) | (b & d) | (c & d), 0x8F1BBCDC),
+                _ => (b ^ c ^ d, 0xCA62C1D6),
+            };
+            let temp = a
+                .rotate_left(5)
+                .wrapping_add(f)
+                .wrapping_add(e)
+                .wrapping_add(k)
+                .wrapping_add(word);
+            e = d;
+            d = c;
+            c = b.rotate_left(30);
+            b = a;
+            a = temp;
+        }
+        for (h, value) in h.iter_mut().zip([a, b, c, d, e]) {
+            *h = h.wrapping_add(value);
+        }
+    }
+
+    h.iter().map(|word| format!("{word:08x}")).collect()
+}
@@ -0,0 +1,267 @@
+use std::{
+    fs,
+    io::Read,
+    path::PathBuf,
+    sync::mpsc::{self, Receiver, TryRecvError},
+    thread,
+};
+
+use e_chip::{sha1_hex, Chip8, FontSet, IndexIncrement, Variant, VblankWait, MAX_EXECUTION_SPEED};
+use egui::Color32;
+use serde_json::Value;
+
+use crate::gui::palette;
+
+/// Where the files of the CHIP-8 database are stored.
+const DATABASE_URL: &str =
+    "https://raw.githubusercontent.com/chip-8/chip-8-database/master/database";
+/// The files of the database: the index of the program of every ROM hash, the programs with their
+/// ROMs, and the platforms with their default quirks and speed.
+const DATABASE_FILES: [&str; 3] = ["sha1-hashes.json", "programs.json", "platforms.json"];
+
+/// What the CHIP-8 database knows about a ROM.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RomInfo {
+    pub title: String,
+    /// The variant of the platform the ROM runs on.
+    variant: Variant,
+    /// The quirks of the platform, named like in the database.
+    platform_quirks: Value,
+    /// The quirks the ROM needs that differ from those of the platform.
+    rom_quirks: Value,
+    /// The cycles per frame the ROM should run at.
+    tickrate: Option<u64>,
+    /// The display colors the ROM is meant to be played with.
+    pub colors: Option<[Color32; 4]>,
+    /// The font the ROM expects. `None` if E-CHIP does not have it.
+    pub font_set: Option<FontSet>,
+}
+
+impl RomInfo {
+    /// Create an interpreter with the variant, quirks, speed and font of the ROM.
+    pub fn configure(&self) -> Chip8 {
+        let mut chip8 = match self.variant {
+            Variant::VIPHYBRID => Chip8::vip_hybrid(),
+            Variant::CHIP8X => Chip8::chip8x(),
+            Variant::CHIP48 => Chip8::chip48(),
+            Variant::SCHIP10 => Chip8::super_chip1_0(),
+            Variant::SCHIP11 => Chip8::super_chip1_1(),
+            Variant::XOCHIP => Chip8::xo_chip(),
+            _ => Chip8::chip8(),
+        };
+        // The quirks of the ROM take precedence over those of the platform, and the quirks the
+        // database does not know keep the ones of the variant
+        let flag = |name: &str| {
+            self.rom_quirks[name]
+                .as_bool()
+                .or(self.platform_quirks[name].as_bool())
+        };
+        let quirks = &mut chip8.quirks;
+        if let Some(logic) = flag("logic") {
+            quirks.bitwise_reset_vf = logic;
+        }
+        if let Some(shift) = flag("shift") {
+            quirks.direct_shifting = shift;
+        }
+        match (flag("memoryLeaveIUnchanged"), flag("memoryIncrementByX")) {
+            (Some(true), _) => quirks.save_load_increment = IndexIncrement::Unchanged,
+            (_, Some(true)) => quirks.save_load_increment = IndexIncrement::X,
+            (None, None) => {}
+            _ => quirks.save_load_increment = IndexIncrement::XPlusOne,
+        }
+        if let Some(jump) = flag("jump") {
+            quirks.jump_to_x = jump;
+        }
+        match flag("vblank") {
+            // SUPER-CHIP only waits in lowres mode
+            Some(true) if quirks.wait_for_vblank == VblankWait::Never => {
+                quirks.wait_for_vblank = VblankWait::Always
+            }
+            Some(false) => quirks.wait_for_vblank = VblankWait::Never,
+            _ => {}
+        }
+        if let Some(wrap) = flag("wrap") {
+            quirks.edge_clipping = !wrap;
+        }
+        if let Some(tickrate) = self.tickrate {
+            chip8.execution_speed = tickrate.clamp(1, MAX_EXECUTION_SPEED as u64) as u32;
+        }
+        if let Some(font_set) = self.font_set {
+            chip8.set_font_set(font_set);
+        }
+        chip8
+    }
+}
+
+/// The CHIP-8 database.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Database {
+    hashes: Value,
+    programs: Value,
+    platforms: Value,
+}
+
+impl Database {
+    /// Look up a ROM by its SHA-1. `None` if the database does not know it, or only knows it for
+    /// platforms E-CHIP does not support.
+    pub fn lookup(&self, rom: &[u8]) -> Option<RomInfo> {
+        let hash = sha1_hex(rom);
+        let program = &self.programs[self.hashes[&hash].as_u64()? as usize];
+        let info = &program["roms"][&hash];
+        let (platform, variant) = info["platforms"]
+            .as_array()?
+            .iter()
+            .filter_map(Value::as_str)
+            .find_map(|platform| Some((platform, platform_variant(platform)?)))?;
+        let platform_info = self
+            .platforms
+            .as_array()?
+            .iter()
+            .find(|entry| entry["id"] == platform)
+            .unwrap_or(&Value::Null);
+        Some(RomInfo {
+            title: program["title"].as_str().unwrap_or_default().to_string(),
+            variant,
+            platform_quirks: platform_info["quirks"].clone(),
+            rom_quirks: info["quirkyPlatforms"][platform].clone(),
+            tickrate: info["tickrate"]
+                .as_u64()
+                .or(platform_info["defaultTickrate"].as_u64()),
+            colors: info["colors"]["pixels"].as_array().and_then(|pixels| {
+                let pixels: Vec<Color32> = pixels
+                    .iter()
+                    .filter_map(|pixel| parse_color(pixel.as_str()?))
+                    .collect();
+                match pixels[..] {
+                    [off, on] => Some(palette(off, on)),
+                    [off, first, second, both, ..] => Some([off, first, second, both]),
+                    _ => None,
+                }
+            }),
+            font_set: match info["fontStyle"].as_str() {
+                Some("octo") => Some(FontSet::Octo),
+                Some("vip") => Some(FontSet::Vip),
+                Some("dream6800") => Some(FontSet::Dream6800),
+                Some("eti660") => Some(FontSet::Eti660),
+                _ => None,
+            },
+        })
+    }
+}
+
+/// Get the variant of a platform of the database. `None` for platforms E-CHIP does not support.
+fn platform_variant(platform: &str) -> Option<Variant> {
+    match platform {
+        "originalChip8" | "modernChip8" => Some(Variant::CHIP8),
+        "hybridVIP" => Some(Variant::VIPHYBRID),
+        "chip8x" => Some(Variant::CHIP8X),
+        "chip48" => Some(Variant::CHIP48),
+        "superchip1" => Some(Variant::SCHIP10),
+        "superchip" => Some(Variant::SCHIP11),
+        "xochip" => Some(Variant::XOCHIP),
+        _ => None,
+    }
+}
+
+/// Read a color like `#FF8800`.
+fn parse_color(text: &str) -> Option<Color32> {
+    let digits = text.strip_prefix('#')?;
+    let value = u32::from_str_radix(digits, 16)
+        .ok()
+        .filter(|_| digits.len() == 6)?;
+    let [_, r, g, b] = value.to_be_bytes();
+    Some(Color32::from_rgb(r, g, b))
+}
+
+/// Downloads the CHIP-8 database in the background and looks up ROMs in it.
+#[derive(Debug, Default)]
+pub struct RomDatabase {
+    /// The database, or why it could not be downloaded.
+    pub database: Option<Result<Database, String>>,
+    /// Receives the database while it is downloading.
+    loading: Option<Receiver<Result<Database, String>>>,
+}
+
+impl RomDatabase {
+    /// Start downloading the database if it was not downloaded yet.
+    pub fn load(&mut self) {
+        if self.database.is_some() || self.loading.is_some() {
+            return;
+        }
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            let _ = sender.send(fetch_database());
+        });
+        self.loading = Some(receiver);
+    }
+
+    /// Check if the database is downloading.
+    pub fn is_busy(&self) -> bool {
+        self.loading.is_some()
+    }
+
+    /// Collect the database once it is downloaded.
+    pub fn poll(&mut self) {
+        let Some(loading) = &self.loading else {
+            return;
+        };
+        match loading.try_recv() {
+            Ok(database) => self.database = Some(database),
+            Err(TryRecvError::Empty) => return,
+            Err(TryRecvError::Disconnected) => {
+                self.database = Some(Err("the download failed".to_string()))
+            }
+        }
+        self.loading = None;
+    }
+
+    /// Look up a ROM. `None` if it is unknown or the database is not downloaded.
+    pub fn lookup(&self, rom: &[u8]) -> Option<RomInfo> {
+        self.database.as_ref()?.as_ref().ok()?.lookup(rom)
+    }
+}
+
+/// Get the folder the database is kept in between launches.
+fn cache_folder() -> Option<PathBuf> {
+    Some(dirs::cache_dir()?.join("e-chip").join("chip-8-database"))
+}
+
+/// Download a file of the database and keep it in the cache, or read the cached one if it can't
+/// be downloaded.
+fn fetch(name: &str) -> Result<Value, String> {
+    let cached = cache_folder().map(|folder| folder.join(name));
+    let bytes = ureq::get(&format!("{DATABASE_URL}/{name}"))
+        .call()
+        .map_err(|e| e.to_string())
+        .and_then(|response| {
+            let mut bytes = Vec::new();
+            response
+                .into_reader()
+                .read_to_end(&mut bytes)
+                .map_err(|e| e.to_string())?;
+            Ok(bytes)
+        });
+    let bytes = match (bytes, &cached) {
+        (Ok(bytes), Some(path)) => {
+            if let Some(folder) = path.parent() {
+                let _ = fs::create_dir_all(folder);
+            }
+            let _ = fs::write(path, &bytes);
+            bytes
+        }
+        (Ok(bytes), None) => bytes,
+        (Err(e), Some(path)) => fs::read(path).map_err(|_| e)?,
+        (Err(e), None) => return Err(e),
+    };
+    serde_json::from_slice(&bytes).map_err(|e| format!("{name} is invalid: {e}"))
+}
+
+/// Download the database.
+fn fetch_database() -> Result<Database, String> {
+    let [hashes, programs, platforms] = DATABASE_FILES.map(fetch);
+    Ok(Database {
+        hashes: hashes?,
+        programs: programs?,
+        platforms: platforms?,
+    })
+}
@@ -0,0 +1,620 @@
+use std::fmt;
+
+use crate::{Chip8, IndexIncrement, Quirks, Symbols, Variant};
+
+/// An instruction of a disassembly. See [`disassemble`].
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct DisassembledInstruction {
+    /// The address of the instruction.
+    pub address: u16,
+    /// The opcode. A lone byte at the end of the disassembled bytes is the high byte.
+    pub opcode: u16,
+    /// The address in the two bytes after `F000`, which belong to the instruction.
+    pub long_address: Option<u16>,
+    /// The assembly name of the instruction, like `LD` or `DRW`, or `DW` and `DB` for data.
+    pub mnemonic: &'static str,
+    /// The operands, like `V1, #24`.
+    pub operands: String,
+    /// What the instruction does, like `Skip if Vx == nn`. See [`explain_instruction`].
+    pub explanation: &'static str,
+    /// The address a jump or call goes to or `I` is set to.
+    pub target: Option<u16>,
+    /// The name of the address of the instruction. See [`DisassembledInstruction::name_symbols`].
+    pub label: Option<String>,
+}
+
+impl DisassembledInstruction {
+    /// Get how many bytes the instruction takes.
+    #[inline]
+    pub fn size(&self) -> u16 {
+        if self.long_address.is_some() {
+            4
+        } else {
+            2
+        }
+    }
+
+    /// Name the instruction and its target with `symbols`, so `CALL #20A` becomes `CALL draw`.
+    pub fn name_symbols(&mut self, symbols: &Symbols) {
+        self.label = symbols.name(self.address).map(str::to_string);
+        let Some(target) = self.target else {
+            return;
+        };
+        if let Some(name) = symbols.name(target) {
+            let address = match self.long_address {
+                Some(_) => format!("#{target:04X}"),
+                None => format!("#{target:03X}"),
+            };
+            self.operands = self.operands.replace(&address, name);
+        }
+    }
+}
+
+impl fmt::Display for DisassembledInstruction {
+    /// A line of a listing: `0200  6124       LD V1, #24         ; Vx = nn`, after a line with the
+    /// label if there is one.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(label) = &self.label {
+            writeln!(f, "{label}:")?;
+        }
+        let bytes = match self.long_address {
+            Some(address) => format!("{:04X} {:04X}", self.opcode, address),
+            None if self.mnemonic == "DB" => format!("{:02X}", self.opcode >> 8),
+            None => format!("{:04X}", self.opcode),
+        };
+        let instruction = format!("{} {}", self.mnemonic, self.operands);
+        write!(
+            f,
+            "{:04X}  {:<9}  {:<18} ; {}",
+            self.address,
+            bytes,
+            instruction.trim_end(),
+            self.explanation
+        )
+    }
+}
+
+/// Disassemble `bytes` loaded at `address` two bytes at a time, like the interpreter reads them.
+/// Sprites and other data are disassembled as instructions too, with `DW` for opcodes that are not
+/// instructions on `variant`. A lone byte at the end becomes `DB`.
+pub fn disassemble(
+    bytes: &[u8],
+    address: u16,
+    quirks: &Quirks,
+    variant: &Variant,
+) -> Vec<DisassembledInstruction> {
+    let mut instructions = Vec::new();
+    let mut offset = 0;
+    while offset < bytes.len() {
+        let address = address.wrapping_add(offset as u16);
+        let Some(&low) = bytes.get(offset + 1) else {
+            let byte = bytes[offset];
+            instructions.push(DisassembledInstruction {
+                address,
+                opcode: (byte as u16) << 8,
+                long_address: None,
+                mnemonic: "DB",
+                operands: format!("#{byte:02X}"),
+                explanation: "Data",
+                target: None,
+                label: None,
+            });
+            break;
+        };
+        let opcode = (bytes[offset] as u16) << 8 | low as u16;
+        let long_address = (opcode == 0xF000 && variant.supports_xochip())
+            .then(|| bytes.get(offset + 2..offset + 4))
+            .flatten()
+            .map(|word| (word[0] as u16) << 8 | word[1] as u16);
+        let (mnemonic, operands) = match long_address {
+            Some(long_address) => ("LD", format!("I, #{long_address:04X}")),
+            None => mnemonic(opcode, quirks, variant),
+        };
+        let target = match (opcode >> 12, mnemonic) {
+            _ if long_address.is_some() => long_address,
+            (0x0, "SYS") | (0x1 | 0x2 | 0xA, _) | (0xB, "JP") => Some(opcode & 0x0FFF),
+            _ => None,
+        };
+        let instruction = DisassembledInstruction {
+            address,
+            opcode,
+            long_address,
+            mnemonic,
+            operands,
+            explanation: if mnemonic == "DW" {
+                "Data"
+            } else {
+                explain_instruction(opcode, quirks, variant).1
+            },
+            target,
+            label: None,
+        };
+        offset += instruction.size() as usize;
+        instructions.push(instruction);
+    }
+    instructions
+}
+
+/// Get the assembly name and operands of an opcode, or `DW` and the opcode if it is not an
+/// instruction on `variant`.
+fn mnemonic(opcode: u16, quirks: &Quirks, variant: &Variant) -> (&'static str, String) {
+    let x = (opcode & 0x0F00) >> 8;
+    let y = (opcode & 0x00F0) >> 4;
+    let n = opcode & 0x000F;
+    let nn = opcode & 0x00FF;
+    let nnn = opcode & 0x0FFF;
+    let vx = format!("V{x:X}");
+    let vx_vy = format!("V{x:X}, V{y:X}");
+    let vx_nn = format!("V{x:X}, #{nn:02X}");
+
+    let data = ("DW", format!("#{opcode:04X}"));
+    let (mnemonic, operands) = match opcode >> 12 {
+        0x0 => match opcode {
+            0x00C0..=0x00CF if variant.supports_scrolling() => ("SCD", n.to_string()),
+            0x00D0..=0x00DF if variant.supports_xochip() => ("SCU", n.to_string()),
+            0x00E0 => ("CLS", String::new()),
+            0x00EE => ("RET", String::new()),
+            0x00FB if variant.supports_scrolling() => ("SCR", String::new()),
+            0x00FC if variant.supports_scrolling() => ("SCL", String::new()),
+            0x00FD if variant.supports_schip() => ("EXIT", String::new()),
+            0x00FE if variant.supports_schip() => ("LOW", String::new()),
+            0x00FF if variant.supports_schip() => ("HIGH", String::new()),
+            0x02A0 if *variant == Variant::CHIP8X => ("BGSTEP", String::new()),
+            _ => ("SYS", format!("#{nnn:03X}")),
+        },
+        0x1 => ("JP", format!("#{nnn:03X}")),
+        0x2 => ("CALL", format!("#{nnn:03X}")),
+        0x3 => ("SE", vx_nn),
+        0x4 => ("SNE", vx_nn),
+        0x5 => match n {
+            0x0 => ("SE", vx_vy),
+            0x1 if *variant == Variant::CHIP8X => ("ADDN", vx_vy),
+            0x2 if variant.supports_xochip() => ("SAVE", format!("V{x:X} - V{y:X}")),
+            0x3 if variant.supports_xochip() => ("LOAD", format!("V{x:X} - V{y:X}")),
+            _ => return data,
+        },
+        0x6 => ("LD", vx_nn),
+        0x7 => ("ADD", vx_nn),
+        0x8 => match n {
+            0x0 => ("LD", vx_vy),
+            0x1 => ("OR", vx_vy),
+            0x2 => ("AND", vx_vy),
+            0x3 => ("XOR", vx_vy),
+            0x4 => ("ADD", vx_vy),
+            0x5 => ("SUB", vx_vy),
+            0x6 => ("SHR", vx_vy),
+            0x7 => ("SUBN", vx_vy),
+            0xE => ("SHL", vx_vy),
+            _ => return data,
+        },
+        0x9 if n == 0 => ("SNE", vx_vy),
+        0xA => ("LD", format!("I, #{nnn:03X}")),
+        0xB if *variant == Variant::CHIP8X => ("COL", format!("V{x:X}, V{y:X}, {n}")),
+        0xB if quirks.jump_to_x => ("JP", format!("V{x:X}, #{nnn:03X}")),
+        0xB => ("JP", format!("V0, #{nnn:03X}")),
+        0xC => ("RND", vx_nn),
+        0xD => ("DRW", format!("V{x:X}, V{y:X}, {n}")),
+        0xE => match nn {
+            0x9E => ("SKP", vx),
+            0xA1 => ("SKNP", vx),
+            0xF2 if *variant == Variant::CHIP8X => ("SKP2", vx),
+            0xF5 if *variant == Variant::CHIP8X => ("SKNP2", vx),
+            _ => return data,
+        },
+        0xF => match nn {
+            0x01 if variant.supports_xochip() => ("PLANE", x.to_string()),
+            0x02 if variant.supports_xochip() && x == 0 => ("AUDIO", String::new()),
+            0x07 => ("LD", format!("{vx}, DT")),
+            0x0A => ("LD", format!("{vx}, K")),
+            0x15 => ("LD", format!("DT, {vx}")),
+            0x18 => ("LD", format!("ST, {vx}")),
+            0x1E => ("ADD", format!("I, {vx}")),
+            0x29 => ("LD", format!("F, {vx}")),
+            0x30 if variant.supports_schip() => ("LD", format!("HF, {vx}")),
+            0x33 => ("LD", format!("B, {vx}")),
+            0x3A if variant.supports_xochip() => ("PITCH", vx),
+            0x55 => ("LD", format!("[I], {vx}")),
+            0x65 => ("LD", format!("{vx}, [I]")),
+            0x75 if variant.supports_schip() => ("LD", format!("R, {vx}")),
+            0x85 if variant.supports_schip() => ("LD", format!("{vx}, R")),
+            _ => return data,
+        },
+        _ => return data,
+    };
+    (mnemonic, operands)
+}
+
+impl Chip8 {
+    /// Disassemble the program in memory, see [`Chip8::program_in_memory`], with the current quirks
+    /// and variant, and name addresses with the symbols.
+    pub fn disassemble_program(&self) -> Vec<DisassembledInstruction> {
+        let mut instructions = disassemble(
+            self.program_in_memory(),
+            self.start_address,
+            &self.quirks,
+            &self.variant,
+        );
+        for instruction in &mut instructions {
+            instruction.name_symbols(&self.symbols);
+        }
+        instructions
+    }
+
+    /// Disassemble up to `count` instructions of memory before `address` and `count` from it on,
+    /// with the current quirks and variant, and name addresses with the symbols. The instructions
+    /// before `address` are read two bytes at a time from an address that lines up with it, so the
+    /// instruction at `address` is always in the listing.
+    pub fn disassemble_around(&self, address: u16, count: usize) -> Vec<DisassembledInstruction> {
+        let ram = &self.memory.ram;
+        let address = (address as usize).min(ram.len());
+        let start = address - (address / 2).min(count) * 2;
+        let end = (address + count * 2).min(ram.len());
+        let mut instructions = disassemble(
+            &ram[start..address],
+            start as u16,
+            &self.quirks,
+            &self.variant,
+        );
+        instructions.extend(disassemble(
+            &ram[address..end],
+            address as u16,
+            &self.quirks,
+            &self.variant,
+        ));
+        for instruction in &mut instructions {
+            instruction.name_symbols(&self.symbols);
+        }
+        instructions
+    }
+}
+
+/// Break down an opcode into a generic pattern and explanation, taking quirks and variant into account.  
+///
+/// For example, when given the opcode `3124`, the function will return `("3xnn", "Skip if Vx == nn")`
+#[inline]
+pub fn explain_instruction(
+    opcode: u16,
+    quirks: &Quirks,
+    variant: &Variant,
+) -> (&'static str, &'static str) {
+    let unknown = ("????", "Illegal instruction");
+    match opcode >> 12 {
+        0x0 => {
+            if variant.supports_scrolling() && opcode & 0xFFF0 == 0x00C0 {
+                ("00Cn", "Scroll down by n pixels")
+            } else if variant.supports_xochip() && opcode & 0xFFF0 == 0x00D0 {
+                ("00Dn", "Scroll up by n pixels")
+            } else {
+                match opcode {
+                    0x0000 => ("0000", "Empty (Stops emulator)"),
+                    0x00E0 => ("00E0", "Clear screen"),
+                    0x00EE => ("00EE", "Return from subroutine"),
+                    0x00FB if variant.supports_scrolling() => ("00FB", "Scroll right by 4 pixels"),
+                    0x00FC if variant.supports_scrolling() => ("00FC", "Scroll left by 4 pixels"),
+                    0x00FD if variant.supports_schip() => ("00FD", "Exit the interpreter"),
+                    0x00FE if variant.supports_schip() => ("00FE", "Disable highres mode"),
+                    0x00FF if variant.supports_schip() => ("00FF", "Enable highres mode"),
+                    0x02A0 if *variant == Variant::CHIP8X => ("02A0", "Step the background color"),
+                    _ if *variant == Variant::VIPHYBRID => {
+                        ("0nnn", "Run machine code routine at nnn")
+                    }
+                    _ => ("0nnn", "Machine code routine"),
+                }
+            }
+        }
+        0x1 => ("1nnn", "Jump to nnn"),
+        0x2 => ("2nnn", "Call subroutine at nnn"),
+        0x3 => ("3xnn", "Skip if Vx == nn"),
+        0x4 => ("4xnn", "Skip if Vx != nn"),
+        0x5 if variant.supports_xochip() && opcode & 0x000F == 2 => {
+            ("5xy2", "Write Vx to Vy (I unchanged)")
+        }
+        0x5 if variant.supports_xochip() && opcode & 0x000F == 3 => {
+            ("5xy3", "Read Vx to Vy (I unchanged)")
+        }
+        0x5 if *variant == Variant::CHIP8X && opcode & 0x000F == 1 => {
+            ("5xy1", "Vx = Vx + Vy per nibble (3 bits each)")
+        }
+        0x5 => ("5xy0", "Skip if Vx == Vy"),
+        0x6 => ("6xnn", "Vx = nn"),
+        0x7 => ("7xnn", "Vx = Vx + nn"),
+        0x8 => match opcode & 0x000F {
+            0x0 => ("8xy0", "Vx = Vy"),
+            0x1 if quirks.bitwise_reset_vf => ("8xy1", "Vx = Vx OR Vy (VF = 0)"),
+            0x1 => ("8xy1", "Vx = Vx OR Vy"),
+            0x2 if quirks.bitwise_reset_vf => ("8xy2", "Vx = Vx AND Vy (VF = 0)"),
+            0x2 => ("8xy2", "Vx = Vx AND Vy"),
+            0x3 if quirks.bitwise_reset_vf => ("8xy3", "Vx = Vx XOR Vy (VF = 0)"),
+            0x3 => ("8xy3", "Vx = Vx XOR Vy"),
+            0x4 => ("8xy4", "Vx = Vx + Vy (VF = overflow?)"),
+            0x5 => ("8xy5", "Vx = Vx - Vy (VF = no underflow?)"),
+            0x6 if quirks.direct_shifting => ("8xy6", "Vx = Vx >> 1 (VF = shifted bit)"),
+            0x6 => ("8xy6", "Vx = Vy >> 1 (VF = shifted bit)"),
+            0x7 => ("8xy7", "Vx = Vy - Vx (VF = no underflow?)"),
+            0xE if quirks.direct_shifting => ("8xyE", "Vx = Vx << 1 (VF = shifted bit)"),
+            0xE => ("8xyE", "Vx = Vy << 1 (VF = shifted bit)"),
+            _ => unknown,
+        },
+        0x9 => ("9xy0", "Skip if Vx != Vy"),
+        0xA => ("Annn", "I = nnn"),
+        0xB if *variant == Variant::CHIP8X && opcode & 0x000F == 0 => {
+            ("Bxy0", "Color 8x4 zones at (Vx, V(x+1)) with Vy")
+        }
+        0xB if *variant == Variant::CHIP8X => {
+            ("Bxyn", "Color 8xn sprite area at (Vx, V(x+1)) with Vy")
+        }
+        0xB if quirks.jump_to_x => ("Bxnn", "Jump to nnn + Vx"),
+        0xB => ("Bnnn", "Jump to nnn + V0"),
+        0xC => ("Cxnn", "Vx = random AND nn"),
+        0xD if variant.supports_schip() && opcode & 0x000F == 0 && quirks.lowres_tall_sprites => {
+            ("Dxy0", "Draw 16x16 sprite (8x16 in lowres) at (Vx, Vy)")
+        }
+        0xD if variant.supports_schip() && opcode & 0x000F == 0 => {
+            ("Dxy0", "Draw 16x16 sprite at (Vx, Vy)")
+        }
+        0xD => ("Dxyn", "Draw 8xn sprite at (Vx, Vy)"),
+        0xE => match opcode & 0x00FF {
+            0x9E => ("Ex9E", "Skip if key code Vx is down"),
+            0xA1 => ("ExA1", "Skip if key code Vx is up"),
+            0xF2 if *variant == Variant::CHIP8X => ("ExF2", "Skip if key Vx of keypad 2 is down"),
+            0xF5 if *variant == Variant::CHIP8X => ("ExF5", "Skip if key Vx of keypad 2 is up"),
+            _ => unknown,
+        },
+        0xF => match opcode & 0x00FF {
+            0x00 if variant.supports_xochip() && opcode == 0xF000 => {
+                ("F000", "I = next 16 bits (skips them)")
+            }
+            0x01 if variant.supports_xochip() => ("Fn01", "Select drawing planes n"),
+            0x02 if variant.supports_xochip() && opcode == 0xF002 => {
+                ("F002", "Load audio pattern from I")
+            }
+            0x07 => ("Fx07", "Vx = delay"),
+            0x0A => ("Fx0A", "Wait for key press and save to Vx"),
+            0x15 => ("Fx15", "delay = Vx"),
+            0x18 => ("Fx18", "sound = Vx"),
+            0x1E => ("Fx1E", "I = I + Vx"),
+            0x29 => ("Fx29", "I = font for Vx"),
+            0x30 if variant.supports_schip() => ("Fx30", "I = big font for Vx"),
+            0x33 => ("Fx33", "Write Vx as BCD"),
+            0x3A if variant.supports_xochip() => ("Fx3A", "pitch = Vx"),
+            0x55 => match quirks.save_load_increment {
+                IndexIncrement::XPlusOne => ("Fx55", "Write V0 to Vx (I = I + x + 1)"),
+                IndexIncrement::X => ("Fx55", "Write V0 to Vx (I = I + x)"),
+                IndexIncrement::Unchanged => ("Fx55", "Write V0 to Vx"),
+            },
+            0x65 => match quirks.save_load_increment {
+                IndexIncrement::XPlusOne => ("Fx65", "Read V0 to Vx (I = I + x + 1)"),
+                IndexIncrement::X => ("Fx65", "Read V0 to Vx (I = I + x)"),
+                IndexIncrement::Unchanged => ("Fx65", "Read V0 to Vx"),
+            },
+            0x75 if variant.supports_schip() => ("Fx75", "Save V0 to Vx to persistent flags"),
+            0x85 if variant.supports_schip() => ("Fx85", "Load V0 to Vx from persistent flags"),
+            _ => unknown,
+        },
+        _ => unknown,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Instructions that disassemble the same way on every variant.
+    const COMMON: &[(u16, &str)] = &[
+        (0x00E0, "CLS"),
+        (0x00EE, "RET"),
+        (0x1234, "JP #234"),
+        (0x2234, "CALL #234"),
+        (0x3124, "SE V1, #24"),
+        (0x4124, "SNE V1, #24"),
+        (0x5120, "SE V1, V2"),
+        (0x6124, "LD V1, #24"),
+        (0x7124, "ADD V1, #24"),
+        (0x8120, "LD V1, V2"),
+        (0x8121, "OR V1, V2"),
+        (0x8122, "AND V1, V2"),
+        (0x8123, "XOR V1, V2"),
+        (0x8124, "ADD V1, V2"),
+        (0x8125, "SUB V1, V2"),
+        (0x8126, "SHR V1, V2"),
+        (0x8127, "SUBN V1, V2"),
+        (0x812E, "SHL V1, V2"),
+        (0x9120, "SNE V1, V2"),
+        (0xA234, "LD I, #234"),
+        (0xC124, "RND V1, #24"),
+        (0xD125, "DRW V1, V2, 5"),
+        (0xE19E, "SKP V1"),
+        (0xE1A1, "SKNP V1"),
+        (0xF107, "LD V1, DT"),
+        (0xF10A, "LD V1, K"),
+        (0xF115, "LD DT, V1"),
+        (0xF118, "LD ST, V1"),
+        (0xF11E, "ADD I, V1"),
+        (0xF129, "LD F, V1"),
+        (0xF133, "LD B, V1"),
+        (0xF155, "LD [I], V1"),
+        (0xF165, "LD V1, [I]"),
+        // Not instructions on any variant
+        (0x5124, "DW #5124"),
+        (0x8128, "DW #8128"),
+        (0x9121, "DW #9121"),
+        (0xE1FF, "DW #E1FF"),
+        (0xF1FF, "DW #F1FF"),
+    ];
+
+    /// Instructions that disassemble differently depending on the variant.
+    fn variant_table(variant: Variant) -> &'static [(u16, &'static str)] {
+        match variant {
+            Variant::CHIP8 | Variant::VIPHYBRID | Variant::ETI660 => &[
+                (0x0123, "SYS #123"),
+                (0x00C1, "SYS #0C1"),
+                (0x00FF, "SYS #0FF"),
+                (0x5121, "DW #5121"),
+                (0x5122, "DW #5122"),
+                (0xB234, "JP V0, #234"),
+                (0xE1F2, "DW #E1F2"),
+                (0xF000, "DW #F000"),
+                (0xF130, "DW #F130"),
+                (0xF175, "DW #F175"),
+            ],
+            Variant::CHIP8X => &[
+                (0x02A0, "BGSTEP"),
+                (0x5121, "ADDN V1, V2"),
+                (0xB125, "COL V1, V2, 5"),
+                (0xE1F2, "SKP2 V1"),
+                (0xE1F5, "SKNP2 V1"),
+                (0xF130, "DW #F130"),
+            ],
+            Variant::CHIP48 => &[
+                (0x00FF, "SYS #0FF"),
+                (0xB234, "JP V2, #234"),
+                (0xF130, "DW #F130"),
+            ],
+            Variant::SCHIP10 => &[
+                (0x00C1, "SYS #0C1"),
+                (0x00FB, "SYS #0FB"),
+                (0x00FD, "EXIT"),
+                (0x00FE, "LOW"),
+                (0x00FF, "HIGH"),
+                (0xB234, "JP V2, #234"),
+                (0xF130, "LD HF, V1"),
+                (0xF175, "LD R, V1"),
+                (0xF185, "LD V1, R"),
+            ],
+            Variant::SCHIP11 => &[
+                (0x00C1, "SCD 1"),
+                (0x00D1, "SYS #0D1"),
+                (0x00FB, "SCR"),
+                (0x00FC, "SCL"),
+                (0x00FD, "EXIT"),
+                (0xB234, "JP V2, #234"),
+                (0xF130, "LD HF, V1"),
+                (0xF13A, "DW #F13A"),
+            ],
+            Variant::XOCHIP => &[
+                (0x00C1, "SCD 1"),
+                (0x00D1, "SCU 1"),
+                (0x5122, "SAVE V1 - V2"),
+                (0x5123, "LOAD V1 - V2"),
+                (0xB234, "JP V0, #234"),
+                (0xF201, "PLANE 2"),
+                (0xF002, "AUDIO"),
+                (0xF102, "DW #F102"),
+                (0xF13A, "PITCH V1"),
+                // F000 needs the two bytes after it
+                (0xF000, "DW #F000"),
+            ],
+        }
+    }
+
+    /// Disassemble each opcode on its own and compare the mnemonic and operands with the table.
+    fn check(chip8: &Chip8, table: &[(u16, &str)]) {
+        for &(opcode, expected) in table {
+            let instructions =
+                disassemble(&opcode.to_be_bytes(), 0x200, &chip8.quirks, &chip8.variant);
+            assert_eq!(instructions.len(), 1);
+            let instruction = &instructions[0];
+            let text = format!("{} {}", instruction.mnemonic, instruction.operands);
+            assert_eq!(
+                text.trim_end(),
+                expected,
+                "{opcode:04X} on {:?}",
+                chip8.variant
+            );
+            let explanation = explain_instruction(opcode, &chip8.quirks, &chip8.variant).1;
+            if instruction.mnemonic == "DW" {
+                assert_eq!(instruction.explanation, "Data");
+            } else {
+                assert_eq!(instruction.explanation, explanation);
+            }
+        }
+    }
+
+    #[test]
+    fn opcode_tables() {
+        for chip8 in [
+            Chip8::chip8(),
+            Chip8::vip_hybrid(),
+            Chip8::chip8x(),
+            Chip8::eti660(),
+            Chip8::chip48(),
+            Chip8::super_chip1_0(),
+            Chip8::super_chip1_1(),
+            Chip8::xo_chip(),
+        ] {
+            check(&chip8, COMMON);
+            check(&chip8, variant_table(chip8.variant));
+        }
+    }
+
+    #[test]
+    fn quirks() {
+        let mut chip8 = Chip8::chip8();
+        chip8.quirks.jump_to_x = true;
+        check(&chip8, &[(0xB234, "JP V2, #234")]);
+        chip8.quirks.jump_to_x = false;
+        check(&chip8, &[(0xB234, "JP V0, #234")]);
+    }
+
+    #[test]
+    fn long_index() {
+        let chip8 = Chip8::xo_chip();
+        let bytes = [0xF0, 0x00, 0x12, 0x34, 0x00, 0xE0];
+        let instructions = disassemble(&bytes, 0x200, &chip8.quirks, &chip8.variant);
+        assert_eq!(instructions.len(), 2);
+        let long = &instructions[0];
+        assert_eq!((long.mnemonic, long.operands.as_str()), ("LD", "I, #1234"));
+        assert_eq!(long.long_address, Some(0x1234));
+        assert_eq!(long.target, Some(0x1234));
+        assert_eq!(long.size(), 4);
+        assert_eq!(long.explanation, "I = next 16 bits (skips them)");
+        assert_eq!(
+            long.to_string(),
+            "0200  F000 1234  LD I, #1234        ; I = next 16 bits (skips them)"
+        );
+        // The address is not disassembled as an instruction
+        assert_eq!(instructions[1].address, 0x204);
+        assert_eq!(instructions[1].mnemonic, "CLS");
+
+        // Other variants read F000 as two bytes of data
+        let chip8 = Chip8::super_chip1_1();
+        let instructions = disassemble(&bytes, 0x200, &chip8.quirks, &chip8.variant);
+        let addresses: Vec<_> = instructions.iter().map(|i| i.address).collect();
+        assert_eq!(addresses, [0x200, 0x202, 0x204]);
+        assert_eq!(instructions[0].mnemonic, "DW");
+    }
+
+    #[test]
+    fn data() {
+        let chip8 = Chip8::chip8();
+        let instructions = disassemble(&[0x81, 0x28, 0x3C], 0x300, &chip8.quirks, &chip8.variant);
+        assert_eq!(instructions.len(), 2);
+        assert_eq!(
+            instructions[0].to_string(),
+            "0300  8128       DW #8128           ; Data"
+        );
+        assert_eq!(
+            instructions[1].to_string(),
+            "0302  3C         DB #3C             ; Data"
+        );
+        assert_eq!(instructions[1].opcode, 0x3C00);
+    }
+
+    #[test]
+    fn targets_and_symbols() {
+        let chip8 = Chip8::chip8();
+        let bytes = [0x22, 0x06, 0xA2, 0x08, 0x12, 0x00, 0x00, 0xEE];
+        let mut instructions = disassemble(&bytes, 0x200, &chip8.quirks, &chip8.variant);
+        let targets: Vec<_> = instructions.iter().map(|i| i.target).collect();
+        assert_eq!(targets, [Some(0x206), Some(0x208), Some(0x200), None]);
+
+        let mut symbols = Symbols::default();
+        symbols.insert(0x206, "draw");
+        for instruction in &mut instructions {
+            instruction.name_symbols(&symbols);
+        }
+        assert_eq!(instructions[0].operands, "draw");
+        assert_eq!(instructions[3].label.as_deref(), Some("draw"));
+        assert_eq!(instructions[1].operands, "I, #208");
+    }
+}
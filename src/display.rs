@@ -1,10 +1,15 @@
+#[cfg(feature = "gui")]
 use egui::{Color32, ColorImage};
 
-/// A monochrome 64x32 display.
+/// A 64x32, 64x48 or 128x64 display with two bitplanes.
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Display {
-    /// The state of each pixel of the screen.
-    pub pixels: Vec<bool>,
+    /// The state of each pixel of the screen, for each plane. Only XO-CHIP draws to the second
+    /// plane.
+    pub planes: [Vec<bool>; 2],
+    /// The height of the display in lowres mode: 32, or 48 for the ETI-660.
+    lowres_height: usize,
 }
 
 /// The direction where to shift to screen.
@@ -12,8 +17,10 @@ pub enum ScrollDirection {
     Right,
     Left,
     Down,
+    Up,
 }
 
+#[cfg(feature = "gui")]
 pub const DISPLAY_SCALE: usize = 10;
 
 impl Display {
@@ -21,7 +28,17 @@ impl Display {
     #[inline]
     pub fn small() -> Display {
         Display {
-            pixels: vec![false; 64 * 32],
+            planes: [vec![false; 64 * 32], vec![false; 64 * 32]],
+            lowres_height: 32,
+        }
+    }
+
+    /// 64x48 pixels. ETI-660.
+    #[inline]
+    pub fn tall() -> Display {
+        Display {
+            planes: [vec![false; 64 * 48], vec![false; 64 * 48]],
+            lowres_height: 48,
         }
     }
 
@@ -29,21 +46,50 @@ impl Display {
     #[inline]
     pub fn big() -> Display {
         Display {
-            pixels: vec![false; 128 * 64],
+            planes: [vec![false; 128 * 64], vec![false; 128 * 64]],
+            lowres_height: 32,
+        }
+    }
+
+    /// Get the width and height of the display in the given mode.
+    #[inline]
+    pub fn size(&self, highres: bool) -> (usize, usize) {
+        if highres {
+            (128, 64)
+        } else {
+            (64, self.lowres_height)
         }
     }
 
     /// Turn off all pixels.
     #[inline]
     pub fn clear(&mut self) {
-        self.pixels.fill(false);
+        self.clear_planes(0b11);
+    }
+
+    /// Turn off all pixels of the selected planes. Bit 0 of `planes` selects the first plane, bit 1
+    /// the second.
+    #[inline]
+    pub fn clear_planes(&mut self, planes: u8) {
+        for (i, plane) in self.planes.iter_mut().enumerate() {
+            if planes & (1 << i) != 0 {
+                plane.fill(false);
+            }
+        }
     }
 
-    /// Scroll the screen by a certain amount of pixels.
+    /// Get which planes a pixel is on in: bit 0 for the first plane, bit 1 for the second.
+    #[inline]
+    pub fn planes_at(&self, index: usize) -> u8 {
+        self.planes[0][index] as u8 | (self.planes[1][index] as u8) << 1
+    }
+
+    /// Scroll the selected planes of the screen by a certain amount of pixels.
     pub fn scroll(
         &mut self,
         direction: ScrollDirection,
         amount: usize,
+        planes: u8,
         highres: bool,
         scroll_quirk: bool,
     ) {
@@ -53,69 +99,77 @@ impl Display {
         } else {
             amount
         };
-        let width = if highres { 128 } else { 64 };
-        let height = if highres { 64 } else { 32 };
-
-        match direction {
-            ScrollDirection::Right => {
-                for y in 0..height {
-                    for x in (amount..width).rev() {
-                        let source = x - amount + y * width;
-                        let destination = x + y * width;
-                        self.pixels[destination] = self.pixels[source];
-                        self.pixels[source] = false;
+        let (width, height) = self.size(highres);
+
+        for (i, pixels) in self.planes.iter_mut().enumerate() {
+            if planes & (1 << i) == 0 {
+                continue;
+            }
+            match direction {
+                ScrollDirection::Right => {
+                    for y in 0..height {
+                        for x in (amount..width).rev() {
+                            let source = x - amount + y * width;
+                            let destination = x + y * width;
+                            pixels[destination] = pixels[source];
+                            pixels[source] = false;
+                        }
                     }
                 }
-            }
-            ScrollDirection::Left => {
-                for y in 0..height {
-                    for x in 0..(width - amount) {
-                        let source = x + amount + y * width;
-                        let destination = x + y * width;
-                        self.pixels[destination] = self.pixels[source];
-                        self.pixels[source] = false;
+                ScrollDirection::Left => {
+                    for y in 0..height {
+                        for x in 0..(width - amount) {
+                            let source = x + amount + y * width;
+                            let destination = x + y * width;
+                            pixels[destination] = pixels[source];
+                            pixels[source] = false;
+                        }
                     }
                 }
-            }
-            ScrollDirection::Down => {
-                for y in (amount..height).rev() {
-                    for x in 0..width {
-                        let source = x + (y - amount) * width;
-                        let destination = x + y * width;
-                        self.pixels[destination] = self.pixels[source];
-                        self.pixels[source] = false;
+                ScrollDirection::Down => {
+                    for y in (amount..height).rev() {
+                        for x in 0..width {
+                            let source = x + (y - amount) * width;
+                            let destination = x + y * width;
+                            pixels[destination] = pixels[source];
+                            pixels[source] = false;
+                        }
+                    }
+                }
+                ScrollDirection::Up => {
+                    for y in 0..(height - amount) {
+                        for x in 0..width {
+                            let source = x + (y + amount) * width;
+                            let destination = x + y * width;
+                            pixels[destination] = pixels[source];
+                            pixels[source] = false;
+                        }
                     }
                 }
             }
         }
     }
 
-    /// Transform the display pixels into a scaled up image.
+    /// Transform the display pixels into a scaled up image. `color` gives the color of the pixel at
+    /// a column and row.
+    #[cfg(feature = "gui")]
     #[inline]
-    pub fn render(
-        &self,
-        highres: bool,
-        background_color: Color32,
-        fill_color: Color32,
-    ) -> ColorImage {
+    pub fn render(&self, highres: bool, color: impl Fn(usize, usize) -> Color32) -> ColorImage {
         let scale = if highres {
             DISPLAY_SCALE / 2 // big screen
         } else {
             DISPLAY_SCALE // small screen
         };
-        let width = if highres { 128 } else { 64 };
-        let height = if highres { 64 } else { 32 };
+        let (width, height) = self.size(highres);
 
-        let mut image_data = vec![background_color; width * scale * height * scale];
+        let mut image_data = vec![Color32::BLACK; width * scale * height * scale];
 
         for y in 0..height {
             for x in 0..width {
-                if self.pixels[x + y * width] {
-                    for yi in 0..scale {
-                        for xi in 0..scale {
-                            image_data[(x * scale + xi) + ((y * scale + yi) * width * scale)] =
-                                fill_color;
-                        }
+                let color = color(x, y);
+                for yi in 0..scale {
+                    for xi in 0..scale {
+                        image_data[(x * scale + xi) + ((y * scale + yi) * width * scale)] = color;
                     }
                 }
             }
@@ -0,0 +1,490 @@
+#[cfg(target_arch = "wasm32")]
+use std::sync::atomic::{AtomicBool, Ordering};
+#[cfg(not(target_arch = "wasm32"))]
+use std::{
+    sync::mpsc::RecvTimeoutError,
+    thread::{self, sleep},
+};
+use std::{
+    sync::{
+        mpsc::{self, Receiver, Sender, TryRecvError},
+        Arc, Mutex, MutexGuard,
+    },
+    time::Duration,
+};
+
+use web_time::Instant;
+
+use crate::{Chip8, Comparison, HaltReason};
+
+/// The duration of a single frame - the interpreter runs at 60 fps.
+pub const FRAME_DURATION: Duration = Duration::from_nanos(16666667);
+/// How many frames are executed in the time of one frame while fast-forwarding.
+pub const TURBO_FACTOR: u32 = 8;
+/// How many frames the interpreter can wait for a key press without input before
+/// [`DriverEvent::WaitingForInput`] is sent.
+pub const KEY_WAIT_WARNING_FRAMES: u64 = 300;
+/// How many frames [`EmulatorDriver::poll`] catches up on at most, so the interpreter does not race
+/// after the browser tab was in the background.
+#[cfg(target_arch = "wasm32")]
+const MAX_CATCH_UP_FRAMES: u32 = 4;
+
+/// A command for the interpreter thread of an [`EmulatorDriver`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DriverCommand {
+    /// Start executing instructions.
+    Start,
+    /// Stop executing instructions.
+    Stop,
+    /// Execute one instruction while stopped.
+    StepCycle,
+    /// Execute the rest of the current frame while stopped.
+    StepFrame,
+    /// Go back one cycle while stopped. See [`Chip8::step_back_cycle`].
+    StepBackCycle,
+    /// Go back to the start of the frame while stopped. See [`Chip8::step_back_frame`].
+    StepBackFrame,
+    /// Reset the interpreter and reload the last loaded ROM.
+    Reset,
+    /// Reset the registers, timers and stack, but keep RAM and the display. See [`Chip8::reset_cpu`].
+    ResetCpu,
+    /// Clear the display and the timers only. See [`Chip8::clear_display_and_timers`].
+    ClearDisplayAndTimers,
+    /// Reset the interpreter and load a new ROM.
+    LoadRom(Vec<u8>),
+    /// Set the ROM that is reloaded on reset without resetting the interpreter, for example after
+    /// restoring a save state.
+    SetRom(Vec<u8>),
+    /// Set the state of the keypad.
+    SetKeys([bool; 16]),
+    /// Set the state of the second keypad of the CHIP-8X.
+    SetKeys2([bool; 16]),
+    /// Report a released key, which may resolve the `Fx0A` instruction. See
+    /// [`Chip8::key_released`](crate::Chip8::key_released).
+    ReleaseKey(u8),
+    /// Pause (`true`) or resume (`false`) execution without stopping the interpreter, for example
+    /// while the window is minimized.
+    Suspend(bool),
+    /// Choose what advances the interpreter to the next frame.
+    SetPacing(FramePacing),
+    /// Execute one frame while running with [`FramePacing::Vsync`].
+    AdvanceFrame,
+    /// Fast-forward (`true`) by running [`TURBO_FACTOR`] frames at a time, or go back to normal
+    /// speed (`false`).
+    SetTurbo(bool),
+    /// Tell the interpreter thread whether the frontend can deliver keypad input right now, that is
+    /// whether it has key bindings and an input device that reaches it.
+    SetInputAvailable(bool),
+}
+
+/// What advances a running interpreter to the next frame.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FramePacing {
+    /// The interpreter thread runs a frame and sleeps until the next one, 60 times per second.
+    #[default]
+    Timer,
+    /// The frontend sends [`DriverCommand::AdvanceFrame`] every time it draws, so exactly one frame
+    /// is executed per displayed frame. Runs faster than 60 fps on displays with a higher refresh rate.
+    Vsync,
+}
+
+/// Something that happened on the interpreter thread. See [`EmulatorDriver::subscribe`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DriverEvent {
+    /// A command changed the interpreter state.
+    StateChanged,
+    /// A frame was completed. Has the number of frames completed since the last reset, see
+    /// [`Chip8::get_frame_count`].
+    Frame(u64),
+    /// The interpreter stopped running, with the reason if it halted.
+    Stopped(Option<HaltReason>),
+    /// The interpreter has been waiting for a key press (`Fx0A`) for [`KEY_WAIT_WARNING_FRAMES`]
+    /// frames while the frontend reported that no input is available, so it will likely wait
+    /// forever. Sent once per wait.
+    WaitingForInput,
+}
+
+/// A function called on the interpreter thread after every completed frame. See
+/// [`EmulatorDriver::on_frame`].
+type FrameCallback = Box<dyn FnMut(&Chip8) + Send>;
+
+/// Runs a [`Chip8`] on its own thread at 60 frames per second.
+///
+/// The interpreter is controlled with [`DriverCommand`]s. Its state can be inspected (and configured)
+/// by locking it.
+///
+/// Browsers have no threads, so on `wasm32` the interpreter runs in [`EmulatorDriver::poll`]
+/// instead, which the frontend calls every time it draws.
+pub struct EmulatorDriver {
+    /// The interpreter, shared with the thread.
+    interpreter: Arc<Mutex<Chip8>>,
+    /// Sends commands to the thread. The thread exits when this is dropped.
+    commands: Sender<DriverCommand>,
+    /// Receivers of interpreter events.
+    subscribers: Arc<Mutex<Vec<Sender<DriverEvent>>>>,
+    /// A second interpreter that runs in lockstep with the first one, shared with the thread.
+    comparison: Arc<Mutex<Option<Comparison>>>,
+    /// Called after every completed frame, shared with the thread.
+    frame_callbacks: Arc<Mutex<Vec<FrameCallback>>>,
+    /// The state of the interpreter thread, run by [`EmulatorDriver::poll`].
+    #[cfg(target_arch = "wasm32")]
+    thread: Mutex<DriverThread>,
+    /// Whether commands were sent since the last [`EmulatorDriver::poll`].
+    #[cfg(target_arch = "wasm32")]
+    pending: AtomicBool,
+}
+
+impl EmulatorDriver {
+    /// Start the interpreter thread.
+    ///
+    /// `set_sound` is called on the interpreter thread (in [`EmulatorDriver::poll`] on `wasm32`) whenever the buzzer should start (`true`) or
+    /// stop (`false`) playing.
+    pub fn new(chip8: Chip8, set_sound: impl FnMut(bool) + Send + 'static) -> EmulatorDriver {
+        let interpreter = Arc::new(Mutex::new(chip8));
+        let subscribers = Arc::new(Mutex::new(Vec::new()));
+        let comparison = Arc::new(Mutex::new(None));
+        let frame_callbacks = Arc::new(Mutex::new(Vec::new()));
+        let (commands, receiver) = mpsc::channel();
+
+        let thread = DriverThread {
+            interpreter: Arc::clone(&interpreter),
+            commands: receiver,
+            subscribers: Arc::clone(&subscribers),
+            comparison: Arc::clone(&comparison),
+            frame_callbacks: Arc::clone(&frame_callbacks),
+            set_sound: Box::new(set_sound),
+            sound_playing: false,
+            rom: Vec::new(),
+            suspended: false,
+            pacing: FramePacing::default(),
+            turbo: false,
+            input_available: true,
+            key_wait_frames: 0,
+            key_wait_warned: false,
+            #[cfg(target_arch = "wasm32")]
+            next_frame: Instant::now(),
+        };
+        #[cfg(not(target_arch = "wasm32"))]
+        thread::spawn(move || thread.run());
+
+        EmulatorDriver {
+            interpreter,
+            commands,
+            subscribers,
+            comparison,
+            frame_callbacks,
+            #[cfg(target_arch = "wasm32")]
+            thread: Mutex::new(thread),
+            #[cfg(target_arch = "wasm32")]
+            pending: AtomicBool::new(false),
+        }
+    }
+
+    /// Do the work of the interpreter thread in the browser: apply the sent commands and execute
+    /// the frames that are due. Call it every time the frontend draws, and keep drawing while the
+    /// interpreter runs.
+    #[cfg(target_arch = "wasm32")]
+    pub fn poll(&self) {
+        self.pending.store(false, Ordering::Relaxed);
+        self.thread.lock().unwrap().poll();
+    }
+
+    /// Check if commands were sent that the next [`EmulatorDriver::poll`] has to apply, so the
+    /// frontend should draw again soon.
+    #[cfg(target_arch = "wasm32")]
+    pub fn has_pending_commands(&self) -> bool {
+        self.pending.load(Ordering::Relaxed)
+    }
+
+    /// Send a command to the interpreter thread.
+    #[inline]
+    pub fn send(&self, command: DriverCommand) {
+        // The thread only exits when the driver is dropped
+        let _ = self.commands.send(command);
+        #[cfg(target_arch = "wasm32")]
+        self.pending.store(true, Ordering::Relaxed);
+    }
+
+    /// Lock the interpreter for inspection or configuration.
+    #[inline]
+    pub fn lock(&self) -> MutexGuard<'_, Chip8> {
+        self.interpreter.lock().unwrap()
+    }
+
+    /// Lock the comparison interpreter to inspect it, or to start or stop comparing by setting it.
+    ///
+    /// Lock the main interpreter first if both are needed. A new comparison starts on the next
+    /// [`DriverCommand::Reset`].
+    #[inline]
+    pub fn lock_comparison(&self) -> MutexGuard<'_, Option<Comparison>> {
+        self.comparison.lock().unwrap()
+    }
+
+    /// Receive interpreter events.
+    pub fn subscribe(&self) -> Receiver<DriverEvent> {
+        let (sender, receiver) = mpsc::channel();
+        self.subscribers.lock().unwrap().push(sender);
+        receiver
+    }
+
+    /// Call `callback` on the interpreter thread after every completed frame, before the next one
+    /// starts, for tools that must see every frame boundary exactly (recorders, netplay, scripts).
+    ///
+    /// Unlike [`DriverEvent::Frame`], which arrives some time later, the interpreter can not change
+    /// while the callback runs. Keep it short, as it delays the interpreter. The interpreter is
+    /// already locked, so the callback must not lock it through the driver.
+    pub fn on_frame(&self, callback: impl FnMut(&Chip8) + Send + 'static) {
+        self.frame_callbacks
+            .lock()
+            .unwrap()
+            .push(Box::new(callback));
+    }
+}
+
+/// The state owned by the interpreter thread.
+struct DriverThread {
+    interpreter: Arc<Mutex<Chip8>>,
+    commands: Receiver<DriverCommand>,
+    subscribers: Arc<Mutex<Vec<Sender<DriverEvent>>>>,
+    comparison: Arc<Mutex<Option<Comparison>>>,
+    frame_callbacks: Arc<Mutex<Vec<FrameCallback>>>,
+    set_sound: Box<dyn FnMut(bool) + Send>,
+    /// The last value passed to `set_sound`.
+    sound_playing: bool,
+    /// The last loaded ROM, reloaded on reset.
+    rom: Vec<u8>,
+    /// If true, no frames are executed even if the interpreter is running.
+    suspended: bool,
+    /// What advances the interpreter to the next frame.
+    pacing: FramePacing,
+    /// If true, every frame tick runs [`TURBO_FACTOR`] frames.
+    turbo: bool,
+    /// Whether the frontend can deliver keypad input.
+    input_available: bool,
+    /// How many frames the interpreter has been waiting for a key press.
+    key_wait_frames: u64,
+    /// Whether [`DriverEvent::WaitingForInput`] was sent for the current wait.
+    key_wait_warned: bool,
+    /// When the next frame is due.
+    #[cfg(target_arch = "wasm32")]
+    next_frame: Instant,
+}
+
+impl DriverThread {
+    #[cfg(not(target_arch = "wasm32"))]
+    fn run(mut self) {
+        loop {
+            let frame_start = Instant::now();
+
+            if !self.handle_commands() {
+                return;
+            }
+
+            let running = self.interpreter.lock().unwrap().is_running();
+            if running && !self.suspended && self.pacing == FramePacing::Timer {
+                self.run_frame();
+
+                sleep(FRAME_DURATION.saturating_sub(frame_start.elapsed())); // wait for frame to end
+            } else {
+                if !running || self.suspended {
+                    self.update_sound(false);
+                }
+
+                // Nothing to do until the next command
+                match self.commands.recv_timeout(FRAME_DURATION) {
+                    Ok(command) => self.execute(command),
+                    Err(RecvTimeoutError::Timeout) => {}
+                    Err(RecvTimeoutError::Disconnected) => return,
+                }
+            }
+        }
+    }
+
+    /// Execute the frames that are due since the last poll.
+    #[cfg(target_arch = "wasm32")]
+    fn poll(&mut self) {
+        self.handle_commands();
+
+        let now = Instant::now();
+        let running = self.interpreter.lock().unwrap().is_running();
+        if !running || self.suspended || self.pacing != FramePacing::Timer {
+            if !running || self.suspended {
+                self.update_sound(false);
+            }
+            self.next_frame = now;
+            return;
+        }
+
+        if now.duration_since(self.next_frame) > FRAME_DURATION * MAX_CATCH_UP_FRAMES {
+            self.next_frame = now;
+        }
+        while self.next_frame <= now && self.interpreter.lock().unwrap().is_running() {
+            self.run_frame();
+            self.next_frame += FRAME_DURATION;
+        }
+    }
+
+    /// Handle all pending commands. Returns `false` if the driver was dropped.
+    fn handle_commands(&mut self) -> bool {
+        loop {
+            match self.commands.try_recv() {
+                Ok(command) => self.execute(command),
+                Err(TryRecvError::Empty) => return true,
+                Err(TryRecvError::Disconnected) => return false,
+            }
+        }
+    }
+
+    /// Execute a frame of the running interpreter, or several while fast-forwarding, and update the
+    /// sound.
+    fn run_frame(&mut self) {
+        let interpreter = Arc::clone(&self.interpreter);
+        let mut chip8 = interpreter.lock().unwrap();
+        let frames = if self.turbo { TURBO_FACTOR } else { 1 };
+        for _ in 0..frames {
+            chip8.run_frame();
+            if let Some(comparison) = self.comparison.lock().unwrap().as_mut() {
+                comparison.run_frame(&chip8);
+            }
+            if !chip8.is_running() {
+                break;
+            }
+            self.frame_completed(&chip8);
+            self.watch_key_wait(&chip8);
+        }
+
+        self.update_sound(chip8.is_running() && chip8.sound_on && chip8.get_sound() > 1);
+        if !chip8.is_running() {
+            self.notify(DriverEvent::Stopped(chip8.halt_reason.clone()));
+        }
+    }
+
+    /// Run the frame callbacks and tell the subscribers that a frame was completed.
+    fn frame_completed(&self, chip8: &Chip8) {
+        for callback in self.frame_callbacks.lock().unwrap().iter_mut() {
+            callback(chip8);
+        }
+        self.notify(DriverEvent::Frame(chip8.get_frame_count()));
+    }
+
+    /// Count the frames spent waiting for a key press and warn the subscribers once if the wait is
+    /// long and no input is available.
+    fn watch_key_wait(&mut self, chip8: &Chip8) {
+        if !chip8.is_waiting_for_key() {
+            self.key_wait_frames = 0;
+            self.key_wait_warned = false;
+            return;
+        }
+        self.key_wait_frames += 1;
+        if self.key_wait_frames >= KEY_WAIT_WARNING_FRAMES
+            && !self.input_available
+            && !self.key_wait_warned
+        {
+            self.key_wait_warned = true;
+            self.notify(DriverEvent::WaitingForInput);
+        }
+    }
+
+    fn execute(&mut self, command: DriverCommand) {
+        if self.apply(command) {
+            self.notify(DriverEvent::StateChanged);
+        }
+    }
+
+    /// Apply a command to the interpreter. Returns `false` if nothing changed.
+    fn apply(&mut self, command: DriverCommand) -> bool {
+        let mut chip8 = self.interpreter.lock().unwrap();
+        match command {
+            DriverCommand::Start => chip8.start(),
+            DriverCommand::Stop => chip8.stop(),
+            DriverCommand::StepCycle if !chip8.is_running() => {
+                let frame_completed = chip8.step_cycle();
+                if let Some(comparison) = self.comparison.lock().unwrap().as_mut() {
+                    comparison.step_cycle(&chip8, frame_completed);
+                }
+                if frame_completed {
+                    self.frame_completed(&chip8);
+                }
+            }
+            DriverCommand::StepFrame if !chip8.is_running() => {
+                chip8.step_frame();
+                if let Some(comparison) = self.comparison.lock().unwrap().as_mut() {
+                    comparison.run_frame(&chip8);
+                }
+                self.frame_completed(&chip8);
+            }
+            DriverCommand::StepBackCycle if !chip8.is_running() => return chip8.step_back_cycle(),
+            DriverCommand::StepBackFrame if !chip8.is_running() => return chip8.step_back_frame(),
+            DriverCommand::StepCycle
+            | DriverCommand::StepFrame
+            | DriverCommand::StepBackCycle
+            | DriverCommand::StepBackFrame => return false,
+            DriverCommand::Reset => {
+                chip8.reset_and_load(&self.rom);
+                if let Some(comparison) = self.comparison.lock().unwrap().as_mut() {
+                    comparison.restart(&self.rom);
+                }
+            }
+            DriverCommand::ResetCpu => chip8.reset_cpu(),
+            DriverCommand::ClearDisplayAndTimers => chip8.clear_display_and_timers(),
+            DriverCommand::LoadRom(rom) => {
+                chip8.reset_and_load(&rom);
+                if let Some(comparison) = self.comparison.lock().unwrap().as_mut() {
+                    comparison.restart(&rom);
+                }
+                self.rom = rom;
+            }
+            DriverCommand::SetRom(rom) => self.rom = rom,
+            DriverCommand::SetKeys(keys) => {
+                if chip8.keypad == keys {
+                    return false;
+                }
+                chip8.set_keys(keys);
+            }
+            DriverCommand::SetKeys2(keys) => {
+                if chip8.keypad2 == keys {
+                    return false;
+                }
+                chip8.set_keys2(keys);
+            }
+            DriverCommand::ReleaseKey(key) => {
+                if let Some(comparison) = self.comparison.lock().unwrap().as_mut() {
+                    comparison.release_key(key);
+                }
+                if !chip8.key_released(key) {
+                    return false;
+                }
+            }
+            DriverCommand::Suspend(suspended) => self.suspended = suspended,
+            DriverCommand::SetPacing(pacing) => self.pacing = pacing,
+            DriverCommand::SetTurbo(turbo) => self.turbo = turbo,
+            DriverCommand::SetInputAvailable(available) => self.input_available = available,
+            DriverCommand::AdvanceFrame => {
+                if !chip8.is_running() || self.suspended || self.pacing != FramePacing::Vsync {
+                    return false;
+                }
+                drop(chip8);
+                self.run_frame();
+                // run_frame already notified the subscribers
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Start or stop the buzzer if its state changed.
+    fn update_sound(&mut self, playing: bool) {
+        if playing != self.sound_playing {
+            self.sound_playing = playing;
+            (self.set_sound)(playing);
+        }
+    }
+
+    /// Send an event to all subscribers, forgetting the ones that hung up.
+    fn notify(&self, event: DriverEvent) {
+        self.subscribers
+            .lock()
+            .unwrap()
+            .retain(|subscriber| subscriber.send(event.clone()).is_ok());
+    }
+}
@@ -0,0 +1,59 @@
+use std::{io, sync::Arc};
+
+use crate::{crash_dump::sha1_hex, storage::SharedStorage, Chip8, PersistentStorage};
+
+impl Chip8 {
+    /// Keep persistent flags in `storage` from now on, and read the flags of the loaded ROM from it.
+    /// Clones of the interpreter share the storage.
+    pub fn set_persistent_storage(&mut self, storage: impl PersistentStorage + 'static) {
+        self.persistent_storage = SharedStorage(Arc::new(storage));
+        if self.persistent_flags_rom.is_some() {
+            let result = self.load_persistent_flags();
+            self.note_persistent_flags_result(result);
+        }
+    }
+
+    /// Remember the ROM whose persistent flags are used and read them, so every ROM keeps its own.
+    pub(crate) fn use_persistent_flags_of(&mut self, rom: &[u8]) {
+        self.persistent_flags_rom = Some(sha1_hex(rom));
+        self.persistent_flags = [0; 16];
+        if self.variant.persistent_flag_count() > 0 {
+            let result = self.load_persistent_flags();
+            self.note_persistent_flags_result(result);
+        }
+    }
+
+    /// Describe where the persistent flags of the loaded ROM are kept, like the path of their file.
+    /// `None` before a ROM is loaded or if the storage does not say.
+    pub fn persistent_flags_location(&self) -> Option<String> {
+        let rom = self.persistent_flags_rom.as_ref()?;
+        self.persistent_storage.0.location(rom)
+    }
+
+    /// Read the persistent flag registers of the loaded ROM from the persistent storage. They are
+    /// zero if the ROM has not saved any yet.
+    pub fn load_persistent_flags(&mut self) -> io::Result<()> {
+        self.persistent_flags = match &self.persistent_flags_rom {
+            Some(rom) => self.persistent_storage.0.load(rom)?,
+            None => [0; 16],
+        };
+        Ok(())
+    }
+
+    /// Write the persistent flag registers of the loaded ROM to the persistent storage. Does nothing
+    /// before a ROM is loaded.
+    pub fn save_persistent_flags(&self) -> io::Result<()> {
+        match &self.persistent_flags_rom {
+            Some(rom) => self.persistent_storage.0.save(rom, &self.persistent_flags),
+            None => Ok(()),
+        }
+    }
+
+    /// Keep the error of the last persistent flag read or write for the inspector, or forget it if
+    /// it worked. A program that can not save its flags keeps running.
+    pub(crate) fn note_persistent_flags_result(&mut self, result: io::Result<()>) {
+        self.persistent_flags_error = result
+            .err()
+            .map(|e| format!("Could not store persistent flags: {e}"));
+    }
+}
@@ -0,0 +1,96 @@
+use std::{fs, io, path::PathBuf};
+
+use crate::{image::encode_png, Chip8};
+
+/// Writes every completed frame as a numbered PNG image into a folder, for making videos with
+/// external tools at exactly 60 frames per second.
+///
+/// All frames have the same size: SUPER-CHIP lowres frames are drawn at double scale so they match
+/// the highres ones.
+#[derive(Debug, Clone, PartialEq, PartialOrd)]
+pub struct FrameDump {
+    /// The folder the images are written to.
+    folder: PathBuf,
+    /// How many image pixels wide and high a CHIP-8 pixel is.
+    scale: usize,
+    /// The colors of pixels that are off, on in the first plane, on in the second plane and on in
+    /// both planes as RGBA.
+    colors: [[u8; 4]; 4],
+    /// The number of frames written.
+    frames: u64,
+    /// Why writing stopped, if a frame could not be written.
+    error: Option<String>,
+}
+
+impl FrameDump {
+    /// Get the folder the images are written to.
+    #[inline]
+    pub fn folder(&self) -> &PathBuf {
+        &self.folder
+    }
+    /// Get the number of frames written.
+    #[inline]
+    pub const fn frames(&self) -> u64 {
+        self.frames
+    }
+    /// Get the error that stopped writing, if any.
+    #[inline]
+    pub fn error(&self) -> Option<&str> {
+        self.error.as_deref()
+    }
+
+    /// Write the display of `chip8` as the next frame. Does nothing once a frame failed to write.
+    pub(crate) fn write_frame(&mut self, chip8: &Chip8) {
+        if self.error.is_some() {
+            return;
+        }
+
+        let scale = if chip8.variant.supports_schip() && !chip8.highres {
+            self.scale * 2
+        } else {
+            self.scale
+        };
+        let (width, height, rgb) = chip8.display_rgb(self.colors, scale);
+        let png = encode_png(width as u32, height as u32, &rgb);
+
+        let path = self.folder.join(format!("frame_{:06}.png", self.frames));
+        match fs::write(&path, png) {
+            Ok(()) => self.frames += 1,
+            Err(e) => self.error = Some(format!("could not write {}: {e}", path.display())),
+        }
+    }
+}
+
+impl Chip8 {
+    /// Start writing every completed frame as a PNG image into `folder`, which is created if it does
+    /// not exist. Each CHIP-8 pixel becomes a `scale` by `scale` square of the given RGBA colors,
+    /// without their alpha: of pixels that are off, on in the first plane, on in the second plane
+    /// and on in both planes.
+    pub fn start_frame_dump(
+        &mut self,
+        folder: impl Into<PathBuf>,
+        scale: usize,
+        colors: [[u8; 4]; 4],
+    ) -> io::Result<()> {
+        let folder = folder.into();
+        fs::create_dir_all(&folder)?;
+        self.frame_dump = Some(FrameDump {
+            folder,
+            scale: scale.max(1),
+            colors,
+            frames: 0,
+            error: None,
+        });
+        Ok(())
+    }
+    /// Stop writing frames and get the finished dump, if dumping.
+    #[inline]
+    pub fn stop_frame_dump(&mut self) -> Option<FrameDump> {
+        self.frame_dump.take()
+    }
+    /// Get the frame dump in progress, if dumping.
+    #[inline]
+    pub const fn get_frame_dump(&self) -> Option<&FrameDump> {
+        self.frame_dump.as_ref()
+    }
+}
@@ -0,0 +1,57 @@
+use std::thread::sleep;
+
+use web_time::Instant;
+
+use crate::{Chip8, FRAME_DURATION};
+
+/// Where an interpreter run by [`run_frontend`] shows its display, plays its buzzer and gets its
+/// keys. Implement it to embed the interpreter in another frontend, like SDL, a terminal or a web
+/// page, without the egui app.
+pub trait Frontend {
+    /// Get the state of the keypad before a frame, `true` for every held key.
+    fn poll_keys(&mut self) -> [bool; 16];
+    /// Show the display after a frame. Read it with [`Chip8::render_rgba`] or
+    /// [`Chip8::get_pixel`].
+    fn draw(&mut self, chip8: &Chip8);
+    /// Start (`true`) or stop (`false`) playing the buzzer. Only called when it changes.
+    fn set_sound(&mut self, on: bool);
+    /// Check if the frontend wants to stop, for example because its window was closed. Asked
+    /// before every frame.
+    fn should_quit(&mut self) -> bool {
+        false
+    }
+}
+
+/// Start `chip8` and run it against `frontend` at 60 frames per second, until the interpreter
+/// stops or the frontend wants to quit. The buzzer is stopped before returning.
+///
+/// Releasing a key (or pressing it, with the `key_wait_on_press` quirk) resolves the `Fx0A`
+/// instruction, like in the app.
+pub fn run_frontend(chip8: &mut Chip8, frontend: &mut impl Frontend) {
+    let mut sound_playing = false;
+    chip8.start();
+    while chip8.is_running() && !frontend.should_quit() {
+        let frame_start = Instant::now();
+
+        let keys = frontend.poll_keys();
+        let released = (0..16).find(|&key| chip8.keypad[key] && !keys[key]);
+        if let Some(key) = released {
+            chip8.key_released(key as u8);
+        }
+        chip8.set_keys(keys);
+
+        chip8.run_frame();
+        frontend.draw(chip8);
+
+        let sound = chip8.is_running() && chip8.sound_on && chip8.get_sound() > 1;
+        if sound != sound_playing {
+            frontend.set_sound(sound);
+            sound_playing = sound;
+        }
+
+        sleep(FRAME_DURATION.saturating_sub(frame_start.elapsed())); // wait for frame to end
+    }
+    if sound_playing {
+        frontend.set_sound(false);
+    }
+}
@@ -0,0 +1,188 @@
+use std::{
+    collections::HashMap,
+    fs,
+    hash::{DefaultHasher, Hash, Hasher},
+    io,
+};
+
+use gilrs::{Button, EventType, Gilrs};
+
+/// The file the per-ROM gamepad mappings are stored in.
+const PROFILES_PATH: &str = "gamepad.dat";
+
+/// The gamepad buttons that can be mapped to the CHIP-8 keypad and their names.
+pub const BUTTONS: [(Button, &str); 16] = [
+    (Button::DPadUp, "D-pad up"),
+    (Button::DPadDown, "D-pad down"),
+    (Button::DPadLeft, "D-pad left"),
+    (Button::DPadRight, "D-pad right"),
+    (Button::South, "South (A/Cross)"),
+    (Button::East, "East (B/Circle)"),
+    (Button::West, "West (X/Square)"),
+    (Button::North, "North (Y/Triangle)"),
+    (Button::LeftTrigger, "Left bumper"),
+    (Button::RightTrigger, "Right bumper"),
+    (Button::LeftTrigger2, "Left trigger"),
+    (Button::RightTrigger2, "Right trigger"),
+    (Button::Select, "Select"),
+    (Button::Start, "Start"),
+    (Button::LeftThumb, "Left stick"),
+    (Button::RightThumb, "Right stick"),
+];
+
+/// The CHIP-8 key each button of [`BUTTONS`] presses, or `None` if the button is unbound.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct GamepadMapping(pub [Option<u8>; 16]);
+
+impl Default for GamepadMapping {
+    /// The D-pad on 2/4/6/8 and the face buttons on 5/A/B/C, which suits most games.
+    fn default() -> Self {
+        let mut keys = [None; 16];
+        keys[..8].copy_from_slice(&[
+            Some(0x2),
+            Some(0x8),
+            Some(0x4),
+            Some(0x6),
+            Some(0x5),
+            Some(0xA),
+            Some(0xB),
+            Some(0xC),
+        ]);
+        GamepadMapping(keys)
+    }
+}
+
+impl GamepadMapping {
+    /// Parse a mapping written by [`GamepadMapping::to_line`].
+    fn from_line(line: &str) -> Option<GamepadMapping> {
+        let mut keys = [None; 16];
+        let mut values = line.split(' ');
+        for key in &mut keys {
+            *key = match values.next()? {
+                "-" => None,
+                value => Some(u8::from_str_radix(value, 16).ok().filter(|&k| k < 16)?),
+            };
+        }
+        Some(GamepadMapping(keys))
+    }
+
+    /// Write the mapping as one hex digit or `-` per button.
+    fn to_line(self) -> String {
+        let values: Vec<String> = self
+            .0
+            .iter()
+            .map(|key| key.map_or("-".into(), |k| format!("{k:X}")))
+            .collect();
+        values.join(" ")
+    }
+}
+
+/// Turns gamepad input into CHIP-8 keypad state, with a mapping saved per ROM.
+pub struct GamepadInput {
+    /// `None` if gamepads are not supported on this system.
+    gilrs: Option<Gilrs>,
+    /// The mapping in use.
+    pub mapping: GamepadMapping,
+    /// The saved mappings, by ROM hash.
+    profiles: HashMap<u64, GamepadMapping>,
+    /// The hash of the ROM the mapping is for.
+    rom_hash: u64,
+}
+
+impl Default for GamepadInput {
+    fn default() -> Self {
+        let gilrs = Gilrs::new()
+            .inspect_err(|e| println!("Gamepads are not available: {e}"))
+            .ok();
+        let profiles = fs::read_to_string(PROFILES_PATH)
+            .unwrap_or_default()
+            .lines()
+            .filter_map(|line| {
+                let (hash, mapping) = line.split_once(' ')?;
+                Some((
+                    u64::from_str_radix(hash, 16).ok()?,
+                    GamepadMapping::from_line(mapping)?,
+                ))
+            })
+            .collect();
+        GamepadInput {
+            gilrs,
+            mapping: GamepadMapping::default(),
+            profiles,
+            rom_hash: 0,
+        }
+    }
+}
+
+impl GamepadInput {
+    /// Whether a gamepad is connected.
+    pub fn is_connected(&self) -> bool {
+        self.gilrs
+            .as_ref()
+            .is_some_and(|gilrs| gilrs.gamepads().next().is_some())
+    }
+
+    /// Switch to the saved mapping of a ROM, or the default one if it has none. Does nothing if the
+    /// ROM did not change.
+    pub fn select_rom(&mut self, rom: &[u8]) {
+        let mut hasher = DefaultHasher::new();
+        rom.hash(&mut hasher);
+        let hash = hasher.finish();
+        if hash != self.rom_hash {
+            self.rom_hash = hash;
+            self.mapping = self.profiles.get(&hash).copied().unwrap_or_default();
+        }
+    }
+
+    /// Whether the mapping differs from the one saved for the current ROM.
+    pub fn has_unsaved_changes(&self) -> bool {
+        self.mapping
+            != self
+                .profiles
+                .get(&self.rom_hash)
+                .copied()
+                .unwrap_or_default()
+    }
+
+    /// Save the mapping for the current ROM.
+    pub fn save_profile(&mut self) -> io::Result<()> {
+        self.profiles.insert(self.rom_hash, self.mapping);
+        let lines: Vec<String> = self
+            .profiles
+            .iter()
+            .map(|(hash, mapping)| format!("{hash:016x} {}", mapping.to_line()))
+            .collect();
+        fs::write(PROFILES_PATH, lines.join("\n"))
+    }
+
+    /// Read the state of all connected gamepads for this frame.
+    ///
+    /// Returns the state of each CHIP-8 key and the last key that was released, which resolves
+    /// the `Fx0A` instruction.
+    pub fn read(&mut self) -> ([bool; 16], Option<u8>) {
+        let mut keys = [false; 16];
+        let mut released = None;
+        let Some(gilrs) = &mut self.gilrs else {
+            return (keys, released);
+        };
+        let key_of = |button| {
+            let index = BUTTONS.iter().position(|&(b, _)| b == button)?;
+            self.mapping.0[index]
+        };
+
+        while let Some(event) = gilrs.next_event() {
+            if let EventType::ButtonReleased(button, _) = event.event {
+                released = key_of(button).or(released);
+            }
+        }
+        for (_, gamepad) in gilrs.gamepads() {
+            for &(button, _) in &BUTTONS {
+                if let (true, Some(key)) = (gamepad.is_pressed(button), key_of(button)) {
+                    keys[key as usize] = true;
+                }
+            }
+        }
+
+        (keys, released)
+    }
+}
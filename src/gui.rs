@@ -1,27 +1,154 @@
-use std::{fs, io::Error, mem::swap};
-
-use e_chip::{Chip8, Quirks};
+#[cfg(target_arch = "wasm32")]
+use std::sync::Mutex;
+use std::{fs, path::Path};
+
+use e_chip::{
+    assemble_octo, bisect_quirks, disassemble, explain_instruction, lockstep_test, test_quirks,
+    AccessKind, BisectReport, BreakpointHit, Chip8, ChromeTrace, Comparison, DriverCommand,
+    EmulatorDriver, Expression, FontSet, FrameEventKind, FramePacing, IndexIncrement,
+    LockstepReport, QuirkTestReport, Quirks, ReferenceTrace, Replay, ReplaySession, StateError,
+    Symbols, Variant, VblankWait, MAX_EXECUTION_SPEED, MAX_TIMER_FREQUENCY, TURBO_FACTOR,
+    VIP_CYCLES_PER_FRAME,
+};
 use egui::{
-    style::ScrollStyle, Align, Button, Color32, Frame, Grid, Id, Label, Layout, Margin, RichText,
-    ScrollArea, Slider, Stroke, TextEdit, Vec2,
+    style::ScrollStyle, text::LayoutJob, Align, Button, Color32, Frame, Grid, Id, Label, Layout,
+    Margin, Modifiers, Pos2, ProgressBar, RichText, ScrollArea, Slider, Stroke, TextEdit,
+    TextFormat, TextStyle, TextureHandle, TextureOptions, Vec2,
+};
+
+#[cfg(feature = "archive")]
+use crate::archive::ArchiveBrowser;
+#[cfg(feature = "gamepad")]
+use crate::gamepad::{GamepadInput, BUTTONS};
+use crate::{
+    attract::AttractMode,
+    autosave::Autosave,
+    buzzer::Tone,
+    i18n::{set_language, tr, tr_halt_reason, tr_with, Language},
+    keymap::{Hotkey, KeypadInput, KeypadLayout, SLOT_KEYS},
+    recent::RecentRoms,
+    settings::Settings,
+    slots::{SaveSlot, SLOT_COUNT},
+    BUZZER_FREQUENCY,
 };
 
 const PC_COLOR: Color32 = Color32::from_rgb(0, 100, 255);
 const I_COLOR: Color32 = Color32::from_rgb(50, 130, 0);
 const TEXT_COLOR: Color32 = Color32::from_gray(200);
+const CHANGED_COLOR: Color32 = Color32::from_rgb(255, 100, 100);
+const BREAKPOINT_COLOR: Color32 = Color32::from_rgb(230, 60, 60);
+const SYMBOL_COLOR: Color32 = Color32::from_rgb(220, 160, 255);
 
 /*
     TODO:
     - Loading files with dialog
 */
 
+/// Which windows are open.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Windows {
+    /// The loaded ROM window.
+    pub rom: bool,
+    /// The display settings window.
+    pub display_settings: bool,
+    /// The execution history window.
+    pub history: bool,
+    /// The disassembly panel.
+    pub disassembly: bool,
+    /// The memory access log window.
+    pub memory_log: bool,
+    /// The watch expressions window.
+    pub watches: bool,
+    /// The save slots window.
+    pub slots: bool,
+    /// The sound window.
+    pub sound: bool,
+    /// The comparison window.
+    pub comparison: bool,
+    /// The quirk bisect window.
+    pub bisect: bool,
+    /// The lockstep test window.
+    pub lockstep: bool,
+    /// The attract mode window.
+    pub attract: bool,
+    /// Whether the display is shown in its own OS window instead of the main one.
+    pub pop_out_display: bool,
+    /// The hotkey and keypad cheatsheet.
+    pub shortcuts: bool,
+    /// The chip8Archive browser.
+    #[cfg(feature = "archive")]
+    pub archive: bool,
+    /// The gamepad mapping window.
+    #[cfg(feature = "gamepad")]
+    pub gamepad: bool,
+}
+
+/// The configuration of the comparison interpreter and the texture its display is rendered to.
+pub struct ComparisonView {
+    pub variant: Variant,
+    pub quirks: Quirks,
+    pub screen: TextureHandle,
+}
+
+/// Whether the state modal writes the machine to a file or reads it from one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StateTransfer {
+    Export,
+    Import,
+    /// Write a plain-text summary of the machine.
+    Summary,
+    /// Stop recording a Chrome trace and write it.
+    ChromeTrace,
+    /// Write the display as a PNG image at its native resolution.
+    Image {
+        black_and_white: bool,
+    },
+    /// Start writing every frame as a PNG image into a folder, with every CHIP-8 pixel drawn as a
+    /// `scale` by `scale` square.
+    FrameDump {
+        scale: usize,
+    },
+    /// Stop recording a replay and write it.
+    SaveReplay,
+    /// Read a replay and play it back.
+    PlayReplay,
+    /// Write the program in memory as a ROM file.
+    ProgramExport,
+    /// Write a disassembly listing of the program in memory.
+    DisassemblyExport,
+    /// Read names of addresses for the debugger views.
+    SymbolImport,
+    /// Read a font to use in place of the font set.
+    FontImport,
+    /// Write the quirks to a file for sharing.
+    QuirksExport,
+    /// Read quirks written by `QuirksExport`.
+    QuirksImport,
+}
+
+/// Something picked in the menu bar that the app has to follow up on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MenuAction {
+    /// A save slot was loaded.
+    SlotLoaded,
+    /// A recent ROM was picked and should be loaded from this path.
+    OpenRom(String),
+}
+
+/// Draw the menu bar.
 #[inline]
+#[allow(clippy::too_many_arguments)]
 pub fn draw_menu(
     interpreter: &mut Chip8,
     ctx: &egui::Context,
-    show_rom: &mut bool,
-    show_display_settings: &mut bool,
-) {
+    windows: &mut Windows,
+    state_transfer: &mut Option<StateTransfer>,
+    settings: &mut Settings,
+    quirk_test: &mut Option<QuirkTestReport>,
+    slots: &mut SlotsView,
+    recent: &mut RecentRoms,
+) -> Option<MenuAction> {
+    let mut action = None;
     egui::TopBottomPanel::top("menu")
         .exact_height(20.0)
         .resizable(false)
@@ -29,59 +156,330 @@ pub fn draw_menu(
         .show(ctx, |ui| {
             egui::menu::bar(ui, |ui| {
                 ui.add_space(5.0);
-                ui.menu_button("Quirks", |ui| {
-                    ui.menu_button("Presets", |ui| {
-                        if ui.button("CHIP-8 (COSMAC-VIP)").clicked() {
-                            interpreter.quirks = Quirks::vip_chip();
-                        }else if ui.button("CHIP-8 (Octo)/XO-CHIP").clicked() {
-                            interpreter.quirks = Quirks::octo_chip();
-                        } else if ui.button("SUPER-CHIP 1.1").clicked() {
-                            interpreter.quirks = Quirks::super_chip1_1();
+                ui.menu_button(tr("File"), |ui| {
+                    ui.menu_button(tr("Recent"), |ui| {
+                        if recent.paths().is_empty() {
+                            ui.label(tr("No recent ROMs"));
+                        }
+                        for path in recent.paths() {
+                            let name = Path::new(path)
+                                .file_name()
+                                .map_or(path.clone(), |name| name.to_string_lossy().to_string());
+                            if ui.add_enabled(!interpreter.is_running(), Button::new(name))
+                                .on_hover_text(path)
+                                .clicked() {
+                                action = Some(MenuAction::OpenRom(path.clone()));
+                                ui.close_menu();
+                            }
+                        }
+                        ui.separator();
+                        if ui.add_enabled(!recent.paths().is_empty(), Button::new(tr("Clear recent ROMs"))).clicked() {
+                            recent.clear();
+                            if let Err(e) = recent.save() {
+                                println!("Could not save the recent ROMs: {e}");
+                            }
+                            ui.close_menu();
                         }
                     });
+                });
+                ui.menu_button(tr("Quirks"), |ui| {
+                    if let Some((platform, preset)) = draw_quirk_options(ui, &mut interpreter.quirks) {
+                        if settings.verify_quirks {
+                            *quirk_test = Some(test_quirks(interpreter, platform, preset));
+                        }
+                    }
+                    ui.separator();
+                    if ui.button(tr("Export quirks"))
+                        .on_hover_text(tr("Save the quirks to a file, to share the exact setup a ROM needs."))
+                        .clicked() {
+                        *state_transfer = Some(StateTransfer::QuirksExport);
+                        ui.close_menu();
+                    }
+                    if ui.button(tr("Import quirks"))
+                        .on_hover_text(tr("Use the quirks saved in a file."))
+                        .clicked() {
+                        *state_transfer = Some(StateTransfer::QuirksImport);
+                        ui.close_menu();
+                    }
+                });
 
-                    ui.checkbox(
-                        &mut interpreter.quirks.bitwise_reset_vf,
-                        "Bitwise operations reset VF",
-                    ).on_hover_text("If true, the 8xy1, 8xy2 and 8xy3 opcodes will set VF to 0.\nIf true, the 8xy1, 8xy2 and 8xy3 opcodes will not modify VF.");
-                    ui.checkbox(
-                        &mut interpreter.quirks.direct_shifting,
-                        "Shift Vx directly",
-                    ).on_hover_text("If true, the 8xy6 and 8xyE opcodes will set Vx to Vx >> 1.\nIf false, the 8xy6 and 8xyE opcodes will set Vx to Vy >> 1.");
-                    ui.checkbox(
-                        &mut interpreter.quirks.jump_to_x,
-                        "Jump with offset Vx",
-                    ).on_hover_text("If true, the Bnnn opcode will jump to nnn + V0.\nIf false, the Bnnn opcode will jump to nnn + Vx.");
-                    ui.checkbox(
-                        &mut interpreter.quirks.save_load_increment,
-                        "Memory access index register increment",
-                    ).on_hover_text("If true, the Fx55 and Fx65 opcodes will not modify I.\nIf false, the Fx55 and Fx65 opcodes will set I to I + x + 1.");
-                    ui.checkbox(
-                        &mut interpreter.quirks.edge_clipping,
-                        "Clip sprites at edges",
-                    ).on_hover_text("If true, the Dxyn opcode will clip sprites that go off the edge of the screen.\nIf false, the Dxyn opcode will wrap sprites that go off the edge of the screen around.");
-                    ui.checkbox(
-                        &mut interpreter.quirks.wait_for_vblank,
-                        "Wait for vblank interrupt",
-                    ).on_hover_text("If true, the Dxyn opcode will wait for a vblank interrupt (happens 60 times a second) before drawing.\nIf false, the Dxyn opcode will draw immediately.");
-                    ui.checkbox(
-                        &mut interpreter.quirks.lowres_scroll,
-                        "Legacy scrolling",
-                    ).on_hover_text("Only applies to SUPER-CHIP: If `true`, the scroll opcodes (`00Cn`, `00FB`, `00FC`) in lowres mode will scroll by half pixels.\nIf `false`, the scroll opcodes in lowres mode will scroll the expected amount of full pixels.");
+                ui.menu_button(tr("State"), |ui| {
+                    if ui.button(tr("Export state"))
+                        .on_hover_text(tr("Save the full machine state to a file, for example to attach an exact reproduction to a bug report."))
+                        .clicked() {
+                        *state_transfer = Some(StateTransfer::Export);
+                        ui.close_menu();
+                    }
+                    if ui.add_enabled(!interpreter.is_running(), Button::new(tr("Import state")))
+                        .on_hover_text(tr("Replace the machine with a state saved by \"Export state\"."))
+                        .clicked() {
+                        *state_transfer = Some(StateTransfer::Import);
+                        ui.close_menu();
+                    }
+                    ui.separator();
+                    if ui.button(tr("Copy state summary"))
+                        .on_hover_text(tr("Copy a plain-text summary of the registers, timers, stack, keypad and current instruction to the clipboard."))
+                        .clicked() {
+                        ctx.copy_text(state_summary(interpreter));
+                        ui.close_menu();
+                    }
+                    if ui.button(tr("Save state summary")).clicked() {
+                        *state_transfer = Some(StateTransfer::Summary);
+                        ui.close_menu();
+                    }
+                    if ui.button(tr("Export memory as ROM"))
+                        .on_hover_text(tr("Save memory from the start address (0x200, or 0x600 on the ETI-660) to the last non-zero byte as a .ch8 file, including any changes made since the ROM was loaded."))
+                        .clicked() {
+                        *state_transfer = Some(StateTransfer::ProgramExport);
+                        ui.close_menu();
+                    }
+                    if ui.button(tr("Export disassembly"))
+                        .on_hover_text(tr("Save a listing of the program in memory with the address, opcode, mnemonic and operands of every instruction, using the current variant and quirks. Sprites and other data are listed as instructions too."))
+                        .clicked() {
+                        *state_transfer = Some(StateTransfer::DisassemblyExport);
+                        ui.close_menu();
+                    }
+                    if ui.button(tr("Import symbols"))
+                        .on_hover_text(tr("Read names of addresses from a file with a name and a hex address on every line, like \"main 0x202\", and show them in the RAM panel, the stack and the disassembly. Loading an Octo source file sets them from its labels."))
+                        .clicked() {
+                        *state_transfer = Some(StateTransfer::SymbolImport);
+                        ui.close_menu();
+                    }
+                    ui.separator();
+                    if ui.button(tr("Export display image"))
+                        .on_hover_text(tr("Save the display as a PNG image with one pixel per CHIP-8 pixel, in the display colors."))
+                        .clicked() {
+                        *state_transfer = Some(StateTransfer::Image { black_and_white: false });
+                        ui.close_menu();
+                    }
+                    if ui.button(tr("Export display image (black and white)"))
+                        .on_hover_text(tr("Save the display as a PNG image with one pixel per CHIP-8 pixel, white on black."))
+                        .clicked() {
+                        *state_transfer = Some(StateTransfer::Image { black_and_white: true });
+                        ui.close_menu();
+                    }
+                    ui.separator();
+                    if let Some(trace) = interpreter.get_chrome_trace() {
+                        if ui.button(tr_with("Save Chrome trace ({} frames)", &[&trace.frames()]))
+                            .on_hover_text(tr("Stop recording and save the trace, which can be opened in Perfetto (ui.perfetto.dev)."))
+                            .clicked() {
+                            *state_transfer = Some(StateTransfer::ChromeTrace);
+                            ui.close_menu();
+                        }
+                    } else if ui.button(tr("Record Chrome trace"))
+                        .on_hover_text(tr("Record executed instructions, frames, draws, key reads, sound and subroutine calls for exploring in Perfetto's timeline."))
+                        .clicked() {
+                        interpreter.start_chrome_trace();
+                        ui.close_menu();
+                    }
+                    if let Some(dump) = interpreter.get_frame_dump() {
+                        let hover = match dump.error() {
+                            Some(e) => e.to_string(),
+                            None => dump.folder().display().to_string(),
+                        };
+                        if ui.button(tr_with("Stop recording frames ({} frames)", &[&dump.frames()]))
+                            .on_hover_text(hover)
+                            .clicked() {
+                            interpreter.stop_frame_dump();
+                            ui.close_menu();
+                        }
+                    } else if ui.button(tr("Record frame sequence"))
+                        .on_hover_text(tr("Write every frame as a numbered PNG image into a folder, for making videos at exactly 60 fps."))
+                        .clicked() {
+                        *state_transfer = Some(StateTransfer::FrameDump { scale: 1 });
+                        ui.close_menu();
+                    }
+                    if ui.button(tr("Save slots"))
+                        .on_hover_text(tr("Ten quick save states with a screenshot of each, saved next to the ROM."))
+                        .clicked() {
+                        windows.slots = true;
+                        ui.close_menu();
+                    }
+                    ui.menu_button(tr("Save to slot"), |ui| {
+                        for slot in 0..SLOT_COUNT {
+                            if ui.button(slot_entry(slots, slot)).clicked() {
+                                slots.save(slot, interpreter);
+                                ui.close_menu();
+                            }
+                        }
+                    });
+                    ui.menu_button(tr("Load from slot"), |ui| {
+                        for slot in 0..SLOT_COUNT {
+                            if ui.add_enabled(slots.slots[slot].is_some(), Button::new(slot_entry(slots, slot))).clicked() {
+                                if slots.load(slot, interpreter) {
+                                    action = Some(MenuAction::SlotLoaded);
+                                }
+                                ui.close_menu();
+                            }
+                        }
+                    });
+                    ui.separator();
+                    match interpreter.get_replay() {
+                        Some(replay) if !replay.is_playing() => {
+                            if ui.button(tr_with("Save replay ({} frames)", &[&replay.len()]))
+                                .on_hover_text(tr("Stop recording and save the input and state hashes of every frame."))
+                                .clicked() {
+                                *state_transfer = Some(StateTransfer::SaveReplay);
+                                ui.close_menu();
+                            }
+                        }
+                        Some(_) => {
+                            if ui.button(tr("Stop replay")).clicked() {
+                                interpreter.stop_replay();
+                                ui.close_menu();
+                            }
+                        }
+                        None => {
+                            if ui.button(tr("Record replay"))
+                                .on_hover_text(tr("Record the keypad input of every frame from now on, with a hash of the machine state after each frame."))
+                                .clicked() {
+                                interpreter.start_replay_recording();
+                                ui.close_menu();
+                            }
+                            if ui.add_enabled(!interpreter.is_running(), Button::new(tr("Play replay")))
+                                .on_hover_text(tr("Restore the state a replay was recorded from and play back its input, reporting the first frame where the machine differs from the recording."))
+                                .clicked() {
+                                *state_transfer = Some(StateTransfer::PlayReplay);
+                                ui.close_menu();
+                            }
+                        }
+                    }
                 });
 
-                ui.menu_button("Settings", |ui| {
-                    ui.checkbox(&mut interpreter.sound_on, "Sound");
-                    if ui.button("Display settings").clicked() {
-                        *show_display_settings = true;
+                ui.menu_button(tr("Settings"), |ui| {
+                    ui.checkbox(&mut interpreter.sound_on, tr("Sound"));
+                    ui.checkbox(&mut settings.run_in_background, tr("Run in background"))
+                        .on_hover_text(tr("If false, the interpreter and sound are paused while the window is minimized."));
+                    ui.checkbox(&mut settings.save_on_exit, tr("Save state on exit"))
+                        .on_hover_text(tr("Save the machine when E-CHIP is closed and offer to resume it on the next launch."));
+                    ui.checkbox(&mut settings.crash_dumps, tr("Write crash dumps"))
+                        .on_hover_text(tr("When the interpreter halts, write the machine state, recent instructions, ROM hash and quirks to a crash_*.txt file to attach to bug reports. It can be loaded with Import state."));
+                    ui.checkbox(&mut settings.history_on_halt, tr("Show history on halt"))
+                        .on_hover_text(tr("When the interpreter halts, open the execution history with the last 64 instructions and the registers they changed."));
+                    ui.checkbox(&mut settings.verify_quirks, tr("Verify quirk presets"))
+                        .on_hover_text(tr("When a quirk preset or variant is picked, run a small test program for every quirk and show whether the interpreter behaves like the platform."));
+                    #[cfg(feature = "hot-reload")]
+                    ui.checkbox(&mut settings.reload_on_change, tr("Reload ROM on change"))
+                        .on_hover_text(tr("When the file of the loaded ROM changes, for example because it was assembled again, reset the interpreter and load it again."));
+                    #[cfg(feature = "database")]
+                    ui.checkbox(&mut settings.use_database, tr("Configure ROMs from the CHIP-8 database"))
+                        .on_hover_text(tr("When a ROM is loaded, look it up in the CHIP-8 database and use the variant, quirks, speed, font and colors it needs."));
+                    ui.checkbox(&mut interpreter.vip_timing, tr("Profile COSMAC VIP timing"))
+                        .on_hover_text(tr("Estimate how many machine cycles each instruction would take on the original COSMAC VIP and how much of a frame they use."));
+                    ui.checkbox(&mut interpreter.vip_speed, tr("Run at COSMAC VIP speed"))
+                        .on_hover_text(tr("Charge every instruction the machine cycles it would take on the original COSMAC VIP and end each frame after a VIP frame's worth of them, so games tuned for the VIP run at its speed. Replaces the cycles per frame setting."));
+                    ui.menu_button(tr("Frame pacing"), |ui| {
+                        ui.radio_value(&mut settings.frame_pacing, FramePacing::Timer, tr("Timer (60 fps)"))
+                            .on_hover_text(tr("The interpreter runs on its own at 60 frames per second."));
+                        ui.radio_value(&mut settings.frame_pacing, FramePacing::Vsync, tr("Display refresh (vsync)"))
+                            .on_hover_text(tr("The interpreter runs one frame every time the screen is redrawn. Avoids tearing on 60 Hz displays, but runs faster on displays with a higher refresh rate."));
+                    });
+                    ui.menu_button(tr("Timer frequency"), |ui| {
+                        ui.radio_value(&mut interpreter.timer_frequency, 60, tr("60 Hz (NTSC)"));
+                        ui.radio_value(&mut interpreter.timer_frequency, 50, tr("50 Hz (PAL)"));
+                        ui.add(Slider::new(&mut interpreter.timer_frequency, 1..=MAX_TIMER_FREQUENCY).text("Hz"))
+                            .on_hover_text(tr("How many times per second the delay and sound timers count down. Frames still run at 60 per second."));
+                    });
+                    ui.menu_button(tr("Font"), |ui| {
+                        let custom = interpreter.custom_font().is_some();
+                        for font_set in FontSet::ALL {
+                            if ui.radio(!custom && interpreter.font_set() == font_set, font_set.name())
+                                .on_hover_text(tr("The hex digits programs draw with Fx29, as they looked on this interpreter. Replaces the font in memory right away."))
+                                .clicked() {
+                                interpreter.set_font_set(font_set);
+                            }
+                        }
+                        ui.separator();
+                        if ui.radio(custom, tr("Load font file"))
+                            .on_hover_text(tr("Read a font binary with 80 bytes of small font (16 digits of 5 bytes), optionally followed by 160 bytes of large font (16 digits of 10 bytes), for programs that expect other digits."))
+                            .clicked() {
+                            *state_transfer = Some(StateTransfer::FontImport);
+                            ui.close_menu();
+                        }
+                    });
+                    ui.menu_button(tr("Input"), |ui| {
+                        ui.label(tr("Keypad layout"));
+                        for layout in KeypadLayout::ALL {
+                            ui.radio_value(&mut settings.keypad.layout, layout, layout.name());
+                        }
+                        ui.separator();
+                        ui.add(Slider::new(&mut settings.keypad.min_hold_frames, 0..=10).text(tr("Minimum hold frames")))
+                            .on_hover_text(tr("Keep keys pressed for at least this many frames, so the interpreter does not miss very short taps."));
+                        ui.checkbox(&mut settings.keypad.ignore_repeat, tr("Ignore key repeat"))
+                            .on_hover_text(tr("If true, the repeated key presses your OS sends while a key is held down are ignored.\nIf false, each of them is seen as a new tap."));
+                        ui.checkbox(&mut settings.keypad.latch_clicks, tr("Latch clicked keys"))
+                            .on_hover_text(tr("If true, clicking a key of the on-screen keypad toggles it on or off, so you can step through key checks without holding it.\nIf false, the key is pressed while the mouse button is held down on it."));
+                        #[cfg(feature = "gamepad")]
+                        if ui.button(tr("Gamepad mapping")).clicked() {
+                            windows.gamepad = true;
+                            ui.close_menu();
+                        }
+                    });
+                    ui.menu_button(tr("Language"), |ui| {
+                        for language in Language::ALL {
+                            if ui.radio_value(&mut settings.language, language, language.name()).clicked() {
+                                set_language(language);
+                            }
+                        }
+                    });
+                    ui.checkbox(&mut windows.pop_out_display, tr("Pop out display"))
+                        .on_hover_text(tr("Show the display in its own window, for example on a second monitor."));
+                    if ui.button(tr("Display settings")).clicked() {
+                        windows.display_settings = true;
+                        ui.close_menu();
+                    }
+                    if ui.button( tr("Show loaded ROM")).clicked() {
+                        windows.rom = true;
+                        ui.close_menu();
+                    }
+                    if ui.button(tr("Attract mode"))
+                        .on_hover_text(tr("Cycle through the ROMs of a folder, for exhibitions or for testing a whole library."))
+                        .clicked() {
+                        windows.attract = true;
+                        ui.close_menu();
+                    }
+                    ui.checkbox(&mut windows.disassembly, tr("Show disassembly"))
+                        .on_hover_text(tr("Show the instructions around the program counter in a panel next to the RAM. Click an instruction to toggle a breakpoint on it."));
+                    if ui.button(tr("Show execution history")).clicked() {
+                        windows.history = true;
+                        ui.close_menu();
+                    }
+                    if ui.button(tr("Show memory access log")).clicked() {
+                        windows.memory_log = true;
+                        ui.close_menu();
+                    }
+                    if ui.button(tr("Show watches")).clicked() {
+                        windows.watches = true;
+                        ui.close_menu();
+                    }
+                    if ui.button(tr("Show sound")).clicked() {
+                        windows.sound = true;
+                        ui.close_menu();
+                    }
+                    if ui.button(tr("Shortcuts")).clicked() {
+                        windows.shortcuts = true;
+                        ui.close_menu();
+                    }
+                    if ui.button(tr("Compare variants and quirks")).clicked() {
+                        windows.comparison = true;
+                        ui.close_menu();
+                    }
+                    if ui.button(tr("Find required quirks")).clicked() {
+                        windows.bisect = true;
+                        ui.close_menu();
+                    }
+                    if ui.button(tr("Test against a reference trace")).clicked() {
+                        windows.lockstep = true;
                         ui.close_menu();
                     }
-                    if ui.button( "Show loaded ROM").clicked() {
-                        *show_rom = true;
+                    #[cfg(feature = "archive")]
+                    if ui.button(tr("Browse chip8Archive")).clicked() {
+                        windows.archive = true;
                         ui.close_menu();
                     }
-                    if ui.button("Clear persistent flags")
-                        .on_hover_text("Persistent flags were introduced by SUPER-CHIP to allow saving and loading bytes to persistent storage. E-CHIP stores them in \"{path to E-CHIP}\\flags.dat\".")
+                    let flags_path = interpreter.persistent_flags_location().unwrap_or_else(|| tr("memory only").to_string());
+                    if ui.button(tr("Clear persistent flags"))
+                        .on_hover_text(tr_with("Persistent flags were introduced by SUPER-CHIP to allow saving and loading bytes to persistent storage. E-CHIP stores them for every ROM, for the loaded one in \"{}\".", &[&flags_path]))
                         .clicked() {
                         interpreter.clear_persistent_flags();
                     }
@@ -93,152 +491,1803 @@ pub fn draw_menu(
                 });
             });
         });
+    action
+}
+
+/// Draw the quirk presets and options. Returns the name and quirks of the preset that was picked,
+/// if any.
+fn draw_quirk_options(ui: &mut egui::Ui, quirks: &mut Quirks) -> Option<(&'static str, Quirks)> {
+    let mut picked = None;
+    ui.menu_button(tr("Presets"), |ui| {
+        for (name, preset) in [
+            ("CHIP-8 (COSMAC-VIP)", Quirks::vip_chip()),
+            ("CHIP-8 (Octo)/XO-CHIP", Quirks::octo_chip()),
+            ("CHIP-48", Quirks::chip48()),
+            ("ETI-660", Quirks::vip_chip()),
+            ("SUPER-CHIP 1.0", Quirks::super_chip1_0()),
+            ("SUPER-CHIP 1.1", Quirks::super_chip1_1()),
+        ] {
+            if ui.button(name).clicked() {
+                *quirks = preset;
+                picked = Some((name, preset));
+            }
+        }
+    });
+
+    ui.checkbox(
+        &mut quirks.bitwise_reset_vf,
+        tr("Bitwise operations reset VF"),
+    ).on_hover_text(tr("If true, the 8xy1, 8xy2 and 8xy3 opcodes will set VF to 0.\nIf false, the 8xy1, 8xy2 and 8xy3 opcodes will not modify VF."));
+    ui.checkbox(
+        &mut quirks.direct_shifting,
+        tr("Shift Vx directly"),
+    ).on_hover_text(tr("If true, the 8xy6 and 8xyE opcodes will set Vx to Vx >> 1.\nIf false, the 8xy6 and 8xyE opcodes will set Vx to Vy >> 1."));
+    ui.checkbox(
+        &mut quirks.jump_to_x,
+        tr("Jump with offset Vx"),
+    ).on_hover_text(tr("If true, the Bnnn opcode will jump to nnn + V0.\nIf false, the Bnnn opcode will jump to nnn + Vx."));
+    ui.menu_button(tr("Memory access index register increment"), |ui| {
+        ui.radio_value(
+            &mut quirks.save_load_increment,
+            IndexIncrement::XPlusOne,
+            "x + 1",
+        )
+        .on_hover_text(tr(
+            "The Fx55 and Fx65 opcodes will set I to I + x + 1, like the COSMAC-VIP.",
+        ));
+        ui.radio_value(&mut quirks.save_load_increment, IndexIncrement::X, "x")
+            .on_hover_text(tr(
+                "The Fx55 and Fx65 opcodes will set I to I + x, like CHIP-48 and SUPER-CHIP 1.0.",
+            ));
+        ui.radio_value(
+            &mut quirks.save_load_increment,
+            IndexIncrement::Unchanged,
+            tr("None"),
+        )
+        .on_hover_text(tr(
+            "The Fx55 and Fx65 opcodes will not modify I, like SUPER-CHIP 1.1.",
+        ));
+    });
+    ui.checkbox(
+        &mut quirks.edge_clipping,
+        tr("Clip sprites at edges"),
+    ).on_hover_text(tr("If true, the Dxyn opcode will clip sprites that go off the edge of the screen.\nIf false, the Dxyn opcode will wrap sprites that go off the edge of the screen around."));
+    ui.menu_button(tr("Wait for vblank interrupt"), |ui| {
+        ui.radio_value(&mut quirks.wait_for_vblank, VblankWait::Never, tr("Never"))
+            .on_hover_text(tr("The Dxyn opcode will draw immediately."));
+        ui.radio_value(&mut quirks.wait_for_vblank, VblankWait::LowresOnly, tr("Lowres only"))
+            .on_hover_text(tr("The Dxyn opcode will wait for a vblank interrupt in lowres mode and draw immediately in highres mode, like SUPER-CHIP 1.1 on the HP48."));
+        ui.radio_value(&mut quirks.wait_for_vblank, VblankWait::Always, tr("Always"))
+            .on_hover_text(tr("The Dxyn opcode will wait for a vblank interrupt (happens 60 times a second) before drawing."));
+    });
+    ui.checkbox(
+        &mut quirks.lowres_scroll,
+        tr("Legacy scrolling"),
+    ).on_hover_text(tr("Only applies to SUPER-CHIP: If `true`, the scroll opcodes (`00Cn`, `00FB`, `00FC`) in lowres mode will scroll by half pixels.\nIf `false`, the scroll opcodes in lowres mode will scroll the expected amount of full pixels."));
+    ui.checkbox(
+        &mut quirks.lowres_tall_sprites,
+        tr("8x16 sprites in lowres"),
+    ).on_hover_text(tr("Only applies to SUPER-CHIP: If `true`, the Dxy0 opcode in lowres mode will draw 8x16 sprites, like SUPER-CHIP 1.0.\nIf `false`, the Dxy0 opcode will draw 16x16 sprites in both modes."));
+    ui.checkbox(
+        &mut quirks.resolution_clear,
+        tr("Clear display on resolution change"),
+    ).on_hover_text(tr("Only applies to SUPER-CHIP: If `true`, the resolution opcodes (`00FE`, `00FF`) will clear the display, like Octo.\nIf `false`, the resolution opcodes will keep the display contents, like the original SUPER-CHIP."));
+    ui.checkbox(
+        &mut quirks.deterministic_random,
+        tr("Deterministic random numbers"),
+    ).on_hover_text(tr("If true, the Cxnn opcode will get its numbers from a fixed sequence, which gives the same numbers in the same order every run.\nIf false, the Cxnn opcode will use a true random number generator."));
+    ui.checkbox(
+        &mut quirks.key_wait_on_press,
+        tr("Key wait finishes on press"),
+    ).on_hover_text(tr("If true, the Fx0A opcode will finish as soon as a key is pressed, like many modern interpreters.\nIf false, the Fx0A opcode will wait until the key is released again, like the COSMAC-VIP."));
+    ui.checkbox(
+        &mut quirks.memory_wrap,
+        tr("Wrap memory accesses"),
+    ).on_hover_text(tr("If true, the opcodes that access memory at I, and jumps, calls and returns, will wrap addresses past the end of memory around to 0x000.\nIf false, the interpreter will halt when they go past the end."));
+    picked
+}
+
+/// The input fields of the load ROM modal.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct LoadView {
+    /// The path of the ROM file.
+    pub rom_path: String,
+    /// Pasted program bytes, used instead of the path if not empty.
+    pub hex: String,
+    pub error: Option<String>,
 }
 
+/// A ROM file picked in the browser, until the load modal loads it: its name and contents.
+#[cfg(target_arch = "wasm32")]
+static PICKED_ROM: Mutex<Option<(String, Vec<u8>)>> = Mutex::new(None);
+
+/// Ask for the path of a ROM file, or for pasted program bytes, and load it. In the browser, the
+/// ROM file is picked with the file dialog instead.
+///
+/// Returns `true` if a ROM was loaded.
 #[inline]
 pub fn draw_load_modal(
+    driver: &EmulatorDriver,
     interpreter: &mut Chip8,
     ctx: &egui::Context,
     show_load_modal: &mut bool,
     rom: &mut Vec<u8>,
-    rom_path: &mut String,
-    load_error: &mut Option<Error>,
-) {
+    view: &mut LoadView,
+    loaded_rom_path: &mut Option<String>,
+) -> bool {
+    let mut loaded = false;
     egui::Modal::new(Id::new("Load")).show(ctx, |ui| {
         ui.heading("Load ROM");
 
-        ui.add(TextEdit::singleline(rom_path).hint_text("Enter path..."));
+        // The ROM and the path it was loaded from, if a load was attempted
+        let mut attempt = None;
+
+        #[cfg(not(target_arch = "wasm32"))]
+        ui.add(TextEdit::singleline(&mut view.rom_path).hint_text("Enter path..."));
+        #[cfg(target_arch = "wasm32")]
+        {
+            if ui.button("Choose file...").clicked() {
+                pick_rom_file(ctx);
+            }
+            // Browser files have no path to reload them from
+            if let Some((name, bytes)) = PICKED_ROM.lock().unwrap().take() {
+                attempt = Some((parse_rom_file(&name, bytes, interpreter), None));
+            }
+        }
+        ui.label("or paste the program as hex bytes or Octo :byte lists");
+        ui.add(
+            TextEdit::multiline(&mut view.hex)
+                .hint_text("00E0 A22A 600C ...")
+                .desired_rows(4),
+        );
 
         ui.horizontal(|ui| {
             if ui.button("Load program").clicked() {
-                let loaded_rom = fs::read(&rom_path);
-                if let Err(e) = loaded_rom {
-                    *load_error = Some(e);
+                // Pasted bytes take precedence over the path
+                attempt = Some(if !view.hex.trim().is_empty() {
+                    let loaded_rom = parse_hex_rom(&view.hex)
+                        .map(|rom| (rom, Symbols::default()))
+                        .and_then(|loaded_rom| {
+                            interpreter
+                                .check_program(&loaded_rom.0)
+                                .map(|()| loaded_rom)
+                                .map_err(|e| e.to_string())
+                        });
+                    (loaded_rom, None)
                 } else {
-                    *load_error = None;
-                    *rom = loaded_rom.unwrap();
-
-                    interpreter.reset();
-                    interpreter.load_program(&rom);
-
-                    *show_load_modal = false;
-                    rom_path.clear();
-                }
+                    (
+                        read_rom_file(&view.rom_path, interpreter),
+                        Some(view.rom_path.clone()),
+                    )
+                });
             }
 
             if ui.button("Cancel").clicked() {
                 *show_load_modal = false;
-                rom_path.clear();
+                *view = LoadView::default();
             }
         });
 
-        if let Some(e) = load_error {
+        match attempt {
+            Some((Err(e), _)) => view.error = Some(e),
+            Some((Ok((loaded_rom, symbols)), path)) => {
+                *rom = loaded_rom;
+                interpreter.symbols = symbols;
+                driver.send(DriverCommand::LoadRom(rom.clone()));
+
+                *loaded_rom_path = path;
+                loaded = true;
+                *show_load_modal = false;
+                *view = LoadView::default();
+            }
+            None => {}
+        }
+
+        if let Some(e) = &view.error {
             ui.label(format!("Could not load ROM: {e}"));
         }
     });
+
+    loaded
+}
+
+/// Open the file dialog of the browser, to put the picked file in [`PICKED_ROM`].
+#[cfg(target_arch = "wasm32")]
+fn pick_rom_file(ctx: &egui::Context) {
+    use wasm_bindgen::{closure::Closure, JsCast};
+    use wasm_bindgen_futures::JsFuture;
+
+    let Some(input) = web_sys::window()
+        .and_then(|window| window.document())
+        .and_then(|document| document.create_element("input").ok())
+        .and_then(|element| element.dyn_into::<web_sys::HtmlInputElement>().ok())
+    else {
+        return;
+    };
+    input.set_type("file");
+    let ctx = ctx.clone();
+    let picker = input.clone();
+    let on_change = Closure::once_into_js(move || {
+        let Some(file) = picker.files().and_then(|files| files.get(0)) else {
+            return;
+        };
+        wasm_bindgen_futures::spawn_local(async move {
+            if let Ok(buffer) = JsFuture::from(file.array_buffer()).await {
+                let bytes = js_sys::Uint8Array::new(&buffer).to_vec();
+                *PICKED_ROM.lock().unwrap() = Some((file.name(), bytes));
+                ctx.request_repaint();
+            }
+        });
+    });
+    input.set_onchange(Some(on_change.unchecked_ref()));
+    input.click();
 }
 
+/// Read a ROM file for the interpreter, and its symbols. Octo source files are assembled for where
+/// the program is loaded, and their labels become the symbols.
+pub fn read_rom_file(path: &str, interpreter: &Chip8) -> Result<(Vec<u8>, Symbols), String> {
+    let bytes = fs::read(path).map_err(|e| e.to_string())?;
+    parse_rom_file(path, bytes, interpreter)
+}
+
+/// Turn the contents of a ROM file named `name` into a ROM for the interpreter, and its symbols.
+fn parse_rom_file(
+    name: &str,
+    bytes: Vec<u8>,
+    interpreter: &Chip8,
+) -> Result<(Vec<u8>, Symbols), String> {
+    let loaded_rom = if is_octo_path(name) {
+        String::from_utf8(bytes)
+            .map_err(|e| e.to_string())
+            .and_then(|source| assemble_octo(&source, interpreter.start_address))
+            .map(|program| (program.rom, Symbols::from_labels(&program.labels)))?
+    } else {
+        (bytes, Symbols::default())
+    };
+    interpreter
+        .check_program(&loaded_rom.0)
+        .map_err(|e| e.to_string())?;
+    Ok(loaded_rom)
+}
+
+/// Assemble a ROM from pasted text: hex bytes (`00E0 A22A`, `0x00, 0xE0` or `00e0a22a`) or Octo
+/// `:byte` lists, whose values can also be decimal or `0b` binary.
+pub fn parse_hex_rom(text: &str) -> Result<Vec<u8>, String> {
+    let tokens = text
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|token| !token.is_empty());
+
+    let rom = if text.contains(":byte") {
+        tokens
+            .filter(|&token| token != ":byte")
+            .map(|token| {
+                let value = if let Some(hex) = token.strip_prefix("0x") {
+                    u8::from_str_radix(hex, 16)
+                } else if let Some(binary) = token.strip_prefix("0b") {
+                    u8::from_str_radix(binary, 2)
+                } else {
+                    token.parse()
+                };
+                value.map_err(|_| format!("\"{token}\" is not a byte"))
+            })
+            .collect::<Result<Vec<u8>, String>>()?
+    } else {
+        let digits: String = tokens
+            .map(|token| token.strip_prefix("0x").unwrap_or(token))
+            .collect();
+        if let Some(c) = digits.chars().find(|c| !c.is_ascii_hexdigit()) {
+            return Err(format!("\"{c}\" is not a hex digit"));
+        }
+        if !digits.len().is_multiple_of(2) {
+            return Err("the last byte is missing a hex digit".into());
+        }
+        (0..digits.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&digits[i..i + 2], 16).unwrap())
+            .collect()
+    };
+
+    if rom.is_empty() {
+        return Err("no bytes were pasted".into());
+    }
+    Ok(rom)
+}
+
+/// Export the machine state to a file or import it from one.
+///
+/// Returns `true` if the machine state was exported or imported.
 #[inline]
-pub fn draw_display_settings(
+pub fn draw_state_modal(
+    interpreter: &mut Chip8,
     ctx: &egui::Context,
-    background_color: &mut Color32,
-    fill_color: &mut Color32,
+    state_transfer: &mut Option<StateTransfer>,
+    state_path: &mut String,
+    state_error: &mut Option<String>,
+    colors: [Color32; 4],
+) -> bool {
+    let Some(transfer) = *state_transfer else {
+        return false;
+    };
+    let mut transferred = false;
+
+    egui::Modal::new(Id::new("State")).show(ctx, |ui| {
+        ui.heading(match transfer {
+            StateTransfer::Export => "Export state",
+            StateTransfer::Import => "Import state",
+            StateTransfer::Summary => "Save state summary",
+            StateTransfer::ChromeTrace => "Save Chrome trace",
+            StateTransfer::Image { .. } => "Export display image",
+            StateTransfer::FrameDump { .. } => "Record frame sequence",
+            StateTransfer::SaveReplay => "Save replay",
+            StateTransfer::PlayReplay => "Play replay",
+            StateTransfer::ProgramExport => "Export memory as ROM",
+            StateTransfer::DisassemblyExport => "Export disassembly",
+            StateTransfer::SymbolImport => "Import symbols",
+            StateTransfer::FontImport => "Load font",
+            StateTransfer::QuirksExport => "Export quirks",
+            StateTransfer::QuirksImport => "Import quirks",
+        });
+
+        ui.add(TextEdit::singleline(state_path).hint_text("Enter path..."));
+        if let Some(StateTransfer::FrameDump { scale }) = state_transfer {
+            ui.add(Slider::new(scale, 1..=10).text("Scale"))
+                .on_hover_text("1 writes the native resolution. SUPER-CHIP lowres frames are doubled so all frames have the same size.");
+        }
+
+        ui.horizontal(|ui| {
+            let button = match transfer {
+                StateTransfer::Export
+                | StateTransfer::ProgramExport
+                | StateTransfer::DisassemblyExport
+                | StateTransfer::QuirksExport => "Export",
+                StateTransfer::Import
+                | StateTransfer::SymbolImport
+                | StateTransfer::QuirksImport => "Import",
+                StateTransfer::Summary
+                | StateTransfer::ChromeTrace
+                | StateTransfer::Image { .. }
+                | StateTransfer::SaveReplay => "Save",
+                StateTransfer::FrameDump { .. } => "Start",
+                StateTransfer::PlayReplay => "Play",
+                StateTransfer::FontImport => "Load",
+            };
+            if ui.button(button).clicked() {
+                let result = match transfer {
+                    StateTransfer::Export => {
+                        fs::write(&state_path, interpreter.save_state()).map_err(|e| e.to_string())
+                    }
+                    StateTransfer::Import => fs::read(&state_path)
+                        .map_err(|e| e.to_string())
+                        .and_then(|state| {
+                            // Crash dumps contain a save state too
+                            interpreter
+                                .load_state(&state)
+                                .or_else(|e| match String::from_utf8(state) {
+                                    Ok(dump) => interpreter.load_crash_dump(&dump),
+                                    Err(_) => Err(e),
+                                })
+                                .map_err(|e| e.to_string())
+                        }),
+                    StateTransfer::Summary => fs::write(&state_path, state_summary(interpreter))
+                        .map_err(|e| e.to_string()),
+                    StateTransfer::ChromeTrace => {
+                        let json = interpreter
+                            .get_chrome_trace()
+                            .map(ChromeTrace::to_json)
+                            .unwrap_or_default();
+                        fs::write(&state_path, json).map_err(|e| e.to_string())
+                    }
+                    StateTransfer::Image { black_and_white } => {
+                        let png = if black_and_white {
+                            interpreter.display_png([
+                                Color32::BLACK.to_array(),
+                                Color32::WHITE.to_array(),
+                                Color32::WHITE.to_array(),
+                                Color32::WHITE.to_array(),
+                            ])
+                        } else {
+                            interpreter.display_png(colors.map(|color| color.to_array()))
+                        };
+                        fs::write(&state_path, png).map_err(|e| e.to_string())
+                    }
+                    StateTransfer::FrameDump { scale } => interpreter
+                        .start_frame_dump(&state_path, scale, colors.map(|color| color.to_array()))
+                        .map_err(|e| e.to_string()),
+                    StateTransfer::ProgramExport => fs::write(&state_path, interpreter.program_in_memory())
+                        .map_err(|e| e.to_string()),
+                    StateTransfer::SymbolImport => fs::read_to_string(&state_path)
+                        .map_err(|e| e.to_string())
+                        .and_then(|text| Symbols::parse(&text))
+                        .map(|symbols| interpreter.symbols = symbols),
+                    StateTransfer::FontImport => fs::read(&state_path)
+                        .map_err(|e| e.to_string())
+                        .and_then(|font| interpreter.load_font(&font).map_err(|e| e.to_string())),
+                    StateTransfer::QuirksExport => fs::write(&state_path, interpreter.quirks.to_toml())
+                        .map_err(|e| e.to_string()),
+                    StateTransfer::QuirksImport => fs::read_to_string(&state_path)
+                        .map_err(|e| e.to_string())
+                        .and_then(|text| Quirks::from_toml(&text))
+                        .map(|quirks| interpreter.quirks = quirks),
+                    StateTransfer::DisassemblyExport => {
+                        let listing: Vec<String> = interpreter
+                            .disassemble_program()
+                            .iter()
+                            .map(ToString::to_string)
+                            .collect();
+                        fs::write(&state_path, listing.join("\n")).map_err(|e| e.to_string())
+                    }
+                    StateTransfer::SaveReplay => {
+                        // .txt files only have the input, for sharing with other tools
+                        let replay = match interpreter.get_replay() {
+                            Some(session) if is_text_path(state_path) => {
+                                session.replay().to_text().into_bytes()
+                            }
+                            Some(session) => session.replay().to_bytes(),
+                            None => Vec::new(),
+                        };
+                        fs::write(&state_path, replay).map_err(|e| e.to_string())
+                    }
+                    StateTransfer::PlayReplay => fs::read(&state_path)
+                        .map_err(|e| e.to_string())
+                        .and_then(|data| {
+                            if is_text_path(state_path) {
+                                // Text recordings play from the current state
+                                let text = String::from_utf8_lossy(&data);
+                                Replay::from_text(&text, interpreter.save_state())
+                            } else {
+                                Replay::from_bytes(&data).map_err(|e| e.to_string())
+                            }
+                        })
+                        .and_then(|replay| {
+                            interpreter
+                                .start_replay_playback(replay)
+                                .map_err(|e| e.to_string())
+                        }),
+                };
+
+                if let Err(e) = result {
+                    *state_error = Some(e);
+                } else {
+                    if transfer == StateTransfer::ChromeTrace {
+                        interpreter.stop_chrome_trace();
+                    }
+                    if transfer == StateTransfer::SaveReplay {
+                        interpreter.stop_replay();
+                    }
+                    transferred = matches!(transfer, StateTransfer::Export | StateTransfer::Import);
+                    *state_error = None;
+                    *state_transfer = None;
+                    state_path.clear();
+                }
+            }
+
+            if ui.button("Cancel").clicked() {
+                *state_error = None;
+                *state_transfer = None;
+                state_path.clear();
+            }
+        });
+
+        if let Some(e) = state_error {
+            ui.label(format!("Could not transfer state: {e}"));
+        }
+    });
+
+    transferred
+}
+
+/// Check if a path is an `.8o` Octo source file.
+fn is_octo_path(path: &str) -> bool {
+    Path::new(path)
+        .extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("8o"))
+}
+
+/// Check if a path is a `.txt` file.
+fn is_text_path(path: &str) -> bool {
+    Path::new(path)
+        .extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("txt"))
+}
+
+/// The quick save slots and the textures their screenshots are rendered to.
+pub struct SlotsView {
+    /// The path of the ROM the slots are saved next to, if it was loaded from a file.
+    pub rom: Option<String>,
+    pub slots: [Option<SaveSlot>; SLOT_COUNT],
+    pub thumbnails: Vec<TextureHandle>,
+    /// Why the last slot could not be saved or loaded.
+    pub error: Option<String>,
+}
+
+impl SlotsView {
+    /// Switch to the slots of another ROM, reading them from its folder.
+    pub fn set_rom(&mut self, rom: Option<&str>) {
+        if self.rom.as_deref() != rom {
+            self.rom = rom.map(str::to_string);
+            self.slots = SaveSlot::load_all(rom);
+            self.error = None;
+        }
+    }
+
+    /// Save the machine to a slot and write its file.
+    pub fn save(&mut self, slot: usize, interpreter: &Chip8) {
+        let saved = SaveSlot::capture(interpreter);
+        self.error = saved
+            .save(self.rom.as_deref(), slot)
+            .err()
+            .map(|e| format!("Could not save slot {}: {e}", slot + 1));
+        self.slots[slot] = Some(saved);
+    }
+
+    /// Load the machine from a slot. Returns `true` if it was loaded.
+    pub fn load(&mut self, slot: usize, interpreter: &mut Chip8) -> bool {
+        let Some(saved) = &self.slots[slot] else {
+            return false;
+        };
+        match interpreter.load_state(&saved.state) {
+            Ok(()) => {
+                self.error = None;
+                true
+            }
+            Err(e) => {
+                self.error = Some(format!("Could not load slot {}: {e}", slot + 1));
+                false
+            }
+        }
+    }
+
+    /// Empty a slot and delete its file.
+    pub fn delete(&mut self, slot: usize) {
+        SaveSlot::delete(self.rom.as_deref(), slot);
+        self.slots[slot] = None;
+    }
+}
+
+/// Show the save slots with their screenshots, and save the machine to them or load it from them.
+///
+/// Returns `true` if a slot was loaded.
+pub fn draw_save_slots(
+    interpreter: &mut Chip8,
+    view: &mut SlotsView,
+    colors: [Color32; 4],
     open: &mut bool,
-) {
-    egui::Window::new("Display settings")
+    ctx: &egui::Context,
+) -> bool {
+    let mut loaded = false;
+    egui::Window::new("Save slots")
+        .open(open)
+        .auto_sized()
+        .show(ctx, |ui| {
+            ui.visuals_mut().override_text_color = Some(TEXT_COLOR);
+
+            Grid::new("slots").num_columns(3).show(ui, |ui| {
+                for i in 0..SLOT_COUNT {
+                    ui.vertical(|ui| {
+                        ui.label(format!("Slot {}", i + 1));
+                        if let Some(saved) = &view.slots[i] {
+                            ui.label(saved.timestamp());
+                        }
+                    });
+                    let thumbnail = &mut view.thumbnails[i];
+                    match &view.slots[i] {
+                        Some(saved) => {
+                            thumbnail.set(
+                                saved.thumbnail(colors[0], colors[1]),
+                                TextureOptions::NEAREST,
+                            );
+                            let height = 128.0 * saved.height() as f32 / saved.width.max(1) as f32;
+                            ui.add(egui::Image::new((thumbnail.id(), Vec2::new(128.0, height))))
+                                .on_hover_text(format!("Frame {}", saved.frame));
+                        }
+                        None => {
+                            ui.add_sized(Vec2::new(128.0, 64.0), Label::new("Empty"));
+                        }
+                    }
+
+                    ui.vertical(|ui| {
+                        if ui.button("Save").clicked() {
+                            view.save(i, interpreter);
+                        }
+                        if view.slots[i].is_some() {
+                            if ui.button("Load").clicked() {
+                                loaded |= view.load(i, interpreter);
+                            }
+                            if ui.button("Delete").clicked() {
+                                view.delete(i);
+                            }
+                        }
+                    });
+                    ui.end_row();
+                }
+            });
+
+            if let Some(e) = &view.error {
+                ui.colored_label(Color32::RED, e);
+            }
+        });
+    loaded
+}
+
+/// Offer to resume the machine saved when the app was last closed.
+///
+/// Returns `true` if the saved machine was resumed.
+#[inline]
+pub fn draw_resume_modal(
+    interpreter: &mut Chip8,
+    driver: &EmulatorDriver,
+    ctx: &egui::Context,
+    autosave: &mut Option<Autosave>,
+    rom: &mut Vec<u8>,
+    loaded_rom_path: &mut Option<String>,
+    resume_error: &mut Option<StateError>,
+) -> bool {
+    let Some(save) = autosave else {
+        return false;
+    };
+    let mut decided = false;
+    let mut resumed = false;
+
+    egui::Modal::new(Id::new("Resume")).show(ctx, |ui| {
+        ui.heading("Resume");
+        ui.label(format!("Continue {} where you left off?", save.rom_path));
+
+        ui.horizontal(|ui| {
+            if ui.button("Resume").clicked() {
+                match interpreter.load_state(&save.state) {
+                    Ok(()) => {
+                        // The ROM is only needed for resetting, its program is already in memory
+                        *rom = fs::read(&save.rom_path).unwrap_or_default();
+                        driver.send(DriverCommand::SetRom(rom.clone()));
+                        *loaded_rom_path = Some(save.rom_path.clone());
+                        *resume_error = None;
+                        decided = true;
+                        resumed = true;
+                    }
+                    Err(e) => *resume_error = Some(e),
+                }
+            } else if ui.button("Start fresh").clicked() {
+                Autosave::delete();
+                *resume_error = None;
+                decided = true;
+            }
+        });
+
+        if let Some(e) = resume_error {
+            ui.label(format!("Could not resume: {e}"));
+        }
+    });
+
+    if decided {
+        *autosave = None;
+    }
+    resumed
+}
+
+/// How the display colors are changed after the palette is applied.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ColorMode {
+    /// Show the palette as it is.
+    #[default]
+    Palette,
+    /// Show the palette in shades of gray.
+    Grayscale,
+    /// Show the background as black and the other colors as white, or the other way around if the
+    /// background is the lighter color.
+    Monochrome,
+}
+
+/// Accessibility filters for the display that work with any palette.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct DisplayFilter {
+    pub mode: ColorMode,
+    /// Invert the colors after `mode` is applied.
+    pub invert: bool,
+}
+
+impl DisplayFilter {
+    /// Get the colors the display is rendered with.
+    pub fn apply(&self, colors: [Color32; 4]) -> [Color32; 4] {
+        let luminance =
+            |c: Color32| (c.r() as u32 * 299 + c.g() as u32 * 587 + c.b() as u32 * 114) / 1000;
+        let colors = match self.mode {
+            ColorMode::Palette => colors,
+            ColorMode::Grayscale => colors.map(|c| Color32::from_gray(luminance(c) as u8)),
+            ColorMode::Monochrome if luminance(colors[1]) < luminance(colors[0]) => [
+                Color32::WHITE,
+                Color32::BLACK,
+                Color32::BLACK,
+                Color32::BLACK,
+            ],
+            ColorMode::Monochrome => [
+                Color32::BLACK,
+                Color32::WHITE,
+                Color32::WHITE,
+                Color32::WHITE,
+            ],
+        };
+        if self.invert {
+            colors.map(|c| Color32::from_rgb(255 - c.r(), 255 - c.g(), 255 - c.b()))
+        } else {
+            colors
+        }
+    }
+}
+
+/// Get the display colors for a background and fill color. XO-CHIP pixels that are on in the second
+/// plane or in both planes get shades between them.
+pub fn palette(background_color: Color32, fill_color: Color32) -> [Color32; 4] {
+    [
+        background_color,
+        fill_color,
+        background_color.lerp_to_gamma(fill_color, 1.0 / 3.0),
+        background_color.lerp_to_gamma(fill_color, 2.0 / 3.0),
+    ]
+}
+
+#[inline]
+pub fn draw_display_settings(
+    ctx: &egui::Context,
+    colors: &mut [Color32; 4],
+    filter: &mut DisplayFilter,
+    open: &mut bool,
+) {
+    egui::Window::new("Display settings")
+        .open(open)
+        .auto_sized()
+        .show(ctx, |ui| {
+            ui.scope_builder(egui::UiBuilder::new(), |ui| {
+                Grid::new("colors")
+                    .num_columns(2)
+                    .spacing([40.0, 4.0])
+                    .show(ui, |ui| {
+                        let labels = [
+                            "Background color",
+                            "Fill color",
+                            "Second plane color",
+                            "Both planes color",
+                        ];
+                        for (i, (color, label)) in colors.iter_mut().zip(labels).enumerate() {
+                            let mut rgb = [color.r(), color.g(), color.b()];
+                            let label = ui.label(label);
+                            if i >= 2 {
+                                label.on_hover_text("Only used by XO-CHIP.");
+                            }
+                            ui.color_edit_button_srgb(&mut rgb);
+                            *color = Color32::from_rgb(rgb[0], rgb[1], rgb[2]);
+                            ui.end_row();
+                        }
+                    });
+            });
+
+            if ui.button("Swap").clicked() {
+                colors.swap(0, 1);
+            }
+
+            ui.horizontal(|ui| {
+                if ui.button("Default").clicked() {
+                    *colors = palette(Color32::BLACK, Color32::WHITE);
+                }
+                if ui.button("Octo").clicked() {
+                    *colors = ["#996600", "#FFCC00", "#FF6600", "#662200"]
+                        .map(|hex| Color32::from_hex(hex).unwrap());
+                }
+                if ui.button("Matrix").clicked() {
+                    *colors = palette(Color32::BLACK, Color32::GREEN);
+                }
+            });
+
+            // Pairs that stay distinguishable with color vision deficiencies, from the Okabe-Ito palette
+            ui.label("Accessible");
+            ui.horizontal(|ui| {
+                if ui
+                    .button("Deuteranopia/Protanopia")
+                    .on_hover_text("Blue and yellow")
+                    .clicked()
+                {
+                    *colors = palette(
+                        Color32::from_hex("#0072B2").unwrap(),
+                        Color32::from_hex("#F0E442").unwrap(),
+                    );
+                }
+                if ui
+                    .button("Tritanopia")
+                    .on_hover_text("Dark teal and light vermillion")
+                    .clicked()
+                {
+                    *colors = palette(
+                        Color32::from_hex("#00363D").unwrap(),
+                        Color32::from_hex("#FFA07A").unwrap(),
+                    );
+                }
+                if ui
+                    .button("High contrast")
+                    .on_hover_text("Black and yellow")
+                    .clicked()
+                {
+                    *colors = palette(Color32::BLACK, Color32::YELLOW);
+                }
+            });
+
+            // Applied on top of any palette, so the colors above stay as they are
+            ui.separator();
+            ui.horizontal(|ui| {
+                ui.radio_value(&mut filter.mode, ColorMode::Palette, "Palette");
+                ui.radio_value(&mut filter.mode, ColorMode::Grayscale, "Grayscale");
+                ui.radio_value(&mut filter.mode, ColorMode::Monochrome, "Monochrome")
+                    .on_hover_text(
+                        "The darker color is shown as black and the lighter one as white.",
+                    );
+            });
+            ui.checkbox(&mut filter.invert, "Invert colors");
+        });
+}
+
+#[inline]
+pub fn draw_rom(rom: &mut Vec<u8>, start_address: u16, open: &mut bool, ctx: &egui::Context) {
+    egui::Window::new("ROM")
+        .open(open)
+        .fixed_size(Vec2::new(230.0, 300.0))
+        .resizable(false)
+        .show(ctx, |ui| {
+            ui.spacing_mut().scroll = ScrollStyle::solid();
+            ui.visuals_mut().override_text_color = Some(TEXT_COLOR);
+
+            ScrollArea::vertical()
+                .scroll([false, true])
+                .auto_shrink(false)
+                .scroll_bar_visibility(egui::scroll_area::ScrollBarVisibility::AlwaysVisible)
+                .show(ui, |ui| {
+                    ui.horizontal_top(|ui| {
+                        ui.horizontal_wrapped(|ui| {
+                            let mut addresses = String::new();
+                            for i in (0..rom.len()).step_by(8) {
+                                addresses += &format!("{:04X}\n", i + start_address as usize);
+                            }
+                            addresses.pop(); // Remove last newline
+
+                            ui.label(&addresses);
+                        });
+
+                        ui.add_space(-2.0);
+                        ui.separator();
+                        ui.add_space(-2.0);
+
+                        ui.horizontal_wrapped(|ui| {
+                            let mut bytes = String::new();
+                            for i in 0..rom.len() {
+                                bytes += &format!("{:02X} ", rom[i]);
+                            }
+                            ui.label(bytes);
+                        });
+                    });
+                });
+        });
+}
+
+/// Show every hotkey and the keys mapped to the CHIP-8 keypad by the current layout.
+pub fn draw_shortcuts(layout: KeypadLayout, open: &mut bool, ctx: &egui::Context) {
+    egui::Window::new(tr("Shortcuts"))
+        .open(open)
+        .collapsible(false)
+        .resizable(false)
+        .anchor(egui::Align2::CENTER_CENTER, Vec2::ZERO)
+        .show(ctx, |ui| {
+            ui.visuals_mut().override_text_color = Some(TEXT_COLOR);
+
+            Grid::new("hotkeys").num_columns(2).show(ui, |ui| {
+                for hotkey in Hotkey::ALL {
+                    let (modifiers, key) = hotkey.shortcut(layout);
+                    ui.label(tr(hotkey.name()));
+                    let mut shortcut =
+                        ctx.format_shortcut(&egui::KeyboardShortcut::new(modifiers, key));
+                    if hotkey.paused_only() {
+                        shortcut += tr(" (paused)");
+                    }
+                    ui.label(shortcut);
+                    ui.end_row();
+                }
+                for (name, modifiers) in [
+                    ("Save to slot", Modifiers::CTRL),
+                    ("Load from slot", Modifiers::SHIFT),
+                ] {
+                    ui.label(tr(name));
+                    let first = egui::KeyboardShortcut::new(modifiers, SLOT_KEYS[0]);
+                    let last = egui::KeyboardShortcut::new(modifiers, SLOT_KEYS[SLOT_COUNT - 1]);
+                    ui.label(format!(
+                        "{} - {}",
+                        ctx.format_shortcut(&first),
+                        ctx.format_shortcut(&last)
+                    ));
+                    ui.end_row();
+                }
+            });
+
+            ui.separator();
+            ui.label(tr_with("Keypad: {}", &[&layout.name()]));
+            // The CHIP-8 keypad is laid out as 123C/456D/789E/A0BF
+            let keys = layout.keys();
+            Grid::new("keypad mapping").num_columns(4).show(ui, |ui| {
+                for row in [
+                    [1, 2, 3, 0xC],
+                    [4, 5, 6, 0xD],
+                    [7, 8, 9, 0xE],
+                    [0xA, 0, 0xB, 0xF],
+                ] {
+                    for key in row {
+                        let host = keys[key].map_or("-", |k| k.symbol_or_name());
+                        ui.label(format!("{key:X}: {host}"));
+                    }
+                    ui.end_row();
+                }
+            });
+        });
+}
+
+#[inline]
+pub fn draw_history(interpreter: &Chip8, open: &mut bool, ctx: &egui::Context) {
+    egui::Window::new("Execution history")
+        .open(open)
+        .default_size(Vec2::new(480.0, 300.0))
+        .show(ctx, |ui| {
+            ui.spacing_mut().scroll = ScrollStyle::solid();
+            ui.visuals_mut().override_text_color = Some(TEXT_COLOR);
+
+            ScrollArea::vertical()
+                .auto_shrink(false)
+                .stick_to_bottom(true)
+                .show(ui, |ui| {
+                    for entry in interpreter.get_trace().iter() {
+                        let (pattern, explanation) = explain_instruction(
+                            entry.opcode,
+                            &interpreter.quirks,
+                            &interpreter.variant,
+                        );
+
+                        let mut changes = String::new();
+                        for (register, before, after) in entry.changed_registers() {
+                            changes += &format!(" V{:X}:{:02X}->{:02X}", register, before, after);
+                        }
+                        if entry.I_before != entry.I_after {
+                            changes += &format!(" I:{:04X}->{:04X}", entry.I_before, entry.I_after);
+                        }
+
+                        ui.horizontal(|ui| {
+                            ui.label(format!(
+                                "{:04X}  {:04X}  {}  {}",
+                                entry.address, entry.opcode, pattern, explanation
+                            ));
+                            if interpreter.vip_timing {
+                                ui.colored_label(
+                                    Color32::KHAKI,
+                                    format!("({} cycles)", entry.vip_cycles),
+                                );
+                            }
+                            ui.colored_label(Color32::YELLOW, changes);
+                        });
+                    }
+
+                    // The instruction that will be executed next
+                    let (pattern, explanation) = explain_instruction(
+                        interpreter.get_current_opcode(),
+                        &interpreter.quirks,
+                        &interpreter.variant,
+                    );
+                    ui.colored_label(
+                        PC_COLOR,
+                        format!(
+                            "{:04X}  {:04X}  {}  {}",
+                            interpreter.get_program_counter(),
+                            interpreter.get_current_opcode(),
+                            pattern,
+                            explanation
+                        ),
+                    );
+                });
+        });
+}
+
+/// How many milliseconds of the buzzer waveform the sound window shows.
+const WAVEFORM_MILLISECONDS: f32 = 10.0;
+
+/// Show the waveform the buzzer is playing like an oscilloscope, and the sound timer.
+pub fn draw_sound(interpreter: &Chip8, open: &mut bool, ctx: &egui::Context) {
+    egui::Window::new("Sound")
+        .open(open)
+        .resizable(false)
+        .show(ctx, |ui| {
+            ui.visuals_mut().override_text_color = Some(TEXT_COLOR);
+
+            let sound = interpreter.get_sound();
+            // The same condition the driver starts the buzzer with
+            let playing = interpreter.is_running() && interpreter.sound_on && sound > 1;
+
+            let (response, painter) =
+                ui.allocate_painter(Vec2::new(320.0, 100.0), egui::Sense::hover());
+            let rect = response.rect;
+            painter.rect_filled(rect, 0.0, Color32::BLACK);
+            painter.hline(
+                rect.x_range(),
+                rect.center().y,
+                Stroke::new(1.0, Color32::from_gray(40)),
+            );
+
+            // Scroll the wave with the emulated time, one point per pixel
+            let tone = Tone::of(interpreter);
+            let start = interpreter.get_elapsed_time().as_secs_f32();
+            let points: Vec<Pos2> = (0..=rect.width() as usize)
+                .map(|x| {
+                    let y = if !playing {
+                        rect.center().y
+                    } else {
+                        let t = start + x as f32 / rect.width() * WAVEFORM_MILLISECONDS / 1000.0;
+                        if tone.is_high(t * tone.frequency()) {
+                            rect.top() + 10.0
+                        } else {
+                            rect.bottom() - 10.0
+                        }
+                    };
+                    Pos2::new(rect.left() + x as f32, y)
+                })
+                .collect();
+            painter.add(egui::Shape::line(points, Stroke::new(1.5, Color32::GREEN)));
+
+            ui.label(format!(
+                "Sound timer: {} ({:.2} s left)",
+                sound,
+                sound as f32 / interpreter.timer_frequency.max(1) as f32
+            ));
+            ui.add(ProgressBar::new(sound as f32 / 255.0).desired_width(320.0));
+            ui.label(match tone {
+                _ if sound > 1 && !interpreter.sound_on => "Buzzer: muted".to_string(),
+                _ if !playing => "Buzzer: silent".to_string(),
+                Tone::Square => format!("Buzzer: {} Hz square wave", BUZZER_FREQUENCY),
+                Tone::Pattern { sample_rate, .. } => format!(
+                    "Buzzer: audio pattern at {:.0} samples/s (pitch {})",
+                    sample_rate,
+                    interpreter.get_pitch()
+                ),
+            })
+            .on_hover_text("The buzzer plays while the sound timer is above 1. XO-CHIP programs can replace the square wave with an audio pattern.");
+        });
+}
+
+/// The filters of the memory access log window.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MemoryLogView {
+    /// The lowest address to show, in hex.
+    pub from: String,
+    /// The highest address to show, in hex.
+    pub to: String,
+    pub reads: bool,
+    pub writes: bool,
+    /// The address of the watchpoint being entered, in hex.
+    pub watch: String,
+}
+
+impl Default for MemoryLogView {
+    fn default() -> Self {
+        MemoryLogView {
+            from: String::new(),
+            to: String::new(),
+            reads: true,
+            writes: true,
+            watch: String::new(),
+        }
+    }
+}
+
+/// List the last memory reads and writes made by instructions, filtered by address range and kind,
+/// and the watchpoints that stop the interpreter when a byte is read or written.
+pub fn draw_memory_log(
+    interpreter: &mut Chip8,
+    view: &mut MemoryLogView,
+    open: &mut bool,
+    ctx: &egui::Context,
+) {
+    egui::Window::new("Memory access log")
+        .open(open)
+        .default_size(Vec2::new(360.0, 300.0))
+        .show(ctx, |ui| {
+            ui.spacing_mut().scroll = ScrollStyle::solid();
+            ui.visuals_mut().override_text_color = Some(TEXT_COLOR);
+
+            ui.horizontal(|ui| {
+                ui.label("From");
+                ui.add(
+                    TextEdit::singleline(&mut view.from)
+                        .hint_text("000")
+                        .desired_width(40.0),
+                );
+                ui.label("to");
+                ui.add(
+                    TextEdit::singleline(&mut view.to)
+                        .hint_text("FFF")
+                        .desired_width(40.0),
+                );
+                ui.checkbox(&mut view.reads, "Reads");
+                ui.checkbox(&mut view.writes, "Writes");
+            });
+            ui.horizontal(|ui| {
+                ui.label("Watch");
+                ui.add(
+                    TextEdit::singleline(&mut view.watch)
+                        .hint_text("000")
+                        .desired_width(40.0),
+                );
+                let address = u16::from_str_radix(view.watch.trim(), 16).ok();
+                ui.add_enabled_ui(address.is_some(), |ui| {
+                    for (kind, label) in [
+                        (AccessKind::Read, "Break on read"),
+                        (AccessKind::Write, "Break on write"),
+                    ] {
+                        if ui.button(label).clicked() {
+                            interpreter.breakpoints.watch(address.unwrap(), kind);
+                        }
+                    }
+                });
+            });
+            let watchpoints: Vec<(u16, AccessKind)> =
+                interpreter.breakpoints.watchpoints().collect();
+            for (address, kind) in watchpoints {
+                ui.horizontal(|ui| {
+                    let kind_name = match kind {
+                        AccessKind::Read => "read",
+                        AccessKind::Write => "write",
+                    };
+                    ui.colored_label(
+                        BREAKPOINT_COLOR,
+                        format!("Break on {kind_name} of {address:04X}"),
+                    );
+                    if ui.small_button("x").clicked() {
+                        interpreter.breakpoints.unwatch(address, kind);
+                    }
+                });
+            }
+            // An empty or invalid bound does not filter
+            let from = u16::from_str_radix(view.from.trim(), 16).unwrap_or(0);
+            let to = u16::from_str_radix(view.to.trim(), 16).unwrap_or(u16::MAX);
+            ui.separator();
+
+            ScrollArea::vertical()
+                .auto_shrink(false)
+                .stick_to_bottom(true)
+                .show(ui, |ui| {
+                    for access in interpreter.get_access_log().iter().filter(|access| {
+                        (from..=to).contains(&access.address)
+                            && match access.kind {
+                                AccessKind::Read => view.reads,
+                                AccessKind::Write => view.writes,
+                            }
+                    }) {
+                        let (kind, color) = match access.kind {
+                            AccessKind::Read => ("R", I_COLOR),
+                            AccessKind::Write => ("W", CHANGED_COLOR),
+                        };
+                        ui.horizontal(|ui| {
+                            ui.colored_label(color, kind);
+                            ui.label(format!(
+                                "{:04X} = {:02X}  by {:04X} at {:04X}",
+                                access.address, access.value, access.opcode, access.program_counter
+                            ));
+                        });
+                    }
+                });
+        });
+}
+
+/// A watch expression of the watches window.
+pub struct Watch {
+    /// The expression as it was entered.
+    pub source: String,
+    pub expression: Expression,
+    /// Whether the running interpreter stops when the expression is true.
+    pub break_on_true: bool,
+}
+
+/// The watch expressions and the input field of the watches window.
+#[derive(Default)]
+pub struct WatchView {
+    /// The expression being entered.
+    pub input: String,
+    /// Why the entered expression could not be added.
+    pub error: Option<String>,
+    pub watches: Vec<Watch>,
+    /// Where the interpreter ran back to, or why it could not.
+    pub reversed: Option<String>,
+}
+
+impl WatchView {
+    /// Get the expressions that stop the interpreter when they become true.
+    pub fn break_watches(&self) -> Vec<Expression> {
+        self.watches
+            .iter()
+            .filter(|watch| watch.break_on_true)
+            .map(|watch| watch.expression.clone())
+            .collect()
+    }
+}
+
+/// Show the watch expressions evaluated against the interpreter. While stopped, the interpreter can
+/// run back to when an expression became true.
+pub fn draw_watches(
+    interpreter: &mut Chip8,
+    view: &mut WatchView,
+    open: &mut bool,
+    ctx: &egui::Context,
+) {
+    egui::Window::new("Watches")
+        .open(open)
+        .default_width(320.0)
+        .show(ctx, |ui| {
+            ui.visuals_mut().override_text_color = Some(TEXT_COLOR);
+
+            ui.horizontal(|ui| {
+                let response = ui.add(
+                    TextEdit::singleline(&mut view.input)
+                        .hint_text("V[3] + V[4], mem[I+1], delay > 0...")
+                        .desired_width(220.0),
+                );
+                let submitted =
+                    response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+                if ui.button("Add").clicked() || submitted {
+                    match Expression::parse(&view.input) {
+                        Ok(expression) => {
+                            view.watches.push(Watch {
+                                source: std::mem::take(&mut view.input),
+                                expression,
+                                break_on_true: false,
+                            });
+                            view.error = None;
+                        }
+                        Err(e) => view.error = Some(e),
+                    }
+                }
+            });
+            if let Some(e) = &view.error {
+                ui.colored_label(Color32::RED, e);
+            }
+
+            let mut removed = None;
+            let mut reverse = None;
+            Grid::new("watches").num_columns(5).show(ui, |ui| {
+                for (i, watch) in view.watches.iter_mut().enumerate() {
+                    ui.label(&watch.source);
+                    match watch.expression.evaluate(interpreter) {
+                        Some(value) => {
+                            let triggered = watch.break_on_true && value != 0;
+                            ui.colored_label(
+                                if triggered { Color32::RED } else { Color32::YELLOW },
+                                format!("{value} (0x{value:X})"),
+                            );
+                        }
+                        None => {
+                            ui.colored_label(Color32::GRAY, "-").on_hover_text(
+                                "Division by zero, or an index outside of the registers, memory, keypad or stack.",
+                            );
+                        }
+                    }
+                    ui.checkbox(&mut watch.break_on_true, "Break")
+                        .on_hover_text("Stop the interpreter before the cycle where the expression becomes true (not 0).");
+                    if ui
+                        .add_enabled(!interpreter.is_running(), egui::Button::new("Run back"))
+                        .on_hover_text("Run backwards to the last time the expression became true (not 0).")
+                        .clicked()
+                    {
+                        reverse = Some(i);
+                    }
+                    if ui.button("Remove").clicked() {
+                        removed = Some(i);
+                    }
+                    ui.end_row();
+                }
+            });
+            if let Some(i) = removed {
+                view.watches.remove(i);
+            }
+            if let Some(watch) = reverse.and_then(|i| view.watches.get(i)) {
+                view.reversed = Some(match interpreter.reverse_until(&watch.expression) {
+                    Some((frame, cycle)) => format!(
+                        "{} became true in frame {frame}, cycle {cycle}.",
+                        watch.source
+                    ),
+                    None => format!(
+                        "{} did not become true in the last {} frames.",
+                        watch.source,
+                        interpreter.rewind_frames()
+                    ),
+                });
+            }
+            if let Some(reversed) = &view.reversed {
+                ui.label(reversed);
+            }
+        });
+}
+
+/// The settings of the attract mode window.
+pub struct AttractView {
+    /// The folder with the ROMs to cycle through.
+    pub folder: String,
+    /// How many seconds each ROM runs.
+    pub seconds: u32,
+    /// Why the attract mode could not start.
+    pub error: Option<String>,
+}
+
+/// Cycle through the ROMs of a folder. Returns the ROM that was started, if any.
+pub fn draw_attract(
+    interpreter: &mut Chip8,
+    attract: &mut Option<AttractMode>,
+    view: &mut AttractView,
+    open: &mut bool,
+    ctx: &egui::Context,
+) -> Option<Vec<u8>> {
+    let mut started = None;
+    egui::Window::new("Attract mode")
+        .open(open)
+        .auto_sized()
+        .show(ctx, |ui| {
+            ui.visuals_mut().override_text_color = Some(TEXT_COLOR);
+
+            let Some(mode) = attract else {
+                ui.label("Run every ROM of a folder for a while, one after another.\nA .replay file with the same name as a ROM plays back its input.");
+                ui.add(TextEdit::singleline(&mut view.folder).hint_text("Enter folder..."));
+                ui.add(Slider::new(&mut view.seconds, 5..=600).text("Seconds per ROM"));
+                if ui.button("Start").clicked() {
+                    match AttractMode::open(Path::new(&view.folder), view.seconds) {
+                        Ok(mut mode) => {
+                            started = mode.start(interpreter);
+                            *attract = Some(mode);
+                            view.error = None;
+                        }
+                        Err(e) => view.error = Some(e.to_string()),
+                    }
+                }
+                if let Some(e) = &view.error {
+                    ui.colored_label(Color32::RED, format!("Could not start: {e}"));
+                }
+                return;
+            };
+
+            let (current, total) = mode.position();
+            ui.label(format!(
+                "ROM {}/{}: {}",
+                current + 1,
+                total,
+                mode.current().rom.display()
+            ));
+            if mode.current().replay.is_some() {
+                ui.label("Playing back its replay");
+            }
+            ui.label(format!("{} runs", mode.runs()));
+            if !mode.failures().is_empty() {
+                ui.separator();
+                ui.colored_label(Color32::RED, format!("{} failures", mode.failures().len()));
+                ScrollArea::vertical().max_height(150.0).show(ui, |ui| {
+                    for (rom, reason) in mode.failures() {
+                        ui.label(format!("{rom}: {reason}"));
+                    }
+                });
+            }
+            if ui.button("Stop").clicked() {
+                *attract = None;
+            }
+        });
+    started
+}
+
+/// The settings and result of the quirk bisect window.
+pub struct BisectView {
+    /// How many frames to run the ROM for.
+    pub frames: u32,
+    pub report: Option<BisectReport>,
+}
+
+/// Run the ROM under all quirk combinations and show which quirks change the outcome.
+pub fn draw_bisect(
+    interpreter: &Chip8,
+    rom: &[u8],
+    view: &mut BisectView,
+    open: &mut bool,
+    ctx: &egui::Context,
+) {
+    egui::Window::new("Find required quirks")
+        .open(open)
+        .auto_sized()
+        .show(ctx, |ui| {
+            ui.visuals_mut().override_text_color = Some(TEXT_COLOR);
+
+            ui.label("Run the ROM without input under every quirk combination\nand find out which quirks change the display or halt it.");
+            ui.add(Slider::new(&mut view.frames, 1..=3600).text("Frames"));
+            if ui.button("Run").clicked() {
+                view.report = Some(bisect_quirks(interpreter, rom, view.frames));
+            }
+
+            let Some(report) = &view.report else {
+                return;
+            };
+            ui.separator();
+            ui.label(format!(
+                "{} combinations, {} different outcomes",
+                report.outcomes.len(),
+                report.distinct_outcomes
+            ));
+            Grid::new("quirk effects").num_columns(2).show(ui, |ui| {
+                for effect in &report.effects {
+                    ui.label(tr(effect.quirk));
+                    if effect.changes_outcome {
+                        ui.colored_label(Color32::YELLOW, "Changes the outcome");
+                    } else {
+                        ui.colored_label(Color32::GRAY, "No effect");
+                    }
+                    ui.end_row();
+                }
+            });
+        });
+}
+
+/// The reference trace file and result of the lockstep test window.
+pub struct LockstepView {
+    /// The path of the reference trace.
+    pub path: String,
+    /// How many instructions to record.
+    pub steps: usize,
+    pub report: Option<LockstepReport>,
+    /// Why the reference trace could not be read or written, or how it was recorded.
+    pub message: Option<String>,
+}
+
+/// Run the ROM one instruction at a time next to a reference trace and show the first step where
+/// they differ, or record a reference trace.
+pub fn draw_lockstep(
+    interpreter: &Chip8,
+    rom: &[u8],
+    view: &mut LockstepView,
+    open: &mut bool,
+    ctx: &egui::Context,
+) {
+    egui::Window::new("Lockstep test")
+        .open(open)
+        .auto_sized()
+        .show(ctx, |ui| {
+            ui.visuals_mut().override_text_color = Some(TEXT_COLOR);
+
+            ui.label("Run the ROM without input one instruction at a time next to a trace
+recorded by a reference implementation and find the first difference
+in the registers, display or memory.");
+            ui.add(TextEdit::singleline(&mut view.path).hint_text("Enter trace path..."));
+            ui.horizontal(|ui| {
+                if ui.button("Compare").clicked() {
+                    match fs::read_to_string(&view.path)
+                        .map_err(|e| e.to_string())
+                        .and_then(|text| ReferenceTrace::parse(&text))
+                    {
+                        Ok(reference) => {
+                            view.report = Some(lockstep_test(interpreter, rom, &reference));
+                            view.message = None;
+                        }
+                        Err(e) => {
+                            view.report = None;
+                            view.message = Some(e);
+                        }
+                    }
+                }
+                if ui
+                    .button("Record")
+                    .on_hover_text("Record a trace with the current variant, quirks and speed, to compare later builds or configurations against.")
+                    .clicked()
+                {
+                    let reference = ReferenceTrace::record(interpreter, rom, view.steps);
+                    view.message = Some(match fs::write(&view.path, reference.to_text()) {
+                        Ok(()) => format!("Recorded {} steps", reference.steps().len()),
+                        Err(e) => e.to_string(),
+                    });
+                }
+                ui.add(Slider::new(&mut view.steps, 1..=100_000).logarithmic(true).text("Steps"));
+            });
+            if let Some(message) = &view.message {
+                ui.label(message);
+            }
+
+            let Some(report) = &view.report else {
+                return;
+            };
+            ui.separator();
+            let Some(divergence) = &report.divergence else {
+                ui.colored_label(
+                    Color32::GREEN,
+                    format!("All {} steps match the reference", report.steps),
+                );
+                return;
+            };
+            ui.colored_label(
+                Color32::RED,
+                format!(
+                    "Step {} of {} differs from the reference",
+                    divergence.step, report.steps
+                ),
+            );
+            if let Some((address, opcode)) = divergence.instruction {
+                ui.label(format!("After {opcode:04X} at {address:03X}"));
+            }
+            for difference in &divergence.differences {
+                ui.label(difference);
+            }
+        });
+}
+
+/// Run the ROM in a second interpreter with another variant or quirks and show where it diverges.
+pub fn draw_comparison(
+    interpreter: &Chip8,
+    driver: &EmulatorDriver,
+    view: &mut ComparisonView,
+    colors: [Color32; 4],
+    open: &mut bool,
+    ctx: &egui::Context,
+) {
+    egui::Window::new("Comparison")
+        .open(open)
+        .auto_sized()
+        .show(ctx, |ui| {
+            ui.visuals_mut().override_text_color = Some(TEXT_COLOR);
+
+            let mut comparison = driver.lock_comparison();
+            let Some(running) = comparison.as_ref() else {
+                ui.label("Run the ROM in a second interpreter with the same input\nto find the first frame where the displays differ.");
+                ui.horizontal(|ui| {
+                    ui.radio_value(&mut view.variant, Variant::CHIP8, "CHIP-8");
+                    ui.radio_value(&mut view.variant, Variant::VIPHYBRID, "CHIP-8 (hybrid)");
+                    ui.radio_value(&mut view.variant, Variant::CHIP8X, "CHIP-8X");
+                    ui.radio_value(&mut view.variant, Variant::CHIP48, "CHIP-48");
+                    ui.radio_value(&mut view.variant, Variant::ETI660, "ETI-660");
+                    ui.radio_value(&mut view.variant, Variant::SCHIP10, "SUPER-CHIP 1.0");
+                    ui.radio_value(&mut view.variant, Variant::SCHIP11, "SUPER-CHIP 1.1");
+                    ui.radio_value(&mut view.variant, Variant::XOCHIP, "XO-CHIP");
+                });
+                ui.menu_button(tr("Quirks"), |ui| draw_quirk_options(ui, &mut view.quirks));
+
+                if ui
+                    .add_enabled(!interpreter.is_running(), Button::new("Start comparing"))
+                    .on_hover_text("Resets both interpreters.")
+                    .clicked()
+                {
+                    let mut other = match view.variant {
+                        Variant::CHIP8 => Chip8::chip8(),
+                        Variant::VIPHYBRID => Chip8::vip_hybrid(),
+                        Variant::CHIP8X => Chip8::chip8x(),
+                        Variant::CHIP48 => Chip8::chip48(),
+                        Variant::ETI660 => Chip8::eti660(),
+                        Variant::SCHIP10 => Chip8::super_chip1_0(),
+                        Variant::SCHIP11 => Chip8::super_chip1_1(),
+                        Variant::XOCHIP => Chip8::xo_chip(),
+                    };
+                    other.quirks = view.quirks;
+                    other.execution_speed = interpreter.execution_speed;
+                    other.timer_frequency = interpreter.timer_frequency;
+                    *comparison = Some(Comparison::new(other));
+                    driver.send(DriverCommand::Reset);
+                }
+                return;
+            };
+
+            view.screen.set(
+                running.interpreter().get_display(colors),
+                TextureOptions::LINEAR,
+            );
+            ui.add(egui::Image::new((view.screen.id(), view.screen.size_vec2() / 2.0)));
+
+            match running.divergence() {
+                Some(frame) => {
+                    ui.colored_label(Color32::RED, format!("Diverged at frame {}", frame));
+                }
+                None => {
+                    ui.colored_label(
+                        Color32::LIGHT_GREEN,
+                        format!("Identical for {} frames", running.frames()),
+                    );
+                }
+            }
+            if let Some(reason) = &running.interpreter().halt_reason {
+                ui.colored_label(
+                    Color32::RED,
+                    tr_with("Halted: {}", &[&tr_halt_reason(reason)]),
+                );
+            }
+
+            if ui.button("Stop comparing").clicked() {
+                *comparison = None;
+            }
+        });
+}
+
+/// List the programs of the chip8Archive and download them.
+#[cfg(feature = "archive")]
+pub fn draw_archive(
+    archive: &mut ArchiveBrowser,
+    can_load: bool,
+    open: &mut bool,
+    ctx: &egui::Context,
+) {
+    archive.list();
+
+    egui::Window::new("chip8Archive")
         .open(open)
-        .auto_sized()
+        .default_size(Vec2::new(420.0, 360.0))
         .show(ctx, |ui| {
-            ui.scope_builder(egui::UiBuilder::new(), |ui| {
-                Grid::new("colors")
-                    .num_columns(2)
-                    .spacing([40.0, 4.0])
-                    .show(ui, |ui| {
-                        let mut bg = [
-                            background_color.r(),
-                            background_color.g(),
-                            background_color.b(),
-                        ];
-                        ui.label("Background color");
-                        ui.color_edit_button_srgb(&mut bg);
-                        *background_color = Color32::from_rgb(bg[0], bg[1], bg[2]);
-
-                        ui.end_row();
-                        let mut fill = [fill_color.r(), fill_color.g(), fill_color.b()];
-                        ui.label("Fill color");
-                        ui.color_edit_button_srgb(&mut fill);
-                        *fill_color = Color32::from_rgb(fill[0], fill[1], fill[2]);
-                    });
-            });
+            ui.spacing_mut().scroll = ScrollStyle::solid();
+            ui.visuals_mut().override_text_color = Some(TEXT_COLOR);
 
-            if ui.button("Swap").clicked() {
-                swap(background_color, fill_color);
+            ui.add(TextEdit::singleline(&mut archive.filter).hint_text("Search..."));
+            if let Some(e) = &archive.download_error {
+                ui.colored_label(Color32::RED, format!("Could not download the ROM: {e}"));
             }
 
-            ui.horizontal(|ui| {
-                if ui.button("Default").clicked() {
-                    *background_color = Color32::BLACK;
-                    *fill_color = Color32::WHITE;
+            let programs = match &archive.programs {
+                Some(Ok(programs)) => programs,
+                Some(Err(e)) => {
+                    ui.colored_label(Color32::RED, format!("Could not list the programs: {e}"));
+                    return;
                 }
-                if ui.button("Octo").clicked() {
-                    *background_color = Color32::from_hex("#996600").unwrap();
-                    *fill_color = Color32::from_hex("#FFCC00").unwrap();
+                None => {
+                    ui.spinner();
+                    return;
                 }
-                if ui.button("Matrix").clicked() {
-                    *background_color = Color32::BLACK;
-                    *fill_color = Color32::GREEN;
+            };
+
+            let filter = archive.filter.to_lowercase();
+            let mut download = None;
+            ScrollArea::vertical().auto_shrink(false).show(ui, |ui| {
+                for program in programs.iter().filter(|program| {
+                    program.title.to_lowercase().contains(&filter)
+                        || program.authors.to_lowercase().contains(&filter)
+                }) {
+                    ui.horizontal(|ui| {
+                        if ui
+                            .add_enabled(can_load && program.variant.is_some(), Button::new("Load"))
+                            .on_disabled_hover_text(if program.variant.is_none() {
+                                "This program is made for a variant E-CHIP does not support."
+                            } else {
+                                "Pause the interpreter to load a program."
+                            })
+                            .clicked()
+                        {
+                            download = Some(program.clone());
+                        }
+                        ui.label(&program.title).on_hover_text(&program.description);
+                        ui.colored_label(Color32::GRAY, &program.authors);
+                    });
                 }
             });
+
+            if let Some(program) = download {
+                archive.download(program);
+            }
         });
 }
 
-#[inline]
-pub fn draw_rom(rom: &mut Vec<u8>, open: &mut bool, ctx: &egui::Context) {
-    egui::Window::new("ROM")
+/// Edit which CHIP-8 key each gamepad button presses and save it for the current ROM.
+#[cfg(feature = "gamepad")]
+pub fn draw_gamepad(gamepad: &mut GamepadInput, open: &mut bool, ctx: &egui::Context) {
+    egui::Window::new(tr("Gamepad mapping"))
         .open(open)
-        .fixed_size(Vec2::new(230.0, 300.0))
         .resizable(false)
         .show(ctx, |ui| {
-            ui.spacing_mut().scroll = ScrollStyle::solid();
             ui.visuals_mut().override_text_color = Some(TEXT_COLOR);
 
-            ScrollArea::vertical()
-                .scroll([false, true])
-                .auto_shrink(false)
-                .scroll_bar_visibility(egui::scroll_area::ScrollBarVisibility::AlwaysVisible)
-                .show(ui, |ui| {
-                    ui.horizontal_top(|ui| {
-                        ui.horizontal_wrapped(|ui| {
-                            let mut addresses = String::new();
-                            for i in (0..rom.len()).step_by(8) {
-                                addresses += &format!("{:04X}\n", i + 0x200);
+            if !gamepad.is_connected() {
+                ui.label(tr("No gamepad connected."));
+            }
+            Grid::new("gamepad mapping").num_columns(2).show(ui, |ui| {
+                for (i, &(_, name)) in BUTTONS.iter().enumerate() {
+                    ui.label(name);
+                    let key = &mut gamepad.mapping.0[i];
+                    egui::ComboBox::from_id_salt(("gamepad button", i))
+                        .selected_text(key.map_or("-".into(), |k| format!("{k:X}")))
+                        .show_ui(ui, |ui| {
+                            ui.selectable_value(key, None, "-");
+                            for k in 0..16 {
+                                ui.selectable_value(key, Some(k), format!("{k:X}"));
                             }
-                            addresses.pop(); // Remove last newline
-
-                            ui.label(&addresses);
                         });
+                    ui.end_row();
+                }
+            });
 
-                        ui.add_space(-2.0);
-                        ui.separator();
-                        ui.add_space(-2.0);
+            ui.horizontal(|ui| {
+                if ui
+                    .add_enabled(
+                        gamepad.has_unsaved_changes(),
+                        Button::new(tr("Save for this ROM")),
+                    )
+                    .clicked()
+                {
+                    if let Err(e) = gamepad.save_profile() {
+                        println!("Could not save the gamepad mapping: {e}");
+                    }
+                }
+                if ui.button(tr("Default mapping")).clicked() {
+                    gamepad.mapping = Default::default();
+                }
+            });
+        });
+}
 
-                        ui.horizontal_wrapped(|ui| {
-                            let mut bytes = String::new();
-                            for i in 0..rom.len() {
-                                bytes += &format!("{:02X} ", rom[i]);
-                            }
-                            ui.label(bytes);
-                        });
-                    });
-                });
+/// Draw the interpreter display and the halt message with the instructions leading up to it.
+/// `waiting_for_input` warns that the program waits for a key press that can't arrive. If
+/// `scale_to_fit` is true, the display is scaled to fill the available space.
+pub fn draw_screen(
+    ui: &mut egui::Ui,
+    screen: &TextureHandle,
+    interpreter: &Chip8,
+    turbo: bool,
+    waiting_for_input: bool,
+    scale_to_fit: bool,
+) {
+    ui.add_space(-5.0);
+    if let Some(reason) = &interpreter.halt_reason {
+        ui.with_layout(Layout::top_down_justified(Align::Center), |ui| {
+            ui.colored_label(
+                Color32::RED,
+                tr_with("Halted: {}", &[&tr_halt_reason(reason)]),
+            );
+            for line in halt_context_lines(interpreter) {
+                ui.colored_label(Color32::LIGHT_RED, RichText::new(line).monospace());
+            }
+        });
+    } else if let Some(hit) = interpreter.breakpoints.hit() {
+        ui.with_layout(Layout::top_down_justified(Align::Center), |ui| {
+            ui.colored_label(
+                Color32::GOLD,
+                match hit {
+                    BreakpointHit::Address(address) => tr_with(
+                        "Stopped at the breakpoint at {}",
+                        &[&format!("{address:04X}")],
+                    ),
+                    BreakpointHit::Condition(condition) => {
+                        tr_with("Stopped because {} became true", &[&condition.source()])
+                    }
+                    BreakpointHit::Access(access) => tr_with(
+                        match access.kind {
+                            AccessKind::Read => "Stopped because {} at {} read {}",
+                            AccessKind::Write => "Stopped because {} at {} wrote {}",
+                        },
+                        &[
+                            &format!("{:04X}", access.opcode),
+                            &format!("{:04X}", access.program_counter),
+                            &format!("{:04X}", access.address),
+                        ],
+                    ),
+                },
+            );
+        });
+    } else if waiting_for_input {
+        ui.with_layout(Layout::top_down_justified(Align::Center), |ui| {
+            ui.colored_label(
+                Color32::GOLD,
+                tr("The program is waiting for keypad input. Check the key mappings and that the window has focus."),
+            );
+        });
+    } else if turbo {
+        ui.with_layout(Layout::top_down_justified(Align::Center), |ui| {
+            ui.colored_label(Color32::GOLD, tr_with("Fast-forward x{}", &[&TURBO_FACTOR]));
         });
+    }
+    ui.centered_and_justified(|ui| {
+        let image = egui::Image::new((screen.id(), screen.size_vec2()));
+        if scale_to_fit {
+            ui.add(image.fit_to_exact_size(ui.available_size()))
+        } else {
+            ui.add(image)
+        }
+    });
 }
 
 #[inline]
 pub fn draw_controls(
     interpreter: &mut Chip8,
-    rom: &mut Vec<u8>,
+    driver: &EmulatorDriver,
     show_load_modal: &mut bool,
+    speed_in_ips: &mut bool,
     ctx: &egui::Context,
 ) {
     egui::TopBottomPanel::top("control panel")
@@ -248,73 +2297,248 @@ pub fn draw_controls(
 
             ui.horizontal(|ui| {
                 if ui
-                    .add_enabled(!interpreter.is_running(), Button::new("Load ROM"))
+                    .add_enabled(!interpreter.is_running(), Button::new(tr("Load ROM")))
                     .clicked()
                 {
                     *show_load_modal = true;
                 }
 
                 if interpreter.is_running() {
-                    if ui.button("Pause").clicked() {
-                        interpreter.stop();
+                    if ui.button(tr("Pause")).clicked() {
+                        driver.send(DriverCommand::Stop);
                     }
                 } else {
-                    if ui.button("Run").clicked() {
-                        interpreter.start();
+                    if ui.button(tr("Run")).clicked() {
+                        driver.send(DriverCommand::Start);
                     }
                 }
 
+                let can_rewind = !interpreter.is_running() && interpreter.rewind_frames() > 0;
                 if ui
-                    .add_enabled(!interpreter.is_running(), Button::new("Step cycle"))
-                    .on_hover_text("Execute one instruction")
+                    .add_enabled(can_rewind, Button::new(tr("Step back")))
+                    .on_hover_text(tr("Undo the last instruction"))
                     .clicked()
                 {
-                    interpreter.execute_cycle();
-                    if interpreter.frame_cycle == interpreter.execution_speed {
-                        interpreter.tick_frame();
-                    }
+                    driver.send(DriverCommand::StepBackCycle);
                 }
                 if ui
-                    .add_enabled(!interpreter.is_running(), Button::new("Step frame"))
-                    .on_hover_text("Execute until this frame completes")
+                    .add_enabled(can_rewind, Button::new(tr("Frame back")))
+                    .on_hover_text(tr("Go back to the start of this frame, or of the previous one"))
                     .clicked()
                 {
-                    for _ in interpreter.frame_cycle..interpreter.execution_speed {
-                        interpreter.execute_cycle();
-                    }
-                    interpreter.tick_frame();
+                    driver.send(DriverCommand::StepBackFrame);
+                }
+                if ui
+                    .add_enabled(!interpreter.is_running(), Button::new(tr("Step cycle")))
+                    .on_hover_text(tr("Execute one instruction"))
+                    .clicked()
+                {
+                    driver.send(DriverCommand::StepCycle);
+                }
+                if ui
+                    .add_enabled(!interpreter.is_running(), Button::new(tr("Step frame")))
+                    .on_hover_text(tr("Execute until this frame completes"))
+                    .clicked()
+                {
+                    driver.send(DriverCommand::StepFrame);
                 }
 
                 if ui
-                    .add_enabled(!interpreter.is_running(), Button::new("Reset"))
+                    .add_enabled(!interpreter.is_running(), Button::new(tr("Reset")))
+                    .on_hover_text(tr("Reset the interpreter and reload the ROM"))
                     .clicked()
                 {
-                    interpreter.reset();
-                    interpreter.load_program(&rom);
+                    driver.send(DriverCommand::Reset);
                 }
+                ui.add_enabled_ui(!interpreter.is_running(), |ui| {
+                    ui.menu_button(tr("Soft reset"), |ui| {
+                        if ui
+                            .button(tr("Reset CPU only"))
+                            .on_hover_text(tr("Reset registers, timers and the stack, but keep RAM and the display"))
+                            .clicked()
+                        {
+                            driver.send(DriverCommand::ResetCpu);
+                            ui.close_menu();
+                        }
+                        if ui.button(tr("Clear display and timers only")).clicked() {
+                            driver.send(DriverCommand::ClearDisplayAndTimers);
+                            ui.close_menu();
+                        }
+                    });
+                });
 
                 ui.visuals_mut().override_text_color = Some(TEXT_COLOR);
 
                 ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
-                    ui.label(format!(
-                        "Cycle: {}/{}",
-                        interpreter.frame_cycle, interpreter.execution_speed,
+                    if interpreter.vip_speed {
+                        ui.label(tr_with("Cycle: {}", &[&interpreter.frame_cycle]))
+                            .on_hover_text(tr(
+                                "There are 60 frames per second and as many cycles per frame as fit in a COSMAC VIP frame.",
+                            ));
+                    } else {
+                        ui.label(tr_with(
+                            "Cycle: {}/{}",
+                            &[&interpreter.frame_cycle, &interpreter.execution_speed],
+                        ))
+                        .on_hover_text(tr_with(
+                            "There are 60 frames per second and {} cycles per frame.",
+                            &[&interpreter.execution_speed],
+                        ));
+                    }
+
+                    let unit = if *speed_in_ips { tr("IPS") } else { tr("cycles/frame") };
+                    if ui
+                        .small_button(unit)
+                        .on_hover_text(tr("Switch between cycles per frame and instructions per second"))
+                        .clicked()
+                    {
+                        *speed_in_ips = !*speed_in_ips;
+                    }
+                    // The speed is set by the VIP's machine cycles instead
+                    let enabled = !interpreter.vip_speed;
+                    if *speed_in_ips {
+                        let mut instructions = interpreter.get_instructions_per_second();
+                        if ui
+                            .add_enabled(
+                                enabled,
+                                Slider::new(&mut instructions, 60..=MAX_EXECUTION_SPEED * 60).step_by(60.0).integer(),
+                            )
+                            .changed()
+                        {
+                            interpreter.set_instructions_per_second(instructions);
+                        }
+                    } else {
+                        ui.add_enabled(
+                            enabled,
+                            Slider::new(&mut interpreter.execution_speed, 1..=MAX_EXECUTION_SPEED).integer(),
+                        );
+                    }
+
+                    let elapsed = interpreter.get_elapsed_time();
+                    ui.label(tr_with(
+                        "Frame: {} ({})",
+                        &[
+                            &interpreter.get_frame_count(),
+                            &format!(
+                                "{}:{:02}.{:03}",
+                                elapsed.as_secs() / 60,
+                                elapsed.as_secs() % 60,
+                                elapsed.subsec_millis()
+                            ),
+                        ],
                     ))
-                    .on_hover_text(format!(
-                        "There are 60 frames per second and {} cycles per frame.",
-                        interpreter.execution_speed
+                    .on_hover_text(tr(
+                        "Frames completed since the last reset and the emulated time they took.",
                     ));
-
-                    ui.add(Slider::new(&mut interpreter.execution_speed, 1..=10000).integer());
+                    if let Some(replay) = interpreter.get_replay() {
+                        draw_replay_status(ui, replay);
+                    }
                 });
             });
 
             ui.add_space(2.5);
+            draw_timeline(ui, interpreter);
+            ui.add_space(2.5);
         });
 }
 
+/// Show how far a replay was recorded or played back and whether playback matches the recording.
+fn draw_replay_status(ui: &mut egui::Ui, replay: &ReplaySession) {
+    if !replay.is_playing() {
+        ui.colored_label(
+            Color32::RED,
+            tr_with("Recording replay: {} frames", &[&replay.len()]),
+        );
+    } else if let Some(frame) = replay.divergence() {
+        ui.colored_label(
+            Color32::RED,
+            tr_with("Replay diverged at frame {}", &[&frame]),
+        )
+        .on_hover_text(tr(
+            "The machine state after this frame differs from the recording, so the replay no longer reproduces it.",
+        ));
+    } else if replay.is_finished() {
+        ui.colored_label(
+            Color32::LIGHT_GREEN,
+            tr_with("Replay verified: {} frames", &[&replay.len()]),
+        );
+    } else {
+        ui.label(tr_with(
+            "Replay: frame {}/{}",
+            &[&replay.position(), &replay.len()],
+        ));
+    }
+}
+
+/// Draw a strip showing when draws, key reads and sound changes happened during the frame. While
+/// running the last completed frame is shown, while paused the current one with a cursor.
+fn draw_timeline(ui: &mut egui::Ui, interpreter: &Chip8) {
+    let (events, cursor) = if interpreter.is_running() {
+        (interpreter.get_last_frame_events(), None)
+    } else {
+        (
+            interpreter.get_frame_events(),
+            Some(interpreter.frame_cycle),
+        )
+    };
+
+    let (rect, response) =
+        ui.allocate_exact_size(Vec2::new(ui.available_width(), 12.0), egui::Sense::hover());
+    let painter = ui.painter_at(rect);
+    painter.rect_filled(rect, 2.0, Color32::from_gray(30));
+
+    let cycles = interpreter.execution_speed.max(1) as f32;
+    let x_of = |cycle: u32| rect.left() + rect.width() * (cycle as f32 / cycles).min(1.0);
+
+    for event in events {
+        let (color, top, bottom) = match event.kind {
+            FrameEventKind::Draw => (Color32::LIGHT_GREEN, 0.0, 1.0 / 3.0),
+            FrameEventKind::KeyRead => (Color32::LIGHT_BLUE, 1.0 / 3.0, 2.0 / 3.0),
+            FrameEventKind::Sound => (Color32::GOLD, 2.0 / 3.0, 1.0),
+        };
+        let x = x_of(event.cycle);
+        painter.line_segment(
+            [
+                Pos2::new(x, rect.top() + rect.height() * top),
+                Pos2::new(x, rect.top() + rect.height() * bottom),
+            ],
+            Stroke::new(1.5, color),
+        );
+    }
+
+    if let Some(cycle) = cursor {
+        let x = x_of(cycle);
+        painter.line_segment(
+            [Pos2::new(x, rect.top()), Pos2::new(x, rect.bottom())],
+            Stroke::new(1.0, TEXT_COLOR),
+        );
+    }
+
+    response.on_hover_ui(|ui| {
+        let count = |kind| events.iter().filter(|e| e.kind == kind).count();
+        ui.colored_label(
+            Color32::LIGHT_GREEN,
+            format!("Draws: {}", count(FrameEventKind::Draw)),
+        );
+        ui.colored_label(
+            Color32::LIGHT_BLUE,
+            format!("Key reads: {}", count(FrameEventKind::KeyRead)),
+        );
+        ui.colored_label(
+            Color32::GOLD,
+            format!("Sound changes: {}", count(FrameEventKind::Sound)),
+        );
+    });
+}
+
 #[inline]
-pub fn draw_variant_specifics(interpreter: &mut Chip8, rom: &Vec<u8>, ctx: &egui::Context) {
+pub fn draw_variant_specifics(
+    interpreter: &mut Chip8,
+    rom: &Vec<u8>,
+    verify_quirks: bool,
+    quirk_test: &mut Option<QuirkTestReport>,
+    ctx: &egui::Context,
+) {
     egui::TopBottomPanel::bottom("specifics")
         .show_separator_line(true)
         .resizable(false)
@@ -323,11 +2547,7 @@ pub fn draw_variant_specifics(interpreter: &mut Chip8, rom: &Vec<u8>, ctx: &egui
             ui.visuals_mut().override_text_color = Some(TEXT_COLOR);
 
             ui.horizontal(|ui| {
-                let current_variant = match interpreter.variant {
-                    e_chip::Variant::CHIP8 => "CHIP-8",
-                    e_chip::Variant::SCHIP11 => "SUPER-CHIP 1.1",
-                    e_chip::Variant::XOCHIP => "XO-CHIP",
-                };
+                let current_variant = variant_name(&interpreter.variant);
 
                 ui.add_space(1.0);
 
@@ -339,11 +2559,59 @@ pub fn draw_variant_specifics(interpreter: &mut Chip8, rom: &Vec<u8>, ctx: &egui
                         .menu_button(current_variant, |ui| {
                             if ui.button("CHIP-8").clicked() {
                                 *interpreter = Chip8::chip8();
-                                interpreter.load_program(rom);
+                                interpreter.reset_and_load(rom);
+                                if verify_quirks {
+                                    *quirk_test = Some(test_quirks(interpreter, "CHIP-8 (COSMAC-VIP)", Quirks::vip_chip()));
+                                }
+                                ui.close_menu();
+                            } else if ui.button("CHIP-8 (hybrid)").on_hover_text("Runs 0nnn machine code routines on an emulated CDP1802, like the COSMAC VIP.").clicked() {
+                                *interpreter = Chip8::vip_hybrid();
+                                interpreter.reset_and_load(rom);
+                                if verify_quirks {
+                                    *quirk_test = Some(test_quirks(interpreter, "CHIP-8 (COSMAC-VIP)", Quirks::vip_chip()));
+                                }
+                                ui.close_menu();
+                            } else if ui.button("CHIP-8X").clicked() {
+                                *interpreter = Chip8::chip8x();
+                                interpreter.reset_and_load(rom);
+                                if verify_quirks {
+                                    *quirk_test = Some(test_quirks(interpreter, "CHIP-8X", Quirks::vip_chip()));
+                                }
+                                ui.close_menu();
+                            } else if ui.button("CHIP-48").clicked() {
+                                *interpreter = Chip8::chip48();
+                                interpreter.reset_and_load(rom);
+                                if verify_quirks {
+                                    *quirk_test = Some(test_quirks(interpreter, "CHIP-48", Quirks::chip48()));
+                                }
+                                ui.close_menu();
+                            } else if ui.button("ETI-660").clicked() {
+                                *interpreter = Chip8::eti660();
+                                interpreter.reset_and_load(rom);
+                                if verify_quirks {
+                                    *quirk_test = Some(test_quirks(interpreter, "ETI-660", Quirks::vip_chip()));
+                                }
+                                ui.close_menu();
+                            } else if ui.button("SUPER-CHIP 1.0").clicked() {
+                                *interpreter = Chip8::super_chip1_0();
+                                interpreter.reset_and_load(rom);
+                                if verify_quirks {
+                                    *quirk_test = Some(test_quirks(interpreter, "SUPER-CHIP 1.0", Quirks::super_chip1_0()));
+                                }
                                 ui.close_menu();
                             } else if ui.button("SUPER-CHIP 1.1").clicked() {
                                 *interpreter = Chip8::super_chip1_1();
-                                interpreter.load_program(rom);
+                                interpreter.reset_and_load(rom);
+                                if verify_quirks {
+                                    *quirk_test = Some(test_quirks(interpreter, "SUPER-CHIP 1.1", Quirks::super_chip1_1()));
+                                }
+                                ui.close_menu();
+                            } else if ui.button("XO-CHIP").clicked() {
+                                *interpreter = Chip8::xo_chip();
+                                interpreter.reset_and_load(rom);
+                                if verify_quirks {
+                                    *quirk_test = Some(test_quirks(interpreter, "XO-CHIP (Octo)", Quirks::octo_chip()));
+                                }
                                 ui.close_menu();
                             }
                         })
@@ -354,7 +2622,14 @@ pub fn draw_variant_specifics(interpreter: &mut Chip8, rom: &Vec<u8>, ctx: &egui
                     }
                 }
 
-                if interpreter.variant != e_chip::Variant::CHIP8 {
+                if !verify_quirks {
+                    *quirk_test = None;
+                }
+                if let Some(report) = quirk_test {
+                    draw_quirk_test_badge(ui, interpreter, report);
+                }
+
+                if interpreter.variant.supports_schip() {
                     ui.separator();
 
                     ui.colored_label(
@@ -366,21 +2641,54 @@ pub fn draw_variant_specifics(interpreter: &mut Chip8, rom: &Vec<u8>, ctx: &egui
                         },
                     );
 
+                    if interpreter.variant.supports_xochip() {
+                        ui.separator();
+                        ui.label(format!("Planes: {}", interpreter.get_selected_planes()))
+                            .on_hover_text("The planes Fn01 selected for drawing, clearing and scrolling: 1 for the first, 2 for the second, 3 for both.");
+                    }
+
                     ui.separator();
                     ui.label("Persistent flags:");
                     ui.spacing_mut().item_spacing.x = 5.0;
-                    for n in interpreter.get_persistent_flags() {
+                    let flags = interpreter.get_persistent_flags();
+                    for n in &flags[..interpreter.variant.persistent_flag_count()] {
                         ui.colored_label(Color32::KHAKI, format!("{:02X}", n));
                     }
+                    if let Some(e) = interpreter.get_persistent_flags_error() {
+                        ui.colored_label(Color32::RED, "Not saved").on_hover_text(e);
+                    }
                 }
 
                 ui.separator();
+
+                if interpreter.vip_timing {
+                    let used = interpreter.get_last_frame_vip_cycles();
+                    let fraction = used as f32 / VIP_CYCLES_PER_FRAME as f32;
+                    ui.label("VIP frame:");
+                    ui.add(
+                        ProgressBar::new(fraction.min(1.0))
+                            .desired_width(160.0)
+                            .fill(if fraction > 1.0 {
+                                Color32::DARK_RED
+                            } else {
+                                Color32::DARK_GREEN
+                            })
+                            .text(format!("{}/{}", used, VIP_CYCLES_PER_FRAME)),
+                    )
+                    .on_hover_text(
+                        "Estimated COSMAC VIP machine cycles used by the last frame. A real VIP could not execute this frame in time if the bar is red.",
+                    );
+                }
             });
         });
 }
 
 #[inline]
-pub fn draw_registers_and_keypad(interpreter: &Chip8, ctx: &egui::Context) {
+pub fn draw_registers_and_keypad(
+    interpreter: &Chip8,
+    keypad: &mut KeypadInput,
+    ctx: &egui::Context,
+) {
     egui::TopBottomPanel::bottom("registers")
         .show_separator_line(true)
         .resizable(false)
@@ -409,7 +2717,11 @@ pub fn draw_registers_and_keypad(interpreter: &Chip8, ctx: &egui::Context) {
                                 ui.horizontal(|ui| {
                                     ui.label("Index (I):");
                                     ui.colored_label(
-                                        I_COLOR,
+                                        if interpreter.i_changed() {
+                                            CHANGED_COLOR
+                                        } else {
+                                            I_COLOR
+                                        },
                                         format!("{:04X}", interpreter.get_i()),
                                     );
                                 });
@@ -420,11 +2732,20 @@ pub fn draw_registers_and_keypad(interpreter: &Chip8, ctx: &egui::Context) {
                                         PC_COLOR,
                                         format!("{:04X}", interpreter.get_program_counter()),
                                     );
+                                    if let Some(name) = interpreter
+                                        .symbols
+                                        .describe(interpreter.get_program_counter())
+                                    {
+                                        ui.colored_label(SYMBOL_COLOR, name);
+                                    }
                                 });
 
                                 ui.horizontal(|ui| {
                                     ui.label("Instruction:");
                                     ui.colored_label(PC_COLOR, instruction_breakdown.0);
+                                    if let Some(name) = current_target_name(interpreter) {
+                                        ui.colored_label(SYMBOL_COLOR, format!("-> {name}"));
+                                    }
                                 });
 
                                 ui.end_row();
@@ -435,6 +2756,20 @@ pub fn draw_registers_and_keypad(interpreter: &Chip8, ctx: &egui::Context) {
                                         Color32::ORANGE,
                                         format!("{:02X}", interpreter.get_stack_pointer()),
                                     );
+                                    // Warn when a ROM recurses close to the limit
+                                    ui.colored_label(
+                                        if interpreter.is_stack_near_overflow() {
+                                            Color32::RED
+                                        } else {
+                                            Color32::GRAY
+                                        },
+                                        format!(
+                                            "(max {:02X} of {:02X})",
+                                            interpreter.get_max_stack_depth(),
+                                            interpreter.get_stack_size()
+                                        ),
+                                    )
+                                    .on_hover_text("The deepest the stack has been since the last reset, and the size of the stack.");
                                 });
 
                                 ui.horizontal(|ui| {
@@ -445,7 +2780,23 @@ pub fn draw_registers_and_keypad(interpreter: &Chip8, ctx: &egui::Context) {
                                     );
                                 });
 
-                                ui.label(instruction_breakdown.1);
+                                ui.horizontal(|ui| {
+                                    ui.label(instruction_breakdown.1);
+                                    if interpreter.vip_timing {
+                                        ui.colored_label(
+                                            Color32::KHAKI,
+                                            format!(
+                                                "({} cycles)",
+                                                interpreter.vip_cycle_cost(
+                                                    interpreter.get_current_opcode()
+                                                )
+                                            ),
+                                        )
+                                        .on_hover_text(
+                                            "Estimated cost on the COSMAC VIP in machine cycles",
+                                        );
+                                    }
+                                });
 
                                 ui.end_row();
                             });
@@ -467,7 +2818,12 @@ pub fn draw_registers_and_keypad(interpreter: &Chip8, ctx: &egui::Context) {
                                 for i in 0..16 {
                                     ui.centered_and_justified(|ui| {
                                         ui.colored_label(
-                                            Color32::YELLOW,
+                                            // Highlight registers changed by the last step
+                                            if interpreter.register_changed(i) {
+                                                CHANGED_COLOR
+                                            } else {
+                                                Color32::YELLOW
+                                            },
                                             format!("{:02X}", interpreter.get_register(i)),
                                         )
                                     });
@@ -475,16 +2831,30 @@ pub fn draw_registers_and_keypad(interpreter: &Chip8, ctx: &egui::Context) {
                                 ui.end_row();
 
                                 ui.label("Stack: ");
+                                let depth = interpreter.get_stack_pointer() as usize;
+                                let max_depth = interpreter.get_max_stack_depth() as usize;
                                 for i in 0..interpreter.stack_size {
-                                    let stack_text =
+                                    let return_name = (i < depth)
+                                        .then(|| interpreter.symbols.describe(interpreter.read_stack(i)))
+                                        .flatten();
+                                    let stack_text = if i < depth {
                                         RichText::new(format!("{:03X}", interpreter.read_stack(i)))
-                                            .color(Color32::ORANGE);
+                                            .color(Color32::ORANGE)
+                                    } else if i < max_depth {
+                                        // Used earlier, up to the deepest the stack has been
+                                        RichText::new("...").color(Color32::GRAY)
+                                    } else {
+                                        RichText::new("-").color(Color32::DARK_GRAY)
+                                    };
                                     ui.centered_and_justified(|ui| {
-                                        ui.label(if i == interpreter.get_stack_pointer() as usize {
-                                            stack_text.underline() // Highlight the value the stack pointer is pointing to
+                                        let label = ui.label(if i + 1 == depth {
+                                            stack_text.underline() // Highlight the value on top of the stack
                                         } else {
                                             stack_text
-                                        })
+                                        });
+                                        if let Some(name) = return_name {
+                                            label.on_hover_text(format!("Returns to {name}"));
+                                        }
                                     });
                                 }
                                 ui.end_row();
@@ -518,44 +2888,56 @@ pub fn draw_registers_and_keypad(interpreter: &Chip8, ctx: &egui::Context) {
 
                 ui.separator();
 
-                // Keypad
-                ui.vertical(|ui| {
-                    ui.add_space(5.0);
-                    ui.spacing_mut().item_spacing = Vec2::new(-10.0, -1.0);
-                    ui.visuals_mut().override_text_color = Some(TEXT_COLOR);
-                    Grid::new("keys").show(ui, |ui| {
-                        draw_key(ui, "1", interpreter.get_key_state(1));
-                        draw_key(ui, "2", interpreter.get_key_state(2));
-                        draw_key(ui, "3", interpreter.get_key_state(3));
-                        draw_key(ui, "C", interpreter.get_key_state(12));
-                        ui.end_row();
-                        draw_key(ui, "4", interpreter.get_key_state(4));
-                        draw_key(ui, "5", interpreter.get_key_state(5));
-                        draw_key(ui, "6", interpreter.get_key_state(6));
-                        draw_key(ui, "D", interpreter.get_key_state(13));
-                        ui.end_row();
-                        draw_key(ui, "7", interpreter.get_key_state(7));
-                        draw_key(ui, "8", interpreter.get_key_state(8));
-                        draw_key(ui, "9", interpreter.get_key_state(9));
-                        draw_key(ui, "E", interpreter.get_key_state(14));
-                        ui.end_row();
-                        draw_key(ui, "A", interpreter.get_key_state(10));
-                        draw_key(ui, "0", interpreter.get_key_state(0));
-                        draw_key(ui, "B", interpreter.get_key_state(11));
-                        draw_key(ui, "F", interpreter.get_key_state(15));
-                    });
-                });
+                // Keypad, and the second keypad of the CHIP-8X
+                draw_keypad(ui, interpreter, keypad, 0);
+                if interpreter.variant == Variant::CHIP8X {
+                    ui.separator();
+                    draw_keypad(ui, interpreter, keypad, 1);
+                }
             });
 
             ui.add_space(2.5);
         });
 }
 
-/// Draw a single key visual.
-fn draw_key(ui: &mut egui::Ui, text: &str, key: bool) {
+/// Draw an on-screen keypad: 0 for the main keypad, 1 for the second keypad of the CHIP-8X.
+fn draw_keypad(ui: &mut egui::Ui, interpreter: &Chip8, keypad: &mut KeypadInput, pad: usize) {
+    ui.vertical(|ui| {
+        ui.add_space(5.0);
+        ui.spacing_mut().item_spacing = Vec2::new(-10.0, -1.0);
+        ui.visuals_mut().override_text_color = Some(TEXT_COLOR);
+        Grid::new(("keys", pad)).show(ui, |ui| {
+            for row in [[1, 2, 3, 12], [4, 5, 6, 13], [7, 8, 9, 14], [10, 0, 11, 15]] {
+                for key in row {
+                    let latched = keypad.latch_clicks && keypad.is_clicked(pad, key);
+                    let pressed = if pad == 0 {
+                        interpreter.get_key_state(key)
+                    } else {
+                        interpreter.get_key2_state(key)
+                    };
+                    let response = draw_key(ui, &format!("{:X}", key), pressed, latched);
+                    keypad.click(
+                        pad,
+                        key,
+                        response.is_pointer_button_down_on(),
+                        response.clicked(),
+                    );
+                }
+                ui.end_row();
+            }
+        });
+    });
+}
+
+/// Draw a single key visual, outlined in gold if it is latched on. The key can be clicked.
+fn draw_key(ui: &mut egui::Ui, text: &str, key: bool, latched: bool) -> egui::Response {
     Frame::default()
         .inner_margin(Margin::symmetric(11.0, 8.0))
-        .stroke(Stroke::new(1.0, Color32::WHITE))
+        .stroke(if latched {
+            Stroke::new(2.0, Color32::GOLD)
+        } else {
+            Stroke::new(1.0, Color32::WHITE)
+        })
         .fill(if key { Color32::WHITE } else { Color32::BLACK })
         .show(ui, |ui| {
             ui.add_enabled(
@@ -566,11 +2948,19 @@ fn draw_key(ui: &mut egui::Ui, text: &str, key: bool) {
                         .size(12.0),
                 ),
             );
-        });
+        })
+        .response
+        .interact(egui::Sense::click())
 }
 
 #[inline]
-pub fn draw_ram(track_pc: &mut bool, interpreter: &Chip8, ctx: &egui::Context) {
+pub fn draw_ram(
+    track_pc: &mut bool,
+    heatmap: &mut bool,
+    edit: &mut Option<(u16, String)>,
+    interpreter: &mut Chip8,
+    ctx: &egui::Context,
+) {
     egui::SidePanel::right("ram")
         .show_separator_line(true)
         .default_width(242.5)
@@ -581,6 +2971,9 @@ pub fn draw_ram(track_pc: &mut bool, interpreter: &Chip8, ctx: &egui::Context) {
 
                 ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
                     ui.checkbox(track_pc, "Track PC");
+                    ui.checkbox(heatmap, "Heatmap").on_hover_text(
+                        "Color bytes by how often instructions read (blue) or wrote (red) them since the last reset.",
+                    );
                 });
             });
             ui.separator();
@@ -591,13 +2984,7 @@ pub fn draw_ram(track_pc: &mut bool, interpreter: &Chip8, ctx: &egui::Context) {
                 .show(ui, |ui| {
                     ui.horizontal_top(|ui| {
                         ui.horizontal_wrapped(|ui| {
-                            let mut addresses = String::new();
-                            for i in (0..interpreter.ram_len()).step_by(8) {
-                                addresses += &format!("{:04X}\n", i);
-                            }
-                            addresses.pop(); // Remove last newline
-
-                            ui.label(&addresses);
+                            draw_ram_addresses(ui, interpreter);
                         });
 
                         ui.add_space(-2.0);
@@ -608,133 +2995,469 @@ pub fn draw_ram(track_pc: &mut bool, interpreter: &Chip8, ctx: &egui::Context) {
                             ui.spacing_mut().item_spacing.x -= 1.; // remove space around colored bytes
                             ui.visuals_mut().override_text_color = Some(TEXT_COLOR);
 
-                            let mut bytes = String::new();
-                            for i in 0..interpreter.ram_len() as u16 {
-                                if i == interpreter.get_program_counter() {
-                                    bytes.pop(); // Remove space
-                                    if !bytes.is_empty() {
-                                        ui.label(&bytes);
+                            // Bytes can be edited while paused
+                            let editable = !interpreter.is_running();
+                            if !editable {
+                                *edit = None;
+                            }
+                            let editing = edit.as_ref().map(|(address, _)| *address);
+                            let pc = interpreter.get_program_counter();
+                            // The current instruction is highlighted unless it is being edited
+                            let highlight_pc = editing
+                                .is_none_or(|address| address != pc && address != pc.wrapping_add(1));
+
+                            let max_count = if *heatmap {
+                                interpreter.get_access_log().max_access_count()
+                            } else {
+                                0
+                            };
+                            let mut run = RamRun {
+                                bytes: String::new(),
+                                heat: None,
+                                editable,
+                                clicked: None,
+                            };
+                            for i in (0..interpreter.ram_len()).map(|i| i as u16) {
+                                if Some(i) == editing {
+                                    run.flush(ui, i as usize);
+                                    if let Some((_, text)) = edit.as_mut() {
+                                        let response = ui.add(
+                                            TextEdit::singleline(text)
+                                                .char_limit(2)
+                                                .desired_width(14.0)
+                                                .margin(Margin::ZERO),
+                                        );
+                                        if response.lost_focus() {
+                                            // Enter writes the byte, anything else cancels
+                                            let enter = ui.input(|input| input.key_pressed(egui::Key::Enter));
+                                            if let (true, Ok(value)) = (enter, u8::from_str_radix(text.trim(), 16)) {
+                                                interpreter.poke(i, value);
+                                            }
+                                            *edit = None;
+                                        } else if !response.has_focus() {
+                                            response.request_focus();
+                                        }
                                     }
-                                    bytes.clear();
+                                } else if highlight_pc && i == pc {
+                                    run.flush(ui, i as usize);
                                 // Highlight the current instruction
-                                } else if i == interpreter.get_program_counter() + 1 {
+                                } else if highlight_pc && i == pc + 1 {
                                     if *track_pc {
                                         ui.scroll_to_cursor(Some(Align::TOP));
                                     }
-                                    ui.label(
-                                        RichText::new(format!(
-                                            "{:02X} {:02X}",
-                                            interpreter.read_byte(i - 1),
-                                            interpreter.read_byte(i)
-                                        ))
-                                        .background_color(PC_COLOR),
-                                    );
+                                    let text = RichText::new(format!(
+                                        "{:02X} {:02X}",
+                                        interpreter.read_byte(i - 1),
+                                        interpreter.read_byte(i)
+                                    ))
+                                    .background_color(PC_COLOR);
+                                    run.show(ui, text, i as usize + 1, 2);
                                 // Highlight the place the index register is pointing to
                                 } else if i == interpreter.get_i() {
-                                    bytes.pop(); // Remove space
-                                    if !bytes.is_empty() {
-                                        ui.label(&bytes);
-                                    }
-                                    bytes.clear();
-                                    ui.label(
-                                        RichText::new(format!("{:02X}", interpreter.read_byte(i)))
-                                            .background_color(I_COLOR),
-                                    );
+                                    run.flush(ui, i as usize);
+                                    let text = RichText::new(format!("{:02X}", interpreter.read_byte(i)))
+                                        .background_color(I_COLOR);
+                                    run.show(ui, text, i as usize + 1, 1);
                                 } else {
-                                    bytes += &format!("{:02X} ", interpreter.read_byte(i));
+                                    let heat = if *heatmap {
+                                        heat_color(interpreter.get_access_log().access_counts(i), max_count)
+                                    } else {
+                                        None
+                                    };
+                                    if heat != run.heat {
+                                        run.flush(ui, i as usize);
+                                        run.heat = heat;
+                                    }
+                                    run.bytes += &format!("{:02X} ", interpreter.read_byte(i));
                                 }
                             }
-                            bytes.pop(); // Remove last space
-                            ui.label(&bytes);
+                            run.flush(ui, interpreter.ram_len());
+                            if let Some(address) = run.clicked {
+                                *edit = Some((address, format!("{:02X}", interpreter.read_byte(address))));
+                            }
                         });
                     });
                 });
         });
 }
 
-/// Break down an opcode into a generic pattern and explanation, taking quirks and variant into account.  
-///
-/// For example, when given the opcode `3124`, the function will return `("3xnn", "Skip if Vx != nn")`
-#[inline]
-pub fn explain_instruction(
-    opcode: u16,
-    quirks: &Quirks,
-    variant: &e_chip::Variant,
-) -> (&'static str, &'static str) {
-    let unknown = ("????", "Illegal instruction");
-    match opcode >> 12 {
-        0x0 => {
-            if opcode & 0xFFF0 == 0x00C0 {
-                ("00Cn", "Scroll down by n pixels")
-            } else {
-                match opcode {
-                    0x0000 => ("0000", "Empty (Stops emulator)"),
-                    0x00E0 => ("00E0", "Clear screen"),
-                    0x00EE => ("00EE", "Return from subroutine"),
-                    0x00FB if variant.supports_schip() => ("00FB", "Scroll right by 4 pixels"),
-                    0x00FC if variant.supports_schip() => ("00FB", "Scroll left by 4 pixels"),
-                    0x00FD if variant.supports_schip() => ("00FD", "Exit the interpreter"),
-                    0x00FE if variant.supports_schip() => ("00FE", "Disable highres mode"),
-                    0x00FF if variant.supports_schip() => ("00FF", "Enable highres mode"),
-                    _ => ("0nnn", "Machine code routine"),
-                }
+/// Show the address of every row of the RAM panel. Rows with a breakpoint are shown in red, and
+/// rows with a symbol in purple, which are named when hovered. Clicking a row toggles a breakpoint
+/// at its first address, and right-clicking it lists the breakpoints of every instruction of the
+/// row.
+fn draw_ram_addresses(ui: &mut egui::Ui, interpreter: &mut Chip8) {
+    let rows = interpreter.ram_len().div_ceil(8);
+    let font_id = TextStyle::Body.resolve(ui.style());
+    let mut addresses = LayoutJob::default();
+    for row in 0..rows {
+        let address = (row * 8) as u16;
+        let breakpoint = (address..=address + 7).any(|a| interpreter.breakpoints.contains(a));
+        let symbol = (address..=address + 7).any(|a| interpreter.symbols.name(a).is_some());
+        let newline = if row + 1 < rows { "\n" } else { "" }; // No newline after the last row
+        addresses.append(
+            &format!("{address:04X}{newline}"),
+            0.0,
+            TextFormat::simple(
+                font_id.clone(),
+                if breakpoint {
+                    BREAKPOINT_COLOR
+                } else if symbol {
+                    SYMBOL_COLOR
+                } else {
+                    ui.visuals().text_color()
+                },
+            ),
+        );
+    }
+
+    let response = ui.add(Label::new(addresses).sense(egui::Sense::click()));
+    let rect = response.rect;
+    let row_at = |pos: Pos2| ((pos.y - rect.top()) / rect.height() * rows as f32) as usize;
+    let mut hover_text = "Click to toggle a breakpoint at the start of a row.\nRight-click for the other instructions of the row.".to_string();
+    if let Some(row) = response.hover_pos().map(row_at) {
+        for address in (row * 8..row * 8 + 8).map(|a| a as u16) {
+            if let Some(name) = interpreter.symbols.name(address) {
+                hover_text += &format!("\n{address:04X}: {name}");
+            }
+        }
+    }
+    let response = response.on_hover_text(hover_text);
+    if let Some(pos) = response.interact_pointer_pos() {
+        if response.clicked() {
+            interpreter.breakpoints.toggle((row_at(pos) * 8) as u16);
+        } else if response.secondary_clicked() {
+            ui.memory_mut(|memory| memory.data.insert_temp(response.id, row_at(pos)));
+        }
+    }
+    response.context_menu(|ui| {
+        let row: usize = ui.memory(|memory| memory.data.get_temp(response.id).unwrap_or(0));
+        for address in (row * 8..row * 8 + 8).step_by(2).map(|a| a as u16) {
+            let mut set = interpreter.breakpoints.contains(address);
+            let text = match interpreter.symbols.name(address) {
+                Some(name) => format!("Breakpoint at {address:04X} ({name})"),
+                None => format!("Breakpoint at {address:04X}"),
+            };
+            if ui.checkbox(&mut set, text).changed() {
+                interpreter.breakpoints.toggle(address);
             }
         }
-        0x1 => ("1nnn", "Jump to nnn"),
-        0x2 => ("2nnn", "Call subroutine at nnn"),
-        0x3 => ("3xnn", "Skip if Vx == nn"),
-        0x4 => ("4xnn", "Skip if Vx != nn"),
-        0x5 => ("5xy0", "Skip if Vx == Vy"),
-        0x6 => ("6xnn", "Vx = nn"),
-        0x7 => ("7xnn", "Vx = Vx + nn"),
-        0x8 => match opcode & 0x000F {
-            0x0 => ("8xy0", "Vx = Vy"),
-            0x1 if quirks.bitwise_reset_vf => ("8xy1", "Vx = Vx OR Vy (VF = 0)"),
-            0x1 => ("8xy1", "Vx = Vx OR Vy"),
-            0x2 if quirks.bitwise_reset_vf => ("8xy2", "Vx = Vx AND Vy (VF = 0)"),
-            0x2 => ("8xy2", "Vx = Vx AND Vy"),
-            0x3 if quirks.bitwise_reset_vf => ("8xy3", "Vx = Vx XOR Vy (VF = 0)"),
-            0x3 => ("8xy3", "Vx = Vx XOR Vy"),
-            0x4 => ("8xy4", "Vx = Vx + Vy (VF = overflow?)"),
-            0x5 => ("8xy5", "Vx = Vx - Vy (VF = no underflow?)"),
-            0x6 if quirks.bitwise_reset_vf => ("8xy6", "Vx = Vx >> 1 (VF = shifted bit)"),
-            0x6 => ("8xy6", "Vx = Vy >> 1 (VF = shifted bit)"),
-            0x7 => ("8xy7", "Vx = Vy - Vx (VF = no underflow?)"),
-            0xE if quirks.bitwise_reset_vf => ("8xyE", "Vx = Vx << 1 (VF = shifted bit)"),
-            0xE => ("8xyE", "Vx = Vy << 1 (VF = shifted bit)"),
-            _ => unknown,
-        },
-        0x9 => ("9xy0", "Skip if Vx != Vy"),
-        0xA => ("Annn", "I = nnn"),
-        0xB if quirks.jump_to_x => ("Bxnn", "Jump to nnn + Vx"),
-        0xB => ("Bnnn", "Jump to nnn + V0"),
-        0xC => ("Cnnn", "Vx = random AND nn"),
-        0xD if variant.supports_schip() && opcode & 0x000F == 0 => {
-            ("Dxy0", "Draw 16x16 sprite at (Vx, Vy)")
+        if ui.button("Clear all breakpoints").clicked() {
+            interpreter.breakpoints.clear_all();
+            ui.close_menu();
+        }
+    });
+}
+
+/// How many instructions the disassembly panel shows before and after the program counter.
+const DISASSEMBLY_CONTEXT: usize = 48;
+
+/// Draw the instructions around the program counter, with the current one highlighted and kept in
+/// view while running or stepping. Instructions with a breakpoint are shown in red, and clicking one toggles its
+/// breakpoint.
+pub fn draw_disassembly(interpreter: &mut Chip8, ctx: &egui::Context) {
+    egui::SidePanel::right("disassembly")
+        .show_separator_line(true)
+        .default_width(260.0)
+        .resizable(false)
+        .show(ctx, |ui| {
+            ui.heading("Disassembly");
+            ui.separator();
+            ui.spacing_mut().scroll = ScrollStyle::solid();
+            ScrollArea::vertical().auto_shrink(false).show(ui, |ui| {
+                ui.spacing_mut().item_spacing.y = 0.0;
+                ui.visuals_mut().override_text_color = Some(TEXT_COLOR);
+                let pc = interpreter.get_program_counter();
+                // Also follow the program counter when stepping
+                let id = ui.id().with("program counter");
+                let moved = ui.memory(|memory| memory.data.get_temp::<u16>(id)) != Some(pc);
+                ui.memory_mut(|memory| memory.data.insert_temp(id, pc));
+                for instruction in interpreter.disassemble_around(pc, DISASSEMBLY_CONTEXT) {
+                    if let Some(label) = &instruction.label {
+                        ui.colored_label(SYMBOL_COLOR, format!("{label}:"));
+                    }
+                    let address = instruction.address;
+                    let mut text = RichText::new(format!(
+                        "{address:04X}  {:04X}  {} {}",
+                        instruction.opcode, instruction.mnemonic, instruction.operands
+                    ));
+                    if interpreter.breakpoints.contains(address) {
+                        text = text.color(BREAKPOINT_COLOR);
+                    }
+                    if address == pc {
+                        text = text.background_color(PC_COLOR);
+                    }
+                    let response = ui
+                        .add(Label::new(text).sense(egui::Sense::click()))
+                        .on_hover_text(instruction.explanation);
+                    if address == pc && (interpreter.is_running() || moved) {
+                        response.scroll_to_me(Some(Align::Center));
+                    }
+                    if response.clicked() {
+                        interpreter.breakpoints.toggle(address);
+                    }
+                }
+            });
+        });
+}
+
+/// Consecutive RAM bytes with the same heatmap color, shown as one label.
+struct RamRun {
+    bytes: String,
+    heat: Option<Color32>,
+    /// Whether clicking a byte starts editing it.
+    editable: bool,
+    /// The address of the byte that was clicked.
+    clicked: Option<u16>,
+}
+
+impl RamRun {
+    /// Show the bytes, which end before `end`, with their heatmap color if any.
+    fn flush(&mut self, ui: &mut egui::Ui, end: usize) {
+        self.bytes.pop(); // Remove space
+        if !self.bytes.is_empty() {
+            let text = RichText::new(self.bytes.as_str());
+            let text = match self.heat {
+                Some(color) => text.background_color(color),
+                None => text,
+            };
+            self.show(ui, text, end, self.bytes.len().div_ceil(3));
+        }
+        self.bytes.clear();
+    }
+
+    /// Show `count` bytes that end before `end` as one label, and remember which one was clicked.
+    fn show(&mut self, ui: &mut egui::Ui, text: RichText, end: usize, count: usize) {
+        if !self.editable {
+            ui.label(text);
+            return;
+        }
+        let (pos, galley, response) = Label::new(text)
+            .sense(egui::Sense::click())
+            .layout_in_ui(ui);
+        if let Some(pointer) = response
+            .interact_pointer_pos()
+            .filter(|_| response.clicked())
+        {
+            // Every byte is two digits and a space
+            let index = galley.cursor_from_pos(pointer - pos).ccursor.index / 3;
+            self.clicked = Some((end - count + index.min(count - 1)) as u16);
+        }
+        ui.painter().galley(pos, galley, ui.visuals().text_color());
+    }
+}
+
+/// Get the heatmap color of a byte from its read and write counts: brighter the more often it was
+/// accessed, red if it was ever written and blue if it was only read.
+fn heat_color((reads, writes): (u32, u32), max_count: u32) -> Option<Color32> {
+    let count = reads.saturating_add(writes);
+    if count == 0 {
+        return None;
+    }
+    // Four levels on a logarithmic scale, so rarely accessed bytes still stand out
+    let level = 1 + ((count as f32).ln_1p() / (max_count as f32).ln_1p() * 3.0).round() as u8;
+    let intensity = 40 + level * 40;
+    Some(if writes > 0 {
+        Color32::from_rgb(intensity, 30, 30)
+    } else {
+        Color32::from_rgb(30, 30, intensity)
+    })
+}
+
+/// Show whether the interpreter passed the quirk test of the picked platform, running the test
+/// again if the variant or quirks changed since.
+fn draw_quirk_test_badge(ui: &mut egui::Ui, interpreter: &Chip8, report: &mut QuirkTestReport) {
+    if report.is_stale(interpreter) {
+        *report = test_quirks(interpreter, report.platform, report.expected);
+    }
+
+    ui.separator();
+    if report.passed() {
+        ui.colored_label(
+            Color32::GREEN,
+            tr_with("Behaves like {}", &[&report.platform]),
+        )
+        .on_hover_text(tr(
+            "Every quirk that can be tested with this variant behaves like the platform.",
+        ));
+    } else {
+        ui.colored_label(
+            Color32::RED,
+            tr_with("Does not behave like {}", &[&report.platform]),
+        )
+        .on_hover_text(tr_with(
+            "Quirks that differ: {}",
+            &[&report
+                .mismatches
+                .iter()
+                .map(|&quirk| tr(quirk))
+                .collect::<Vec<_>>()
+                .join(", ")],
+        ));
+    }
+}
+
+/// The display name of a variant.
+fn variant_name(variant: &Variant) -> &'static str {
+    match variant {
+        Variant::CHIP8 => "CHIP-8",
+        Variant::VIPHYBRID => "CHIP-8 (hybrid)",
+        Variant::CHIP8X => "CHIP-8X",
+        Variant::CHIP48 => "CHIP-48",
+        Variant::ETI660 => "ETI-660",
+        Variant::SCHIP10 => "SUPER-CHIP 1.0",
+        Variant::SCHIP11 => "SUPER-CHIP 1.1",
+        Variant::XOCHIP => "XO-CHIP",
+    }
+}
+
+/// Build the window title from the ROM name, variant and run state. `rom_title` is shown in place
+/// of the file name, and `unsaved` adds a marker for state changes since the last export, import or
+/// resume.
+pub fn window_title(
+    interpreter: &Chip8,
+    rom_path: Option<&str>,
+    rom_title: Option<&str>,
+    unsaved: bool,
+) -> String {
+    let rom = match rom_title {
+        Some(title) => title.into(),
+        None => rom_path
+            .and_then(|path| Path::new(path).file_name())
+            .map_or("untitled".into(), |name| name.to_string_lossy()),
+    };
+    let status = if interpreter.halt_reason.is_some() {
+        "halted"
+    } else if interpreter.is_running() {
+        "running"
+    } else {
+        "paused"
+    };
+    format!(
+        "E-CHIP - {rom} ({}, {status}){}",
+        variant_name(&interpreter.variant),
+        if unsaved { " *" } else { "" }
+    )
+}
+
+/// Disassemble the instructions leading up to a halt, marking the one that halted with `>`.
+pub fn halt_context_lines(interpreter: &Chip8) -> Vec<String> {
+    let context = interpreter.get_halt_context();
+    context
+        .iter()
+        .enumerate()
+        .map(|(i, &(address, opcode))| {
+            let (pattern, explanation) =
+                explain_instruction(opcode, &interpreter.quirks, &interpreter.variant);
+            format!(
+                "{} {:04X}  {:04X}  {}  {}",
+                if i == context.len() - 1 { ">" } else { " " },
+                address,
+                opcode,
+                pattern,
+                explanation
+            )
+        })
+        .collect()
+}
+
+/// Get the name of the address the current instruction jumps to, calls or sets I to, if it has one.
+fn current_target_name(interpreter: &Chip8) -> Option<&str> {
+    let pc = interpreter.get_program_counter();
+    // Four bytes for F000 nnnn
+    let bytes: Vec<u8> = (pc..pc.saturating_add(4))
+        .take_while(|&address| (address as usize) < interpreter.ram_len())
+        .map(|address| interpreter.read_byte(address))
+        .collect();
+    let instruction = disassemble(&bytes, pc, &interpreter.quirks, &interpreter.variant)
+        .into_iter()
+        .next()?;
+    interpreter.symbols.name(instruction.target?)
+}
+
+/// Describe the machine state in plain text, for screen readers or for pasting into a chat.
+pub fn state_summary(interpreter: &Chip8) -> String {
+    let opcode = interpreter.get_current_opcode();
+    let (pattern, explanation) =
+        explain_instruction(opcode, &interpreter.quirks, &interpreter.variant);
+
+    let mut summary = format!(
+        "Variant: {}\nStatus: {}\n",
+        variant_name(&interpreter.variant),
+        if let Some(reason) = &interpreter.halt_reason {
+            format!("halted: {reason}")
+        } else if interpreter.is_waiting_for_key() {
+            format!(
+                "waiting for a key press to save in V{:X}",
+                interpreter.get_key_destination_register()
+            )
+        } else if interpreter.is_running() {
+            "running".to_string()
+        } else {
+            "paused".to_string()
+        }
+    );
+    summary += &format!("PC: {:04X}", interpreter.get_program_counter());
+    if let Some(name) = interpreter
+        .symbols
+        .describe(interpreter.get_program_counter())
+    {
+        summary += &format!(" ({name})");
+    }
+    summary += &format!(
+        "\nCurrent instruction: {:04X} ({}: {})\n",
+        opcode, pattern, explanation
+    );
+    if interpreter.halt_reason.is_some() {
+        summary += "Instructions before the halt:\n";
+        for line in halt_context_lines(interpreter) {
+            summary += &line;
+            summary += "\n";
+        }
+    }
+
+    summary += "Registers:";
+    for i in 0..16 {
+        summary += &format!(" V{:X}={:02X}", i, interpreter.get_register(i));
+    }
+    summary += &format!(" I={:04X}\n", interpreter.get_i());
+
+    summary += &format!(
+        "Timers: delay={} sound={}\n",
+        interpreter.get_delay(),
+        interpreter.get_sound()
+    );
+
+    summary += "Stack:";
+    for i in 0..interpreter.get_stack_pointer() as usize {
+        let address = interpreter.read_stack(i);
+        summary += &format!(" {address:04X}");
+        if let Some(name) = interpreter.symbols.describe(address) {
+            summary += &format!(" ({name})");
         }
-        0xD => ("Dxyn", "Draw 8xn sprite at (Vx, Vy)"),
-        0xE => match opcode & 0x00FF {
-            0x9E => ("Ex9E", "Skip if key code Vx is down"),
-            0xA1 => ("ExA1", "Skip if key code Vx is up"),
-            _ => unknown,
-        },
-        0xF => match opcode & 0x00FF {
-            0x07 => ("Fx07", "Vx = delay"),
-            0x0A => ("Fx0A", "Wait for key press and save to Vx"),
-            0x15 => ("Fx15", "delay = Vx"),
-            0x18 => ("Fx18", "sound = Vx"),
-            0x1E => ("Fx1E", "I = I + Vx"),
-            0x29 => ("Fx29", "I = font for Vx"),
-            0x30 if variant.supports_schip() => ("Fx30", "I = big font for Vx"),
-            0x33 => ("Fx33", "Write Vx as BCD"),
-            0x55 if quirks.save_load_increment => ("Fx55", "Write V0 to Vx"),
-            0x55 => ("Fx65", "Write V0 to Vx (I = I + x)"),
-            0x65 if quirks.save_load_increment => ("Fx65", "Read V0 to Vx"),
-            0x65 => ("Fx65", "Read V0 to Vx (I = I + x)"),
-            0x75 if variant.supports_schip() => ("Fx75", "Save V0 to Vx to persistent flags"),
-            0x85 if variant.supports_schip() => ("Fx85", "Load V0 to Vx from persistent flags"),
-            _ => unknown,
-        },
-        _ => unknown,
+    }
+    if interpreter.get_stack_pointer() == 0 {
+        summary += " empty";
+    }
+
+    summary += "\nKeys pressed:";
+    let pressed: Vec<_> = (0..16)
+        .filter(|&key| interpreter.get_key_state(key))
+        .map(|key| format!(" {:X}", key))
+        .collect();
+    if pressed.is_empty() {
+        summary += " none";
+    } else {
+        summary += &pressed.concat();
+    }
+    summary += "\n";
+
+    summary
+}
+
+/// The menu entry of a save slot: its number and when it was saved.
+fn slot_entry(slots: &SlotsView, slot: usize) -> String {
+    match &slots.slots[slot] {
+        Some(saved) => tr_with("Slot {}: {}", &[&(slot + 1), &saved.timestamp()]),
+        None => tr_with("Slot {}: empty", &[&(slot + 1)]),
     }
 }
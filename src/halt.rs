@@ -0,0 +1,73 @@
+use std::fmt;
+
+/// The hint added to halts caused by addresses past the end of memory.
+const MEMORY_HINT: &str =
+    "Enable \"Wrap memory accesses\" in the quirks or try a variant with more memory.";
+
+/// Why the interpreter halted. Displays as the message shown to the user, in English.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum HaltReason {
+    /// The opcode is not an instruction of the variant.
+    IllegalInstruction { opcode: u16 },
+    /// A `0nnn` machine code routine, on a variant that does not run them.
+    MachineCodeUnsupported { opcode: u16 },
+    /// The machine code routine at `address` ran for too long without returning to the
+    /// interpreter.
+    MachineCodeDidNotReturn { address: u16 },
+    /// The instruction at `address` accessed memory past its end, without the `memory_wrap` quirk.
+    MemoryOutOfBounds { address: u16, opcode: u16 },
+    /// The instruction at `address` jumped, called or returned to `target` past the end of memory,
+    /// without the `memory_wrap` quirk.
+    JumpOutOfBounds {
+        address: u16,
+        opcode: u16,
+        target: u16,
+    },
+    /// The `2nnn` at `address` called a subroutine with the stack full.
+    StackOverflow { address: u16, opcode: u16 },
+    /// The `00EE` at `address` returned with the stack empty.
+    StackUnderflow { address: u16, opcode: u16 },
+    /// The ROM is `size` bytes, but only `capacity` fit in memory.
+    RomTooLarge { size: usize, capacity: usize },
+}
+
+impl fmt::Display for HaltReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HaltReason::IllegalInstruction { opcode } => {
+                write!(f, "Illegal instruction: {opcode:04X}")
+            }
+            HaltReason::MachineCodeUnsupported { opcode } => write!(
+                f,
+                "Machine code routines are not supported: {opcode:04X}. Try a different CHIP-8 variant."
+            ),
+            HaltReason::MachineCodeDidNotReturn { address } => write!(
+                f,
+                "The machine code routine at {address:03X} did not return to the interpreter."
+            ),
+            HaltReason::MemoryOutOfBounds { address, opcode } => write!(
+                f,
+                "Memory access out of bounds at {address:04X}: {opcode:04X}. {MEMORY_HINT}"
+            ),
+            HaltReason::JumpOutOfBounds {
+                address,
+                opcode,
+                target,
+            } => write!(
+                f,
+                "Jump out of bounds at {address:04X}: {opcode:04X} jumps to {target:04X}. {MEMORY_HINT}"
+            ),
+            HaltReason::StackOverflow { address, opcode } => {
+                write!(f, "Stack overflow at {address:04X}: {opcode:04X}")
+            }
+            HaltReason::StackUnderflow { address, opcode } => {
+                write!(f, "Stack underflow at {address:04X}: {opcode:04X}")
+            }
+            HaltReason::RomTooLarge { size, capacity } => write!(
+                f,
+                "Could not load the ROM: the program is {size} bytes, but only {capacity} fit in memory."
+            ),
+        }
+    }
+}
@@ -0,0 +1,101 @@
+use std::{
+    fs,
+    path::PathBuf,
+    sync::mpsc::{self, Receiver},
+    time::{Duration, Instant},
+};
+
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+
+/// How long the ROM file has to stay unchanged before it is reloaded, so it is not read while an
+/// assembler or editor is still writing it.
+const SETTLE_TIME: Duration = Duration::from_millis(200);
+
+/// Watches the file of the loaded ROM, to reload it when it changes on disk.
+pub struct RomWatcher {
+    /// Repainted when the file changes, so the change is noticed while the interpreter is paused.
+    ctx: egui::Context,
+    /// The path of the watched ROM, as it was loaded.
+    path: Option<String>,
+    /// The absolute path of the watched ROM, which changes are compared with.
+    file: PathBuf,
+    /// Watches the folder of the ROM, since many editors replace files instead of writing them.
+    watcher: Option<RecommendedWatcher>,
+    /// Receives the changes in the folder.
+    events: Option<Receiver<notify::Result<Event>>>,
+    /// When the ROM last changed, if it was not reloaded since.
+    changed: Option<Instant>,
+}
+
+impl RomWatcher {
+    pub fn new(ctx: &egui::Context) -> RomWatcher {
+        RomWatcher {
+            ctx: ctx.clone(),
+            path: None,
+            file: PathBuf::new(),
+            watcher: None,
+            events: None,
+            changed: None,
+        }
+    }
+
+    /// Watch another ROM file, or stop watching with `None`. Does nothing if it is already watched.
+    pub fn watch(&mut self, path: Option<&str>) {
+        if self.path.as_deref() == path {
+            return;
+        }
+        self.path = path.map(str::to_string);
+        self.watcher = None;
+        self.events = None;
+        self.changed = None;
+        if let Some(path) = path {
+            if let Err(e) = self.start(path) {
+                println!("Could not watch {path} for changes: {e}");
+            }
+        }
+    }
+
+    /// Start watching the folder of a ROM file.
+    fn start(&mut self, path: &str) -> Result<(), String> {
+        let file = fs::canonicalize(path).map_err(|e| e.to_string())?;
+        let folder = file.parent().ok_or("the ROM is not in a folder")?;
+        let (sender, receiver) = mpsc::channel();
+        let ctx = self.ctx.clone();
+        let mut watcher = notify::recommended_watcher(move |event: notify::Result<Event>| {
+            let _ = sender.send(event);
+            ctx.request_repaint();
+        })
+        .map_err(|e| e.to_string())?;
+        watcher
+            .watch(folder, RecursiveMode::NonRecursive)
+            .map_err(|e| e.to_string())?;
+        self.file = file;
+        self.watcher = Some(watcher);
+        self.events = Some(receiver);
+        Ok(())
+    }
+
+    /// Check if the ROM changed on disk and has not changed again for a moment, so it should be
+    /// reloaded now.
+    pub fn poll(&mut self) -> bool {
+        if let Some(events) = &self.events {
+            for event in events.try_iter().flatten() {
+                let written = matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_));
+                if written && event.paths.contains(&self.file) {
+                    self.changed = Some(Instant::now());
+                }
+            }
+        }
+        match self.changed {
+            Some(time) if time.elapsed() >= SETTLE_TIME => {
+                self.changed = None;
+                true
+            }
+            Some(time) => {
+                self.ctx.request_repaint_after(SETTLE_TIME - time.elapsed());
+                false
+            }
+            None => false,
+        }
+    }
+}
@@ -0,0 +1,326 @@
+use std::{
+    fmt::Display,
+    sync::atomic::{AtomicU8, Ordering},
+};
+
+use e_chip::HaltReason;
+
+/// A language the GUI can be shown in.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum Language {
+    #[default]
+    English,
+    Spanish,
+}
+
+impl Language {
+    /// All languages, in the order they are listed in the settings.
+    pub const ALL: [Language; 2] = [Language::English, Language::Spanish];
+
+    /// The name of the language in that language.
+    pub const fn name(&self) -> &'static str {
+        match self {
+            Language::English => "English",
+            Language::Spanish => "Español",
+        }
+    }
+}
+
+/// The current GUI language. Global so every draw function can translate without passing it around.
+static LANGUAGE: AtomicU8 = AtomicU8::new(Language::English as u8);
+
+/// Change the GUI language.
+pub fn set_language(language: Language) {
+    LANGUAGE.store(language as u8, Ordering::Relaxed);
+}
+
+/// Get the current GUI language.
+pub fn language() -> Language {
+    match LANGUAGE.load(Ordering::Relaxed) {
+        1 => Language::Spanish,
+        _ => Language::English,
+    }
+}
+
+/// Translate an English GUI string to the current language. Strings without a translation are shown
+/// in English.
+///
+/// Strings with values in them use `{}` as a placeholder, see [`tr_with`].
+pub fn tr(text: &'static str) -> &'static str {
+    match language() {
+        Language::English => text,
+        Language::Spanish => spanish(text).unwrap_or(text),
+    }
+}
+
+/// Translate an English GUI string and replace its `{}` placeholders with `values` in order.
+pub fn tr_with(text: &'static str, values: &[&dyn Display]) -> String {
+    let mut result = String::new();
+    for (i, part) in tr(text).split("{}").enumerate() {
+        if let Some(value) = i.checked_sub(1).and_then(|i| values.get(i)) {
+            result += &value.to_string();
+        }
+        result += part;
+    }
+    result
+}
+
+/// Translate why the interpreter halted.
+pub fn tr_halt_reason(reason: &HaltReason) -> String {
+    let hex = |value: &u16| format!("{value:04X}");
+    match reason {
+        HaltReason::IllegalInstruction { opcode } => {
+            tr_with("Illegal instruction: {}", &[&hex(opcode)])
+        }
+        HaltReason::MachineCodeUnsupported { opcode } => tr_with(
+            "Machine code routines are not supported: {}. Try a different CHIP-8 variant.",
+            &[&hex(opcode)],
+        ),
+        HaltReason::MachineCodeDidNotReturn { address } => tr_with(
+            "The machine code routine at {} did not return to the interpreter.",
+            &[&format!("{address:03X}")],
+        ),
+        HaltReason::MemoryOutOfBounds { address, opcode } => tr_with(
+            "Memory access out of bounds at {}: {}. Enable \"Wrap memory accesses\" in the quirks or try a variant with more memory.",
+            &[&hex(address), &hex(opcode)],
+        ),
+        HaltReason::JumpOutOfBounds {
+            address,
+            opcode,
+            target,
+        } => tr_with(
+            "Jump out of bounds at {}: {} jumps to {}. Enable \"Wrap memory accesses\" in the quirks or try a variant with more memory.",
+            &[&hex(address), &hex(opcode), &hex(target)],
+        ),
+        HaltReason::StackOverflow { address, opcode } => {
+            tr_with("Stack overflow at {}: {}", &[&hex(address), &hex(opcode)])
+        }
+        HaltReason::StackUnderflow { address, opcode } => {
+            tr_with("Stack underflow at {}: {}", &[&hex(address), &hex(opcode)])
+        }
+        HaltReason::RomTooLarge { size, capacity } => tr_with(
+            "Could not load the ROM: the program is {} bytes, but only {} fit in memory.",
+            &[size, capacity],
+        ),
+    }
+}
+
+/// The Spanish translation of a GUI string.
+fn spanish(text: &str) -> Option<&'static str> {
+    Some(match text {
+        // Menus
+        "File" => "Archivo",
+        "Recent" => "Recientes",
+        "No recent ROMs" => "No hay ROMs recientes",
+        "Clear recent ROMs" => "Borrar ROMs recientes",
+        "Quirks" => "Peculiaridades",
+        "State" => "Estado",
+        "Settings" => "Ajustes",
+        "Export state" => "Exportar estado",
+        "Save the full machine state to a file, for example to attach an exact reproduction to a bug report." => "Guarda el estado completo de la máquina en un archivo, por ejemplo para adjuntar una reproducción exacta a un informe de error.",
+        "Import state" => "Importar estado",
+        "Replace the machine with a state saved by \"Export state\"." => "Reemplaza la máquina por un estado guardado con \"Exportar estado\".",
+        "Copy state summary" => "Copiar resumen del estado",
+        "Copy a plain-text summary of the registers, timers, stack, keypad and current instruction to the clipboard." => "Copia al portapapeles un resumen en texto plano de los registros, temporizadores, pila, teclado e instrucción actual.",
+        "Save state summary" => "Guardar resumen del estado",
+        "Export memory as ROM" => "Exportar memoria como ROM",
+        "Export disassembly" => "Exportar desensamblado",
+        "Import symbols" => "Importar símbolos",
+        "Export quirks" => "Exportar peculiaridades",
+        "Import quirks" => "Importar peculiaridades",
+        "Save the quirks to a file, to share the exact setup a ROM needs." => "Guardar las peculiaridades en un archivo, para compartir la configuración exacta que necesita una ROM.",
+        "Use the quirks saved in a file." => "Usar las peculiaridades guardadas en un archivo.",
+        "Show disassembly" => "Mostrar desensamblado",
+        "Show the instructions around the program counter in a panel next to the RAM. Click an instruction to toggle a breakpoint on it." => "Muestra las instrucciones alrededor del contador de programa en un panel junto a la RAM. Haz clic en una instrucción para activar o desactivar un punto de interrupción en ella.",
+        "Read names of addresses from a file with a name and a hex address on every line, like \"main 0x202\", and show them in the RAM panel, the stack and the disassembly. Loading an Octo source file sets them from its labels." => "Lee nombres de direcciones de un archivo con un nombre y una dirección hexadecimal en cada línea, como \"main 0x202\", y los muestra en el panel de RAM, la pila y el desensamblado. Cargar un archivo fuente de Octo los toma de sus etiquetas.",
+        "Save a listing of the program in memory with the address, opcode, mnemonic and operands of every instruction, using the current variant and quirks. Sprites and other data are listed as instructions too." => "Guarda un listado del programa en memoria con la dirección, el código de operación, el mnemónico y los operandos de cada instrucción, usando la variante y las peculiaridades actuales. Los sprites y otros datos también se listan como instrucciones.",
+        "Save slots" => "Ranuras de guardado",
+        "Ten quick save states with a screenshot of each, saved next to the ROM." => "Diez estados de guardado rápido con una captura de pantalla de cada uno, guardados junto a la ROM.",
+        "Save to slot" => "Guardar en ranura",
+        "Load from slot" => "Cargar de ranura",
+        "Slot {}: {}" => "Ranura {}: {}",
+        "Slot {}: empty" => "Ranura {}: vacía",
+        "Save memory from the start address (0x200, or 0x600 on the ETI-660) to the last non-zero byte as a .ch8 file, including any changes made since the ROM was loaded." => "Guarda la memoria desde la dirección de inicio (0x200, o 0x600 en la ETI-660) hasta el último byte distinto de cero como archivo .ch8, incluidos los cambios hechos desde que se cargó la ROM.",
+        "Export display image" => "Exportar imagen de la pantalla",
+        "Save the display as a PNG image with one pixel per CHIP-8 pixel, in the display colors." => "Guarda la pantalla como imagen PNG con un píxel por cada píxel de CHIP-8, en los colores de la pantalla.",
+        "Export display image (black and white)" => "Exportar imagen de la pantalla (blanco y negro)",
+        "Save the display as a PNG image with one pixel per CHIP-8 pixel, white on black." => "Guarda la pantalla como imagen PNG con un píxel por cada píxel de CHIP-8, en blanco sobre negro.",
+        "Save Chrome trace ({} frames)" => "Guardar traza de Chrome ({} fotogramas)",
+        "Stop recording and save the trace, which can be opened in Perfetto (ui.perfetto.dev)." => "Detiene la grabación y guarda la traza, que se puede abrir en Perfetto (ui.perfetto.dev).",
+        "Record Chrome trace" => "Grabar traza de Chrome",
+        "Stop recording frames ({} frames)" => "Detener la grabación de fotogramas ({} fotogramas)",
+        "Record frame sequence" => "Grabar secuencia de fotogramas",
+        "Write every frame as a numbered PNG image into a folder, for making videos at exactly 60 fps." => "Escribe cada fotograma como una imagen PNG numerada en una carpeta, para hacer vídeos a exactamente 60 fps.",
+        "Record executed instructions, frames, draws, key reads, sound and subroutine calls for exploring in Perfetto's timeline." => "Graba las instrucciones ejecutadas, fotogramas, dibujos, lecturas de teclas, sonido y llamadas a subrutinas para explorarlos en la línea de tiempo de Perfetto.",
+        "Save replay ({} frames)" => "Guardar repetición ({} fotogramas)",
+        "Stop recording and save the input and state hashes of every frame." => "Detiene la grabación y guarda la entrada y los hashes del estado de cada fotograma.",
+        "Stop replay" => "Detener repetición",
+        "Record replay" => "Grabar repetición",
+        "Record the keypad input of every frame from now on, with a hash of the machine state after each frame." => "Graba desde ahora la entrada del teclado de cada fotograma, con un hash del estado de la máquina después de cada uno.",
+        "Play replay" => "Reproducir repetición",
+        "Restore the state a replay was recorded from and play back its input, reporting the first frame where the machine differs from the recording." => "Restaura el estado desde el que se grabó una repetición y reproduce su entrada, indicando el primer fotograma en el que la máquina difiere de la grabación.",
+        "Recording replay: {} frames" => "Grabando repetición: {} fotogramas",
+        "Replay diverged at frame {}" => "La repetición divergió en el fotograma {}",
+        "The machine state after this frame differs from the recording, so the replay no longer reproduces it." => "El estado de la máquina después de este fotograma difiere de la grabación, así que la repetición ya no la reproduce.",
+        "Replay verified: {} frames" => "Repetición verificada: {} fotogramas",
+        "Replay: frame {}/{}" => "Repetición: fotograma {}/{}",
+        "Sound" => "Sonido",
+        "Run in background" => "Ejecutar en segundo plano",
+        "If false, the interpreter and sound are paused while the window is minimized." => "Si está desactivado, el intérprete y el sonido se pausan mientras la ventana está minimizada.",
+        "Save state on exit" => "Guardar estado al salir",
+        "Save the machine when E-CHIP is closed and offer to resume it on the next launch." => "Guarda la máquina al cerrar E-CHIP y ofrece reanudarla en el siguiente inicio.",
+        "Write crash dumps" => "Escribir volcados de fallo",
+        "Show history on halt" => "Mostrar historial al detenerse",
+        "When the interpreter halts, open the execution history with the last 64 instructions and the registers they changed." => "Cuando el intérprete se detiene, abre el historial de ejecución con las últimas 64 instrucciones y los registros que cambiaron.",
+        "When the interpreter halts, write the machine state, recent instructions, ROM hash and quirks to a crash_*.txt file to attach to bug reports. It can be loaded with Import state." => "Cuando el intérprete se detiene, escribe el estado de la máquina, las últimas instrucciones, el hash de la ROM y los quirks en un archivo crash_*.txt para adjuntar a informes de errores. Se puede cargar con Importar estado.",
+        "Verify quirk presets" => "Verificar los preajustes de peculiaridades",
+        "Reload ROM on change" => "Recargar la ROM al cambiar",
+        "When the file of the loaded ROM changes, for example because it was assembled again, reset the interpreter and load it again." => "Cuando cambie el archivo de la ROM cargada, por ejemplo porque se volvió a ensamblar, reiniciar el intérprete y cargarla de nuevo.",
+        "Configure ROMs from the CHIP-8 database" => "Configurar ROMs desde la base de datos de CHIP-8",
+        "When a ROM is loaded, look it up in the CHIP-8 database and use the variant, quirks, speed, font and colors it needs." => "Al cargar una ROM, buscarla en la base de datos de CHIP-8 y usar la variante, las peculiaridades, la velocidad, la fuente y los colores que necesita.",
+        "When a quirk preset or variant is picked, run a small test program for every quirk and show whether the interpreter behaves like the platform." => "Al elegir un preajuste de peculiaridades o una variante, ejecuta un pequeño programa de prueba para cada peculiaridad y muestra si el intérprete se comporta como la plataforma.",
+        "Behaves like {}" => "Se comporta como {}",
+        "Does not behave like {}" => "No se comporta como {}",
+        "Every quirk that can be tested with this variant behaves like the platform." => "Todas las peculiaridades que se pueden probar con esta variante se comportan como en la plataforma.",
+        "Quirks that differ: {}" => "Peculiaridades que difieren: {}",
+        "Profile COSMAC VIP timing" => "Perfilar la temporización del COSMAC VIP",
+        "Run at COSMAC VIP speed" => "Ejecutar a la velocidad del COSMAC VIP",
+        "Charge every instruction the machine cycles it would take on the original COSMAC VIP and end each frame after a VIP frame's worth of them, so games tuned for the VIP run at its speed. Replaces the cycles per frame setting." => "Cobra a cada instrucción los ciclos de máquina que tardaría en el COSMAC VIP original y termina cada fotograma tras los de un fotograma del VIP, para que los juegos ajustados al VIP vayan a su velocidad. Sustituye al ajuste de ciclos por fotograma.",
+        "Estimate how many machine cycles each instruction would take on the original COSMAC VIP and how much of a frame they use." => "Estima cuántos ciclos de máquina tardaría cada instrucción en el COSMAC VIP original y qué parte de un fotograma ocupan.",
+        "Frame pacing" => "Ritmo de fotogramas",
+        "Timer (60 fps)" => "Temporizador (60 fps)",
+        "The interpreter runs on its own at 60 frames per second." => "El intérprete se ejecuta por su cuenta a 60 fotogramas por segundo.",
+        "Display refresh (vsync)" => "Refresco de pantalla (vsync)",
+        "The interpreter runs one frame every time the screen is redrawn. Avoids tearing on 60 Hz displays, but runs faster on displays with a higher refresh rate." => "El intérprete ejecuta un fotograma cada vez que se redibuja la pantalla. Evita el tearing en pantallas de 60 Hz, pero va más rápido en pantallas con mayor frecuencia de refresco.",
+        "Font" => "Fuente",
+        "The hex digits programs draw with Fx29, as they looked on this interpreter. Replaces the font in memory right away." => "Los dígitos hexadecimales que los programas dibujan con Fx29, como se veían en este intérprete. Reemplaza la fuente en memoria al instante.",
+        "Load font file" => "Cargar archivo de fuente",
+        "Read a font binary with 80 bytes of small font (16 digits of 5 bytes), optionally followed by 160 bytes of large font (16 digits of 10 bytes), for programs that expect other digits." => "Lee un binario de fuente con 80 bytes de fuente pequeña (16 dígitos de 5 bytes), seguidos opcionalmente de 160 bytes de fuente grande (16 dígitos de 10 bytes), para programas que esperan otros dígitos.",
+        "Input" => "Entrada",
+        "Keypad layout" => "Distribución del teclado",
+        "Minimum hold frames" => "Fotogramas mínimos de pulsación",
+        "Keep keys pressed for at least this many frames, so the interpreter does not miss very short taps." => "Mantiene las teclas pulsadas durante al menos estos fotogramas, para que el intérprete no pierda pulsaciones muy cortas.",
+        "Ignore key repeat" => "Ignorar la repetición de teclas",
+        "If true, the repeated key presses your OS sends while a key is held down are ignored.\nIf false, each of them is seen as a new tap." => "Si está activado, se ignoran las pulsaciones repetidas que envía el sistema operativo mientras se mantiene una tecla.\nSi está desactivado, cada una cuenta como una pulsación nueva.",
+        "Latch clicked keys" => "Enclavar las teclas pulsadas con el ratón",
+        "If true, clicking a key of the on-screen keypad toggles it on or off, so you can step through key checks without holding it.\nIf false, the key is pressed while the mouse button is held down on it." => "Si está activado, hacer clic en una tecla del teclado en pantalla la activa o desactiva, para avanzar paso a paso por las comprobaciones de teclas sin mantenerla.\nSi está desactivado, la tecla se pulsa mientras se mantiene el botón del ratón sobre ella.",
+        "Language" => "Idioma",
+        "Pop out display" => "Separar la pantalla",
+        "Show the display in its own window, for example on a second monitor." => "Muestra la pantalla en su propia ventana, por ejemplo en un segundo monitor.",
+        "Display settings" => "Ajustes de pantalla",
+        "Show loaded ROM" => "Mostrar la ROM cargada",
+        "Show execution history" => "Mostrar el historial de ejecución",
+        "Show memory access log" => "Mostrar el registro de accesos a memoria",
+        "Show watches" => "Mostrar las expresiones vigiladas",
+        "Show sound" => "Mostrar el sonido",
+        "Attract mode" => "Modo demostración",
+        "Cycle through the ROMs of a folder, for exhibitions or for testing a whole library." => "Recorre las ROM de una carpeta, para exposiciones o para probar una biblioteca entera.",
+        "Compare variants and quirks" => "Comparar variantes y peculiaridades",
+        "Find required quirks" => "Buscar las peculiaridades necesarias",
+        "Test against a reference trace" => "Probar contra una traza de referencia",
+        "Browse chip8Archive" => "Explorar chip8Archive",
+        "Clear persistent flags" => "Borrar los flags persistentes",
+        "Persistent flags were introduced by SUPER-CHIP to allow saving and loading bytes to persistent storage. E-CHIP stores them for every ROM, for the loaded one in \"{}\"." => "SUPER-CHIP introdujo los flags persistentes para guardar y cargar bytes en almacenamiento persistente. E-CHIP los guarda para cada ROM, los de la ROM cargada en \"{}\".",
+        "memory only" => "solo en memoria",
+
+        // Quirks
+        "Presets" => "Preajustes",
+        "Bitwise operations reset VF" => "Las operaciones bit a bit ponen VF a 0",
+        "If true, the 8xy1, 8xy2 and 8xy3 opcodes will set VF to 0.\nIf false, the 8xy1, 8xy2 and 8xy3 opcodes will not modify VF." => "Si está activado, los opcodes 8xy1, 8xy2 y 8xy3 ponen VF a 0.\nSi está desactivado, los opcodes 8xy1, 8xy2 y 8xy3 no modifican VF.",
+        "Shift Vx directly" => "Desplazar Vx directamente",
+        "If true, the 8xy6 and 8xyE opcodes will set Vx to Vx >> 1.\nIf false, the 8xy6 and 8xyE opcodes will set Vx to Vy >> 1." => "Si está activado, los opcodes 8xy6 y 8xyE asignan Vx >> 1 a Vx.\nSi está desactivado, los opcodes 8xy6 y 8xyE asignan Vy >> 1 a Vx.",
+        "Jump with offset Vx" => "Saltar con desplazamiento Vx",
+        "If true, the Bnnn opcode will jump to nnn + V0.\nIf false, the Bnnn opcode will jump to nnn + Vx." => "Si está activado, el opcode Bnnn salta a nnn + V0.\nSi está desactivado, el opcode Bnnn salta a nnn + Vx.",
+        "Memory access index register increment" => "Incremento del registro índice al acceder a memoria",
+        "The Fx55 and Fx65 opcodes will set I to I + x + 1, like the COSMAC-VIP." => "Los opcodes Fx55 y Fx65 asignan I + x + 1 a I, como la COSMAC-VIP.",
+        "The Fx55 and Fx65 opcodes will set I to I + x, like CHIP-48 and SUPER-CHIP 1.0." => "Los opcodes Fx55 y Fx65 asignan I + x a I, como CHIP-48 y SUPER-CHIP 1.0.",
+        "None" => "Ninguno",
+        "The Fx55 and Fx65 opcodes will not modify I, like SUPER-CHIP 1.1." => "Los opcodes Fx55 y Fx65 no modifican I, como SUPER-CHIP 1.1.",
+        "Clip sprites at edges" => "Recortar sprites en los bordes",
+        "If true, the Dxyn opcode will clip sprites that go off the edge of the screen.\nIf false, the Dxyn opcode will wrap sprites that go off the edge of the screen around." => "Si está activado, el opcode Dxyn recorta los sprites que salen de la pantalla.\nSi está desactivado, el opcode Dxyn hace que los sprites que salen de la pantalla aparezcan por el lado opuesto.",
+        "Wait for vblank interrupt" => "Esperar la interrupción de vblank",
+        "Never" => "Nunca",
+        "The Dxyn opcode will draw immediately." => "El opcode Dxyn dibuja inmediatamente.",
+        "Lowres only" => "Solo en baja resolución",
+        "The Dxyn opcode will wait for a vblank interrupt in lowres mode and draw immediately in highres mode, like SUPER-CHIP 1.1 on the HP48." => "El opcode Dxyn espera una interrupción de vblank en baja resolución y dibuja inmediatamente en alta resolución, como SUPER-CHIP 1.1 en la HP48.",
+        "Always" => "Siempre",
+        "The Dxyn opcode will wait for a vblank interrupt (happens 60 times a second) before drawing." => "El opcode Dxyn espera una interrupción de vblank (ocurre 60 veces por segundo) antes de dibujar.",
+        "Legacy scrolling" => "Desplazamiento antiguo",
+        "Key wait finishes on press" => "La espera de tecla termina al pulsar",
+        "If true, the Fx0A opcode will finish as soon as a key is pressed, like many modern interpreters.\nIf false, the Fx0A opcode will wait until the key is released again, like the COSMAC-VIP." => "Si está activado, el opcode Fx0A termina en cuanto se pulsa una tecla, como muchos intérpretes modernos.\nSi está desactivado, el opcode Fx0A espera hasta que se suelta la tecla, como el COSMAC-VIP.",
+        "8x16 sprites in lowres" => "Sprites de 8x16 en baja resolución",
+        "Only applies to SUPER-CHIP: If `true`, the Dxy0 opcode in lowres mode will draw 8x16 sprites, like SUPER-CHIP 1.0.\nIf `false`, the Dxy0 opcode will draw 16x16 sprites in both modes." => "Solo se aplica a SUPER-CHIP: si está activado, el opcode Dxy0 en baja resolución dibuja sprites de 8x16, como SUPER-CHIP 1.0.\nSi está desactivado, el opcode Dxy0 dibuja sprites de 16x16 en ambos modos.",
+        "Only applies to SUPER-CHIP: If `true`, the scroll opcodes (`00Cn`, `00FB`, `00FC`) in lowres mode will scroll by half pixels.\nIf `false`, the scroll opcodes in lowres mode will scroll the expected amount of full pixels." => "Solo se aplica a SUPER-CHIP: si está activado, los opcodes de desplazamiento (`00Cn`, `00FB`, `00FC`) en baja resolución desplazan medios píxeles.\nSi está desactivado, desplazan la cantidad esperada de píxeles completos.",
+
+        "Clear display on resolution change" => "Borrar la pantalla al cambiar de resolución",
+        "Only applies to SUPER-CHIP: If `true`, the resolution opcodes (`00FE`, `00FF`) will clear the display, like Octo.\nIf `false`, the resolution opcodes will keep the display contents, like the original SUPER-CHIP." => "Solo se aplica a SUPER-CHIP: si está activado, los opcodes de resolución (`00FE`, `00FF`) borran la pantalla, como Octo.\nSi está desactivado, conservan el contenido de la pantalla, como el SUPER-CHIP original.",
+        "Deterministic random numbers" => "Números aleatorios deterministas",
+        "If true, the Cxnn opcode will get its numbers from a fixed sequence, which gives the same numbers in the same order every run.\nIf false, the Cxnn opcode will use a true random number generator." => "Si está activado, el opcode Cxnn obtiene sus números de una secuencia fija, que da los mismos números en el mismo orden en cada ejecución.\nSi está desactivado, el opcode Cxnn usa un generador de números aleatorios real.",
+        "Wrap memory accesses" => "Dar la vuelta a los accesos a memoria",
+        "If true, the opcodes that access memory at I, and jumps, calls and returns, will wrap addresses past the end of memory around to 0x000.\nIf false, the interpreter will halt when they go past the end." => "Si está activado, los opcodes que acceden a la memoria en I, y los saltos, llamadas y retornos, dan la vuelta a 0x000 con las direcciones que pasan del final de la memoria.\nSi está desactivado, el intérprete se detiene cuando pasan del final.",
+
+        // Controls
+        "Load ROM" => "Cargar ROM",
+        "Pause" => "Pausa",
+        "Run" => "Ejecutar",
+        "Step back" => "Retroceder",
+        "Undo the last instruction" => "Deshacer la última instrucción",
+        "Frame back" => "Retroceder fotograma",
+        "Go back to the start of this frame, or of the previous one" => "Volver al inicio de este fotograma, o del anterior",
+        "Step cycle" => "Avanzar ciclo",
+        "Execute one instruction" => "Ejecuta una instrucción",
+        "Step frame" => "Avanzar fotograma",
+        "Execute until this frame completes" => "Ejecuta hasta que termine este fotograma",
+        "Reset" => "Reiniciar",
+        "Reset the interpreter and reload the ROM" => "Reinicia el intérprete y vuelve a cargar la ROM",
+        "Soft reset" => "Reinicio parcial",
+        "Reset CPU only" => "Reiniciar solo la CPU",
+        "Reset registers, timers and the stack, but keep RAM and the display" => "Reinicia los registros, temporizadores y la pila, pero conserva la RAM y la pantalla",
+        "Clear display and timers only" => "Borrar solo la pantalla y los temporizadores",
+        "Cycle: {}/{}" => "Ciclo: {}/{}",
+        "Cycle: {}" => "Ciclo: {}",
+        "There are 60 frames per second and as many cycles per frame as fit in a COSMAC VIP frame." => "Hay 60 fotogramas por segundo y tantos ciclos por fotograma como caben en un fotograma del COSMAC VIP.",
+        "There are 60 frames per second and {} cycles per frame." => "Hay 60 fotogramas por segundo y {} ciclos por fotograma.",
+        "IPS" => "IPS",
+        "Timer frequency" => "Frecuencia de los temporizadores",
+        "60 Hz (NTSC)" => "60 Hz (NTSC)",
+        "50 Hz (PAL)" => "50 Hz (PAL)",
+        "How many times per second the delay and sound timers count down. Frames still run at 60 per second." => "Cuántas veces por segundo cuentan hacia atrás los temporizadores de retardo y sonido. Los fotogramas siguen siendo 60 por segundo.",
+        "cycles/frame" => "ciclos/fotograma",
+        "Switch between cycles per frame and instructions per second" => "Cambiar entre ciclos por fotograma e instrucciones por segundo",
+        "Frame: {} ({})" => "Fotograma: {} ({})",
+        "Frames completed since the last reset and the emulated time they took." => "Fotogramas completados desde el último reinicio y el tiempo emulado que han durado.",
+
+        // Shortcuts
+        "Shortcuts" => "Atajos",
+        "Run/Pause" => "Ejecutar/Pausa",
+        " (paused)" => " (en pausa)",
+        "Keypad: {}" => "Teclado: {}",
+        "Fast-forward (hold)" => "Avance rápido (mantener)",
+        "Fast-forward x{}" => "Avance rápido x{}",
+        "Stopped at the breakpoint at {}" => "Detenido en el punto de interrupción de {}",
+        "Stopped because {} became true" => "Detenido porque {} se cumplió",
+        "Stopped because {} at {} read {}" => "Detenido porque {} en {} leyó {}",
+        "Stopped because {} at {} wrote {}" => "Detenido porque {} en {} escribió {}",
+        "The program is waiting for keypad input. Check the key mappings and that the window has focus." => "El programa está esperando una tecla. Comprueba la asignación de teclas y que la ventana tenga el foco.",
+
+        // Gamepad
+        "Gamepad mapping" => "Asignación del mando",
+        "No gamepad connected." => "No hay ningún mando conectado.",
+        "Save for this ROM" => "Guardar para esta ROM",
+        "Default mapping" => "Asignación por defecto",
+
+        // Halt messages
+        "Halted: {}" => "Detenido: {}",
+        "Illegal instruction: {}" => "Instrucción ilegal: {}",
+        "Machine code routines are not supported: {}. Try a different CHIP-8 variant." => "Las rutinas en código máquina no están soportadas: {}. Prueba otra variante de CHIP-8.",
+        "The machine code routine at {} did not return to the interpreter." => "La rutina en código máquina de {} no volvió al intérprete.",
+        "Memory access out of bounds at {}: {}. Enable \"Wrap memory accesses\" in the quirks or try a variant with more memory." => "Acceso a memoria fuera de límites en {}: {}. Activa \"Dar la vuelta a los accesos a memoria\" en las peculiaridades o prueba una variante con más memoria.",
+        "Jump out of bounds at {}: {} jumps to {}. Enable \"Wrap memory accesses\" in the quirks or try a variant with more memory." => "Salto fuera de límites en {}: {} salta a {}. Activa \"Dar la vuelta a los accesos a memoria\" en las peculiaridades o prueba una variante con más memoria.",
+        "Stack overflow at {}: {}" => "Desbordamiento de pila en {}: {}",
+        "Stack underflow at {}: {}" => "Subdesbordamiento de pila en {}: {}",
+        "Could not load the ROM: the program is {} bytes, but only {} fit in memory." => "No se pudo cargar la ROM: el programa ocupa {} bytes, pero solo caben {} en la memoria.",
+        _ => return None,
+    })
+}
@@ -0,0 +1,105 @@
+use crate::Chip8;
+
+impl Chip8 {
+    /// Encode the display as a PNG image with one image pixel per CHIP-8 pixel: 64x32 in lowres
+    /// mode (64x48 on the ETI-660), 128x64 in highres mode. `colors` are the RGBA colors of pixels
+    /// that are off, on in the first plane, on in the second plane and on in both planes, without
+    /// their alpha. The CHIP-8X uses its own colors instead.
+    pub fn display_png(&self, colors: [[u8; 4]; 4]) -> Vec<u8> {
+        let (width, height, rgb) = self.display_rgb(colors, 1);
+        encode_png(width as u32, height as u32, &rgb)
+    }
+
+    /// Get the display as 8-bit RGB pixels with every CHIP-8 pixel drawn as a `scale` by `scale`
+    /// square. Returns the width, height and pixels of the image.
+    pub(crate) fn display_rgb(
+        &self,
+        colors: [[u8; 4]; 4],
+        scale: usize,
+    ) -> (usize, usize, Vec<u8>) {
+        let observation = self.observe();
+        let mut rgb = Vec::with_capacity(observation.pixels.len() * scale * scale * 3);
+        for y in 0..observation.height {
+            let line: Vec<u8> = (0..observation.width)
+                .flat_map(|x| {
+                    let [r, g, b, _] = self.pixel_color(colors, x, y);
+                    [r, g, b].repeat(scale)
+                })
+                .collect();
+            for _ in 0..scale {
+                rgb.extend(&line);
+            }
+        }
+        (observation.width * scale, observation.height * scale, rgb)
+    }
+}
+
+/// Encode 8-bit RGB pixels as an uncompressed PNG image.
+pub(crate) fn encode_png(width: u32, height: u32, rgb: &[u8]) -> Vec<u8> {
+    let mut png = b"\x89PNG\r\n\x1a\n".to_vec();
+
+    let mut header = Vec::with_capacity(13);
+    header.extend(width.to_be_bytes());
+    header.extend(height.to_be_bytes());
+    header.extend([8, 2, 0, 0, 0]); // 8 bits per channel, RGB, no interlacing
+    write_chunk(&mut png, b"IHDR", &header);
+
+    // Every row starts with its filter type, which is none
+    let mut raw = Vec::with_capacity(rgb.len() + height as usize);
+    for row in rgb.chunks(width as usize * 3) {
+        raw.push(0);
+        raw.extend(row);
+    }
+
+    // A zlib stream of stored (uncompressed) deflate blocks
+    let mut zlib = vec![0x78, 0x01];
+    let blocks: Vec<_> = raw.chunks(u16::MAX as usize).collect();
+    for (i, block) in blocks.iter().enumerate() {
+        let len = block.len() as u16;
+        zlib.push((i == blocks.len() - 1) as u8);
+        zlib.extend(len.to_le_bytes());
+        zlib.extend((!len).to_le_bytes());
+        zlib.extend(*block);
+    }
+    zlib.extend(adler32(&raw).to_be_bytes());
+    write_chunk(&mut png, b"IDAT", &zlib);
+
+    write_chunk(&mut png, b"IEND", &[]);
+    png
+}
+
+/// Append a PNG chunk with its length and checksum.
+fn write_chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    png.extend((data.len() as u32).to_be_bytes());
+    let start = png.len();
+    png.extend(kind);
+    png.extend(data);
+    let crc = crc32(&png[start..]);
+    png.extend(crc.to_be_bytes());
+}
+
+/// The CRC-32 checksum used by PNG chunks.
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = u32::MAX;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xEDB88320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+/// The Adler-32 checksum used by zlib streams.
+fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for &byte in data {
+        a = (a + byte as u32) % 65521;
+        b = (b + a) % 65521;
+    }
+    (b << 16) | a
+}
@@ -0,0 +1,335 @@
+use e_chip::FRAME_DURATION;
+use egui::{Event, InputState, Key, Modifiers};
+use web_time::Instant;
+
+use crate::slots::SLOT_COUNT;
+
+/// Which host keys are mapped to the CHIP-8 keypad.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum KeypadLayout {
+    /// The usual 4x4 grid on the left side of the keyboard (1234/QWER/ASDF/ZXCV).
+    #[default]
+    Classic,
+    /// The same grid moved to the right side of the keyboard (7890/UIOP/JKL;/M,./).
+    LeftHanded,
+    /// Arrow keys for 2/4/6/8, Z for 5 and X for A. Good for games that only use a few keys.
+    Arrows,
+}
+
+impl KeypadLayout {
+    /// All layouts, in the order they are shown in the settings.
+    pub const ALL: [KeypadLayout; 3] = [
+        KeypadLayout::Classic,
+        KeypadLayout::LeftHanded,
+        KeypadLayout::Arrows,
+    ];
+
+    /// The name shown in the settings.
+    pub const fn name(&self) -> &'static str {
+        match self {
+            KeypadLayout::Classic => "Classic (1234/QWER)",
+            KeypadLayout::LeftHanded => "Left-handed (7890/UIOP)",
+            KeypadLayout::Arrows => "Arrow keys + ZX",
+        }
+    }
+
+    /// The host key bound to each CHIP-8 key from 0 to F, or `None` if the key is unbound.
+    pub const fn keys(&self) -> [Option<Key>; 16] {
+        match self {
+            KeypadLayout::Classic => [
+                Some(Key::X),    // 0
+                Some(Key::Num1), // 1
+                Some(Key::Num2), // 2
+                Some(Key::Num3), // 3
+                Some(Key::Q),    // 4
+                Some(Key::W),    // 5
+                Some(Key::E),    // 6
+                Some(Key::A),    // 7
+                Some(Key::S),    // 8
+                Some(Key::D),    // 9
+                Some(Key::Z),    // A
+                Some(Key::C),    // B
+                Some(Key::Num4), // C
+                Some(Key::R),    // D
+                Some(Key::F),    // E
+                Some(Key::V),    // F
+            ],
+            KeypadLayout::LeftHanded => [
+                Some(Key::Comma),     // 0
+                Some(Key::Num7),      // 1
+                Some(Key::Num8),      // 2
+                Some(Key::Num9),      // 3
+                Some(Key::U),         // 4
+                Some(Key::I),         // 5
+                Some(Key::O),         // 6
+                Some(Key::J),         // 7
+                Some(Key::K),         // 8
+                Some(Key::L),         // 9
+                Some(Key::M),         // A
+                Some(Key::Period),    // B
+                Some(Key::Num0),      // C
+                Some(Key::P),         // D
+                Some(Key::Semicolon), // E
+                Some(Key::Slash),     // F
+            ],
+            KeypadLayout::Arrows => [
+                None,                  // 0
+                None,                  // 1
+                Some(Key::ArrowUp),    // 2
+                None,                  // 3
+                Some(Key::ArrowLeft),  // 4
+                Some(Key::Z),          // 5
+                Some(Key::ArrowRight), // 6
+                None,                  // 7
+                Some(Key::ArrowDown),  // 8
+                None,                  // 9
+                Some(Key::X),          // A
+                None,                  // B
+                None,                  // C
+                None,                  // D
+                None,                  // E
+                None,                  // F
+            ],
+        }
+    }
+}
+
+/// The number keys of the save slots, 1 to 9 and 0 for the tenth. Ctrl saves to a slot and Shift
+/// loads from it.
+pub const SLOT_KEYS: [Key; SLOT_COUNT] = [
+    Key::Num1,
+    Key::Num2,
+    Key::Num3,
+    Key::Num4,
+    Key::Num5,
+    Key::Num6,
+    Key::Num7,
+    Key::Num8,
+    Key::Num9,
+    Key::Num0,
+];
+
+/// An emulator action with a keyboard shortcut.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Hotkey {
+    RunPause,
+    StepFrame,
+    StepCycle,
+    StepBackFrame,
+    StepBackCycle,
+    Reset,
+    LoadRom,
+    ShowRom,
+    DisplaySettings,
+    History,
+    CopySummary,
+    ToggleSound,
+    Shortcuts,
+    Turbo,
+}
+
+impl Hotkey {
+    /// All hotkeys, in the order they are checked and listed in the shortcut overlay.
+    ///
+    /// Shortcuts with Shift come before the same key without it, because a shortcut without Shift
+    /// also matches while Shift is held.
+    pub const ALL: [Hotkey; 14] = [
+        Hotkey::RunPause,
+        Hotkey::StepFrame,
+        Hotkey::StepCycle,
+        Hotkey::StepBackFrame,
+        Hotkey::StepBackCycle,
+        Hotkey::Reset,
+        Hotkey::LoadRom,
+        Hotkey::ShowRom,
+        Hotkey::DisplaySettings,
+        Hotkey::History,
+        Hotkey::CopySummary,
+        Hotkey::ToggleSound,
+        Hotkey::Shortcuts,
+        Hotkey::Turbo,
+    ];
+
+    /// The name shown in the shortcut overlay.
+    pub const fn name(&self) -> &'static str {
+        match self {
+            Hotkey::RunPause => "Run/Pause",
+            Hotkey::StepFrame => "Step frame",
+            Hotkey::StepCycle => "Step cycle",
+            Hotkey::StepBackFrame => "Frame back",
+            Hotkey::StepBackCycle => "Step back",
+            Hotkey::Reset => "Reset",
+            Hotkey::LoadRom => "Load ROM",
+            Hotkey::ShowRom => "Show loaded ROM",
+            Hotkey::DisplaySettings => "Display settings",
+            Hotkey::History => "Show execution history",
+            Hotkey::CopySummary => "Copy state summary",
+            Hotkey::ToggleSound => "Sound",
+            Hotkey::Shortcuts => "Shortcuts",
+            Hotkey::Turbo => "Fast-forward (hold)",
+        }
+    }
+
+    /// The modifiers and key that trigger the action with a keypad layout.
+    pub const fn shortcut(&self, layout: KeypadLayout) -> (Modifiers, Key) {
+        // The left-handed keypad has keys on comma and period, so stepping moves to the other side
+        // of the keyboard
+        let (back, forward) = match layout {
+            KeypadLayout::LeftHanded => (Key::Z, Key::X),
+            _ => (Key::Comma, Key::Period),
+        };
+        match self {
+            Hotkey::RunPause => (Modifiers::NONE, Key::Space),
+            Hotkey::StepFrame => (Modifiers::SHIFT, forward),
+            Hotkey::StepCycle => (Modifiers::NONE, forward),
+            Hotkey::StepBackFrame => (Modifiers::SHIFT, back),
+            Hotkey::StepBackCycle => (Modifiers::NONE, back),
+            Hotkey::Reset => (Modifiers::CTRL, Key::R),
+            Hotkey::LoadRom => (Modifiers::CTRL, Key::O),
+            Hotkey::ShowRom => (Modifiers::CTRL, Key::P),
+            Hotkey::DisplaySettings => (Modifiers::CTRL, Key::D),
+            Hotkey::History => (Modifiers::CTRL, Key::H),
+            Hotkey::CopySummary => (
+                Modifiers {
+                    shift: true,
+                    ..Modifiers::CTRL
+                },
+                Key::C,
+            ),
+            Hotkey::ToggleSound => (Modifiers::CTRL, Key::S),
+            Hotkey::Shortcuts => (Modifiers::NONE, Key::F1),
+            Hotkey::Turbo => (Modifiers::NONE, Key::Tab),
+        }
+    }
+
+    /// Whether the action lasts while the keys are held down instead of happening once per press.
+    pub const fn is_held(&self) -> bool {
+        matches!(self, Hotkey::Turbo)
+    }
+
+    /// Whether the hotkey only works while the interpreter is paused.
+    pub const fn paused_only(&self) -> bool {
+        matches!(
+            self,
+            Hotkey::StepFrame
+                | Hotkey::StepCycle
+                | Hotkey::StepBackFrame
+                | Hotkey::StepBackCycle
+                | Hotkey::Reset
+                | Hotkey::LoadRom
+        )
+    }
+}
+
+/// Turns host keyboard input into CHIP-8 keypad state.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeypadInput {
+    /// Which host keys are mapped to the CHIP-8 keypad.
+    pub layout: KeypadLayout,
+    /// Keep a key pressed for at least this many frames, so taps shorter than a frame are not missed.
+    pub min_hold_frames: u32,
+    /// If true, the key presses the OS generates while a key is held down are ignored.
+    /// If false, each of them is seen by the interpreter as a new tap.
+    pub ignore_repeat: bool,
+    /// If true, clicking a key of the on-screen keypad toggles it on or off.
+    /// If false, the key is pressed while the mouse button is held down on it.
+    pub latch_clicks: bool,
+    /// Until when each key stays pressed because of `min_hold_frames`.
+    held_until: [Option<Instant>; 16],
+    /// The keys pressed or latched on the on-screen keypad and on the second on-screen keypad of the
+    /// CHIP-8X.
+    clicked: [[bool; 16]; 2],
+    /// The last key released on the on-screen keypad since the keypad was read.
+    click_released: Option<u8>,
+}
+
+impl Default for KeypadInput {
+    fn default() -> Self {
+        KeypadInput {
+            layout: KeypadLayout::default(),
+            min_hold_frames: 0,
+            ignore_repeat: true,
+            latch_clicks: false,
+            held_until: [None; 16],
+            clicked: [[false; 16]; 2],
+            click_released: None,
+        }
+    }
+}
+
+impl KeypadInput {
+    /// Read the keyboard state for this frame.
+    ///
+    /// Returns the state of each CHIP-8 key and the last key that was released, which resolves
+    /// the `Fx0A` instruction.
+    pub fn read(&mut self, input: &InputState) -> ([bool; 16], Option<u8>) {
+        let now = Instant::now();
+        let layout = self.layout.keys();
+
+        let mut released = None;
+        let mut repeated = [false; 16];
+        for event in &input.events {
+            let Event::Key {
+                key,
+                pressed,
+                repeat,
+                ..
+            } = event
+            else {
+                continue;
+            };
+            let Some(index) = layout.iter().position(|k| *k == Some(*key)) else {
+                continue;
+            };
+
+            if *pressed && *repeat {
+                // Let the key go for a frame so the interpreter sees a new press
+                if !self.ignore_repeat {
+                    repeated[index] = true;
+                    released = Some(index as u8);
+                }
+            } else if *pressed {
+                self.held_until[index] = Some(now + FRAME_DURATION * self.min_hold_frames);
+            } else {
+                released = Some(index as u8);
+            }
+        }
+
+        let mut keys = [false; 16];
+        for (index, host_key) in layout.iter().enumerate() {
+            let down = host_key.is_some_and(|k| input.key_down(k));
+            let held = self.held_until[index].is_some_and(|until| until > now);
+            keys[index] = (down || held || self.clicked[0][index]) && !repeated[index];
+        }
+
+        (keys, released.or(self.click_released.take()))
+    }
+
+    /// Read the second keypad of the CHIP-8X, which only has the on-screen keys.
+    #[inline]
+    pub fn read_second(&self) -> [bool; 16] {
+        self.clicked[1]
+    }
+
+    /// Check if a key is pressed or latched on an on-screen keypad: 0 for the main keypad, 1 for the
+    /// second keypad of the CHIP-8X.
+    #[inline]
+    pub fn is_clicked(&self, pad: usize, key: usize) -> bool {
+        self.clicked[pad][key]
+    }
+
+    /// Update a key of an on-screen keypad with the mouse: `down` is whether the mouse button is
+    /// held down on it and `clicked` whether it was clicked this frame. Only releasing a key of the
+    /// main keypad resolves `Fx0A`.
+    pub fn click(&mut self, pad: usize, key: usize, down: bool, clicked: bool) {
+        let was_pressed = self.clicked[pad][key];
+        self.clicked[pad][key] = if self.latch_clicks {
+            was_pressed != clicked
+        } else {
+            down
+        };
+        if pad == 0 && was_pressed && !self.clicked[pad][key] {
+            self.click_released = Some(key as u8);
+        }
+    }
+}
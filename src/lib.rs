@@ -1,166 +1,478 @@
-use std::fs;
+use std::time::Duration;
 
+use chip8x::ColorMap;
 use display::{Display, ScrollDirection};
+#[cfg(feature = "gui")]
 use egui::Color32;
-use memory::Memory;
-use rand::Rng;
+use memory::{Memory, BIG_FONT_ADDRESS, BIG_FONT_SIZE, FONT_ADDRESS, SMALL_FONT_SIZE};
+use rewind::RewindBuffer;
+use script::InputScript;
+use storage::SharedStorage;
 
+pub use access_log::{AccessKind, AccessLog, MemoryAccess, ACCESS_LOG_LENGTH};
+pub use agent::Observation;
+pub use bisect::{bisect_quirks, BisectReport, Outcome, QuirkEffect};
+pub use breakpoints::{BreakpointHit, Breakpoints};
+pub use chrome_trace::ChromeTrace;
+pub use compare::Comparison;
+pub use crash_dump::sha1_hex;
+pub use disasm::{disassemble, explain_instruction, DisassembledInstruction};
+pub use driver::{
+    DriverCommand, DriverEvent, EmulatorDriver, FramePacing, FRAME_DURATION,
+    KEY_WAIT_WARNING_FRAMES, TURBO_FACTOR,
+};
+pub use frame_dump::FrameDump;
+pub use frontend::{run_frontend, Frontend};
+pub use halt::HaltReason;
+pub use lockstep::{lockstep_test, Divergence, LockstepReport, ReferenceStep, ReferenceTrace};
+pub use memory::{FontError, FontSet, LoadError};
+pub use octo::{assemble_octo, OctoProgram};
+pub use quirk_test::{test_quirks, QuirkTestReport};
+pub use quirks::IndexIncrement;
 pub use quirks::Quirks;
 pub use quirks::Variant;
+pub use quirks::VblankWait;
+pub use replay::{Replay, ReplayFrame, ReplaySession};
+pub use rewind::REWIND_FRAMES;
+pub use script::InputStep;
+pub use state::StateError;
+#[cfg(target_arch = "wasm32")]
+pub use storage::LocalStorage;
+pub use storage::{persistent_flags_folder, FileStorage, MemoryStorage, PersistentStorage};
+pub use symbols::Symbols;
+pub use timing::{MAX_EXECUTION_SPEED, MAX_TIMER_FREQUENCY, VIP_CYCLES_PER_FRAME};
+pub use trace::{FrameEvent, FrameEventKind, Trace, TraceEntry, HALT_CONTEXT_LENGTH, TRACE_LENGTH};
+pub use watch::Expression;
 
+mod access_log;
+mod agent;
+mod bisect;
+mod breakpoints;
+mod cdp1802;
+mod chip8x;
+mod chrome_trace;
+mod compare;
+mod crash_dump;
+mod disasm;
 mod display;
+mod driver;
+mod flags;
+mod frame_dump;
+mod frontend;
+mod halt;
+mod image;
+mod lockstep;
 mod memory;
+mod octo;
+mod quirk_test;
 mod quirks;
+mod replay;
+mod rewind;
+mod script;
+mod state;
+mod storage;
+mod symbols;
+mod timing;
+mod trace;
+mod watch;
 
 /// The CHIP-8 interpreter context.
+///
+/// With the `serde` feature, the machine can be serialized and deserialized with serde. The
+/// breakpoints, the symbols and the debugging records (trace, access log, rewind buffer,
+/// recordings) are left out and start empty, and the persistent storage is the default one.
 #[derive(Debug, PartialEq, PartialOrd, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[allow(non_snake_case)]
 pub struct Chip8 {
     /// 16 general purpose 8-bit registers, usually referred to as Vx, where x is a hex digit.  
     /// VF is used as a flag by some instructions.
     V: [u8; 16],
-    /// The address register. 16-bit, but only the lowest 12 bits are used.
+    /// The address register. 16-bit, but only the lowest 12 bits are used, except by XO-CHIP.
     I: u16,
     /// The program counter. 16-bit.
     program_counter: u16,
     /// The stack pointer. 8-bit.
     stack_pointer: u8,
-    /// The delay timer, decremented 60 times per second. Is accessible by programs.
+    /// The delay timer, decremented `timer_frequency` times per second. Is accessible by programs.
     delay: u8,
-    /// The sound timer, decremented 60 times per second. Plays a sound frequency when greater than 1.
+    /// The sound timer, decremented `timer_frequency` times per second. Plays a sound frequency when greater than 1.
     sound: u8,
-    /// 4KB of RAM. The first 512 bytes are reserved.
+    /// 4KB of RAM, or 64KB for XO-CHIP. The first 512 bytes are reserved.
     memory: Memory,
-    /// A monochrome 64x32-pixel display.
+    /// A 64x32-pixel display with two bitplanes.
     display: Display,
+    /// The bitplanes that drawing, clearing and scrolling affect, selected with the Fn01 instruction
+    /// of XO-CHIP. Bit 0 is the first plane, bit 1 the second.
+    selected_planes: u8,
+    /// The background and zone colors of the CHIP-8X.
+    color_map: ColorMap,
     /// If false, the display will have a resolution of 64x32.
     /// Otherwise, if the selected variant supports it, the resolution will be 128x64.
     pub highres: bool,
     /// 16 keys corresponding to hex digits.
     keypad: [bool; 16],
+    /// The second keypad of the CHIP-8X.
+    keypad2: [bool; 16],
+    /// Timed key events that are pressed on the keypad in addition to `keypad`.
+    input_script: InputScript,
     /// Stores return addresses for subroutines.
     stack: Vec<u16>,
+    /// The deepest the stack has been since the last reset.
+    max_stack_depth: u8,
+    /// The 128 1-bit samples the buzzer plays, loaded with the F002 instruction of XO-CHIP. `None`
+    /// plays a plain square wave.
+    audio_pattern: Option<[u8; 16]>,
+    /// The playback rate of the audio pattern, set with the Fx3A instruction of XO-CHIP. 64 plays
+    /// 4000 samples per second.
+    pitch: u8,
+    /// The state of the number generator of the `deterministic_random` quirk.
+    random_state: u16,
 
     // Configuration and control
     /// What kind of CHIP-8 variant to run as.
     pub variant: Variant,
     /// The desired implementation quirks.
     pub quirks: Quirks,
+    /// The address programs are loaded at and start from: 0x200, or 0x600 for the ETI-660.
+    pub start_address: u16,
     /// Sound will play if true.
     pub sound_on: bool,
-    /// The size of the stack. 12 in CHIP-8 mode, 16 in SCHIP mode.
+    /// The size of the stack. 12 in CHIP-8 mode, 16 in the other modes.
     pub stack_size: usize,
     /// The current cycle in a frame.
     pub frame_cycle: u32,
+    /// The number of frames completed since the last reset.
+    frame_count: u64,
     /// How many cycles to execute in one frame.
     pub execution_speed: u32,
+    /// How many times per second the delay and sound timers are decremented: 60 by default, 50 for
+    /// PAL systems. Frames still run at 60 per second.
+    pub timer_frequency: u32,
+    /// How far the timers are to their next decrement, in 1/60ths of a decrement.
+    timer_phase: u32,
+    /// If true, the cost of executed instructions on the COSMAC VIP is profiled.
+    pub vip_timing: bool,
+    /// If true, frames end after the COSMAC VIP machine cycles of a 60 Hz frame instead of after
+    /// `execution_speed` cycles, so every instruction takes as long as it would on the VIP.
+    pub vip_speed: bool,
+    /// The estimated COSMAC VIP machine cycles spent in the current frame.
+    frame_vip_cycles: u32,
+    /// The estimated COSMAC VIP machine cycles spent in the last completed frame.
+    last_frame_vip_cycles: u32,
+    /// Draws, key reads and sound changes of the current frame.
+    frame_events: Vec<FrameEvent>,
+    /// Draws, key reads and sound changes of the last completed frame.
+    last_frame_events: Vec<FrameEvent>,
     /// Whether the interpreter is executing instructions.
     running: bool,
-    /// If the interpreter halts, this will have a message explaining why.
-    pub halt_message: Option<String>,
+    /// The addresses where the running interpreter stops.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub breakpoints: Breakpoints,
+    /// The names of addresses shown by the debugger views.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub symbols: Symbols,
+    /// If the interpreter halts, this will have the reason why.
+    pub halt_reason: Option<HaltReason>,
+    /// The addresses and opcodes of the instructions leading up to the halt, ending with the one that
+    /// halted.
+    halt_context: Vec<(u16, u16)>,
     /// If true (and quirk is enabled), the display is ready for drawing.
     vblank: bool,
     /// True if waiting for a key press with the Fx0A instruction.
     awaiting_key: bool,
     /// Used by the Fx0A instruction: The register to which the pressed key will be saved.
     key_destination: usize,
-    /// Used by the Fx75 and Fx85 instructions of SUPER-CHIP and XO-CHIP as runtime storage.
-    persistent_flags: [u8; 8],
+    /// Used by the Fx75 and Fx85 instructions of SUPER-CHIP and XO-CHIP as runtime storage. SUPER-CHIP
+    /// only uses the first 8.
+    persistent_flags: [u8; 16],
+    /// The SHA-1 of the loaded ROM, which its persistent flags are stored under. `None` until a ROM
+    /// is loaded.
+    persistent_flags_rom: Option<String>,
+    /// Why the persistent flags could not be read or written the last time, if they could not.
+    persistent_flags_error: Option<String>,
+    /// Where persistent flags are kept.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    persistent_storage: SharedStorage,
+    /// The last executed instructions.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    trace: Trace,
+    /// The last memory reads and writes made by instructions.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    access_log: AccessLog,
+    /// The V registers before the last step (cycle or frame) was executed.
+    V_before_step: [u8; 16],
+    /// The I register before the last step (cycle or frame) was executed.
+    I_before_step: u16,
+    /// Execution recorded for export to Perfetto, if recording.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    chrome_trace: Option<ChromeTrace>,
+    /// Writes completed frames as PNG images, if dumping.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    frame_dump: Option<FrameDump>,
+    /// The replay being recorded or played back, if any.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    replay: Option<ReplaySession>,
+    /// Snapshots of the last frames for running backwards.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    rewind: RewindBuffer,
 }
 
+/// The table the number generator of the `deterministic_random` quirk adds to its state, filled by a
+/// linear congruential generator.
+const RANDOM_TABLE: [u8; 256] = {
+    let mut table = [0; 256];
+    let mut seed: u32 = 1;
+    let mut i = 0;
+    while i < 256 {
+        seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+        table[i] = (seed >> 16) as u8;
+        i += 1;
+    }
+    table
+};
+
 impl Chip8 {
-    /// Create a CHIP-8 interpreter with the quirks of the original COSMAC-VIP implementation.  
-    #[inline]
-    pub fn chip8() -> Chip8 {
-        let stack_size = 12;
+    /// Create an interpreter in its initial state, with everything that differs between the
+    /// variants.
+    fn new(
+        variant: Variant,
+        quirks: Quirks,
+        memory: Memory,
+        display: Display,
+        stack_size: usize,
+        start_address: u16,
+        execution_speed: u32,
+    ) -> Chip8 {
         Chip8 {
             // Registers
             V: [0; 16],
             I: 0,
-            program_counter: 0x200,
+            program_counter: start_address,
             stack_pointer: 0,
             delay: 0,
             sound: 0,
             // Devices
-            memory: Memory::new(),
-            display: Display::small(),
+            memory,
+            display,
+            selected_planes: 1,
+            color_map: ColorMap::default(),
             highres: false,
             keypad: [false; 16],
+            keypad2: [false; 16],
+            input_script: InputScript::default(),
             stack: vec![0; stack_size],
+            max_stack_depth: 0,
+            audio_pattern: None,
+            pitch: 64,
+            random_state: 0,
             // Configuration
-            variant: Variant::CHIP8,
-            quirks: Quirks::vip_chip(),
+            variant,
+            quirks,
+            start_address,
             frame_cycle: 0,
-            execution_speed: 15,
+            frame_count: 0,
+            execution_speed,
+            timer_frequency: 60,
+            timer_phase: 0,
             stack_size,
+            vip_timing: false,
+            vip_speed: false,
+            frame_vip_cycles: 0,
+            last_frame_vip_cycles: 0,
+            frame_events: Vec::new(),
+            last_frame_events: Vec::new(),
             sound_on: true,
             running: false,
-            halt_message: None,
+            breakpoints: Breakpoints::default(),
+            symbols: Symbols::default(),
+            halt_reason: None,
+            halt_context: Vec::new(),
             vblank: true,
             awaiting_key: false,
             key_destination: 0,
-            persistent_flags: [0; 8],
+            persistent_flags: [0; 16],
+            persistent_flags_rom: None,
+            persistent_flags_error: None,
+            persistent_storage: SharedStorage::default(),
+            trace: Trace::default(),
+            access_log: AccessLog::default(),
+            V_before_step: [0; 16],
+            I_before_step: 0,
+            chrome_trace: None,
+            frame_dump: None,
+            replay: None,
+            rewind: RewindBuffer::default(),
         }
     }
 
-    /// Create a SUPER-CHIP 1.1 interpreter.  
+    /// Create a CHIP-8 interpreter with the quirks of the original COSMAC-VIP implementation.  
     #[inline]
-    pub fn super_chip1_1() -> Chip8 {
-        let stack_size = 16;
+    pub fn chip8() -> Chip8 {
+        Chip8::new(
+            Variant::CHIP8,
+            Quirks::vip_chip(),
+            Memory::new(),
+            Display::small(),
+            12,
+            0x200,
+            15,
+        )
+    }
+
+    /// Create a COSMAC VIP CHIP-8 interpreter that runs `0nnn` machine code routines, for hybrid
+    /// programs.  
+    #[inline]
+    pub fn vip_hybrid() -> Chip8 {
         Chip8 {
-            // Registers
-            V: [0; 16],
-            I: 0,
-            program_counter: 0x200,
-            stack_pointer: 0,
-            delay: 0,
-            sound: 0,
-            // Devices
-            memory: Memory::new(),
-            display: Display::big(),
-            highres: false,
-            keypad: [false; 16],
-            stack: vec![0; stack_size],
-            // Configuration
-            variant: Variant::SCHIP11,
-            quirks: Quirks::super_chip1_1(),
-            frame_cycle: 0,
-            execution_speed: 30,
-            stack_size,
-            sound_on: true,
-            running: false,
-            halt_message: None,
-            vblank: true,
-            awaiting_key: false,
-            key_destination: 0,
-            persistent_flags: Chip8::load_persistent_flags(),
+            variant: Variant::VIPHYBRID,
+            ..Chip8::chip8()
+        }
+    }
+
+    /// Create a CHIP-8X interpreter: CHIP-8 with the VP-590 color board and a second keypad, running
+    /// programs from 0x300.  
+    #[inline]
+    pub fn chip8x() -> Chip8 {
+        Chip8::new(
+            Variant::CHIP8X,
+            Quirks::vip_chip(),
+            Memory::new(),
+            Display::small(),
+            12,
+            0x300,
+            15,
+        )
+    }
+
+    /// Create an ETI-660 interpreter: CHIP-8 with a 64x48 display, running programs from 0x600.  
+    #[inline]
+    pub fn eti660() -> Chip8 {
+        Chip8::new(
+            Variant::ETI660,
+            Quirks::vip_chip(),
+            Memory::new(),
+            Display::tall(),
+            12,
+            0x600,
+            15,
+        )
+    }
+
+    /// Create a CHIP-48 interpreter: CHIP-8 with the quirks of the HP48 implementation.  
+    #[inline]
+    pub fn chip48() -> Chip8 {
+        Chip8::new(
+            Variant::CHIP48,
+            Quirks::chip48(),
+            Memory::new(),
+            Display::small(),
+            16,
+            0x200,
+            30,
+        )
+    }
+
+    /// Create a SUPER-CHIP 1.0 interpreter. It lacks the scroll instructions of SUPER-CHIP 1.1 and
+    /// draws `Dxy0` sprites 8 pixels wide in lowres mode.  
+    #[inline]
+    pub fn super_chip1_0() -> Chip8 {
+        Chip8 {
+            variant: Variant::SCHIP10,
+            quirks: Quirks::super_chip1_0(),
+            ..Chip8::super_chip1_1()
         }
     }
 
+    /// Create a SUPER-CHIP 1.1 interpreter.  
+    #[inline]
+    pub fn super_chip1_1() -> Chip8 {
+        Chip8::new(
+            Variant::SCHIP11,
+            Quirks::super_chip1_1(),
+            Memory::new(),
+            Display::big(),
+            16,
+            0x200,
+            30,
+        )
+    }
+
+    /// Create an XO-CHIP interpreter with the quirks of Octo.  
+    #[inline]
+    pub fn xo_chip() -> Chip8 {
+        Chip8::new(
+            Variant::XOCHIP,
+            Quirks::octo_chip(),
+            Memory::big(),
+            Display::big(),
+            16,
+            0x200,
+            1000,
+        )
+    }
+
     /// Set registers and timers to zero, clear the stack, screen and RAM and reload the ROM.
     #[inline]
     pub fn reset(&mut self) {
+        self.reset_cpu();
+        self.frame_count = 0;
+        self.memory.reset();
+        self.display.clear();
+        self.color_map = ColorMap::default();
+        self.highres = false;
+        self.keypad = [false; 16];
+        self.keypad2 = [false; 16];
+        self.input_script.clear();
+        self.last_frame_vip_cycles = 0;
+        self.last_frame_events.clear();
+        self.trace.clear();
+        self.access_log.clear();
+        self.rewind.clear();
+        self.V_before_step = [0; 16];
+        self.I_before_step = 0;
+    }
+
+    /// Set registers and timers to zero and clear the stack, but keep RAM and the display. The program
+    /// restarts at the start address with whatever is in memory.
+    #[inline]
+    pub fn reset_cpu(&mut self) {
         self.V = [0; 16];
         self.I = 0;
-        self.program_counter = 0x200;
+        self.program_counter = self.start_address;
         self.stack_pointer = 0;
         self.delay = 0;
         self.sound = 0;
-        self.memory.reset();
-        self.display.clear();
-        self.highres = false;
-        self.keypad = [false; 16];
         self.stack = vec![0; self.stack_size];
+        self.max_stack_depth = 0;
+        self.selected_planes = 1;
+        self.audio_pattern = None;
+        self.pitch = 64;
+        self.random_state = 0;
         self.awaiting_key = false;
         self.frame_cycle = 0;
+        self.timer_phase = 0;
+        self.frame_vip_cycles = 0;
+        self.frame_events.clear();
         self.vblank = true;
-        self.halt_message = None;
+        self.halt_reason = None;
+        self.halt_context.clear();
     }
 
-    /// Set `running` to `true`.
+    /// Turn off all pixels and set the timers to zero, leaving everything else as it is.
+    #[inline]
+    pub fn clear_display_and_timers(&mut self) {
+        self.display.clear();
+        self.delay = 0;
+        self.sound = 0;
+    }
+
+    /// Set `running` to `true`. A breakpoint at the program counter does not stop the interpreter
+    /// right away.
     #[inline]
     pub fn start(&mut self) {
         self.running = true;
+        self.breakpoints.resume(self.program_counter);
     }
     /// Set `running` to `false`.
     #[inline]
@@ -173,10 +485,84 @@ impl Chip8 {
     fn set_flag(&mut self, value: u8) {
         self.V[0xF] = value;
     }
+    /// Record an event on the frame timeline.
+    #[inline]
+    fn record_event(&mut self, kind: FrameEventKind) {
+        self.frame_events.push(FrameEvent {
+            cycle: self.frame_cycle,
+            kind,
+        });
+        if let Some(trace) = &mut self.chrome_trace {
+            trace.marker(self.frame_cycle, self.execution_speed, kind);
+        }
+    }
     /// Move the program counter to the next instruction (increment by 2).
     #[inline]
     fn increment_program_counter(&mut self) {
-        self.program_counter += 2
+        self.program_counter = self.program_counter.wrapping_add(2)
+    }
+    /// Skip the next instruction. The F000 nnnn instruction of XO-CHIP is 4 bytes long, so it is
+    /// skipped whole.
+    #[inline]
+    fn skip_instruction(&mut self) {
+        if self.variant.supports_xochip()
+            && self
+                .memory
+                .read_opcode(self.program_counter.wrapping_add(2))
+                == 0xF000
+        {
+            self.increment_program_counter();
+        }
+        self.increment_program_counter();
+    }
+
+    /// XOR a byte of a sprite onto a plane of the display, with its leftmost pixel at column
+    /// `dx + first_cell` and row `dy + row`. Returns `true` if a pixel was turned off.
+    fn draw_sprite_byte(
+        &mut self,
+        plane: usize,
+        sprite_byte: u8,
+        dx: u16,
+        dy: u16,
+        row: u16,
+        first_cell: u16,
+    ) -> bool {
+        /*
+            I tried to do this by actually XORing the target pixel with the sprite pixel for
+            a while, but I could not pass the clipping test. I always got ERR2 and I did not
+            know why.
+            I gave up and looked at how Octo does this. I copied the part before the pixel
+            setting, but it still did not work. I then copied the rest and run the test.
+
+            It passed.
+
+            I have no idea why this way works but my way did not.
+        */
+
+        let (width, height) = self.display.size(self.highres);
+        let (width, height) = (width as u16, height as u16);
+
+        let mut overlap = false;
+        for cell in first_cell..first_cell + 8 {
+            if self.quirks.edge_clipping
+                && (dx % width + cell > width - 1 || dy % height + row > height - 1)
+            {
+                break;
+            }
+
+            let sprite_pixel = sprite_byte & (0b10000000 >> (cell - first_cell)) != 0;
+
+            let target_pixel = ((dx + cell) % width + (dy + row) % height * width) as usize;
+
+            if sprite_pixel {
+                let pixels = &mut self.display.planes[plane];
+                if pixels[target_pixel] {
+                    overlap = true;
+                }
+                pixels[target_pixel] = !pixels[target_pixel];
+            }
+        }
+        overlap
     }
     /// Subtract one from the timers.
     #[inline]
@@ -187,53 +573,244 @@ impl Chip8 {
 
     /// Get the opcode that the PC is pointing to.
     #[inline]
-    pub const fn get_current_opcode(&self) -> u16 {
+    pub fn get_current_opcode(&self) -> u16 {
         self.memory.read_opcode(self.program_counter)
     }
     /// Read a byte from memory.
     #[inline]
-    pub const fn read_byte(&self, address: u16) -> u8 {
+    pub fn read_byte(&self, address: u16) -> u8 {
         self.memory.ram[address as usize]
     }
-    /// Write a value to memory.
+    /// Write a byte to memory, for patching the program from a debugger. Does nothing if the
+    /// address is outside of memory.
+    #[inline]
+    pub fn poke(&mut self, address: u16, value: u8) {
+        if let Some(byte) = self.memory.ram.get_mut(address as usize) {
+            *byte = value;
+        }
+    }
+    /// Get the font set whose small font `Fx29` points at.
+    #[inline]
+    pub fn font_set(&self) -> FontSet {
+        self.memory.font_set
+    }
+    /// Switch to another font set and write its font over the one in reserved memory right away,
+    /// replacing a custom font. The rest of memory is left as it is.
+    #[inline]
+    pub fn set_font_set(&mut self, font_set: FontSet) {
+        self.memory.font_set = font_set;
+        self.memory.custom_font = None;
+        self.memory.write_fonts();
+    }
+    /// Get the custom font loaded with [`Chip8::load_font`], if any.
+    #[inline]
+    pub fn custom_font(&self) -> Option<&[u8]> {
+        self.memory.custom_font.as_deref()
+    }
+    /// Use a font from a font file in place of the font set and write it to reserved memory right
+    /// away. The font is 80 bytes of small font (16 digits of 5 bytes, for `Fx29`), optionally
+    /// followed by 160 bytes of large font (16 digits of 10 bytes, for `Fx30`). Without a large font
+    /// the usual one is kept. Nothing changes if the font has another size.
+    pub fn load_font(&mut self, font: &[u8]) -> Result<(), FontError> {
+        if font.len() != SMALL_FONT_SIZE && font.len() != SMALL_FONT_SIZE + BIG_FONT_SIZE {
+            return Err(FontError::WrongSize(font.len()));
+        }
+        self.memory.custom_font = Some(font.to_vec());
+        self.memory.write_fonts();
+        Ok(())
+    }
+    /// Get the program in memory: the bytes from the start address up to the last non-zero one.
+    /// Saved as a ROM, it loads into the same memory, including any changes made since the program
+    /// was loaded.
+    pub fn program_in_memory(&self) -> &[u8] {
+        let start = (self.start_address as usize).min(self.memory.ram.len());
+        let end = self.memory.ram[start..]
+            .iter()
+            .rposition(|&byte| byte != 0)
+            .map_or(start, |last| start + last + 1);
+        &self.memory.ram[start..end]
+    }
+    /// Read a byte from memory for the current instruction and log the access. Reads 0 if the
+    /// address is out of bounds.
+    #[inline]
+    fn load_byte(&mut self, address: u16) -> u8 {
+        let Some(address) = self.checked_address(address) else {
+            return 0;
+        };
+        let value = self.memory.ram[address as usize];
+        self.log_access(AccessKind::Read, address, value);
+        value
+    }
+    /// Write a value to memory and log the access. Nothing is written if the address is out of
+    /// bounds.
     #[inline]
     fn write_byte(&mut self, address: u16, value: u8) {
-        self.memory.ram[address as usize] = value
+        let Some(address) = self.checked_address(address) else {
+            return;
+        };
+        self.memory.ram[address as usize] = value;
+        self.log_access(AccessKind::Write, address, value);
+    }
+    /// Get the address the current instruction accesses for `address`. Addresses past the end of
+    /// memory wrap around with the `memory_wrap` quirk and halt the interpreter otherwise, leaving
+    /// the program counter on the instruction.
+    fn checked_address(&mut self, address: u16) -> Option<u16> {
+        let checked = self.wrapped_address(address);
+        if checked.is_none() {
+            self.halt(HaltReason::MemoryOutOfBounds {
+                address: self.program_counter,
+                opcode: self.get_current_opcode(),
+            });
+        }
+        checked
+    }
+    /// Move the program counter to the target of a jump, call or return. Like memory accesses,
+    /// targets past the end of memory wrap around with the `memory_wrap` quirk and halt the
+    /// interpreter otherwise.
+    fn jump(&mut self, target: u16) {
+        match self.wrapped_address(target) {
+            Some(target) => self.program_counter = target,
+            None => self.halt(HaltReason::JumpOutOfBounds {
+                address: self.program_counter,
+                opcode: self.get_current_opcode(),
+                target,
+            }),
+        }
     }
-    /// Reset memory and load a program into it, starting at 0x200.
+    /// Get where `address` is in memory: the address itself, or wrapped around to 0x000 with the
+    /// `memory_wrap` quirk. `None` if it is past the end of memory without the quirk.
     #[inline]
-    pub fn load_program(&mut self, program: &[u8]) {
+    fn wrapped_address(&self, address: u16) -> Option<u16> {
+        let size = self.memory.ram.len();
+        if (address as usize) < size {
+            Some(address)
+        } else if self.quirks.memory_wrap {
+            Some((address as usize % size) as u16)
+        } else {
+            None
+        }
+    }
+    /// Remember a memory access of the current instruction for the access log.
+    #[inline]
+    fn log_access(&mut self, kind: AccessKind, address: u16, value: u8) {
+        let access = MemoryAccess {
+            kind,
+            address,
+            value,
+            program_counter: self.program_counter,
+            opcode: self.get_current_opcode(),
+        };
+        self.access_log.push(access);
+        self.breakpoints.notice_access(access);
+    }
+    /// Check if a program can be loaded with [`Chip8::load_program`]: it must have at least one byte
+    /// and fit in memory from the start address, which leaves 3584 bytes in 4KB.
+    #[inline]
+    pub fn check_program(&self, program: &[u8]) -> Result<(), LoadError> {
+        let capacity = self.memory.capacity(self.start_address);
+        if program.is_empty() {
+            Err(LoadError::Empty)
+        } else if program.len() > capacity {
+            Err(LoadError::TooLarge {
+                size: program.len(),
+                capacity,
+            })
+        } else {
+            Ok(())
+        }
+    }
+    /// Reset memory and load a program into it, starting at the start address, and read the
+    /// persistent flags it saved before. Nothing changes if the program is empty or does not fit.
+    #[inline]
+    pub fn load_program(&mut self, program: &[u8]) -> Result<(), LoadError> {
+        self.check_program(program)?;
         self.memory.reset();
-        self.memory.load_program(program);
+        self.memory.load_program(program, self.start_address);
+        self.use_persistent_flags_of(program);
+        Ok(())
+    }
+    /// Reset the interpreter and load a program, to run it from the start. Without a program memory
+    /// is left empty, and a program that does not fit halts the interpreter, like a ROM reloaded
+    /// after switching to a variant with less memory.
+    pub fn reset_and_load(&mut self, program: &[u8]) {
+        self.reset();
+        if let Err(LoadError::TooLarge { size, capacity }) = self.load_program(program) {
+            self.halt(HaltReason::RomTooLarge { size, capacity });
+        }
     }
 
-    /// Load persistent flag registers from a file.
+    /// Read the display in the form of a texture. `colors` are the colors of pixels that are off, on
+    /// in the first plane, on in the second plane (XO-CHIP) and on in both planes. The CHIP-8X uses
+    /// its own colors instead.
+    #[cfg(feature = "gui")]
     #[inline]
-    pub fn load_persistent_flags() -> [u8; 8] {
-        let mut flags = [0; 8];
-        if let Ok(f) = fs::read("flags.dat") {
-            for i in 0..8 {
-                flags[i] = f[i];
+    pub fn get_display(&self, colors: [Color32; 4]) -> egui::ColorImage {
+        let colors = colors.map(|color| color.to_array());
+        self.display.render(self.highres, |x, y| {
+            let [r, g, b, a] = self.pixel_color(colors, x, y);
+            Color32::from_rgba_unmultiplied(r, g, b, a)
+        })
+    }
+    /// Get the display as 8-bit RGBA pixels, one per CHIP-8 pixel, for frontends that do not use
+    /// egui. `colors` are the RGBA colors of pixels that are off, on in the first plane, on in the
+    /// second plane (XO-CHIP) and on in both planes. The CHIP-8X uses its own colors instead.
+    ///
+    /// Returns the pixels row by row, the width and the height: 64x32 in lowres mode (64x48 on the
+    /// ETI-660) and 128x64 in highres mode.
+    pub fn render_rgba(&self, colors: [[u8; 4]; 4]) -> (Vec<u8>, usize, usize) {
+        let (width, height) = self.display.size(self.highres);
+        let mut rgba = Vec::with_capacity(width * height * 4);
+        for y in 0..height {
+            for x in 0..width {
+                rgba.extend(self.pixel_color(colors, x, y));
             }
-        } else {
-            println!("Did not find a persistent flag file");
         }
-        return flags;
+        (rgba, width, height)
     }
-
-    /// Save persistent flag registers into a file.
+    /// Get the width of the display in the current mode: 64 in lowres mode, 128 in highres mode.
+    #[inline]
+    pub fn display_width(&self) -> usize {
+        self.display.size(self.highres).0
+    }
+    /// Get the height of the display in the current mode: 32 in lowres mode (48 on the ETI-660), 64
+    /// in highres mode.
+    #[inline]
+    pub fn display_height(&self) -> usize {
+        self.display.size(self.highres).1
+    }
+    /// Check if the pixel at `x`, `y` is on in either plane. Pixels outside the display are off.
     #[inline]
-    pub fn save_persistent_flags(&self) {
-        if let Err(e) = fs::write("flags.dat", self.persistent_flags) {
-            panic!("Could not save persistent flags! What is wrong with your file system? {e}");
+    pub fn get_pixel(&self, x: usize, y: usize) -> bool {
+        self.get_pixel_planes(x, y) != 0
+    }
+    /// Get which planes the pixel at `x`, `y` is on in: bit 0 for the first plane, bit 1 for the
+    /// second (XO-CHIP). Pixels outside the display are off.
+    #[inline]
+    pub fn get_pixel_planes(&self, x: usize, y: usize) -> u8 {
+        let (width, height) = self.display.size(self.highres);
+        if x < width && y < height {
+            self.display.planes_at(x + y * width)
+        } else {
+            0
         }
     }
-
-    /// Read the display in the form of a texture.
+    /// Get the rows of the display from top to bottom, each with its pixels from left to right,
+    /// `true` if the pixel is on in either plane.
+    pub fn display_rows(&self) -> impl Iterator<Item = impl Iterator<Item = bool> + '_> + '_ {
+        let (width, height) = self.display.size(self.highres);
+        (0..height).map(move |y| (0..width).map(move |x| self.get_pixel(x, y)))
+    }
+    /// Get the RGBA color of the pixel at `x`, `y` in the given colors, or in the zone colors of the
+    /// CHIP-8X.
     #[inline]
-    pub fn get_display(&self, background_color: Color32, fill_color: Color32) -> egui::ColorImage {
-        self.display
-            .render(self.highres, background_color, fill_color)
+    pub(crate) fn pixel_color(&self, colors: [[u8; 4]; 4], x: usize, y: usize) -> [u8; 4] {
+        let (width, _) = self.display.size(self.highres);
+        let planes = self.display.planes_at(x + y * width);
+        if self.variant == Variant::CHIP8X {
+            self.color_map.color(x, y, planes != 0)
+        } else {
+            colors[planes as usize]
+        }
     }
     /// Set vblank ready.
     #[inline]
@@ -241,45 +818,256 @@ impl Chip8 {
         self.vblank = true;
     }
 
-    /// Set keypad state.
+    /// Set keypad state. A key that goes down is reported with [`Chip8::key_pressed`]. Ignored
+    /// while a replay is played back.
     #[inline]
     pub fn set_keys(&mut self, keys: [bool; 16]) {
-        self.keypad = keys;
+        if !self.is_playing_replay() {
+            self.record_rewind_keys(keys);
+            let pressed = (0..16).find(|&key| keys[key] && !self.keypad[key]);
+            self.keypad = keys;
+            if let Some(key) = pressed {
+                self.key_pressed(key as u8);
+            }
+        }
+    }
+    /// Report that a key was pressed. Finishes a waiting `Fx0A` instruction with the
+    /// `key_wait_on_press` quirk. Returns `true` if it did.
+    #[inline]
+    pub fn key_pressed(&mut self, key: u8) -> bool {
+        if !self.awaiting_key || !self.quirks.key_wait_on_press {
+            return false;
+        }
+        self.save_awaited_key(key);
+        true
+    }
+    /// Report that a key was released, before the keypad without it is set. Finishes a waiting
+    /// `Fx0A` instruction, unless the `key_wait_on_press` quirk already finished it when the key
+    /// went down. A key that was released before the keypad ever showed it down, like a very short
+    /// tap or a scripted key, finishes it either way. Returns `true` if it did.
+    #[inline]
+    pub fn key_released(&mut self, key: u8) -> bool {
+        let press_seen = self.keypad[key as usize & 0xF];
+        if !self.awaiting_key || (self.quirks.key_wait_on_press && press_seen) {
+            return false;
+        }
+        self.save_awaited_key(key);
+        true
+    }
+    /// Get the next number of the `deterministic_random` quirk: count the state up and add the
+    /// table byte at its low byte to its high byte, which is the number. The numbers come in the
+    /// same order every run.
+    pub(crate) fn deterministic_random_byte(&mut self) -> u8 {
+        self.random_state = self.random_state.wrapping_add(1);
+        let [low, high] = self.random_state.to_le_bytes();
+        let high = high.wrapping_add(RANDOM_TABLE[low as usize]);
+        self.random_state = u16::from_le_bytes([low, high]);
+        high
+    }
+    /// Check if a key is pressed, either on the keypad or by the input script.
+    #[inline]
+    fn is_key_down(&self, key: usize) -> bool {
+        self.keypad[key] || self.input_script.keys()[key]
+    }
+    /// Queue a timed key event. Queued events are played back one after another, one frame at a time.
+    #[inline]
+    pub fn queue_input(&mut self, step: InputStep) {
+        self.input_script.push(step);
+    }
+    /// Remove all queued key events.
+    #[inline]
+    pub fn clear_input_queue(&mut self) {
+        self.input_script.clear();
     }
     /// Save the value of the last pressed key into a register as the result of the Fx0A instruction.
+    /// Ignored while a replay is played back. [`Chip8::key_pressed`] and [`Chip8::key_released`]
+    /// call it when the `key_wait_on_press` quirk says so.
     #[inline]
     pub fn save_awaited_key(&mut self, key: u8) {
+        if self.is_playing_replay() {
+            return;
+        }
+        self.record_replay_release(key);
+        self.record_rewind_release(key);
         self.V[self.key_destination] = key;
         self.awaiting_key = false;
     }
 
+    /// Decrement the timers as many times as `timer_frequency` asks for in a 60th of a second.
+    #[inline]
+    fn tick_timers(&mut self) {
+        self.timer_phase += self.timer_frequency;
+        while self.timer_phase >= 60 {
+            self.update_timers();
+            self.timer_phase -= 60;
+        }
+    }
+
     /// Complete a frame: decrement timers and set vblank.
     pub fn tick_frame(&mut self) {
-        self.update_timers();
+        // A scripted key release also resolves Fx0A
+        if let Some(key) = self.input_script.advance() {
+            self.key_released(key);
+        }
+        self.tick_timers();
         self.set_vblank();
         self.frame_cycle = 0;
+        self.frame_count += 1;
+        self.last_frame_vip_cycles = self.frame_vip_cycles;
+        self.frame_vip_cycles = 0;
+        self.last_frame_events = std::mem::take(&mut self.frame_events);
+        if let Some(trace) = &mut self.chrome_trace {
+            trace.end_frame();
+        }
+        if let Some(mut dump) = self.frame_dump.take() {
+            dump.write_frame(self);
+            self.frame_dump = Some(dump);
+        }
+        self.replay_frame_completed();
+    }
+
+    /// Remember the registers so the inspector can show what the next step changed.
+    #[inline]
+    fn begin_step(&mut self) {
+        self.V_before_step = self.V;
+        self.I_before_step = self.I;
+    }
+
+    /// Execute a frame's worth of cycles and complete the frame.
+    /// If the interpreter stops in the middle of the frame, the frame is not completed.
+    pub fn run_frame(&mut self) {
+        self.begin_step();
+        if self.vip_speed {
+            while !self.is_frame_complete() {
+                self.execute_cycle();
+                if !self.running {
+                    return;
+                }
+            }
+        } else {
+            for _ in 0..self.execution_speed {
+                self.execute_cycle();
+                if !self.running {
+                    return;
+                }
+            }
+        }
+        self.tick_frame();
+    }
+
+    /// Execute one cycle while stopped, completing the frame if it was the last cycle.
+    /// Returns `true` if the frame was completed.
+    pub fn step_cycle(&mut self) -> bool {
+        self.begin_step();
+        self.execute_cycle();
+        if self.is_frame_complete() {
+            self.tick_frame();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Execute the rest of the current frame while stopped and complete it.
+    pub fn step_frame(&mut self) {
+        self.begin_step();
+        if self.vip_speed {
+            while !self.is_frame_complete() {
+                let cycle = self.frame_cycle;
+                self.execute_cycle();
+                // Nothing is executed past the end of memory
+                if self.frame_cycle == cycle {
+                    break;
+                }
+            }
+        } else {
+            for _ in self.frame_cycle..self.execution_speed {
+                self.execute_cycle();
+            }
+        }
+        self.tick_frame();
+    }
+
+    /// Check if the current frame executed all its cycles: `execution_speed` of them, or with
+    /// `vip_speed` as many as fit in the machine cycles of a COSMAC VIP frame.
+    #[inline]
+    pub(crate) fn is_frame_complete(&self) -> bool {
+        if self.vip_speed {
+            self.frame_vip_cycles >= VIP_CYCLES_PER_FRAME
+        } else {
+            self.frame_cycle >= self.execution_speed
+        }
     }
 
     /// Get the next instruction and execute it.
     pub fn execute_cycle(&mut self) {
-        self.halt_message = None;
+        self.halt_reason = None;
+        self.halt_context.clear();
 
-        if self.program_counter >= self.memory.ram.len() as u16 - 2 {
+        if self.program_counter as usize >= self.memory.ram.len() - 2 {
+            self.stop();
+            return;
+        }
+        if self.check_breakpoints() {
             self.stop();
             return;
         }
 
+        self.record_rewind_frame();
         self.frame_cycle += 1;
 
         let instruction: u16 = self.get_current_opcode();
 
-        self.execute_instruction(instruction);
+        if self.awaiting_key {
+            // The VIP keeps scanning the keypad, which takes time too
+            if self.vip_speed {
+                self.frame_vip_cycles += self.vip_cycle_cost(instruction);
+            }
+            return;
+        }
+
+        let address = self.program_counter;
+        let v_before = self.V;
+        let i_before = self.I;
+        let vip_cycles = self.vip_cycle_cost(instruction);
+        if self.vip_timing || self.vip_speed {
+            self.frame_vip_cycles += vip_cycles;
+        }
+
+        if !self.execute_or_wait(instruction) {
+            // Waiting for vblank, the instruction is tried again on the next cycle
+            return;
+        }
+
+        self.trace.push(TraceEntry {
+            address,
+            opcode: instruction,
+            V_before: v_before,
+            V_after: self.V,
+            I_before: i_before,
+            I_after: self.I,
+            vip_cycles,
+        });
+        if let Some(trace) = &mut self.chrome_trace {
+            trace.instruction(self.frame_cycle, self.execution_speed, address, instruction);
+        }
+        if self.check_watchpoints() {
+            self.stop();
+        }
     }
 
     /// Parse and execute an instruction.
+    #[inline]
     pub fn execute_instruction(&mut self, opcode: u16) {
+        self.execute_or_wait(opcode);
+    }
+
+    /// Parse and execute an instruction. Returns `false` if the instruction waits instead, for a
+    /// key press or for vblank with the `wait_for_vblank` quirk, so it is executed again on the
+    /// next cycle.
+    fn execute_or_wait(&mut self, opcode: u16) -> bool {
         if self.awaiting_key {
-            return;
+            return false;
         }
 
         let addr = opcode & 0x0FFF; // 0nnn
@@ -287,6 +1075,7 @@ impl Chip8 {
         let y = ((opcode & 0x00F0) >> 4) as usize; // 00y0
         let byte = (opcode & 0x00FF) as u8; // 00kk
         let nibble = (opcode & 0x000F) as u8; // 000n
+        let i_before = self.I;
 
         match opcode >> 12 {
             0x0 => {
@@ -294,76 +1083,154 @@ impl Chip8 {
                 if opcode == 0x0000 {
                     self.stop();
                 }
-                // 00Cn - Scroll down by n pixels (SUPER-CHIP)
-                else if self.variant.supports_schip() && y == 0xC {
+                // 00Cn - Scroll down by n pixels (SUPER-CHIP 1.1)
+                else if self.variant.supports_scrolling() && y == 0xC {
                     {
                         self.display.scroll(
                             ScrollDirection::Down,
                             nibble as usize,
+                            self.selected_planes,
                             self.highres,
                             self.quirks.lowres_scroll,
-                        )
+                        );
+                        self.record_event(FrameEventKind::Draw);
                     }
+                }
+                // 00Dn - Scroll up by n pixels (XO-CHIP)
+                else if self.variant.supports_xochip() && y == 0xD {
+                    self.display.scroll(
+                        ScrollDirection::Up,
+                        nibble as usize,
+                        self.selected_planes,
+                        self.highres,
+                        self.quirks.lowres_scroll,
+                    );
+                    self.record_event(FrameEventKind::Draw);
                 } else {
                     match byte {
                         // 00E0 - Clear the screen
-                        0xE0 => self.display.clear(),
+                        // Only the selected planes (XO-CHIP)
+                        0xE0 => {
+                            self.display.clear_planes(self.selected_planes);
+                            self.record_event(FrameEventKind::Draw);
+                        }
                         // 00EE - Return from subroutine
                         0xEE => {
-                            self.stack_pointer = self.stack_pointer.saturating_sub(1);
-                            self.program_counter = self.stack[self.stack_pointer as usize];
-                            return;
+                            if self.stack_pointer == 0 {
+                                self.halt(HaltReason::StackUnderflow {
+                                    address: self.program_counter,
+                                    opcode,
+                                });
+                                return true;
+                            }
+                            self.stack_pointer -= 1;
+                            self.jump(self.stack[self.stack_pointer as usize]);
+                            return true;
                         }
                         // 00FF - Enable high resolution mode (SUPER-CHIP)
-                        0xFF if self.variant.supports_schip() => self.highres = true,
                         // 00FE - Disable high resolution mode (SUPER-CHIP)
-                        0xFE if self.variant.supports_schip() => self.highres = false,
-                        // 00FB - Scroll the display 4 pixels right (SUPER-CHIP)
-                        0xFB if self.variant.supports_schip() => self.display.scroll(ScrollDirection::Right, 4,self.highres,self.quirks.lowres_scroll),
-                        // 00FC - Scroll the display 4 pixels left (SUPER-CHIP)
-                        0xFC if self.variant.supports_schip() => {
-                            self.display.scroll(ScrollDirection::Left, 4,self.highres,self.quirks.lowres_scroll)
+                        0xFE | 0xFF if self.variant.supports_schip() => {
+                            self.highres = byte == 0xFF;
+                            if self.quirks.resolution_clear {
+                                self.display.clear();
+                                self.record_event(FrameEventKind::Draw);
+                            }
+                        }
+                        // 00FB - Scroll the display 4 pixels right (SUPER-CHIP 1.1)
+                        0xFB if self.variant.supports_scrolling() => {
+                            self.display.scroll(
+                                ScrollDirection::Right,
+                                4,
+                                self.selected_planes,
+                                self.highres,
+                                self.quirks.lowres_scroll,
+                            );
+                            self.record_event(FrameEventKind::Draw);
+                        }
+                        // 00FC - Scroll the display 4 pixels left (SUPER-CHIP 1.1)
+                        0xFC if self.variant.supports_scrolling() => {
+                            self.display.scroll(
+                                ScrollDirection::Left,
+                                4,
+                                self.selected_planes,
+                                self.highres,
+                                self.quirks.lowres_scroll,
+                            );
+                            self.record_event(FrameEventKind::Draw);
+                        }
+                        // 02A0 - Step the background color (CHIP-8X)
+                        0xA0 if self.variant == Variant::CHIP8X && opcode == 0x02A0 => {
+                            self.step_background();
+                            self.record_event(FrameEventKind::Draw);
                         }
                         // 00FD - Exit the interpreter (SUPER-CHIP)
                         0xFD if self.variant.supports_schip() => {
                             self.stop();
                             self.reset();
                         }
-                        _ => self.halt(format!(
-                            "Machine code routines are not supported: {:04X}. Try a different CHIP-8 variant.",
-                            opcode
-                        )),
+                        // 0nnn - Run the machine code routine at nnn (COSMAC VIP)
+                        _ if self.variant == Variant::VIPHYBRID => {
+                            self.call_machine_code(addr);
+                            return true;
+                        }
+                        _ => self.halt(HaltReason::MachineCodeUnsupported { opcode }),
                     }
                 }
             }
             // 1nnn - Jump to nnn
             0x1 => {
-                self.program_counter = addr;
-                return;
+                self.jump(addr);
+                return true;
             }
             // 2nnn - Call subroutine at nnn
             0x2 => {
+                if self.stack_pointer as usize >= self.stack.len() {
+                    self.halt(HaltReason::StackOverflow {
+                        address: self.program_counter,
+                        opcode,
+                    });
+                    return true;
+                }
                 self.stack[self.stack_pointer as usize] = self.program_counter + 2;
                 self.stack_pointer = self.stack_pointer.saturating_add(1);
-                self.program_counter = addr;
-                return;
+                if self.stack_pointer > self.max_stack_depth {
+                    self.max_stack_depth = self.stack_pointer;
+                }
+                self.jump(addr);
+                return true;
             }
             // 3xnn - Skip if Vx == nn
             0x3 => {
                 if self.V[x] == byte {
-                    self.increment_program_counter();
+                    self.skip_instruction();
                 }
             }
             // 4xnn - Skip if Vx != nn
             0x4 => {
                 if self.V[x] != byte {
-                    self.increment_program_counter();
+                    self.skip_instruction();
                 }
             }
+            // 5xy2 - Write Vx to Vy to addresses starting at I, I is not changed (XO-CHIP)
+            0x5 if self.variant.supports_xochip() && nibble == 2 => {
+                for (offset, i) in register_range(x, y).into_iter().enumerate() {
+                    self.write_byte(self.I.wrapping_add(offset as u16), self.V[i]);
+                }
+            }
+            // 5xy3 - Read from addresses starting at I to Vx to Vy, I is not changed (XO-CHIP)
+            0x5 if self.variant.supports_xochip() && nibble == 3 => {
+                for (offset, i) in register_range(x, y).into_iter().enumerate() {
+                    self.V[i] = self.load_byte(self.I.wrapping_add(offset as u16));
+                }
+            }
+            // 5xy1 - Add the nibbles of Vy to those of Vx separately, keeping 3 bits of each (CHIP-8X)
+            0x5 if self.variant == Variant::CHIP8X && nibble == 1 => {
+                self.V[x] = ((self.V[x] & 0x77) + (self.V[y] & 0x77)) & 0x77;
+            }
             // 5xy0 - Skip if Vx == Vy
             0x5 if nibble == 0 => {
                 if self.V[x] == self.V[y] {
-                    self.increment_program_counter();
+                    self.skip_instruction();
                 }
             }
             // 6xnn - Set Vx = nn
@@ -449,225 +1316,245 @@ impl Chip8 {
                     self.V[x] <<= 1;
                     self.set_flag(shifted >> 7);
                 }
-                _ => self.halt(format!("Illegal instruction: {:04X}", opcode)),
+                _ => self.halt(HaltReason::IllegalInstruction { opcode }),
             },
             // 9xy0 - Skip if Vx != Vy
             0x9 if nibble == 0 => {
                 if self.V[x] != self.V[y] {
-                    self.increment_program_counter();
+                    self.skip_instruction();
                 }
             }
             // Annn - Set I to nnn
             0xA => self.I = addr,
+            // Bxy0 - Set the color of the 8x4 zones given by Vx and V(x + 1) to Vy (CHIP-8X)
+            // Bxyn - Set the color of the area of an 8xn sprite at Vx, V(x + 1) to Vy (CHIP-8X)
+            0xB if self.variant == Variant::CHIP8X => {
+                self.set_zone_color(x, y, nibble);
+                self.record_event(FrameEventKind::Draw);
+            }
             // Bnnn - Jump to nnn + V0
             // Bxnn - Jump to xnn + Vx (quirk)
             0xB => {
-                self.program_counter = addr
-                    + if self.quirks.jump_to_x {
-                        self.V[x]
-                    } else {
-                        self.V[0]
-                    } as u16;
-                return;
+                let offset = if self.quirks.jump_to_x {
+                    self.V[x]
+                } else {
+                    self.V[0]
+                };
+                self.jump(addr + offset as u16);
+                return true;
             }
             // Cxnn - Set Vx = a random value & nn
-            0xC => self.V[x] = rand::thread_rng().gen::<u8>() & byte,
+            0xC => self.V[x] = self.random_byte() & byte,
             // Dxy0 - Draw 16x16 sprite at Vx, Vy from address I (SUPER-CHIP)
+            // With the lowres_tall_sprites quirk, an 8x16 sprite is drawn in lowres mode (SUPER-CHIP 1.0)
+            // Draw to each selected plane, with the sprite of the second plane following the first (XO-CHIP)
             0xD if self.variant.supports_schip() && nibble == 0 => {
-                if self.quirks.wait_for_vblank && !self.vblank {
-                    return;
+                if self.quirks.wait_for_vblank.applies(self.highres) && !self.vblank {
+                    return false;
                 }
 
-                let width = if self.highres { 128 } else { 64 };
-                let height = if self.highres { 64 } else { 32 };
-
                 let dx = self.V[x] as u16;
                 let dy = self.V[y] as u16;
+                let wide = self.highres || !self.quirks.lowres_tall_sprites;
 
                 let mut overlap = false;
-                for row in 0..16 as u16 {
-                    let sprite_byte = self.memory.ram[self.I as usize + row as usize * 2];
-                    for cell in 0..8 {
-                        if self.quirks.edge_clipping
-                            && (dx % width + cell > width - 1 || dy % height + row > height - 1)
-                        {
-                            break;
-                        }
-
-                        let sprite_pixel = sprite_byte & (0b10000000 >> cell) != 0;
-
-                        let target_pixel =
-                            ((dx + cell) % width + (dy + row) % height * width) as usize;
-
-                        if sprite_pixel {
-                            if self.display.pixels[target_pixel] {
-                                overlap = true;
-                            }
-                            self.display.pixels[target_pixel] = !self.display.pixels[target_pixel];
-                        }
+                let mut address = self.I;
+                for plane in 0..2 {
+                    if self.selected_planes & (1 << plane) == 0 {
+                        continue;
                     }
-                    let sprite_byte = self.memory.ram[self.I as usize + row as usize * 2 + 1];
-                    for cell in 8..16 {
-                        if self.quirks.edge_clipping
-                            && (dx % width + cell > width - 1 || dy % height + row > height - 1)
-                        {
-                            break;
-                        }
-
-                        let sprite_pixel = sprite_byte & (0b10000000 >> (cell - 8)) != 0;
-
-                        let target_pixel =
-                            ((dx + cell) % width + (dy + row) % height * width) as usize;
-
-                        if sprite_pixel {
-                            if self.display.pixels[target_pixel] {
-                                overlap = true;
-                            }
-                            self.display.pixels[target_pixel] = !self.display.pixels[target_pixel];
+                    for row in 0..16 as u16 {
+                        let sprite_byte = self.load_byte(address);
+                        overlap |= self.draw_sprite_byte(plane, sprite_byte, dx, dy, row, 0);
+                        address = address.wrapping_add(1);
+                        if wide {
+                            let sprite_byte = self.load_byte(address);
+                            overlap |= self.draw_sprite_byte(plane, sprite_byte, dx, dy, row, 8);
+                            address = address.wrapping_add(1);
                         }
                     }
                 }
                 self.set_flag(if overlap { 1 } else { 0 });
 
                 self.vblank = false;
+                self.record_event(FrameEventKind::Draw);
             }
             // Dxyn - Draw 8xn sprite at Vx, Vy from address I
             // Optionally wait for a vblank interrupt (quirk)
+            // Draw to each selected plane, with the sprite of the second plane following the first (XO-CHIP)
             0xD => {
-                if self.quirks.wait_for_vblank && !self.vblank {
-                    return;
+                if self.quirks.wait_for_vblank.applies(self.highres) && !self.vblank {
+                    return false;
                 }
 
-                /*
-                    I tried to do this by actually XORing the target pixel with the sprite pixel for
-                    a while, but I could not pass the clipping test. I always got ERR2 and I did not
-                    know why.
-                    I gave up and looked at how Octo does this. I copied the part before the pixel
-                    setting, but it still did not work. I then copied the rest and run the test.
-
-                    It passed.
-
-                    I have no idea why this way works but my way did not.
-                */
-
-                let width = if self.highres { 128 } else { 64 };
-                let height = if self.highres { 64 } else { 32 };
-
                 let dx = self.V[x] as u16;
                 let dy = self.V[y] as u16;
 
                 let mut overlap = false;
-                for row in 0..nibble as u16 {
-                    let sprite_byte = self.memory.ram[self.I as usize + row as usize];
-                    for cell in 0..8 {
-                        if self.quirks.edge_clipping
-                            && (dx % width + cell > width - 1 || dy % height + row > height - 1)
-                        {
-                            break;
-                        }
-
-                        let sprite_pixel = sprite_byte & (0b10000000 >> cell) != 0;
-
-                        let target_pixel =
-                            ((dx + cell) % width + (dy + row) % height * width) as usize;
-
-                        if sprite_pixel {
-                            if self.display.pixels[target_pixel] {
-                                overlap = true;
-                            }
-                            self.display.pixels[target_pixel] = !self.display.pixels[target_pixel];
-                        }
+                let mut address = self.I;
+                for plane in 0..2 {
+                    if self.selected_planes & (1 << plane) == 0 {
+                        continue;
+                    }
+                    for row in 0..nibble as u16 {
+                        let sprite_byte = self.load_byte(address);
+                        overlap |= self.draw_sprite_byte(plane, sprite_byte, dx, dy, row, 0);
+                        address = address.wrapping_add(1);
                     }
                 }
                 self.set_flag(if overlap { 1 } else { 0 });
 
                 self.vblank = false;
+                self.record_event(FrameEventKind::Draw);
             }
             0xE => match byte {
                 // Ex9E - Skip if key Vx is down
                 0x9E => {
-                    if self.keypad[(self.V[x] & 0x0F) as usize] {
-                        self.increment_program_counter();
+                    self.record_event(FrameEventKind::KeyRead);
+                    if self.is_key_down((self.V[x] & 0x0F) as usize) {
+                        self.skip_instruction();
                     }
                 }
                 // ExA1 - Skip if key Vx is up
                 0xA1 => {
-                    if !self.keypad[(self.V[x] & 0x0F) as usize] {
-                        self.increment_program_counter();
+                    self.record_event(FrameEventKind::KeyRead);
+                    if !self.is_key_down((self.V[x] & 0x0F) as usize) {
+                        self.skip_instruction();
                     }
                 }
-                _ => self.halt(format!("Illegal instruction: {:04X}", opcode)),
+                // ExF2 - Skip if key Vx of the second keypad is down (CHIP-8X)
+                0xF2 if self.variant == Variant::CHIP8X => {
+                    self.record_event(FrameEventKind::KeyRead);
+                    if self.keypad2[(self.V[x] & 0x0F) as usize] {
+                        self.skip_instruction();
+                    }
+                }
+                // ExF5 - Skip if key Vx of the second keypad is up (CHIP-8X)
+                0xF5 if self.variant == Variant::CHIP8X => {
+                    self.record_event(FrameEventKind::KeyRead);
+                    if !self.keypad2[(self.V[x] & 0x0F) as usize] {
+                        self.skip_instruction();
+                    }
+                }
+                _ => self.halt(HaltReason::IllegalInstruction { opcode }),
             },
             0xF => match byte {
+                // F000 nnnn - Set I to the 16-bit address nnnn in the next two bytes (XO-CHIP)
+                0x00 if self.variant.supports_xochip() && x == 0 => {
+                    self.I = self
+                        .memory
+                        .read_opcode(self.program_counter.wrapping_add(2));
+                    self.increment_program_counter();
+                }
+                // Fn01 - Select the planes n to draw to, clear and scroll (XO-CHIP)
+                0x01 if self.variant.supports_xochip() => self.selected_planes = x as u8 & 0b11,
+                // F002 - Load the 16 bytes at addresses I to I+15 as the audio pattern (XO-CHIP)
+                0x02 if self.variant.supports_xochip() && x == 0 => {
+                    let mut pattern = [0; 16];
+                    for (i, sample) in pattern.iter_mut().enumerate() {
+                        *sample = self.load_byte(self.I.wrapping_add(i as u16));
+                    }
+                    self.audio_pattern = Some(pattern);
+                    self.record_event(FrameEventKind::Sound);
+                }
                 // Fx07 - Set Vx to delay
                 0x07 => self.V[x] = self.delay,
-                // Fx0A - Wait for a key pressed and released and set it to Vx
+                // Fx0A - Wait for a key pressed and released (or only pressed, with the key_wait_on_press quirk) and set it to Vx
                 0x0A => {
+                    self.record_event(FrameEventKind::KeyRead);
                     self.awaiting_key = true;
                     self.key_destination = x;
                 }
                 // Fx15 - Set delay to Vx
                 0x15 => self.delay = self.V[x],
                 // Fx18 - Set sound to Vx
-                0x18 => self.sound = self.V[x],
+                0x18 => {
+                    self.sound = self.V[x];
+                    self.record_event(FrameEventKind::Sound);
+                }
                 // Fx1E - Set I += Vx
-                0x1E => self.I += self.V[x] as u16,
+                0x1E => self.I = self.I.wrapping_add(self.V[x] as u16),
                 // Fx29 - Set I to the address of the font sprite for Vx's lowest nibble
-                0x29 => self.I = (self.V[x] as u16 & 0x000F) * 5,
+                0x29 => self.I = FONT_ADDRESS + (self.V[x] as u16 & 0x000F) * 5,
                 // Fx30 - Set I to the address of the large font sprite for Vx's lowest nibble (SUPER-CHIP)
                 0x30 if self.variant.supports_schip() => {
-                    self.I = (self.V[x] as u16 & 0x000F) * 10 + 16 * 5
+                    self.I = BIG_FONT_ADDRESS + (self.V[x] as u16 & 0x000F) * 10
                 }
                 // Fx33 - Write Vx as BCD to addresses I, I+1 and I+2
                 0x33 => {
                     self.write_byte(self.I, self.V[x] / 100);
-                    self.write_byte(self.I + 1, (self.V[x] / 10) % 10);
-                    self.write_byte(self.I + 2, (self.V[x] % 100) % 10);
+                    self.write_byte(self.I.wrapping_add(1), (self.V[x] / 10) % 10);
+                    self.write_byte(self.I.wrapping_add(2), (self.V[x] % 100) % 10);
+                }
+                // Fx3A - Set the pitch of the audio pattern to Vx (XO-CHIP)
+                0x3A if self.variant.supports_xochip() => {
+                    self.pitch = self.V[x];
+                    self.record_event(FrameEventKind::Sound);
                 }
-                // Fx55 - Write V0 to Vx to addresses I to I+x, I is incremented by x
-                // Or I is not incremented at all (quirk)
+                // Fx55 - Write V0 to Vx to addresses I to I+x, I is incremented by x + 1
+                // Or by x, or not at all (quirk)
                 0x55 => {
                     for i in 0..=x {
-                        self.write_byte(self.I + i as u16, self.V[i]);
-                    }
-                    if !self.quirks.save_load_increment {
-                        self.I += x as u16 + 1
+                        self.write_byte(self.I.wrapping_add(i as u16), self.V[i]);
                     }
+                    self.I = self
+                        .I
+                        .wrapping_add(self.quirks.save_load_increment.amount(x as u16));
                 }
-                // Fx65 - Read from addresses I to I+x to V0 to Vx, I is incremented by x
-                // Or I is not incremented at all (quirk)
+                // Fx65 - Read from addresses I to I+x to V0 to Vx, I is incremented by x + 1
+                // Or by x, or not at all (quirk)
                 0x65 => {
                     for i in 0..=x {
-                        self.V[i] = self.read_byte(self.I + i as u16);
-                    }
-                    if !self.quirks.save_load_increment {
-                        self.I += x as u16 + 1
+                        self.V[i] = self.load_byte(self.I.wrapping_add(i as u16));
                     }
+                    self.I = self
+                        .I
+                        .wrapping_add(self.quirks.save_load_increment.amount(x as u16));
                 }
                 // Fx75 - Save V0-Vx to persistent storage (SUPER-CHIP)
-                0x75 if self.variant.supports_schip() => {
+                // x can be at most 7 in SUPER-CHIP and F in XO-CHIP
+                0x75 if x < self.variant.persistent_flag_count() => {
                     for i in 0..=x {
                         self.persistent_flags[i] = self.V[i];
                     }
-                    self.save_persistent_flags();
+                    let result = self.save_persistent_flags();
+                    self.note_persistent_flags_result(result);
                 }
                 // Fx85 - Load V0-Vx from persistent storage (SUPER-CHIP)
-                0x85 if self.variant.supports_schip() => {
+                0x85 if x < self.variant.persistent_flag_count() => {
                     for i in 0..=x {
                         self.V[i] = self.persistent_flags[i];
                     }
                 }
-                _ => self.halt(format!("Illegal instruction: {:04X}", opcode)),
+                _ => self.halt(HaltReason::IllegalInstruction { opcode }),
             },
-            _ => self.halt(format!("Illegal instruction: {:04X}", opcode)),
+            _ => self.halt(HaltReason::IllegalInstruction { opcode }),
         }
-        self.increment_program_counter();
+        if self.halt_reason.is_some() {
+            // Stay on the instruction that halted, like jumps and stack errors do, so it runs again
+            // from the same I when execution resumes, for example after enabling the memory wrap
+            // quirk
+            self.I = i_before;
+        } else {
+            self.increment_program_counter();
+        }
+        true
     }
 
     /// Stop execution in case of an exceptional event.
-    pub fn halt(&mut self, reason: String) {
+    pub fn halt(&mut self, reason: HaltReason) {
         self.stop();
-        self.halt_message = Some(reason);
+        self.halt_reason = Some(reason);
+
+        // Keep the last instructions from the trace so the halt can be understood later
+        let skip = self.trace.len().saturating_sub(HALT_CONTEXT_LENGTH - 1);
+        self.halt_context = self
+            .trace
+            .iter()
+            .skip(skip)
+            .map(|entry| (entry.address, entry.opcode))
+            .chain([(self.program_counter, self.get_current_opcode())])
+            .collect();
     }
 }
 
@@ -683,6 +1570,16 @@ impl Chip8 {
     pub const fn get_register(&self, i: usize) -> u8 {
         self.V[i]
     }
+    /// Check if register V`i` was changed by the last step (cycle or frame). For the inspector.
+    #[inline]
+    pub const fn register_changed(&self, i: usize) -> bool {
+        self.V[i] != self.V_before_step[i]
+    }
+    /// Check if register I was changed by the last step (cycle or frame). For the inspector.
+    #[inline]
+    pub const fn i_changed(&self) -> bool {
+        self.I != self.I_before_step
+    }
     /// Get register I. For the inspector.
     #[inline]
     pub const fn get_i(&self) -> u16 {
@@ -698,6 +1595,22 @@ impl Chip8 {
     pub const fn get_stack_pointer(&self) -> u8 {
         self.stack_pointer
     }
+    /// Get the deepest the stack has been since the last reset, which is at least the current
+    /// depth. For the inspector.
+    #[inline]
+    pub const fn get_max_stack_depth(&self) -> u8 {
+        if self.max_stack_depth > self.stack_pointer {
+            self.max_stack_depth
+        } else {
+            self.stack_pointer
+        }
+    }
+    /// Check if the stack has been within two entries of overflowing since the last reset. For the
+    /// inspector.
+    #[inline]
+    pub const fn is_stack_near_overflow(&self) -> bool {
+        self.get_max_stack_depth() as usize + 2 >= self.stack_size
+    }
     /// Get the length of the stack. 12 for CHIP-8, 16 for SUPER-CHIP and XO-CHIP. For the inspector.
     #[inline]
     pub const fn get_stack_size(&self) -> usize {
@@ -718,9 +1631,39 @@ impl Chip8 {
     pub const fn get_sound(&self) -> u8 {
         self.sound
     }
+    /// Get the XO-CHIP audio pattern the buzzer plays, if one was loaded.
+    #[inline]
+    pub const fn get_audio_pattern(&self) -> Option<[u8; 16]> {
+        self.audio_pattern
+    }
+    /// Get the 128 1-bit samples of the XO-CHIP audio pattern in the order they are played, starting
+    /// with the highest bit of the first byte. `None` if no pattern was loaded.
+    pub fn get_audio_samples(&self) -> Option<[bool; 128]> {
+        let pattern = self.audio_pattern?;
+        Some(std::array::from_fn(|i| {
+            pattern[i / 8] & (0b10000000 >> (i % 8)) != 0
+        }))
+    }
+    /// Get the XO-CHIP pitch register.
+    #[inline]
+    pub const fn get_pitch(&self) -> u8 {
+        self.pitch
+    }
+    /// Get how many samples of the audio pattern are played per second:
+    /// 4000 * 2 ^ ((pitch - 64) / 48).
+    #[inline]
+    pub fn get_audio_sample_rate(&self) -> f32 {
+        4000.0 * 2f32.powf((self.pitch as f32 - 64.0) / 48.0)
+    }
+    /// Get the bitplanes selected with the XO-CHIP Fn01 instruction. Bit 0 is the first plane, bit 1
+    /// the second.
+    #[inline]
+    pub const fn get_selected_planes(&self) -> u8 {
+        self.selected_planes
+    }
     /// Get the length of RAM. For the inspector.
     #[inline]
-    pub const fn ram_len(&self) -> usize {
+    pub fn ram_len(&self) -> usize {
         self.memory.ram.len()
     }
     /// Get the index of the register where the next key press will be saved as a result of the Fx0A instruction.
@@ -731,23 +1674,399 @@ impl Chip8 {
     }
     /// Get the state of key `i` on the keypad. For the inspector.
     #[inline]
-    pub const fn get_key_state(&self, key: usize) -> bool {
-        self.keypad[key]
+    pub fn get_key_state(&self, key: usize) -> bool {
+        self.is_key_down(key)
     }
     /// Check if the interpreter is waiting for a key press with the Fx0A instruction. For the inspector.
     #[inline]
     pub const fn is_waiting_for_key(&self) -> bool {
         self.awaiting_key
     }
-    /// Get SUPER-CHIP persistent flags. For the inspector.
+    /// Get the number of queued key events that have not been played yet. For the inspector.
+    #[inline]
+    pub fn get_input_queue_len(&self) -> usize {
+        self.input_script.len()
+    }
+    /// Get the estimated COSMAC VIP machine cycles spent in the last completed frame, if `vip_timing`
+    /// or `vip_speed` is enabled. For the inspector.
+    #[inline]
+    pub const fn get_last_frame_vip_cycles(&self) -> u32 {
+        self.last_frame_vip_cycles
+    }
+    /// Get the number of frames completed since the last reset.
+    #[inline]
+    pub const fn get_frame_count(&self) -> u64 {
+        self.frame_count
+    }
+    /// Get the emulated time since the last reset: 1/60 of a second per completed frame.
+    #[inline]
+    pub const fn get_elapsed_time(&self) -> Duration {
+        Duration::from_nanos(self.frame_count * 1_000_000_000 / 60)
+    }
+    /// Get the execution speed in instructions per second: the cycles per frame times 60 frames per
+    /// second.
+    #[inline]
+    pub const fn get_instructions_per_second(&self) -> u32 {
+        self.execution_speed * 60
+    }
+    /// Set the execution speed in instructions per second, rounded to the nearest whole number of
+    /// cycles per frame between one and [`MAX_EXECUTION_SPEED`].
+    #[inline]
+    pub fn set_instructions_per_second(&mut self, instructions: u32) {
+        self.execution_speed = (instructions.saturating_add(30) / 60).clamp(1, MAX_EXECUTION_SPEED);
+    }
+    /// Get the events of the current frame so far. For the inspector.
+    #[inline]
+    pub fn get_frame_events(&self) -> &[FrameEvent] {
+        &self.frame_events
+    }
+    /// Get the events of the last completed frame. For the inspector.
+    #[inline]
+    pub fn get_last_frame_events(&self) -> &[FrameEvent] {
+        &self.last_frame_events
+    }
+    /// Get the last executed instructions. For the inspector.
+    #[inline]
+    pub const fn get_trace(&self) -> &Trace {
+        &self.trace
+    }
+    /// Get the addresses and opcodes of the instructions leading up to the halt, ending with the one
+    /// that halted. Empty if not halted. For the inspector.
+    #[inline]
+    pub fn get_halt_context(&self) -> &[(u16, u16)] {
+        &self.halt_context
+    }
+    /// Get the last memory reads and writes made by instructions. For the inspector.
+    #[inline]
+    pub const fn get_access_log(&self) -> &AccessLog {
+        &self.access_log
+    }
+    /// Start recording execution for export in the Chrome trace event format.
+    #[inline]
+    pub fn start_chrome_trace(&mut self) {
+        self.chrome_trace = Some(ChromeTrace::default());
+    }
+    /// Stop recording execution and get the recording, if recording.
+    #[inline]
+    pub fn stop_chrome_trace(&mut self) -> Option<ChromeTrace> {
+        self.chrome_trace.take()
+    }
+    /// Get the execution recorded so far, if recording.
     #[inline]
-    pub const fn get_persistent_flags(&self) -> [u8; 8] {
+    pub const fn get_chrome_trace(&self) -> Option<&ChromeTrace> {
+        self.chrome_trace.as_ref()
+    }
+    /// Get SUPER-CHIP and XO-CHIP persistent flags. For the inspector.
+    #[inline]
+    pub const fn get_persistent_flags(&self) -> [u8; 16] {
         self.persistent_flags
     }
-    /// Set all persistent flags to zero.
+    /// Get why the persistent flags could not be read or written the last time, if they could not.
+    /// For the inspector.
+    #[inline]
+    pub fn get_persistent_flags_error(&self) -> Option<&str> {
+        self.persistent_flags_error.as_deref()
+    }
+    /// Set all persistent flags of the loaded ROM to zero.
     #[inline]
     pub fn clear_persistent_flags(&mut self) {
-        self.persistent_flags = [0; 8];
-        self.save_persistent_flags();
+        self.persistent_flags = [0; 16];
+        let result = self.save_persistent_flags();
+        self.note_persistent_flags_result(result);
+    }
+}
+
+/// Get the registers from Vx to Vy for the XO-CHIP `5xy2` and `5xy3` instructions, in reverse order
+/// if x > y.
+fn register_range(x: usize, y: usize) -> Vec<usize> {
+    if x <= y {
+        (x..=y).collect()
+    } else {
+        (y..=x).rev().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Load `program` and execute `cycles` instructions.
+    fn run(mut chip8: Chip8, program: &[u8], cycles: usize) -> Chip8 {
+        chip8.reset_and_load(program);
+        chip8.start();
+        for _ in 0..cycles {
+            chip8.execute_cycle();
+        }
+        chip8
+    }
+
+    #[test]
+    fn xo_chip_register_ranges() {
+        let chip8 = run(
+            Chip8::xo_chip(),
+            &[
+                0x61, 0x01, 0x62, 0x02, 0x63, 0x03, 0xA3, 0x00, 0x51, 0x32, 0xA3, 0x10, 0x53, 0x12,
+                0xA3, 0x00, 0x54, 0x53,
+            ],
+            9,
+        );
+        assert_eq!(chip8.halt_reason, None);
+        // 5xy2 saves Vx to Vy, in reverse when x > y
+        assert_eq!(chip8.memory.ram[0x300..0x303], [1, 2, 3]);
+        assert_eq!(chip8.memory.ram[0x310..0x313], [3, 2, 1]);
+        // 5xy3 loads them and leaves I unchanged
+        assert_eq!(chip8.V[4..6], [1, 2]);
+        assert_eq!(chip8.I, 0x300);
+
+        let chip8 = run(Chip8::super_chip1_1(), &[0x51, 0x32], 1);
+        assert_eq!(
+            chip8.halt_reason,
+            Some(HaltReason::IllegalInstruction { opcode: 0x5132 })
+        );
+    }
+
+    #[test]
+    fn xo_chip_long_index() {
+        let chip8 = run(Chip8::xo_chip(), &[0xF0, 0x00, 0x12, 0x34, 0x60, 0x01], 2);
+        assert_eq!(chip8.I, 0x1234);
+        assert_eq!(chip8.V[0], 1);
+        assert_eq!(chip8.program_counter, 0x206);
+
+        let chip8 = run(Chip8::chip8(), &[0xF0, 0x00, 0x12, 0x34], 1);
+        assert_eq!(
+            chip8.halt_reason,
+            Some(HaltReason::IllegalInstruction { opcode: 0xF000 })
+        );
+    }
+
+    #[test]
+    fn skips_step_over_long_index() {
+        // 3xnn, 4xnn and ExA1 skip the whole 4 bytes of F000 nnnn
+        for skip in [[0x30, 0x00], [0x41, 0x01], [0xE0, 0xA1]] {
+            let mut program = skip.to_vec();
+            program.extend([0xF0, 0x00, 0x12, 0x34, 0x61, 0x01]);
+            let chip8 = run(Chip8::xo_chip(), &program, 2);
+            assert_eq!(chip8.I, 0, "{skip:02X?}");
+            assert_eq!(chip8.V[1], 1, "{skip:02X?}");
+            assert_eq!(chip8.program_counter, 0x208, "{skip:02X?}");
+        }
+        // An instruction that is not skipped is executed whole
+        let chip8 = run(
+            Chip8::xo_chip(),
+            &[0x40, 0x00, 0xF0, 0x00, 0x12, 0x34, 0x61, 0x01],
+            3,
+        );
+        assert_eq!((chip8.I, chip8.V[1]), (0x1234, 1));
+        // Other variants only skip 2 bytes
+        let chip8 = run(Chip8::super_chip1_1(), &[0x30, 0x00, 0xF0, 0x00], 1);
+        assert_eq!(chip8.program_counter, 0x204);
+    }
+
+    #[test]
+    fn xo_chip_planes() {
+        // Draw the top row of the 0 of the font on the second plane only
+        let chip8 = run(
+            Chip8::xo_chip(),
+            &[0xF2, 0x01, 0x60, 0x00, 0xF0, 0x29, 0xD0, 0x01],
+            4,
+        );
+        assert_eq!(chip8.get_selected_planes(), 2);
+        assert_eq!(
+            chip8.display.planes[1][..5],
+            [true, true, true, true, false]
+        );
+        assert!(chip8.display.planes[0].iter().all(|&pixel| !pixel));
+        // With both planes selected the second plane draws the next row of the sprite, and
+        // clearing only clears the selected planes
+        let chip8 = run(
+            Chip8::xo_chip(),
+            &[0xF3, 0x01, 0xF0, 0x29, 0xD0, 0x01, 0xF1, 0x01, 0x00, 0xE0],
+            5,
+        );
+        assert!(chip8.display.planes[0].iter().all(|&pixel| !pixel));
+        assert_eq!(
+            chip8.display.planes[1][..5],
+            [true, false, false, true, false]
+        );
+        // No plane at all draws nothing
+        let chip8 = run(Chip8::xo_chip(), &[0xF0, 0x01, 0xF0, 0x29, 0xD0, 0x01], 3);
+        assert_eq!(chip8.get_selected_planes(), 0);
+        assert!(chip8.display.planes.iter().flatten().all(|&pixel| !pixel));
+    }
+
+    #[test]
+    fn xo_chip_audio() {
+        let mut chip8 = Chip8::xo_chip();
+        chip8.reset_and_load(&[0xA2, 0x08, 0xF0, 0x02, 0x60, 0x70, 0xF0, 0x3A]);
+        chip8.memory.ram[0x208] = 0b1010_0000;
+        chip8.memory.ram[0x217] = 0xFF;
+        chip8.start();
+        assert_eq!(chip8.get_audio_pattern(), None);
+        assert_eq!(chip8.get_audio_sample_rate(), 4000.0);
+        for _ in 0..4 {
+            chip8.execute_cycle();
+        }
+        let pattern = chip8.get_audio_pattern().unwrap();
+        assert_eq!((pattern[0], pattern[15]), (0b1010_0000, 0xFF));
+        // Samples are played from the highest bit of the first byte
+        let samples = chip8.get_audio_samples().unwrap();
+        assert_eq!(samples[..4], [true, false, true, false]);
+        assert!(samples[120..].iter().all(|&sample| sample));
+        assert_eq!(chip8.get_pitch(), 0x70);
+        assert_eq!(
+            chip8.get_audio_sample_rate(),
+            4000.0 * 2f32.powf(48.0 / 48.0)
+        );
+
+        let chip8 = run(Chip8::chip8(), &[0xF0, 0x02], 1);
+        assert_eq!(
+            chip8.halt_reason,
+            Some(HaltReason::IllegalInstruction { opcode: 0xF002 })
+        );
+    }
+
+    #[test]
+    fn memory_out_of_bounds() {
+        // Fx55 writes V1 past the end of memory
+        let program = [0x61, 0x55, 0xAF, 0xFF, 0xF1, 0x55];
+        let mut chip8 = run(Chip8::chip8(), &program, 3);
+        assert_eq!(
+            chip8.halt_reason,
+            Some(HaltReason::MemoryOutOfBounds {
+                address: 0x204,
+                opcode: 0xF155
+            })
+        );
+        assert!(!chip8.is_running());
+        assert_eq!((chip8.program_counter, chip8.I), (0x204, 0xFFF));
+        // Resuming with the quirk runs the same instruction again, which now wraps around
+        chip8.quirks.memory_wrap = true;
+        chip8.start();
+        chip8.execute_cycle();
+        assert_eq!(chip8.halt_reason, None);
+        assert_eq!(chip8.program_counter, 0x206);
+        assert_eq!(chip8.memory.ram[0x000], 0x55);
+
+        let mut wrapping = Chip8::chip8();
+        wrapping.quirks.memory_wrap = true;
+        let chip8 = run(wrapping, &program, 3);
+        assert_eq!(chip8.halt_reason, None);
+        assert_eq!(chip8.program_counter, 0x206);
+        assert_eq!(chip8.memory.ram[0xFFF], 0);
+        assert_eq!(chip8.memory.ram[0x000], 0x55);
+    }
+
+    #[test]
+    fn jump_out_of_bounds() {
+        let program = [0x60, 0xFF, 0xBF, 0xFF];
+        let mut chip8 = run(Chip8::chip8(), &program, 2);
+        assert_eq!(
+            chip8.halt_reason,
+            Some(HaltReason::JumpOutOfBounds {
+                address: 0x202,
+                opcode: 0xBFFF,
+                target: 0x10FE
+            })
+        );
+        assert!(!chip8.is_running());
+        assert_eq!(chip8.program_counter, 0x202);
+        chip8.quirks.memory_wrap = true;
+        chip8.start();
+        chip8.execute_cycle();
+        assert_eq!(chip8.halt_reason, None);
+        assert_eq!(chip8.program_counter, 0x0FE);
+
+        let mut wrapping = Chip8::chip8();
+        wrapping.quirks.memory_wrap = true;
+        let chip8 = run(wrapping, &program, 2);
+        assert_eq!(chip8.halt_reason, None);
+        assert_eq!(chip8.program_counter, 0x0FE);
+    }
+
+    #[test]
+    fn program_size() {
+        let mut chip8 = Chip8::chip8();
+        assert_eq!(chip8.load_program(&[]), Err(LoadError::Empty));
+        assert_eq!(chip8.load_program(&[0x12; 3584]), Ok(()));
+        // A program that does not fit leaves memory unchanged
+        assert_eq!(
+            chip8.load_program(&[0x34; 3585]),
+            Err(LoadError::TooLarge {
+                size: 3585,
+                capacity: 3584
+            })
+        );
+        assert_eq!(chip8.memory.ram[0x200], 0x12);
+        assert_eq!(chip8.memory.ram[0xFFF], 0x12);
+        // XO-CHIP has 64KB of memory
+        assert_eq!(Chip8::xo_chip().load_program(&[0x34; 3585]), Ok(()));
+
+        let mut chip8 = Chip8::chip8();
+        chip8.reset_and_load(&[0x34; 4000]);
+        assert_eq!(
+            chip8.halt_reason,
+            Some(HaltReason::RomTooLarge {
+                size: 4000,
+                capacity: 3584
+            })
+        );
+        assert!(!chip8.is_running());
+    }
+
+    #[test]
+    fn start_address_past_memory() {
+        for start_address in [0x1000, 0xFFFF] {
+            let mut chip8 = Chip8::chip8();
+            chip8.start_address = start_address;
+            assert_eq!(
+                chip8.load_program(&[0x12, 0x00]),
+                Err(LoadError::TooLarge {
+                    size: 2,
+                    capacity: 0
+                })
+            );
+            chip8.reset_and_load(&[0x12, 0x00]);
+            assert_eq!(
+                chip8.halt_reason,
+                Some(HaltReason::RomTooLarge {
+                    size: 2,
+                    capacity: 0
+                })
+            );
+            assert!(!chip8.is_running());
+        }
+    }
+
+    #[test]
+    fn stack_underflow() {
+        let chip8 = run(Chip8::chip8(), &[0x00, 0xEE], 1);
+        assert_eq!(
+            chip8.halt_reason,
+            Some(HaltReason::StackUnderflow {
+                address: 0x200,
+                opcode: 0x00EE
+            })
+        );
+        assert!(!chip8.is_running());
+        assert_eq!((chip8.program_counter, chip8.stack_pointer), (0x200, 0));
+    }
+
+    #[test]
+    fn stack_overflow() {
+        // 2202 calls itself until the stack is full
+        let chip8 = Chip8::chip8();
+        let depth = chip8.stack.len();
+        let chip8 = run(chip8, &[0x00, 0xE0, 0x22, 0x02], depth + 2);
+        assert_eq!(
+            chip8.halt_reason,
+            Some(HaltReason::StackOverflow {
+                address: 0x202,
+                opcode: 0x2202
+            })
+        );
+        assert!(!chip8.is_running());
+        assert_eq!(chip8.program_counter, 0x202);
+        assert_eq!(chip8.stack_pointer as usize, depth);
+        assert!(chip8.stack.iter().all(|&address| address == 0x204));
     }
 }
@@ -0,0 +1,363 @@
+use std::fmt::Write;
+
+use crate::{replay::fnv1a, Breakpoints, Chip8};
+
+/// The machine before an instruction, as recorded by a reference implementation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[allow(non_snake_case)]
+pub struct ReferenceStep {
+    pub program_counter: u16,
+    pub I: u16,
+    pub V: [u8; 16],
+    /// An FNV-1a hash of the display pixels, if the reference recorded one. Every pixel is hashed as
+    /// a byte with bit 0 set if it is on in the first plane and bit 1 if it is on in the second.
+    pub display_hash: Option<u64>,
+    /// An FNV-1a hash of the 4KB of memory (64KB for XO-CHIP), if the reference recorded one.
+    pub memory_hash: Option<u64>,
+}
+
+/// The machine before every instruction of a run of a ROM, recorded by a reference implementation
+/// or by E-CHIP itself.
+///
+/// As text, every line is one step: the program counter and I as 4 hex digits and V0 to VF as 2 hex
+/// digits, separated by spaces, optionally followed by the display hash and the memory hash as 16
+/// hex digits. Empty lines and lines starting with `#` are ignored.
+#[derive(Debug, Default, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ReferenceTrace {
+    steps: Vec<ReferenceStep>,
+}
+
+impl ReferenceTrace {
+    /// Get the recorded steps.
+    #[inline]
+    pub fn steps(&self) -> &[ReferenceStep] {
+        &self.steps
+    }
+
+    /// Record `steps` instructions of a ROM with the variant, quirks and speed of `base`, without
+    /// input.
+    pub fn record(base: &Chip8, rom: &[u8], steps: usize) -> ReferenceTrace {
+        let mut chip8 = prepare(base, rom);
+        let mut trace = ReferenceTrace::default();
+        while trace.steps.len() < steps && stuck(&chip8).is_none() {
+            trace.steps.push(ReferenceStep::of(&chip8));
+            step(&mut chip8);
+        }
+        trace
+    }
+
+    /// Read a reference trace in the text format.
+    pub fn parse(text: &str) -> Result<ReferenceTrace, String> {
+        let steps = text
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty() && !line.trim_start().starts_with('#'))
+            .map(|(i, line)| parse_step(line).map_err(|e| format!("Line {}: {e}", i + 1)))
+            .collect::<Result<_, _>>()?;
+        Ok(ReferenceTrace { steps })
+    }
+
+    /// Write the trace in the text format, with the display and memory hashes.
+    pub fn to_text(&self) -> String {
+        let mut text =
+            String::from("# PC   I    V0 V1 V2 V3 V4 V5 V6 V7 V8 V9 VA VB VC VD VE VF\n");
+        for step in &self.steps {
+            let _ = write!(text, "{:04X} {:04X}", step.program_counter, step.I);
+            for v in step.V {
+                let _ = write!(text, " {v:02X}");
+            }
+            if let Some(hash) = step.display_hash {
+                let _ = write!(text, " {hash:016X}");
+            }
+            if let Some(hash) = step.memory_hash {
+                let _ = write!(text, " {hash:016X}");
+            }
+            text.push('\n');
+        }
+        text
+    }
+}
+
+impl ReferenceStep {
+    /// Record the machine as it is now.
+    fn of(chip8: &Chip8) -> ReferenceStep {
+        ReferenceStep {
+            program_counter: chip8.program_counter,
+            I: chip8.I,
+            V: chip8.V,
+            display_hash: Some(fnv1a(
+                (0..chip8.display.planes[0].len()).map(|i| chip8.display.planes_at(i)),
+            )),
+            memory_hash: Some(fnv1a(chip8.memory.ram.iter().copied())),
+        }
+    }
+}
+
+/// Read a line of a reference trace.
+fn parse_step(line: &str) -> Result<ReferenceStep, String> {
+    let fields: Vec<&str> = line.split_whitespace().collect();
+    if fields.len() < 18 {
+        return Err(format!(
+            "expected at least 18 values, found {}",
+            fields.len()
+        ));
+    }
+    let hex = |field: &str| {
+        u64::from_str_radix(field, 16).map_err(|_| format!("'{field}' is not a hex number"))
+    };
+    let mut step = ReferenceStep {
+        program_counter: hex(fields[0])? as u16,
+        I: hex(fields[1])? as u16,
+        V: [0; 16],
+        display_hash: fields.get(18).map(|field| hex(field)).transpose()?,
+        memory_hash: fields.get(19).map(|field| hex(field)).transpose()?,
+    };
+    for (v, field) in step.V.iter_mut().zip(&fields[2..18]) {
+        *v = hex(field)? as u8;
+    }
+    Ok(step)
+}
+
+/// Where E-CHIP first differed from the reference.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Divergence {
+    /// The step of the reference, starting at 0, that the machine did not match.
+    pub step: usize,
+    /// The address and opcode of the instruction executed before the step, which is usually the one
+    /// that went wrong. `None` if the starting state differed.
+    pub instruction: Option<(u16, u16)>,
+    /// What differed, like `V3: expected 12, got 10`.
+    pub differences: Vec<String>,
+}
+
+/// The result of [`lockstep_test`].
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct LockstepReport {
+    /// The number of steps that matched the reference.
+    pub matched: usize,
+    /// The number of steps in the reference.
+    pub steps: usize,
+    pub divergence: Option<Divergence>,
+}
+
+/// Run a ROM with the variant, quirks and speed of `base` one instruction at a time next to a
+/// reference trace and report the first step where the registers, display or memory differ.
+///
+/// Input is not simulated. Random numbers can not match, so after a `Cxnn` instruction the
+/// register is set to the reference's value. Cycles where E-CHIP waits for a key or for vblank do
+/// not count as steps.
+pub fn lockstep_test(base: &Chip8, rom: &[u8], reference: &ReferenceTrace) -> LockstepReport {
+    let mut chip8 = prepare(base, rom);
+    let mut instruction: Option<(u16, u16)> = None;
+    for (i, expected) in reference.steps.iter().enumerate() {
+        if let Some((_, opcode)) = instruction {
+            if opcode >> 12 == 0xC {
+                let x = ((opcode & 0x0F00) >> 8) as usize;
+                chip8.V[x] = expected.V[x];
+            }
+        }
+        let differences = compare(&chip8, expected);
+        if !differences.is_empty() {
+            return LockstepReport {
+                matched: i,
+                steps: reference.steps.len(),
+                divergence: Some(Divergence {
+                    step: i,
+                    instruction,
+                    differences,
+                }),
+            };
+        }
+        if let Some(reason) = stuck(&chip8).filter(|_| i + 1 < reference.steps.len()) {
+            return LockstepReport {
+                matched: i + 1,
+                steps: reference.steps.len(),
+                divergence: Some(Divergence {
+                    step: i + 1,
+                    instruction,
+                    differences: vec![format!("E-CHIP can not go on: {reason}")],
+                }),
+            };
+        }
+        instruction = Some((chip8.program_counter, chip8.get_current_opcode()));
+        step(&mut chip8);
+    }
+    LockstepReport {
+        matched: reference.steps.len(),
+        steps: reference.steps.len(),
+        divergence: None,
+    }
+}
+
+/// List how the machine differs from a step of the reference.
+fn compare(chip8: &Chip8, expected: &ReferenceStep) -> Vec<String> {
+    let actual = ReferenceStep::of(chip8);
+    let mut differences = Vec::new();
+    if actual.program_counter != expected.program_counter {
+        differences.push(format!(
+            "PC: expected {:04X}, got {:04X}",
+            expected.program_counter, actual.program_counter
+        ));
+    }
+    if actual.I != expected.I {
+        differences.push(format!(
+            "I: expected {:04X}, got {:04X}",
+            expected.I, actual.I
+        ));
+    }
+    for (x, (expected, actual)) in expected.V.iter().zip(actual.V).enumerate() {
+        if *expected != actual {
+            differences.push(format!("V{x:X}: expected {expected:02X}, got {actual:02X}"));
+        }
+    }
+    if expected
+        .display_hash
+        .is_some_and(|hash| Some(hash) != actual.display_hash)
+    {
+        differences.push("The display differs".to_string());
+    }
+    if expected
+        .memory_hash
+        .is_some_and(|hash| Some(hash) != actual.memory_hash)
+    {
+        differences.push("Memory differs".to_string());
+    }
+    differences
+}
+
+/// Make a reset, running copy of `base` with a ROM loaded.
+fn prepare(base: &Chip8, rom: &[u8]) -> Chip8 {
+    let mut chip8 = base.clone();
+    chip8.stop_chrome_trace();
+    chip8.stop_frame_dump();
+    chip8.stop_replay();
+    chip8.breakpoints = Breakpoints::default();
+    chip8.reset_and_load(rom);
+    if chip8.halt_reason.is_none() {
+        chip8.start();
+    }
+    chip8
+}
+
+/// Check if the machine can not execute another instruction, and why.
+fn stuck(chip8: &Chip8) -> Option<String> {
+    if chip8.awaiting_key {
+        Some("it waits for a key press, and input is not simulated".to_string())
+    } else if !chip8.is_running() {
+        Some(chip8.halt_reason.as_ref().map_or_else(
+            || "the program counter left memory".to_string(),
+            ToString::to_string,
+        ))
+    } else {
+        None
+    }
+}
+
+/// Execute cycles until an instruction was executed rather than waited on, or the machine stopped.
+fn step(chip8: &mut Chip8) {
+    while chip8.is_running() {
+        let opcode = chip8.get_current_opcode();
+        let waits = opcode >> 12 == 0xD
+            && chip8.quirks.wait_for_vblank.applies(chip8.highres)
+            && !chip8.vblank;
+        chip8.step_cycle();
+        if !waits {
+            return;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Shifts with `8xy6`, reads random numbers and counts in a loop.
+    const ROM: &[u8] = &[
+        0x61, 0x05, 0x62, 0x03, 0x81, 0x26, 0xC3, 0xFF, 0x73, 0x01, 0x12, 0x06,
+    ];
+
+    fn base(direct_shifting: bool) -> Chip8 {
+        let mut chip8 = Chip8::chip8();
+        chip8.quirks.direct_shifting = direct_shifting;
+        chip8
+    }
+
+    #[test]
+    fn matching_trace() {
+        let reference = ReferenceTrace::record(&base(false), ROM, 20);
+        assert_eq!(reference.steps().len(), 20);
+        let reference = ReferenceTrace::parse(&reference.to_text()).unwrap();
+        // Random numbers are taken from the reference
+        let report = lockstep_test(&base(false), ROM, &reference);
+        assert_eq!(
+            report,
+            LockstepReport {
+                matched: 20,
+                steps: 20,
+                divergence: None,
+            }
+        );
+    }
+
+    #[test]
+    fn first_divergence() {
+        let reference = ReferenceTrace::record(&base(true), ROM, 20);
+        let report = lockstep_test(&base(false), ROM, &reference);
+        assert_eq!(report.matched, 3);
+        assert_eq!(
+            report.divergence,
+            Some(Divergence {
+                step: 3,
+                instruction: Some((0x204, 0x8126)),
+                differences: vec!["V1: expected 02, got 01".to_string()],
+            })
+        );
+    }
+
+    #[test]
+    fn stuck_machine() {
+        // E-CHIP halts on an illegal instruction the reference went past
+        let rom = [0x61, 0x05, 0xFF, 0xFF];
+        let reference = ReferenceTrace::parse(
+            "0200 0000 00 00 00 00 00 00 00 00 00 00 00 00 00 00 00 00
+             0202 0000 00 05 00 00 00 00 00 00 00 00 00 00 00 00 00 00
+             0204 0000 00 05 00 00 00 00 00 00 00 00 00 00 00 00 00 00",
+        )
+        .unwrap();
+        let report = lockstep_test(&Chip8::chip8(), &rom, &reference);
+        let divergence = report.divergence.unwrap();
+        assert_eq!(divergence.step, 2);
+        assert_eq!(divergence.instruction, Some((0x202, 0xFFFF)));
+        assert_eq!(divergence.differences, ["PC: expected 0204, got 0202"]);
+
+        // A reference that goes on after E-CHIP waits for a key
+        let rom = [0xF1, 0x0A];
+        let mut reference = ReferenceTrace::record(&Chip8::chip8(), &rom, 3);
+        assert_eq!(reference.steps.len(), 1);
+        let waiting = ReferenceStep {
+            program_counter: 0x202,
+            ..reference.steps[0]
+        };
+        reference.steps.extend([waiting, waiting]);
+        let report = lockstep_test(&Chip8::chip8(), &rom, &reference);
+        assert_eq!(report.matched, 2);
+        let divergence = report.divergence.unwrap();
+        assert_eq!(divergence.step, 2);
+        assert_eq!(
+            divergence.differences,
+            ["E-CHIP can not go on: it waits for a key press, and input is not simulated"]
+        );
+    }
+
+    #[test]
+    fn invalid_traces() {
+        assert_eq!(
+            ReferenceTrace::parse("# PC I V0-VF\n0200 0000 00"),
+            Err("Line 2: expected at least 18 values, found 3".to_string())
+        );
+        assert_eq!(
+            ReferenceTrace::parse("0200 0000 00 00 00 00 00 00 00 00 00 00 00 00 00 00 00 0G"),
+            Err("Line 1: '0G' is not a hex number".to_string())
+        );
+    }
+}
@@ -1,37 +1,66 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")] // hide console window on Windows in release
 
-use std::{
-    sync::{Arc, Mutex},
-    thread::{self, sleep},
-    time::{Duration, Instant},
-};
+use std::sync::{mpsc::Receiver, Arc, Mutex};
+#[cfg(not(target_arch = "wasm32"))]
+use std::thread;
 
-use e_chip::Chip8;
+use autosave::Autosave;
+use buzzer::Tone;
+use e_chip::{
+    Chip8, DriverCommand, DriverEvent, EmulatorDriver, Expression, FramePacing, QuirkTestReport,
+    Quirks, StateError, Variant,
+};
 use eframe::egui;
-use egui::{Color32, ColorImage, Key, Modifiers, TextureHandle, TextureOptions};
+use egui::{Color32, ColorImage, Modifiers, TextureHandle, TextureOptions};
 use gui::*;
-use rodio::{
-    source::{self, SignalGenerator},
-    OutputStream, Sink,
-};
+use keymap::{Hotkey, KeypadInput, SLOT_KEYS};
+use recent::RecentRoms;
+use session::{DebugSession, Sessions};
+use settings::Settings;
+use web_time::{SystemTime, UNIX_EPOCH};
 
+#[cfg(feature = "archive")]
+mod archive;
+mod attract;
+mod autosave;
+mod buzzer;
+#[cfg(feature = "database")]
+mod database;
+#[cfg(feature = "gamepad")]
+mod gamepad;
 mod gui;
+#[cfg(feature = "hot-reload")]
+mod hot_reload;
+mod i18n;
+mod keymap;
+mod recent;
+mod session;
+mod settings;
+mod slots;
 
+/// The frequency of the buzzer's square wave, in Hz.
+const BUZZER_FREQUENCY: f32 = 440.0;
+/// The volume of the buzzer, from 0 to 1.
+const BUZZER_VOLUME: f32 = 0.05;
+
+#[cfg(not(target_arch = "wasm32"))]
 fn main() {
-    let chip8 = Chip8::chip8();
-    let arc_chip = Arc::new(Mutex::new(chip8));
+    use rodio::{OutputStream, Sink};
 
     // setup sound
     let (_stream, stream_handle) = OutputStream::try_default().unwrap();
-    let buzz = SignalGenerator::new(
-        rodio::cpal::SampleRate(48000),
-        440.0,
-        source::Function::Square,
-    );
+    let (buzz, tone) = buzzer::Buzzer::new();
     let sink = Sink::try_new(&stream_handle).unwrap();
-    sink.set_volume(0.05);
+    sink.set_volume(BUZZER_VOLUME);
     sink.append(buzz);
     sink.pause();
+    let set_sound = move |playing| {
+        if playing {
+            sink.play();
+        } else {
+            sink.pause();
+        }
+    };
 
     eframe::run_native(
         "E-CHIP",
@@ -46,276 +75,900 @@ fn main() {
             // This gives us image support:
             egui_extras::install_image_loaders(&cc.egui_ctx);
 
-            Ok(Box::new(Emulator::new(arc_chip, sink, &&cc.egui_ctx)))
+            Ok(Box::new(Emulator::new(set_sound, tone, &cc.egui_ctx)))
         }),
     )
     .unwrap();
 }
 
+/// Start the app in the `e-chip` canvas of the page, in the browser's frame loop.
+#[cfg(target_arch = "wasm32")]
+fn main() {
+    use std::sync::atomic::Ordering;
+
+    use wasm_bindgen::JsCast;
+
+    wasm_bindgen_futures::spawn_local(async {
+        let canvas = web_sys::window()
+            .and_then(|window| window.document())
+            .and_then(|document| document.get_element_by_id("e-chip"))
+            .and_then(|element| element.dyn_into::<web_sys::HtmlCanvasElement>().ok())
+            .expect("the page has no canvas with the id e-chip");
+        let result = eframe::WebRunner::new()
+            .start(
+                canvas,
+                eframe::WebOptions::default(),
+                Box::new(|cc| {
+                    egui_extras::install_image_loaders(&cc.egui_ctx);
+
+                    let (buzzer, tone, playing) = buzzer::WebBuzzer::new();
+                    let set_sound = move |on| playing.store(on, Ordering::Relaxed);
+                    Ok(Box::new(Emulator::new(
+                        set_sound,
+                        tone,
+                        &cc.egui_ctx,
+                        buzzer,
+                    )))
+                }),
+            )
+            .await;
+        if let Err(e) = result {
+            web_sys::console::error_1(&e);
+        }
+    });
+}
+
 /// The app.
 struct Emulator {
-    /// Access to the interpreter.
-    interpreter: Arc<Mutex<Chip8>>,
+    /// Runs the interpreter.
+    driver: EmulatorDriver,
+    /// What the buzzer plays, shared with the audio thread.
+    tone: Arc<Mutex<Tone>>,
+    /// Plays the buzzer in the browser.
+    #[cfg(target_arch = "wasm32")]
+    buzzer: buzzer::WebBuzzer,
 
     /// The texture to which the display is rendered.
     screen: TextureHandle,
-    /// The color of disabled pixels.
-    background_color: Color32,
-    /// The color of enabled pixels.
-    fill_color: Color32,
+    /// The configuration and display of the comparison interpreter.
+    comparison: ComparisonView,
+    /// The settings and result of the quirk bisect window.
+    bisect: BisectView,
+    /// The reference trace and result of the lockstep test window.
+    lockstep: LockstepView,
+    /// The result of the quirk test of the last picked preset or variant.
+    quirk_test: Option<QuirkTestReport>,
+    /// The filters of the memory access log window.
+    memory_log: MemoryLogView,
+    /// The watch expressions.
+    watches: WatchView,
+    /// The quick save slots.
+    slots: SlotsView,
+    /// The settings of the attract mode window.
+    attract_view: AttractView,
+    /// Cycles through the ROMs of a folder, if running.
+    attract: Option<attract::AttractMode>,
+    /// The colors of pixels that are off, on in the first plane, on in the second plane and on in
+    /// both planes.
+    colors: [Color32; 4],
+    /// Accessibility filters applied to the display colors.
+    display_filter: DisplayFilter,
 
     /// The current ROM.
     rom: Vec<u8>,
-    /// The value of the path input field.
-    rom_path: String,
-    /// Possible ROM loading error.
-    load_error: Option<std::io::Error>,
+    /// The input fields of the load ROM modal.
+    load: LoadView,
+    /// The path of the current ROM, if it was loaded from a file.
+    loaded_rom_path: Option<String>,
     /// Whether to show the load ROM modal
     show_load_modal: bool,
+    /// The ROM files loaded last.
+    recent: RecentRoms,
+    /// Watches the file of the current ROM to reload it when it changes.
+    #[cfg(feature = "hot-reload")]
+    rom_watcher: hot_reload::RomWatcher,
 
-    /// Whether to show the ROM window.
-    show_rom_window: bool,
-    /// Whether to show the display settings window.
-    show_display_settings: bool,
+    /// Which windows are open.
+    windows: Windows,
+    /// The watches and open windows saved for each ROM.
+    sessions: Sessions,
+    /// Lists and downloads programs from the chip8Archive.
+    #[cfg(feature = "archive")]
+    archive: archive::ArchiveBrowser,
+    /// Looks up loaded ROMs in the CHIP-8 database.
+    #[cfg(feature = "database")]
+    database: database::RomDatabase,
+    /// Whether the loaded ROM still has to be looked up in the database, which may be downloading.
+    #[cfg(feature = "database")]
+    database_pending: bool,
+    /// The title of the current ROM in the database, with the hash of the ROM it belongs to.
+    #[cfg(feature = "database")]
+    rom_title: Option<(u64, String)>,
+    /// Reads gamepads and keeps their mapping for each ROM.
+    #[cfg(feature = "gamepad")]
+    gamepad: gamepad::GamepadInput,
 
     /// Whether the RAM panel should scroll to the address in the program counter.
     track_pc: bool,
-}
+    /// Whether the RAM panel colors bytes by how often they were accessed.
+    ram_heatmap: bool,
+    /// The address of the RAM byte being edited and the hex digits typed so far.
+    ram_edit: Option<(u16, String)>,
 
-/// The duration of a single frame - the interpreter runs at 60 fps.
-const FRAME_DURATION: Duration = Duration::from_nanos(16666667);
+    /// Emulator settings.
+    settings: Settings,
+    /// Whether the interpreter is currently suspended because the window is minimized.
+    suspended: bool,
+    /// Whether the interpreter is fast-forwarding because the turbo key is held.
+    turbo: bool,
+    /// Interpreter events the frontend reacts to.
+    events: Receiver<DriverEvent>,
+    /// Whether keypad input can reach the interpreter, as last told to the driver.
+    input_available: bool,
+    /// Whether the interpreter has waited a long time for a key press that can't arrive.
+    waiting_for_input: bool,
+    /// Whether to show the export or import state modal.
+    state_transfer: Option<StateTransfer>,
+    /// The value of the state path input field.
+    state_path: String,
+    /// Possible state export or import error.
+    state_error: Option<String>,
+    /// The machine saved when the app was last closed, until the user decides whether to resume it.
+    autosave: Option<Autosave>,
+    /// Possible error when resuming the saved machine.
+    resume_error: Option<StateError>,
+    /// The state of the machine when it was last exported, imported or resumed, to tell whether it
+    /// has unsaved changes.
+    saved_state: Option<Vec<u8>>,
+    /// The window title that was last set.
+    title: String,
+}
 
 impl Emulator {
-    fn new(interpreter: Arc<Mutex<Chip8>>, sink: Sink, ctx: &egui::Context) -> Self {
+    /// Create the app. `set_sound` starts (`true`) or stops (`false`) the buzzer, which plays
+    /// `tone`.
+    fn new(
+        set_sound: impl FnMut(bool) + Send + 'static,
+        tone: Arc<Mutex<Tone>>,
+        ctx: &egui::Context,
+        #[cfg(target_arch = "wasm32")] buzzer: buzzer::WebBuzzer,
+    ) -> Self {
         ctx.style_mut(|style| style.override_text_style = Some(egui::TextStyle::Monospace));
 
-        // The interpreter thread
-        let clone = Arc::clone(&interpreter);
-        thread::spawn(move || 'main: loop {
-            let mut chip8 = clone.lock().unwrap();
+        let driver = EmulatorDriver::new(Chip8::chip8(), set_sound);
 
-            if chip8.is_running() {
-                let frame_start = Instant::now();
-
-                for _ in 0..chip8.execution_speed {
-                    chip8.execute_cycle();
-                    if !chip8.is_running() {
-                        continue 'main;
-                    }
-                }
-
-                chip8.tick_frame();
-
-                // play sound if enabled
-                if chip8.sound_on && chip8.get_sound() > 1 {
-                    if sink.is_paused() {
-                        sink.play();
-                    }
-                } else if !sink.is_paused() {
-                    sink.pause();
+        // Redraw whenever the interpreter changes. In the browser the interpreter only changes
+        // while drawing.
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            let events = driver.subscribe();
+            let repaint_ctx = ctx.clone();
+            thread::spawn(move || {
+                for _ in events {
+                    repaint_ctx.request_repaint();
                 }
+            });
+        }
 
-                drop(chip8); // unlock the mutex for the gui
+        let events = driver.subscribe();
 
-                sleep(FRAME_DURATION.saturating_sub(frame_start.elapsed())); // wait for frame to end
-            } else {
-                // turn off sound
-                if !sink.is_paused() {
-                    sink.pause();
-                }
-            }
-        });
+        #[cfg(feature = "database")]
+        let database = {
+            let mut database = database::RomDatabase::default();
+            database.load();
+            database
+        };
 
         Self {
-            interpreter,
+            driver,
+            tone,
+            #[cfg(target_arch = "wasm32")]
+            buzzer,
             screen: ctx.load_texture(
                 "screen",
                 ColorImage::new([64 * 10, 32 * 10], Color32::BLACK),
                 TextureOptions::NEAREST,
             ),
+            bisect: BisectView {
+                frames: 300,
+                report: None,
+            },
+            lockstep: LockstepView {
+                path: String::new(),
+                steps: 10_000,
+                report: None,
+                message: None,
+            },
+            quirk_test: None,
+            memory_log: MemoryLogView::default(),
+            watches: WatchView::default(),
+            slots: SlotsView {
+                rom: None,
+                slots: slots::SaveSlot::load_all(None),
+                thumbnails: (0..slots::SLOT_COUNT)
+                    .map(|i| {
+                        ctx.load_texture(
+                            format!("slot {}", i + 1),
+                            ColorImage::new([64, 32], Color32::BLACK),
+                            TextureOptions::NEAREST,
+                        )
+                    })
+                    .collect(),
+                error: None,
+            },
+            attract_view: AttractView {
+                folder: String::new(),
+                seconds: 30,
+                error: None,
+            },
+            attract: None,
+            comparison: ComparisonView {
+                variant: Variant::SCHIP11,
+                quirks: Quirks::super_chip1_1(),
+                screen: ctx.load_texture(
+                    "comparison screen",
+                    ColorImage::new([64 * 10, 32 * 10], Color32::BLACK),
+                    TextureOptions::NEAREST,
+                ),
+            },
             rom: vec![0],
-            rom_path: String::new(),
-            load_error: None,
+            load: LoadView::default(),
+            loaded_rom_path: None,
             show_load_modal: false,
-            show_rom_window: false,
-            show_display_settings: false,
+            recent: RecentRoms::load(),
+            #[cfg(feature = "hot-reload")]
+            rom_watcher: hot_reload::RomWatcher::new(ctx),
+            windows: Windows::default(),
+            sessions: Sessions::load(),
+            #[cfg(feature = "archive")]
+            archive: archive::ArchiveBrowser::default(),
+            #[cfg(feature = "database")]
+            database,
+            #[cfg(feature = "database")]
+            database_pending: false,
+            #[cfg(feature = "database")]
+            rom_title: None,
+            #[cfg(feature = "gamepad")]
+            gamepad: gamepad::GamepadInput::default(),
             track_pc: true,
-            background_color: Color32::BLACK,
-            fill_color: Color32::WHITE,
+            ram_heatmap: false,
+            ram_edit: None,
+            settings: Settings::default(),
+            suspended: false,
+            turbo: false,
+            events,
+            input_available: true,
+            waiting_for_input: false,
+            state_transfer: None,
+            state_path: String::new(),
+            state_error: None,
+            autosave: Autosave::load(),
+            resume_error: None,
+            saved_state: None,
+            title: String::new(),
+            colors: palette(Color32::BLACK, Color32::WHITE),
+            display_filter: DisplayFilter::default(),
         }
     }
 }
 
+/// Read the keyboard and update the interpreter's keys. `gamepad` holds the keys pressed and
+/// released on gamepads, which are combined with the keyboard's.
+fn read_keypad(
+    keypad: &mut KeypadInput,
+    driver: &EmulatorDriver,
+    input: &egui::InputState,
+    gamepad: ([bool; 16], Option<u8>),
+) {
+    // We don't want to press keys on the interpreter while using emulator shortcuts
+    if input.modifiers.any() {
+        return;
+    }
+    let (mut keys, mut released) = keypad.read(input);
+    for (key, pressed) in keys.iter_mut().zip(gamepad.0) {
+        *key |= pressed;
+    }
+    released = released.or(gamepad.1);
+
+    // Save the last pressed and released key if executing the Fx0A instruction.
+    if let Some(key) = released {
+        driver.send(DriverCommand::ReleaseKey(key));
+    }
+
+    driver.send(DriverCommand::SetKeys(keys));
+    driver.send(DriverCommand::SetKeys2(keypad.read_second()));
+}
+
+/// Write a crash dump of the halted interpreter into the working directory.
+fn write_crash_dump(interpreter: &Chip8, rom: &[u8]) {
+    let time = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let path = format!("crash_{time}.txt");
+    match std::fs::write(&path, interpreter.crash_dump(rom)) {
+        Ok(()) => println!("Wrote crash dump to {path}"),
+        Err(e) => println!("Could not write crash dump to {path}: {e}"),
+    }
+}
+
+impl Emulator {
+    /// Get the debugging setup of the current ROM.
+    fn debug_session(&self) -> DebugSession {
+        DebugSession {
+            watches: self
+                .watches
+                .watches
+                .iter()
+                .map(|watch| (watch.source.clone(), watch.break_on_true))
+                .collect(),
+            windows: self.windows,
+        }
+    }
+
+    /// Replace the watches and open windows with those of a saved session.
+    fn restore_session(&mut self, session: DebugSession) {
+        self.watches.watches = session
+            .watches
+            .into_iter()
+            .filter_map(|(source, break_on_true)| {
+                let expression = Expression::parse(&source).ok()?;
+                Some(Watch {
+                    source,
+                    expression,
+                    break_on_true,
+                })
+            })
+            .collect();
+        self.windows = session.windows;
+    }
+}
+
 impl eframe::App for Emulator {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        let mut interpreter = self.interpreter.lock().unwrap();
+        // The browser has no interpreter thread, so the interpreter runs here
+        #[cfg(target_arch = "wasm32")]
+        self.driver.poll();
 
-        // read the keyboard and update the interpreter's keys
-        ctx.input_mut(|i| {
-            // Emulator hotkeys
-            if interpreter.is_running() {
-                if i.consume_key(Modifiers::NONE, Key::Space) {
-                    interpreter.stop();
-                }
-            } else {
-                // Controls
-                if i.consume_key(Modifiers::NONE, Key::Space) {
-                    interpreter.start();
-                } else if i.consume_key(Modifiers::SHIFT, Key::Period) {
-                    for _ in interpreter.frame_cycle..interpreter.execution_speed {
-                        interpreter.execute_cycle();
+        // Stop burning CPU while nobody can see the display
+        let minimized = ctx.input(|i| i.viewport().minimized.unwrap_or(false));
+        let suspend = minimized && !self.settings.run_in_background;
+        if suspend != self.suspended {
+            self.suspended = suspend;
+            self.driver.send(DriverCommand::Suspend(suspend));
+        }
+
+        // Go on debugging where it was left when a ROM is loaded again
+        let rom_hash = session::rom_hash(&self.rom);
+        if rom_hash != self.sessions.rom_hash() {
+            let current = self.debug_session();
+            let restored = self.sessions.switch(rom_hash, current);
+            self.restore_session(restored.unwrap_or(DebugSession {
+                watches: Vec::new(),
+                windows: self.windows,
+            }));
+            if let Err(e) = self.sessions.save() {
+                println!("Could not save the debugging sessions: {e}");
+            }
+        }
+
+        let mut interpreter = self.driver.lock();
+        // Keep the break conditions when the interpreter is replaced, for example by picking a
+        // variant or importing a state
+        let conditions = self.watches.break_watches();
+        if interpreter.breakpoints.conditions() != conditions {
+            interpreter.breakpoints.set_conditions(conditions);
+        }
+
+        for event in self.events.try_iter() {
+            match event {
+                DriverEvent::WaitingForInput => self.waiting_for_input = true,
+                DriverEvent::Stopped(Some(_)) => {
+                    if self.settings.crash_dumps {
+                        write_crash_dump(&interpreter, &self.rom);
                     }
-                    interpreter.tick_frame();
-                } else if i.consume_key(Modifiers::NONE, Key::Period) {
-                    interpreter.execute_cycle();
-                    if interpreter.frame_cycle == interpreter.execution_speed {
-                        interpreter.tick_frame();
+                    if self.settings.history_on_halt {
+                        self.windows.history = true;
                     }
-                } else if i.consume_key(Modifiers::CTRL, Key::R) {
-                    interpreter.reset();
-                } else if i.consume_key(Modifiers::CTRL, Key::O) {
-                    self.show_load_modal = true;
                 }
+                _ => {}
             }
-            // Utility
-            if i.consume_key(Modifiers::CTRL, Key::P) {
-                self.show_rom_window = true;
-            } else if i.consume_key(Modifiers::CTRL, Key::D) {
-                self.show_display_settings = true;
-            } else if i.consume_key(Modifiers::CTRL, Key::S) {
-                interpreter.sound_on = !interpreter.sound_on;
-            }
+        }
+        if !interpreter.is_waiting_for_key() {
+            self.waiting_for_input = false;
+        }
 
-            // We don't want to press keys on the interpreter while using emulator shortcuts
-            if !i.modifiers.any() {
-                // Save the last pressed and released key if executing the Fx0A instruction.
-                if interpreter.is_waiting_for_key() {
-                    if i.key_released(egui::Key::X) {
-                        interpreter.save_awaited_key(0);
-                    }
-                    if i.key_released(egui::Key::Num1) {
-                        interpreter.save_awaited_key(1);
-                    }
-                    if i.key_released(egui::Key::Num2) {
-                        interpreter.save_awaited_key(2);
-                    }
-                    if i.key_released(egui::Key::Num3) {
-                        interpreter.save_awaited_key(3);
-                    }
-                    if i.key_released(egui::Key::Q) {
-                        interpreter.save_awaited_key(4);
-                    }
-                    if i.key_released(egui::Key::W) {
-                        interpreter.save_awaited_key(5);
-                    }
-                    if i.key_released(egui::Key::E) {
-                        interpreter.save_awaited_key(6);
-                    }
-                    if i.key_released(egui::Key::A) {
-                        interpreter.save_awaited_key(7);
-                    }
-                    if i.key_released(egui::Key::S) {
-                        interpreter.save_awaited_key(8);
-                    }
-                    if i.key_released(egui::Key::D) {
-                        interpreter.save_awaited_key(9);
-                    }
-                    if i.key_released(egui::Key::Z) {
-                        interpreter.save_awaited_key(10);
-                    }
-                    if i.key_released(egui::Key::C) {
-                        interpreter.save_awaited_key(11);
-                    }
-                    if i.key_released(egui::Key::Num4) {
-                        interpreter.save_awaited_key(12);
-                    }
-                    if i.key_released(egui::Key::R) {
-                        interpreter.save_awaited_key(13);
-                    }
-                    if i.key_released(egui::Key::F) {
-                        interpreter.save_awaited_key(14);
-                    }
-                    if i.key_released(egui::Key::V) {
-                        interpreter.save_awaited_key(15);
+        #[cfg(feature = "gamepad")]
+        let gamepad = {
+            self.gamepad.select_rom(&self.rom);
+            self.gamepad.read()
+        };
+        #[cfg(not(feature = "gamepad"))]
+        let gamepad = ([false; 16], None);
+
+        // read the keyboard and update the interpreter's keys
+        let mut copy_summary = false;
+        let mut turbo = false;
+        let mut focused = false;
+        // The save slots follow the loaded ROM
+        self.slots.set_rom(self.loaded_rom_path.as_deref());
+        let mut slot_loaded = false;
+        ctx.input_mut(|i| {
+            focused = i.focused;
+            for hotkey in Hotkey::ALL {
+                let (modifiers, key) = hotkey.shortcut(self.settings.keypad.layout);
+                if hotkey.is_held() {
+                    turbo = i.modifiers.matches_logically(modifiers) && i.key_down(key);
+                    continue;
+                }
+                if (hotkey.paused_only() && interpreter.is_running())
+                    || !i.consume_key(modifiers, key)
+                {
+                    continue;
+                }
+                match hotkey {
+                    Hotkey::RunPause if interpreter.is_running() => {
+                        self.driver.send(DriverCommand::Stop)
                     }
+                    Hotkey::RunPause => self.driver.send(DriverCommand::Start),
+                    Hotkey::StepFrame => self.driver.send(DriverCommand::StepFrame),
+                    Hotkey::StepCycle => self.driver.send(DriverCommand::StepCycle),
+                    Hotkey::StepBackFrame => self.driver.send(DriverCommand::StepBackFrame),
+                    Hotkey::StepBackCycle => self.driver.send(DriverCommand::StepBackCycle),
+                    Hotkey::Reset => self.driver.send(DriverCommand::Reset),
+                    Hotkey::LoadRom => self.show_load_modal = true,
+                    Hotkey::ShowRom => self.windows.rom = true,
+                    Hotkey::DisplaySettings => self.windows.display_settings = true,
+                    Hotkey::History => self.windows.history = true,
+                    Hotkey::CopySummary => copy_summary = true,
+                    Hotkey::ToggleSound => interpreter.sound_on = !interpreter.sound_on,
+                    Hotkey::Shortcuts => self.windows.shortcuts = !self.windows.shortcuts,
+                    Hotkey::Turbo => {}
+                }
+            }
+            for (slot, key) in SLOT_KEYS.into_iter().enumerate() {
+                if i.consume_key(Modifiers::CTRL, key) {
+                    self.slots.save(slot, &interpreter);
+                } else if i.consume_key(Modifiers::SHIFT, key) {
+                    slot_loaded |= self.slots.load(slot, &mut interpreter);
                 }
+            }
 
-                interpreter.set_keys([
-                    i.key_down(egui::Key::X),    // 0
-                    i.key_down(egui::Key::Num1), // 1
-                    i.key_down(egui::Key::Num2), // 2
-                    i.key_down(egui::Key::Num3), // 3
-                    i.key_down(egui::Key::Q),    // 4
-                    i.key_down(egui::Key::W),    // 5
-                    i.key_down(egui::Key::E),    // 6
-                    i.key_down(egui::Key::A),    // 7
-                    i.key_down(egui::Key::S),    // 8
-                    i.key_down(egui::Key::D),    // 9
-                    i.key_down(egui::Key::Z),    // A
-                    i.key_down(egui::Key::C),    // B
-                    i.key_down(egui::Key::Num4), // C
-                    i.key_down(egui::Key::R),    // D
-                    i.key_down(egui::Key::F),    // E
-                    i.key_down(egui::Key::V),    // F
-                ]);
+            // The popped out display reads the keypad while it has focus
+            if !self.windows.pop_out_display || i.focused {
+                read_keypad(&mut self.settings.keypad, &self.driver, i, gamepad);
             }
         });
+        // The context can't be used while reading input
+        if copy_summary {
+            ctx.copy_text(state_summary(&interpreter));
+        }
+        if turbo != self.turbo {
+            self.turbo = turbo;
+            self.driver.send(DriverCommand::SetTurbo(turbo));
+        }
 
-        draw_menu(
+        let frame_pacing = self.settings.frame_pacing;
+        let menu_action = draw_menu(
             &mut interpreter,
             ctx,
-            &mut self.show_rom_window,
-            &mut self.show_display_settings,
+            &mut self.windows,
+            &mut self.state_transfer,
+            &mut self.settings,
+            &mut self.quirk_test,
+            &mut self.slots,
+            &mut self.recent,
         );
+        slot_loaded |= menu_action == Some(MenuAction::SlotLoaded);
+        if self.settings.frame_pacing != frame_pacing {
+            self.driver
+                .send(DriverCommand::SetPacing(self.settings.frame_pacing));
+        }
         draw_display_settings(
             ctx,
-            &mut self.background_color,
-            &mut self.fill_color,
-            &mut self.show_display_settings,
+            &mut self.colors,
+            &mut self.display_filter,
+            &mut self.windows.display_settings,
+        );
+        draw_ram(
+            &mut self.track_pc,
+            &mut self.ram_heatmap,
+            &mut self.ram_edit,
+            &mut interpreter,
+            ctx,
         );
-        draw_ram(&mut self.track_pc, &interpreter, ctx);
-        draw_registers_and_keypad(&interpreter, ctx);
+        if self.windows.disassembly {
+            draw_disassembly(&mut interpreter, ctx);
+        }
+        draw_registers_and_keypad(&interpreter, &mut self.settings.keypad, ctx);
+
+        if self.windows.rom {
+            draw_rom(
+                &mut self.rom,
+                interpreter.start_address,
+                &mut self.windows.rom,
+                ctx,
+            );
+        }
+        #[cfg(feature = "archive")]
+        {
+            if self.windows.archive {
+                let can_load = !interpreter.is_running() && !self.archive.is_busy();
+                draw_archive(&mut self.archive, can_load, &mut self.windows.archive, ctx);
+            }
+            if let Some((program, rom)) = self.archive.poll() {
+                // Configure the interpreter like the program's metadata says, but keep the emulator settings
+                let mut chip8 = program.configure();
+                chip8.sound_on = interpreter.sound_on;
+                chip8.vip_timing = interpreter.vip_timing;
+                chip8.set_font_set(interpreter.font_set());
+                if let Some(font) = interpreter.custom_font() {
+                    let _ = chip8.load_font(font);
+                }
+                *interpreter = chip8;
 
-        if self.show_rom_window {
-            draw_rom(&mut self.rom, &mut self.show_rom_window, ctx);
+                self.rom = rom;
+                self.loaded_rom_path = None;
+                self.saved_state = None;
+                self.driver.send(DriverCommand::LoadRom(self.rom.clone()));
+            }
+            if self.archive.is_busy() {
+                ctx.request_repaint();
+            }
+        }
+        let colors = self.display_filter.apply(self.colors);
+        if self.windows.comparison {
+            draw_comparison(
+                &interpreter,
+                &self.driver,
+                &mut self.comparison,
+                colors,
+                &mut self.windows.comparison,
+                ctx,
+            );
+        }
+        if self.windows.bisect {
+            draw_bisect(
+                &interpreter,
+                &self.rom,
+                &mut self.bisect,
+                &mut self.windows.bisect,
+                ctx,
+            );
+        }
+        if self.windows.lockstep {
+            draw_lockstep(
+                &interpreter,
+                &self.rom,
+                &mut self.lockstep,
+                &mut self.windows.lockstep,
+                ctx,
+            );
+        }
+        let mut started = None;
+        if self.windows.attract {
+            started = draw_attract(
+                &mut interpreter,
+                &mut self.attract,
+                &mut self.attract_view,
+                &mut self.windows.attract,
+                ctx,
+            );
+        }
+        if let Some(attract) = &mut self.attract {
+            started = started.or_else(|| attract.update(&mut interpreter));
+            if let Some(rom) = started {
+                self.rom = rom;
+                self.loaded_rom_path = Some(attract.current().rom.display().to_string());
+                self.saved_state = None;
+                self.driver.send(DriverCommand::SetRom(self.rom.clone()));
+            } else if !interpreter.is_running() {
+                // None of the ROMs could be loaded
+                self.attract = None;
+            }
+        }
+        if self.windows.history {
+            draw_history(&interpreter, &mut self.windows.history, ctx);
+        }
+        if self.windows.watches {
+            draw_watches(
+                &mut interpreter,
+                &mut self.watches,
+                &mut self.windows.watches,
+                ctx,
+            );
+        }
+        if self.windows.sound {
+            draw_sound(&interpreter, &mut self.windows.sound, ctx);
+        }
+        #[cfg(feature = "gamepad")]
+        if self.windows.gamepad {
+            draw_gamepad(&mut self.gamepad, &mut self.windows.gamepad, ctx);
+        }
+        if self.windows.memory_log {
+            draw_memory_log(
+                &mut interpreter,
+                &mut self.memory_log,
+                &mut self.windows.memory_log,
+                ctx,
+            );
+        }
+        if self.windows.shortcuts {
+            draw_shortcuts(
+                self.settings.keypad.layout,
+                &mut self.windows.shortcuts,
+                ctx,
+            );
         }
-        if self.show_load_modal {
-            draw_load_modal(
+        let mut rom_loaded = self.show_load_modal
+            && draw_load_modal(
+                &self.driver,
                 &mut interpreter,
                 ctx,
                 &mut self.show_load_modal,
                 &mut self.rom,
-                &mut self.rom_path,
-                &mut self.load_error,
-            )
+                &mut self.load,
+                &mut self.loaded_rom_path,
+            );
+        if let Some(MenuAction::OpenRom(path)) = menu_action {
+            match read_rom_file(&path, &interpreter) {
+                Ok((rom, symbols)) => {
+                    self.rom = rom;
+                    interpreter.symbols = symbols;
+                    self.driver.send(DriverCommand::LoadRom(self.rom.clone()));
+                    self.loaded_rom_path = Some(path);
+                    rom_loaded = true;
+                }
+                // Show why in the load ROM modal, where the path can be corrected
+                Err(e) => {
+                    self.load.rom_path = path;
+                    self.load.error = Some(e);
+                    self.show_load_modal = true;
+                }
+            }
         }
-        draw_variant_specifics(&mut interpreter, &self.rom, ctx);
-        draw_controls(
+        if rom_loaded {
+            self.saved_state = None;
+            if let Some(path) = &self.loaded_rom_path {
+                self.recent.add(path);
+                if let Err(e) = self.recent.save() {
+                    println!("Could not save the recent ROMs: {e}");
+                }
+            }
+            #[cfg(feature = "database")]
+            {
+                self.database_pending = self.settings.use_database;
+            }
+        }
+        #[cfg(feature = "hot-reload")]
+        {
+            let watched = self
+                .loaded_rom_path
+                .as_deref()
+                .filter(|_| self.settings.reload_on_change);
+            self.rom_watcher.watch(watched);
+            if let (true, Some(path)) = (self.rom_watcher.poll(), &self.loaded_rom_path) {
+                match read_rom_file(path, &interpreter) {
+                    Ok((rom, symbols)) => {
+                        self.rom = rom;
+                        interpreter.symbols = symbols;
+                        self.saved_state = None;
+                        self.driver.send(DriverCommand::LoadRom(self.rom.clone()));
+                        // The problem was fixed
+                        if self.load.error.is_some() && self.load.rom_path == *path {
+                            self.show_load_modal = false;
+                            self.load = LoadView::default();
+                        }
+                    }
+                    // Show why in the load ROM modal, for example a mistake in Octo source
+                    Err(e) => {
+                        self.load.rom_path = path.clone();
+                        self.load.error = Some(e);
+                        self.show_load_modal = true;
+                    }
+                }
+            }
+        }
+        #[cfg(feature = "database")]
+        {
+            if self.settings.use_database {
+                self.database.load();
+            }
+            self.database.poll();
+            if self.database_pending && !self.database.is_busy() {
+                self.database_pending = false;
+                if let Some(info) = self.database.lookup(&self.rom) {
+                    // Configure the interpreter like the database says, but keep the emulator settings
+                    let mut chip8 = info.configure();
+                    chip8.sound_on = interpreter.sound_on;
+                    chip8.vip_timing = interpreter.vip_timing;
+                    if info.font_set.is_none() {
+                        chip8.set_font_set(interpreter.font_set());
+                        if let Some(font) = interpreter.custom_font() {
+                            let _ = chip8.load_font(font);
+                        }
+                    }
+                    chip8.symbols = std::mem::take(&mut interpreter.symbols);
+                    *interpreter = chip8;
+
+                    if let Some(colors) = info.colors {
+                        self.colors = colors;
+                    }
+                    self.rom_title = Some((session::rom_hash(&self.rom), info.title));
+                    self.driver.send(DriverCommand::LoadRom(self.rom.clone()));
+                }
+            }
+            if self.database_pending {
+                ctx.request_repaint();
+            }
+        }
+        let transferred = draw_state_modal(
             &mut interpreter,
+            ctx,
+            &mut self.state_transfer,
+            &mut self.state_path,
+            &mut self.state_error,
+            self.colors,
+        );
+        let resumed = draw_resume_modal(
+            &mut interpreter,
+            &self.driver,
+            ctx,
+            &mut self.autosave,
             &mut self.rom,
+            &mut self.loaded_rom_path,
+            &mut self.resume_error,
+        );
+        slot_loaded |= self.windows.slots
+            && draw_save_slots(
+                &mut interpreter,
+                &mut self.slots,
+                colors,
+                &mut self.windows.slots,
+                ctx,
+            );
+        if transferred || resumed || slot_loaded {
+            self.saved_state = Some(interpreter.save_state());
+        }
+        draw_variant_specifics(
+            &mut interpreter,
+            &self.rom,
+            self.settings.verify_quirks,
+            &mut self.quirk_test,
+            ctx,
+        );
+        draw_controls(
+            &mut interpreter,
+            &self.driver,
             &mut self.show_load_modal,
+            &mut self.settings.speed_in_ips,
             ctx,
         );
 
+        // Play the interpreter's audio pattern
+        *self.tone.lock().unwrap() = Tone::of(&interpreter);
+        #[cfg(target_arch = "wasm32")]
+        self.buzzer.update();
+
         // draw the display
-        egui::CentralPanel::default().show(ctx, |ui| {
-            self.screen.set(
-                interpreter.get_display(self.background_color, self.fill_color),
-                TextureOptions::LINEAR,
+        self.screen
+            .set(interpreter.get_display(colors), TextureOptions::LINEAR);
+        if self.windows.pop_out_display {
+            egui::CentralPanel::default().show(ctx, |ui| {
+                ui.centered_and_justified(|ui| {
+                    if ui.button("Dock display").clicked() {
+                        self.windows.pop_out_display = false;
+                    }
+                });
+            });
+
+            let mut docked = false;
+            ctx.show_viewport_immediate(
+                egui::ViewportId::from_hash_of("display"),
+                egui::ViewportBuilder::default()
+                    .with_title("E-CHIP display")
+                    .with_inner_size([640.0, 320.0]),
+                |ctx, _| {
+                    egui::CentralPanel::default().show(ctx, |ui| {
+                        draw_screen(
+                            ui,
+                            &self.screen,
+                            &interpreter,
+                            self.turbo,
+                            self.waiting_for_input,
+                            true,
+                        );
+                    });
+                    ctx.input(|i| {
+                        focused |= i.focused;
+                        if i.focused {
+                            read_keypad(&mut self.settings.keypad, &self.driver, i, gamepad);
+                        }
+                        docked = i.viewport().close_requested();
+                    });
+                },
             );
-            ui.add_space(-5.0);
-            if let Some(msg) = &interpreter.halt_message {
-                ui.with_layout(
-                    egui::Layout::top_down_justified(egui::Align::Center),
-                    |ui| {
-                        ui.colored_label(Color32::RED, format!("Halted: {}", msg));
-                    },
+            if docked {
+                self.windows.pop_out_display = false;
+            }
+        } else {
+            egui::CentralPanel::default().show(ctx, |ui| {
+                draw_screen(
+                    ui,
+                    &self.screen,
+                    &interpreter,
+                    self.turbo,
+                    self.waiting_for_input,
+                    false,
                 );
+            });
+        }
+
+        // The keyboard only reaches the interpreter while one of the windows has focus
+        #[cfg(feature = "gamepad")]
+        let focused = focused || self.gamepad.is_connected();
+        if focused != self.input_available {
+            self.input_available = focused;
+            self.driver.send(DriverCommand::SetInputAvailable(focused));
+        }
+
+        // Show what is running in the title bar
+        let unsaved = self
+            .saved_state
+            .as_ref()
+            .is_some_and(|state| *state != interpreter.save_state());
+        #[cfg(feature = "gamepad")]
+        let unsaved = unsaved || self.gamepad.has_unsaved_changes();
+        // The title from the database is kept until another ROM is loaded
+        #[cfg(feature = "database")]
+        let rom_title = self
+            .rom_title
+            .as_ref()
+            .filter(|(hash, _)| *hash == session::rom_hash(&self.rom))
+            .map(|(_, title)| title.as_str());
+        #[cfg(not(feature = "database"))]
+        let rom_title = None;
+        let title = window_title(
+            &interpreter,
+            self.loaded_rom_path.as_deref(),
+            rom_title,
+            unsaved,
+        );
+        if title != self.title {
+            ctx.send_viewport_cmd(egui::ViewportCommand::Title(title.clone()));
+            self.title = title;
+        }
+
+        if ctx.input(|i| i.viewport().close_requested()) {
+            self.sessions.remember(self.debug_session());
+            if let Err(e) = self.sessions.save() {
+                println!("Could not save the debugging sessions: {e}");
             }
-            ui.centered_and_justified(|ui| ui.image((self.screen.id(), self.screen.size_vec2())));
-        });
+        }
+
+        // Save the machine so it can be resumed on the next launch
+        if ctx.input(|i| i.viewport().close_requested()) && self.settings.save_on_exit {
+            // Don't overwrite the save if the user did not decide whether to resume it yet
+            if let (Some(rom_path), None) = (&self.loaded_rom_path, &self.autosave) {
+                let autosave = Autosave {
+                    rom_path: rom_path.clone(),
+                    state: interpreter.save_state(),
+                };
+                if let Err(e) = autosave.save() {
+                    println!("Could not save the machine: {e}");
+                }
+            }
+        }
 
         if interpreter.is_running() {
+            if self.settings.frame_pacing == FramePacing::Vsync {
+                self.driver.send(DriverCommand::AdvanceFrame);
+            }
+            ctx.request_repaint();
+        }
+        // Apply the commands sent while drawing
+        #[cfg(target_arch = "wasm32")]
+        if self.driver.has_pending_commands() {
             ctx.request_repaint();
         }
     }
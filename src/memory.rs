@@ -1,12 +1,118 @@
 /// The memory of the CHIP-8.
-#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Memory {
-    /// 4KB of RAM. 0x000-0x1FF is reserved for the interpreter.
-    pub ram: [u8; 4096],
+    /// 4KB of RAM, or 64KB for XO-CHIP. 0x000-0x1FF is reserved for the interpreter.
+    pub ram: Vec<u8>,
+    /// The small font stored in reserved memory.
+    pub font_set: FontSet,
+    /// A font loaded from a file, stored in reserved memory in place of the font set: 80 bytes of
+    /// small font, optionally followed by 160 bytes of large font.
+    pub custom_font: Option<Vec<u8>>,
 }
 
-/// The text font stored in reserved memory.
-const CHIP8_FONT: [u8; 16 * 5] = [
+/// The small hex fonts of different interpreters. Programs that draw digits with `Fx29` look like
+/// they did on the original machine with its font.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum FontSet {
+    /// The font of Octo, used by most modern interpreters.
+    #[default]
+    Octo,
+    /// The font of the CHIP-8 interpreter of the COSMAC VIP.
+    Vip,
+    /// The 4x5 font of the DREAM 6800.
+    Dream6800,
+    /// The 4x5 font of the ETI-660.
+    Eti660,
+}
+
+impl FontSet {
+    /// All font sets, in the order they are shown in the settings.
+    pub const ALL: [FontSet; 4] = [
+        FontSet::Octo,
+        FontSet::Vip,
+        FontSet::Dream6800,
+        FontSet::Eti660,
+    ];
+
+    /// The name shown in the settings.
+    pub const fn name(&self) -> &'static str {
+        match self {
+            FontSet::Octo => "Octo",
+            FontSet::Vip => "COSMAC VIP",
+            FontSet::Dream6800 => "DREAM 6800",
+            FontSet::Eti660 => "ETI-660",
+        }
+    }
+
+    /// The sprites of the digits 0 to F, 5 bytes each.
+    pub const fn sprites(&self) -> &'static [u8; SMALL_FONT_SIZE] {
+        match self {
+            FontSet::Octo => &CHIP8_FONT,
+            FontSet::Vip => &VIP_FONT,
+            FontSet::Dream6800 => &DREAM_6800_FONT,
+            FontSet::Eti660 => &ETI_660_FONT,
+        }
+    }
+}
+
+/// Why a program could not be loaded. See [`Chip8::load_program`](crate::Chip8::load_program).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum LoadError {
+    /// The program has no bytes.
+    Empty,
+    /// The program does not fit in memory from the start address.
+    TooLarge { size: usize, capacity: usize },
+}
+
+impl std::fmt::Display for LoadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LoadError::Empty => write!(f, "the program is empty"),
+            LoadError::TooLarge { size, capacity } => write!(
+                f,
+                "the program is {size} bytes, but only {capacity} fit in memory"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for LoadError {}
+
+/// Why a font could not be loaded. See [`Chip8::load_font`](crate::Chip8::load_font).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum FontError {
+    /// The font is neither a small font nor a small and a large font.
+    WrongSize(usize),
+}
+
+impl std::fmt::Display for FontError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FontError::WrongSize(size) => write!(
+                f,
+                "the font is {size} bytes, but a font is {SMALL_FONT_SIZE} bytes, or {} with a large font",
+                SMALL_FONT_SIZE + BIG_FONT_SIZE
+            ),
+        }
+    }
+}
+
+impl std::error::Error for FontError {}
+
+/// Where the small font starts in reserved memory. `Fx29` points at its sprites.
+pub(crate) const FONT_ADDRESS: u16 = 0x000;
+/// Where the large font starts in reserved memory, right after the small font, like Octo. `Fx30`
+/// points at its sprites.
+pub(crate) const BIG_FONT_ADDRESS: u16 = FONT_ADDRESS + 16 * 5;
+/// The size of a small font: 16 digits of 5 bytes.
+pub(crate) const SMALL_FONT_SIZE: usize = 16 * 5;
+/// The size of a large font: 16 digits of 10 bytes.
+pub(crate) const BIG_FONT_SIZE: usize = 16 * 10;
+
+/// The small font of Octo.
+const CHIP8_FONT: [u8; SMALL_FONT_SIZE] = [
     0xF0, 0x90, 0x90, 0x90, 0xF0, //0
     0x20, 0x60, 0x20, 0x20, 0x70, //1
     0xF0, 0x10, 0xF0, 0x80, 0xF0, //2
@@ -25,7 +131,68 @@ const CHIP8_FONT: [u8; 16 * 5] = [
     0xF0, 0x80, 0xF0, 0x80, 0x80, //F
 ];
 
-const SCHIP_BIG_FONT: [u8; 10 * 10] = [
+/// The small font of the COSMAC VIP.
+const VIP_FONT: [u8; SMALL_FONT_SIZE] = [
+    0xF0, 0x90, 0x90, 0x90, 0xF0, //0
+    0x60, 0x20, 0x20, 0x20, 0x70, //1
+    0xF0, 0x10, 0xF0, 0x80, 0xF0, //2
+    0xF0, 0x10, 0xF0, 0x10, 0xF0, //3
+    0xA0, 0xA0, 0xF0, 0x20, 0x20, //4
+    0xF0, 0x80, 0xF0, 0x10, 0xF0, //5
+    0xF0, 0x80, 0xF0, 0x90, 0xF0, //6
+    0xF0, 0x10, 0x10, 0x10, 0x10, //7
+    0xF0, 0x90, 0xF0, 0x90, 0xF0, //8
+    0xF0, 0x90, 0xF0, 0x10, 0xF0, //9
+    0xF0, 0x90, 0xF0, 0x90, 0x90, //A
+    0xF0, 0x50, 0x70, 0x50, 0xF0, //B
+    0xF0, 0x80, 0x80, 0x80, 0xF0, //C
+    0xF0, 0x50, 0x50, 0x50, 0xF0, //D
+    0xF0, 0x80, 0xF0, 0x80, 0xF0, //E
+    0xF0, 0x80, 0xF0, 0x80, 0x80, //F
+];
+
+/// The small font of the DREAM 6800, 3 pixels wide.
+const DREAM_6800_FONT: [u8; SMALL_FONT_SIZE] = [
+    0xE0, 0xA0, 0xA0, 0xA0, 0xE0, //0
+    0x40, 0x40, 0x40, 0x40, 0x40, //1
+    0xE0, 0x20, 0xE0, 0x80, 0xE0, //2
+    0xE0, 0x20, 0xE0, 0x20, 0xE0, //3
+    0x80, 0xA0, 0xA0, 0xE0, 0x20, //4
+    0xE0, 0x80, 0xE0, 0x20, 0xE0, //5
+    0xE0, 0x80, 0xE0, 0xA0, 0xE0, //6
+    0xE0, 0x20, 0x20, 0x20, 0x20, //7
+    0xE0, 0xA0, 0xE0, 0xA0, 0xE0, //8
+    0xE0, 0xA0, 0xE0, 0x20, 0xE0, //9
+    0xE0, 0xA0, 0xE0, 0xA0, 0xA0, //A
+    0xC0, 0xA0, 0xE0, 0xA0, 0xC0, //B
+    0xE0, 0x80, 0x80, 0x80, 0xE0, //C
+    0xC0, 0xA0, 0xA0, 0xA0, 0xC0, //D
+    0xE0, 0x80, 0xE0, 0x80, 0xE0, //E
+    0xE0, 0x80, 0xC0, 0x80, 0x80, //F
+];
+
+/// The small font of the ETI-660, 3 pixels wide.
+const ETI_660_FONT: [u8; SMALL_FONT_SIZE] = [
+    0xE0, 0xA0, 0xA0, 0xA0, 0xE0, //0
+    0x20, 0x20, 0x20, 0x20, 0x20, //1
+    0xE0, 0x20, 0xE0, 0x80, 0xE0, //2
+    0xE0, 0x20, 0xE0, 0x20, 0xE0, //3
+    0xA0, 0xA0, 0xE0, 0x20, 0x20, //4
+    0xE0, 0x80, 0xE0, 0x20, 0xE0, //5
+    0xE0, 0x80, 0xE0, 0xA0, 0xE0, //6
+    0xE0, 0x20, 0x20, 0x20, 0x20, //7
+    0xE0, 0xA0, 0xE0, 0xA0, 0xE0, //8
+    0xE0, 0xA0, 0xE0, 0x20, 0xE0, //9
+    0xE0, 0xA0, 0xE0, 0xA0, 0xA0, //A
+    0x80, 0x80, 0xE0, 0xA0, 0xE0, //B
+    0xE0, 0x80, 0x80, 0x80, 0xE0, //C
+    0x20, 0x20, 0xE0, 0xA0, 0xE0, //D
+    0xE0, 0x80, 0xE0, 0x80, 0xE0, //E
+    0xE0, 0x80, 0xC0, 0x80, 0x80, //F
+];
+
+/// The large font of SUPER-CHIP 1.1 stored in reserved memory, 8x10 pixels per digit.
+const BIG_FONT: [u8; BIG_FONT_SIZE] = [
     0x3C, 0x7E, 0xE7, 0xC3, 0xC3, 0xC3, 0xC3, 0xE7, 0x7E, 0x3C, //0
     0x18, 0x38, 0x58, 0x18, 0x18, 0x18, 0x18, 0x18, 0x18, 0x3C, //1
     0x3E, 0x7F, 0xC3, 0x06, 0x0C, 0x18, 0x30, 0x60, 0xFF, 0xFF, //2
@@ -36,35 +203,83 @@ const SCHIP_BIG_FONT: [u8; 10 * 10] = [
     0xFF, 0xFF, 0x03, 0x06, 0x0C, 0x18, 0x30, 0x60, 0x60, 0x60, //7
     0x3C, 0x7E, 0xC3, 0xC3, 0x7E, 0x7E, 0xC3, 0xC3, 0x7E, 0x3C, //8
     0x3C, 0x7E, 0xC3, 0xC3, 0x7F, 0x3F, 0x03, 0x03, 0x3E, 0x7C, //9
-]; // No hex letters
+    // SUPER-CHIP has no hex letters, XO-CHIP does
+    0x3C, 0x7E, 0xE7, 0xC3, 0xFF, 0xFF, 0xC3, 0xC3, 0xC3, 0xC3, //A
+    0xFC, 0xFE, 0xC3, 0xC3, 0xFE, 0xFE, 0xC3, 0xC3, 0xFE, 0xFC, //B
+    0x3C, 0x7E, 0xE7, 0xC0, 0xC0, 0xC0, 0xC0, 0xE7, 0x7E, 0x3C, //C
+    0xFC, 0xFE, 0xC7, 0xC3, 0xC3, 0xC3, 0xC3, 0xC7, 0xFE, 0xFC, //D
+    0xFF, 0xFF, 0xC0, 0xC0, 0xFC, 0xFC, 0xC0, 0xC0, 0xFF, 0xFF, //E
+    0xFF, 0xFF, 0xC0, 0xC0, 0xFC, 0xFC, 0xC0, 0xC0, 0xC0, 0xC0, //F
+];
 
 impl Memory {
-    /// Create memory with the default font.
+    /// Create 4KB of memory with the default font.
     #[inline]
     pub fn new() -> Memory {
-        let mut mem = Memory { ram: [0; 4096] };
-        mem.ram[0..(16 * 5)].copy_from_slice(&CHIP8_FONT); // Save font
-        mem.ram[(16 * 5)..(16 * 5) + (10 * 10)].copy_from_slice(&SCHIP_BIG_FONT);
+        let mut mem = Memory {
+            ram: vec![0; 4096],
+            font_set: FontSet::default(),
+            custom_font: None,
+        };
+        mem.reset();
+        mem
+    }
+
+    /// Create 64KB of memory with the default font. XO-CHIP.
+    #[inline]
+    pub fn big() -> Memory {
+        let mut mem = Memory {
+            ram: vec![0; 0x10000],
+            font_set: FontSet::default(),
+            custom_font: None,
+        };
+        mem.reset();
         mem
     }
 
     /// Clear all non-reserved memory.
     #[inline]
     pub fn reset(&mut self) {
-        self.ram = [0; 4096];
-        self.ram[0..(16 * 5)].copy_from_slice(&CHIP8_FONT); // Save font
-        self.ram[(16 * 5)..(16 * 5) + (10 * 10)].copy_from_slice(&SCHIP_BIG_FONT);
+        self.ram.fill(0);
+        self.write_fonts();
+    }
+
+    /// Write the small font of the font set and the large font to reserved memory, or the custom
+    /// font if there is one, leaving the rest of memory as it is. A custom font without a large font
+    /// keeps the usual large font.
+    #[inline]
+    pub fn write_fonts(&mut self) {
+        let font = FONT_ADDRESS as usize;
+        let big_font = BIG_FONT_ADDRESS as usize;
+        self.ram[big_font..big_font + BIG_FONT_SIZE].copy_from_slice(&BIG_FONT);
+        match &self.custom_font {
+            Some(custom) => self.ram[font..font + custom.len()].copy_from_slice(custom),
+            None => self.ram[font..font + SMALL_FONT_SIZE].copy_from_slice(self.font_set.sprites()),
+        }
+    }
+
+    /// Get how many bytes of a program fit in memory starting at `address`.
+    #[inline]
+    pub fn capacity(&self, address: u16) -> usize {
+        self.ram.len().saturating_sub(address as usize)
     }
 
-    /// Load a program to memory starting at address 0x200.
+    /// Load a program to memory starting at `address`. The part of the program that does not fit is
+    /// left out, check it with [`Memory::capacity`] first.
     #[inline]
-    pub fn load_program(&mut self, rom: &[u8]) {
-        self.ram[0x200..(0x200 + rom.len())].copy_from_slice(rom);
+    pub fn load_program(&mut self, rom: &[u8], address: u16) {
+        let start = (address as usize).min(self.ram.len());
+        let length = rom.len().min(self.ram.len() - start);
+        self.ram[start..(start + length)].copy_from_slice(&rom[..length]);
     }
 
-    /// Read two bytes at the passed address and combine them into an instruction.
+    /// Read two bytes at the passed address and combine them into an instruction. Addresses wrap
+    /// around to 0x000 at the end of memory, so an opcode can be read wherever the program counter
+    /// ended up.
     #[inline]
-    pub const fn read_opcode(&self, address: u16) -> u16 {
-        (self.ram[address as usize] as u16) << 8 | self.ram[(address as usize) + 1] as u16
+    pub fn read_opcode(&self, address: u16) -> u16 {
+        let first = address as usize % self.ram.len();
+        let second = (first + 1) % self.ram.len();
+        (self.ram[first] as u16) << 8 | self.ram[second] as u16
     }
 }
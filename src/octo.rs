@@ -0,0 +1,818 @@
+use std::collections::{BTreeMap, HashMap};
+
+/// A program assembled from Octo source by [`assemble_octo`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct OctoProgram {
+    /// The bytes of the program, to be loaded at the start address it was assembled for.
+    pub rom: Vec<u8>,
+    /// The address of every label.
+    pub labels: BTreeMap<String, u16>,
+}
+
+/// Directives of Octo that are not supported, so they are reported instead of read as labels.
+const UNSUPPORTED: &[&str] = &[
+    ":macro",
+    ":calc",
+    ":unpack",
+    ":next",
+    ":stringmode",
+    ":assert",
+    ":monitor",
+];
+
+/// Words that cannot be used as names of labels, constants or aliases.
+const KEYWORDS: &[&str] = &[
+    "clear",
+    "return",
+    "jump",
+    "jump0",
+    "native",
+    "sprite",
+    "bcd",
+    "save",
+    "load",
+    "saveflags",
+    "loadflags",
+    "i",
+    "delay",
+    "buzzer",
+    "pitch",
+    "key",
+    "-key",
+    "random",
+    "hex",
+    "bighex",
+    "long",
+    "if",
+    "then",
+    "begin",
+    "else",
+    "end",
+    "loop",
+    "again",
+    "while",
+    "hires",
+    "lores",
+    "exit",
+    "plane",
+    "audio",
+    "scroll-right",
+    "scroll-left",
+    "scroll-up",
+    "scroll-down",
+];
+
+/// Assemble a program written in Octo, the CHIP-8 assembly language, to be loaded at
+/// `start_address`. Returns a description of the problem and its line if it cannot be assembled.
+///
+/// Supported are labels (`: main`), `:const`, `:alias`, `:org`, `:call` and `:byte`, every
+/// instruction of CHIP-8, SUPER-CHIP and XO-CHIP, `if ... then`, `if ... begin ... else ... end`,
+/// `loop ... while ... again` and the `<`, `>`, `<=` and `>=` comparisons, which use VF. Execution
+/// starts at `main`, which is jumped to unless it is the first thing in the program. Macros and
+/// `:calc` are not supported.
+pub fn assemble_octo(source: &str, start_address: u16) -> Result<OctoProgram, String> {
+    let tokens = source
+        .lines()
+        .enumerate()
+        .flat_map(|(line, text)| {
+            let code = text.split('#').next().unwrap_or_default();
+            code.split_whitespace().map(move |token| (line + 1, token))
+        })
+        .collect();
+    let mut assembler = Assembler {
+        tokens,
+        position: 0,
+        line: 1,
+        start_address,
+        // Room for the jump to main
+        rom: vec![0; 2],
+        here: 2,
+        jumps_to_main: true,
+        labels: BTreeMap::new(),
+        constants: HashMap::new(),
+        aliases: HashMap::new(),
+        references: Vec::new(),
+        branches: Vec::new(),
+        loops: Vec::new(),
+    };
+    while assembler.position < assembler.tokens.len() {
+        assembler.statement()?;
+    }
+    assembler.finish()
+}
+
+/// A use of a label that was not defined yet.
+struct Reference {
+    /// Where the instruction that uses it is in the ROM.
+    offset: usize,
+    name: String,
+    line: usize,
+    /// Whether the whole 16 bits after the `F000` at `offset` are the address, instead of the low
+    /// 12 bits of the instruction.
+    long: bool,
+}
+
+/// A conditional skip: the instructions that compute the condition, the instruction that skips
+/// when it is true and the one that skips when it is false.
+struct Condition {
+    setup: Vec<u16>,
+    skip_if_true: u16,
+    skip_if_false: u16,
+}
+
+struct Assembler<'a> {
+    tokens: Vec<(usize, &'a str)>,
+    position: usize,
+    /// The line of the last token read.
+    line: usize,
+    start_address: u16,
+    rom: Vec<u8>,
+    /// Where the next instruction is written in the ROM.
+    here: usize,
+    /// Whether the first two bytes are still reserved for a jump to main.
+    jumps_to_main: bool,
+    labels: BTreeMap<String, u16>,
+    constants: HashMap<String, u16>,
+    aliases: HashMap<String, u8>,
+    references: Vec<Reference>,
+    /// The jumps over the open `begin` and `else` blocks.
+    branches: Vec<usize>,
+    /// The start address of every open `loop` and the jumps out of it made by `while`.
+    loops: Vec<(u16, Vec<usize>)>,
+}
+
+impl<'a> Assembler<'a> {
+    fn error(&self, message: impl std::fmt::Display) -> String {
+        format!("Line {}: {message}", self.line)
+    }
+
+    fn next(&mut self) -> Result<&'a str, String> {
+        let Some(&(line, token)) = self.tokens.get(self.position) else {
+            return Err(self.error("Unexpected end of the program"));
+        };
+        self.position += 1;
+        self.line = line;
+        Ok(token)
+    }
+
+    fn peek(&self) -> Option<&'a str> {
+        self.tokens.get(self.position).map(|&(_, token)| token)
+    }
+
+    fn expect(&mut self, expected: &str) -> Result<(), String> {
+        match self.next()? {
+            token if token == expected => Ok(()),
+            token => Err(self.error(format!("Expected `{expected}` but found `{token}`"))),
+        }
+    }
+
+    /// The address of the next instruction.
+    fn address(&self) -> u16 {
+        self.start_address.wrapping_add(self.here as u16)
+    }
+
+    fn emit_byte(&mut self, byte: u8) {
+        if self.here >= self.rom.len() {
+            self.rom.resize(self.here + 1, 0);
+        }
+        self.rom[self.here] = byte;
+        self.here += 1;
+    }
+
+    fn emit(&mut self, opcode: u16) {
+        self.emit_byte((opcode >> 8) as u8);
+        self.emit_byte(opcode as u8);
+    }
+
+    /// Write the address of a jump emitted at `offset`, which is 12 bits.
+    fn patch(&mut self, offset: usize, address: u16) -> Result<(), String> {
+        if address > 0xFFF {
+            return Err(self.error(format!("The address {address:04X} does not fit in 12 bits")));
+        }
+        self.rom[offset] = self.rom[offset] & 0xF0 | (address >> 8) as u8;
+        self.rom[offset + 1] = address as u8;
+        Ok(())
+    }
+
+    /// Emit a jump whose target is patched in later, and get where it is.
+    fn emit_placeholder_jump(&mut self) -> usize {
+        let offset = self.here;
+        self.emit(0x1000);
+        offset
+    }
+
+    fn number(token: &str) -> Option<i64> {
+        let (negative, digits) = match token.strip_prefix('-') {
+            Some(digits) => (true, digits),
+            None => (false, token),
+        };
+        let value = if let Some(hex) = digits.strip_prefix("0x") {
+            i64::from_str_radix(hex, 16).ok()?
+        } else if let Some(binary) = digits.strip_prefix("0b") {
+            i64::from_str_radix(binary, 2).ok()?
+        } else if digits.starts_with(|c: char| c.is_ascii_digit()) {
+            digits.parse().ok()?
+        } else {
+            return None;
+        };
+        Some(if negative { -value } else { value })
+    }
+
+    fn register(&self, token: &str) -> Option<u8> {
+        if let Some(&register) = self.aliases.get(token) {
+            return Some(register);
+        }
+        let digit = token.strip_prefix(['v', 'V'])?;
+        match digit.len() {
+            1 => u8::from_str_radix(digit, 16).ok(),
+            _ => None,
+        }
+    }
+
+    fn expect_register(&mut self) -> Result<u8, String> {
+        let token = self.next()?;
+        self.register(token)
+            .ok_or_else(|| self.error(format!("Expected a register but found `{token}`")))
+    }
+
+    /// Read a number or constant.
+    fn value(&mut self) -> Result<i64, String> {
+        let token = self.next()?;
+        if let Some(value) = Assembler::number(token) {
+            Ok(value)
+        } else if let Some(&value) = self.constants.get(token) {
+            Ok(value as i64)
+        } else if let Some(&address) = self.labels.get(token) {
+            Ok(address as i64)
+        } else {
+            Err(self.error(format!("Expected a number but found `{token}`")))
+        }
+    }
+
+    /// Read a byte, which can also be negative.
+    fn byte(&mut self) -> Result<u8, String> {
+        let value = self.value()?;
+        if (-128..=255).contains(&value) {
+            Ok(value as u8)
+        } else {
+            Err(self.error(format!("{value} does not fit in a byte")))
+        }
+    }
+
+    /// Read a value up to `max`.
+    fn small(&mut self, max: i64) -> Result<u16, String> {
+        let value = self.value()?;
+        if (0..=max).contains(&value) {
+            Ok(value as u16)
+        } else {
+            Err(self.error(format!("{value} is not between 0 and {max}")))
+        }
+    }
+
+    /// Emit `opcode` with an address in its low 12 bits, or in the 16 bits after it if `long`. A
+    /// label that is not defined yet is filled in at the end.
+    fn emit_with_address(&mut self, opcode: u16, long: bool) -> Result<(), String> {
+        let max = if long { 0xFFFF } else { 0xFFF };
+        let token = self.next()?;
+        let address = if let Some(value) = Assembler::number(token) {
+            Some(value)
+        } else if let Some(&value) = self.constants.get(token) {
+            Some(value as i64)
+        } else {
+            self.labels.get(token).map(|&address| address as i64)
+        };
+        let offset = self.here;
+        let address = match address {
+            Some(address) if (0..=max).contains(&address) => address as u16,
+            Some(address) => {
+                return Err(self.error(format!("The address {address:X} is too large")));
+            }
+            None => {
+                self.check_name(token)?;
+                self.references.push(Reference {
+                    offset,
+                    name: token.to_string(),
+                    line: self.line,
+                    long,
+                });
+                0
+            }
+        };
+        if long {
+            self.emit(opcode);
+            self.emit(address);
+        } else {
+            self.emit(opcode | address);
+        }
+        Ok(())
+    }
+
+    /// Check that a name can be given to a label, constant or alias.
+    fn check_name(&self, name: &str) -> Result<(), String> {
+        if KEYWORDS.contains(&name)
+            || name.starts_with(':')
+            || self.register(name).is_some()
+            || Assembler::number(name).is_some()
+        {
+            Err(self.error(format!("`{name}` cannot be used as a name")))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Read a condition like `v0 == 5`, `v1 != v2`, `v3 key` or `v4 < 10`.
+    fn condition(&mut self) -> Result<Condition, String> {
+        let x = self.expect_register()? as u16;
+        let operator = self.next()?;
+        let skip = |skip_if_true, skip_if_false| Condition {
+            setup: Vec::new(),
+            skip_if_true,
+            skip_if_false,
+        };
+        match operator {
+            "key" => return Ok(skip(0xE09E | x << 8, 0xE0A1 | x << 8)),
+            "-key" => return Ok(skip(0xE0A1 | x << 8, 0xE09E | x << 8)),
+            _ => {}
+        }
+
+        let (right, is_register) = match self.peek().and_then(|token| self.register(token)) {
+            Some(y) => {
+                self.position += 1;
+                (y as u16, true)
+            }
+            None => (self.byte()? as u16, false),
+        };
+        match operator {
+            "==" if is_register => Ok(skip(
+                0x5000 | x << 8 | right << 4,
+                0x9000 | x << 8 | right << 4,
+            )),
+            "==" => Ok(skip(0x3000 | x << 8 | right, 0x4000 | x << 8 | right)),
+            "!=" if is_register => Ok(skip(
+                0x9000 | x << 8 | right << 4,
+                0x5000 | x << 8 | right << 4,
+            )),
+            "!=" => Ok(skip(0x4000 | x << 8 | right, 0x3000 | x << 8 | right)),
+            "<" | ">=" | ">" | "<=" => {
+                // VF is set to Vx >= right for < and >=, and to right >= Vx for > and <=
+                let setup = match (operator, is_register) {
+                    ("<" | ">=", true) => vec![0x8F00 | x << 4, 0x8F05 | right << 4],
+                    ("<" | ">=", false) => vec![0x6F00 | right, 0x8F07 | x << 4],
+                    (_, true) => vec![0x8F00 | right << 4, 0x8F05 | x << 4],
+                    (_, false) => vec![0x6F00 | right, 0x8F05 | x << 4],
+                };
+                // < and > are true when VF is 0
+                let (skip_if_true, skip_if_false) = match operator {
+                    "<" | ">" => (0x3F00, 0x4F00),
+                    _ => (0x4F00, 0x3F00),
+                };
+                Ok(Condition {
+                    setup,
+                    skip_if_true,
+                    skip_if_false,
+                })
+            }
+            _ => Err(self.error(format!("Expected a comparison but found `{operator}`"))),
+        }
+    }
+
+    fn statement(&mut self) -> Result<(), String> {
+        let token = self.next()?;
+        if let Some(x) = self.register(token) {
+            return self.register_statement(x as u16);
+        }
+        match token {
+            ":" => {
+                let name = self.next()?;
+                self.check_name(name)?;
+                if self.labels.contains_key(name) || self.constants.contains_key(name) {
+                    return Err(self.error(format!("`{name}` is already defined")));
+                }
+                // A program that starts with main does not need to jump to it
+                if name == "main"
+                    && self.jumps_to_main
+                    && self.labels.is_empty()
+                    && self.here == 2
+                    && self.rom.len() == 2
+                {
+                    self.rom.clear();
+                    self.here = 0;
+                    self.jumps_to_main = false;
+                }
+                self.labels.insert(name.to_string(), self.address());
+            }
+            ":const" => {
+                let name = self.next()?;
+                self.check_name(name)?;
+                let value = self.value()?;
+                self.constants.insert(name.to_string(), value as u16);
+            }
+            ":alias" => {
+                let name = self.next()?;
+                self.check_name(name)?;
+                let register = self.expect_register()?;
+                self.aliases.insert(name.to_string(), register);
+            }
+            ":org" => {
+                let address = self.small(0xFFFF)?;
+                if address < self.start_address {
+                    return Err(self.error(format!(
+                        "The address {address:04X} is before the start of the program"
+                    )));
+                }
+                self.here = (address - self.start_address) as usize;
+            }
+            ":call" => self.emit_with_address(0x2000, false)?,
+            ":byte" => {
+                let byte = self.byte()?;
+                self.emit_byte(byte);
+            }
+            directive if UNSUPPORTED.contains(&directive) => {
+                return Err(self.error(format!("`{directive}` is not supported")));
+            }
+            directive if directive.starts_with(':') => {
+                return Err(self.error(format!("Unknown directive `{directive}`")));
+            }
+            "clear" => self.emit(0x00E0),
+            "return" | ";" => self.emit(0x00EE),
+            "hires" => self.emit(0x00FF),
+            "lores" => self.emit(0x00FE),
+            "exit" => self.emit(0x00FD),
+            "scroll-right" => self.emit(0x00FB),
+            "scroll-left" => self.emit(0x00FC),
+            "scroll-down" => {
+                let n = self.small(0xF)?;
+                self.emit(0x00C0 | n);
+            }
+            "scroll-up" => {
+                let n = self.small(0xF)?;
+                self.emit(0x00D0 | n);
+            }
+            "plane" => {
+                let n = self.small(0x3)?;
+                self.emit(0xF001 | n << 8);
+            }
+            "audio" => self.emit(0xF002),
+            "jump" => self.emit_with_address(0x1000, false)?,
+            "jump0" => self.emit_with_address(0xB000, false)?,
+            "native" => self.emit_with_address(0x0000, false)?,
+            "sprite" => {
+                let x = self.expect_register()? as u16;
+                let y = self.expect_register()? as u16;
+                let n = self.small(0xF)?;
+                self.emit(0xD000 | x << 8 | y << 4 | n);
+            }
+            "bcd" => {
+                let x = self.expect_register()? as u16;
+                self.emit(0xF033 | x << 8);
+            }
+            "save" | "load" => {
+                let x = self.expect_register()? as u16;
+                if self.peek() == Some("-") {
+                    self.position += 1;
+                    let y = self.expect_register()? as u16;
+                    let low = if token == "save" { 2 } else { 3 };
+                    self.emit(0x5000 | x << 8 | y << 4 | low);
+                } else {
+                    let low = if token == "save" { 0x55 } else { 0x65 };
+                    self.emit(0xF000 | x << 8 | low);
+                }
+            }
+            "saveflags" => {
+                let x = self.expect_register()? as u16;
+                self.emit(0xF075 | x << 8);
+            }
+            "loadflags" => {
+                let x = self.expect_register()? as u16;
+                self.emit(0xF085 | x << 8);
+            }
+            "delay" | "buzzer" | "pitch" => {
+                self.expect(":=")?;
+                let x = self.expect_register()? as u16;
+                let low = match token {
+                    "delay" => 0x15,
+                    "buzzer" => 0x18,
+                    _ => 0x3A,
+                };
+                self.emit(0xF000 | x << 8 | low);
+            }
+            "i" => match self.next()? {
+                ":=" => match self.peek() {
+                    Some("hex") => {
+                        self.position += 1;
+                        let x = self.expect_register()? as u16;
+                        self.emit(0xF029 | x << 8);
+                    }
+                    Some("bighex") => {
+                        self.position += 1;
+                        let x = self.expect_register()? as u16;
+                        self.emit(0xF030 | x << 8);
+                    }
+                    Some("long") => {
+                        self.position += 1;
+                        self.emit_with_address(0xF000, true)?;
+                    }
+                    _ => self.emit_with_address(0xA000, false)?,
+                },
+                "+=" => {
+                    let x = self.expect_register()? as u16;
+                    self.emit(0xF01E | x << 8);
+                }
+                operator => {
+                    return Err(self.error(format!("Unknown operator `{operator}` for i")));
+                }
+            },
+            "if" => {
+                let condition = self.condition()?;
+                for opcode in condition.setup {
+                    self.emit(opcode);
+                }
+                match self.next()? {
+                    "then" => self.emit(condition.skip_if_false),
+                    "begin" => {
+                        self.emit(condition.skip_if_true);
+                        let jump = self.emit_placeholder_jump();
+                        self.branches.push(jump);
+                    }
+                    token => {
+                        return Err(
+                            self.error(format!("Expected `then` or `begin` but found `{token}`"))
+                        );
+                    }
+                }
+            }
+            "else" => {
+                let Some(jump) = self.branches.pop() else {
+                    return Err(self.error("`else` without `begin`"));
+                };
+                let end = self.emit_placeholder_jump();
+                self.patch(jump, self.address())?;
+                self.branches.push(end);
+            }
+            "end" => {
+                let Some(jump) = self.branches.pop() else {
+                    return Err(self.error("`end` without `begin`"));
+                };
+                self.patch(jump, self.address())?;
+            }
+            "loop" => self.loops.push((self.address(), Vec::new())),
+            "while" => {
+                let condition = self.condition()?;
+                for opcode in condition.setup {
+                    self.emit(opcode);
+                }
+                self.emit(condition.skip_if_true);
+                let jump = self.emit_placeholder_jump();
+                match self.loops.last_mut() {
+                    Some((_, exits)) => exits.push(jump),
+                    None => return Err(self.error("`while` outside of a loop")),
+                }
+            }
+            "again" => {
+                let Some((start, exits)) = self.loops.pop() else {
+                    return Err(self.error("`again` without `loop`"));
+                };
+                let jump = self.emit_placeholder_jump();
+                self.patch(jump, start)?;
+                for exit in exits {
+                    self.patch(exit, self.address())?;
+                }
+            }
+            _ => {
+                if Assembler::number(token).is_some() || self.constants.contains_key(token) {
+                    // Data
+                    self.position -= 1;
+                    let byte = self.byte()?;
+                    self.emit_byte(byte);
+                } else {
+                    // A label on its own calls it
+                    self.position -= 1;
+                    self.emit_with_address(0x2000, false)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Assemble an instruction that starts with the register Vx, like `v0 += 1`.
+    fn register_statement(&mut self, x: u16) -> Result<(), String> {
+        let operator = self.next()?;
+        let y = self
+            .peek()
+            .and_then(|token| self.register(token))
+            .map(|y| y as u16);
+        if let Some(y) = y {
+            self.position += 1;
+            let low = match operator {
+                ":=" => 0x0,
+                "|=" => 0x1,
+                "&=" => 0x2,
+                "^=" => 0x3,
+                "+=" => 0x4,
+                "-=" => 0x5,
+                ">>=" => 0x6,
+                "=-" => 0x7,
+                "<<=" => 0xE,
+                _ => {
+                    return Err(self.error(format!("Unknown operator `{operator}` for registers")));
+                }
+            };
+            self.emit(0x8000 | x << 8 | y << 4 | low);
+            return Ok(());
+        }
+        match operator {
+            ":=" => match self.peek() {
+                Some("random") => {
+                    self.position += 1;
+                    let nn = self.byte()? as u16;
+                    self.emit(0xC000 | x << 8 | nn);
+                }
+                Some("delay") => {
+                    self.position += 1;
+                    self.emit(0xF007 | x << 8);
+                }
+                Some("key") => {
+                    self.position += 1;
+                    self.emit(0xF00A | x << 8);
+                }
+                _ => {
+                    let nn = self.byte()? as u16;
+                    self.emit(0x6000 | x << 8 | nn);
+                }
+            },
+            "+=" => {
+                let nn = self.byte()? as u16;
+                self.emit(0x7000 | x << 8 | nn);
+            }
+            "-=" => {
+                let nn = self.byte()?.wrapping_neg() as u16;
+                self.emit(0x7000 | x << 8 | nn);
+            }
+            _ => {
+                let token = self.next()?;
+                return Err(self.error(format!(
+                    "Expected a register after `{operator}` but found `{token}`"
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Fill in the labels that were used before they were defined, and the jump to main.
+    fn finish(mut self) -> Result<OctoProgram, String> {
+        if !self.branches.is_empty() {
+            return Err(self.error("`begin` without `end`"));
+        }
+        if !self.loops.is_empty() {
+            return Err(self.error("`loop` without `again`"));
+        }
+        for reference in std::mem::take(&mut self.references) {
+            self.line = reference.line;
+            let Some(&address) = self.labels.get(&reference.name) else {
+                return Err(self.error(format!("`{}` is not defined", reference.name)));
+            };
+            if reference.long {
+                self.rom[reference.offset + 2] = (address >> 8) as u8;
+                self.rom[reference.offset + 3] = address as u8;
+            } else {
+                self.patch(reference.offset, address)?;
+            }
+        }
+        if self.jumps_to_main {
+            let Some(&main) = self.labels.get("main") else {
+                return Err("The program has no `: main` label".to_string());
+            };
+            self.rom[0] = 0x10;
+            self.patch(0, main)?;
+        }
+        Ok(OctoProgram {
+            rom: self.rom,
+            labels: self.labels,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assemble(source: &str) -> Vec<u8> {
+        assemble_octo(source, 0x200).unwrap().rom
+    }
+
+    #[test]
+    fn instructions() {
+        assert_eq!(
+            assemble(
+                ": main clear v0 := 5 v1 += 2 v2 -= 1 v3 := random 0x0F v0 += v1 v4 >>= v4
+                i := 0x300 i += v0 sprite v0 v1 5 bcd v2 save v3 load v4 delay := v5
+                buzzer := v6 v7 := delay v8 := key i := hex v9 jump0 0x210 ;"
+            ),
+            [
+                0x00, 0xE0, 0x60, 0x05, 0x71, 0x02, 0x72, 0xFF, 0xC3, 0x0F, 0x80, 0x14, 0x84, 0x46,
+                0xA3, 0x00, 0xF0, 0x1E, 0xD0, 0x15, 0xF2, 0x33, 0xF3, 0x55, 0xF4, 0x65, 0xF5, 0x15,
+                0xF6, 0x18, 0xF7, 0x07, 0xF8, 0x0A, 0xF9, 0x29, 0xB2, 0x10, 0x00, 0xEE,
+            ]
+        );
+    }
+
+    #[test]
+    fn super_chip_and_xo_chip() {
+        assert_eq!(
+            assemble(
+                ": main hires lores scroll-down 4 scroll-up 2 scroll-left scroll-right exit
+                i := bighex v1 saveflags v2 loadflags v3 save v1 - v3 load v2 - v0 plane 3
+                audio pitch := v4 i := long 0x1234"
+            ),
+            [
+                0x00, 0xFF, 0x00, 0xFE, 0x00, 0xC4, 0x00, 0xD2, 0x00, 0xFC, 0x00, 0xFB, 0x00, 0xFD,
+                0xF1, 0x30, 0xF2, 0x75, 0xF3, 0x85, 0x51, 0x32, 0x52, 0x03, 0xF3, 0x01, 0xF0, 0x02,
+                0xF4, 0x3A, 0xF0, 0x00, 0x12, 0x34,
+            ]
+        );
+    }
+
+    #[test]
+    fn labels_and_data() {
+        let program = assemble_octo(
+            ": data 0xAA 0b1010 -1
+            : main i := data draw ;
+            : draw clear ;",
+            0x200,
+        )
+        .unwrap();
+        // A program that does not start with main jumps to it
+        assert_eq!(
+            program.rom,
+            [
+                0x12, 0x05, 0xAA, 0x0A, 0xFF, 0xA2, 0x02, 0x22, 0x0B, 0x00, 0xEE, 0x00, 0xE0, 0x00,
+                0xEE
+            ]
+        );
+        assert_eq!(program.labels["data"], 0x202);
+        assert_eq!(program.labels["main"], 0x205);
+        assert_eq!(program.labels["draw"], 0x20B);
+    }
+
+    #[test]
+    fn constants_aliases_and_org() {
+        assert_eq!(
+            assemble(
+                ":const SPEED 4 :alias x v3
+                : main x := SPEED x += x jump done
+                :org 0x208 : done :byte 7"
+            ),
+            [0x63, 0x04, 0x83, 0x34, 0x12, 0x08, 0x00, 0x00, 0x07]
+        );
+        assert_eq!(
+            assemble_octo(": main jump main", 0x600).unwrap().rom,
+            [0x16, 0x00]
+        );
+        assert_eq!(
+            assemble(": main i := long data : data 1 2"),
+            [0xF0, 0x00, 0x02, 0x04, 0x01, 0x02]
+        );
+    }
+
+    #[test]
+    fn control_flow() {
+        assert_eq!(
+            assemble(": main if v0 == 3 then v1 := 1 if v0 != v2 then v1 := 2 if v5 key then ;"),
+            [0x40, 0x03, 0x61, 0x01, 0x50, 0x20, 0x61, 0x02, 0xE5, 0xA1, 0x00, 0xEE]
+        );
+        assert_eq!(
+            assemble(": main if v0 == 1 begin v1 := 2 else v1 := 3 end"),
+            [0x30, 0x01, 0x12, 0x08, 0x61, 0x02, 0x12, 0x0A, 0x61, 0x03]
+        );
+        assert_eq!(
+            assemble(": main loop v0 += 1 while v0 != 10 again"),
+            [0x70, 0x01, 0x40, 0x0A, 0x12, 0x08, 0x12, 0x00]
+        );
+        // Comparisons set VF with a subtraction and test it
+        assert_eq!(
+            assemble(": main if v1 < 5 then v2 := 0 if v1 >= v3 then v2 := 1"),
+            [
+                0x6F, 0x05, 0x8F, 0x17, 0x4F, 0x00, 0x62, 0x00, 0x8F, 0x10, 0x8F, 0x35, 0x3F, 0x00,
+                0x62, 0x01,
+            ]
+        );
+    }
+
+    #[test]
+    fn errors() {
+        let error = |source| assemble_octo(source, 0x200).unwrap_err();
+        assert_eq!(
+            error(": main\njump nowhere"),
+            "Line 2: `nowhere` is not defined"
+        );
+        assert_eq!(error(": start clear"), "The program has no `: main` label");
+        assert_eq!(error(":macro"), "Line 1: `:macro` is not supported");
+        assert_eq!(
+            error(": main v0 := 300"),
+            "Line 1: 300 does not fit in a byte"
+        );
+        assert_eq!(error(": main : main"), "Line 1: `main` is already defined");
+        assert_eq!(error(": main loop"), "Line 1: `loop` without `again`");
+        assert_eq!(error(": clear"), "Line 1: `clear` cannot be used as a name");
+    }
+}
@@ -0,0 +1,127 @@
+use crate::{bisect::QUIRKS, Breakpoints, Chip8, Quirks, Variant};
+
+/// The result of [`test_quirks`].
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct QuirkTestReport {
+    /// The name of the platform the quirks were tested against.
+    pub platform: &'static str,
+    /// The quirks of the platform.
+    pub expected: Quirks,
+    /// The variant the test ran as.
+    pub variant: Variant,
+    /// The quirks the test ran with.
+    pub tested: Quirks,
+    /// The names of the quirks, as shown in the quirks menu, that behaved differently from the
+    /// platform.
+    pub mismatches: Vec<&'static str>,
+}
+
+impl QuirkTestReport {
+    /// Check if every quirk that can be observed under the variant behaved like the platform.
+    #[inline]
+    pub fn passed(&self) -> bool {
+        self.mismatches.is_empty()
+    }
+
+    /// Check if the report is out of date because the interpreter's variant or quirks changed.
+    #[inline]
+    pub fn is_stale(&self, interpreter: &Chip8) -> bool {
+        self.variant != interpreter.variant || self.tested != interpreter.quirks
+    }
+}
+
+/// Run a small test program for every quirk with the variant and quirks of `base` and check that
+/// the interpreter behaves like `platform`, whose quirks are `expected`. The programs run on a
+/// copy of `base`, which is not changed.
+///
+/// Quirks that only affect SUPER-CHIP instructions (highres vblank waits, legacy scrolling and
+/// clearing on resolution change) are only tested if the variant supports them.
+pub fn test_quirks(base: &Chip8, platform: &'static str, expected: Quirks) -> QuirkTestReport {
+    let schip = base.variant.supports_schip();
+    let scrolling = base.variant.supports_scrolling();
+    let mut mismatches = Vec::new();
+    let mut check = |quirk: usize, observed: bool, expected: bool| {
+        if observed != expected && !mismatches.contains(&QUIRKS[quirk].0) {
+            mismatches.push(QUIRKS[quirk].0);
+        }
+    };
+
+    // VF = 5, V0 |= V1, VF is 0 if reset
+    let chip8 = run(base, &[0x6F05, 0x6001, 0x6102, 0x8011]);
+    check(0, chip8.V[0xF] == 0, expected.bitwise_reset_vf);
+
+    // V0 = 1, V1 = 4, V0 = V1 >> 1 (2) or V0 >> 1 (0)
+    let chip8 = run(base, &[0x6001, 0x6104, 0x8016]);
+    check(1, chip8.V[0] == 0, expected.direct_shifting);
+
+    // I = 0x300, save V0 and V1, I is 0x302, 0x301 or 0x300 depending on the increment
+    let chip8 = run(base, &[0xA300, 0x6000, 0xF155]);
+    check(
+        2,
+        chip8.I == 0x300 + expected.save_load_increment.amount(1),
+        true,
+    );
+
+    // V0 = 0, V1 = 2, jump to 0x110 + V0 or V1
+    let chip8 = run(base, &[0x6000, 0x6102, 0xB110]);
+    check(3, chip8.program_counter == 0x112, expected.jump_to_x);
+
+    // Draw twice in a frame, the second draw waits if the program counter did not move past it
+    let chip8 = run(base, &[0xA000, 0xD001, 0xD001]);
+    check(
+        4,
+        chip8.program_counter == 0x204,
+        expected.wait_for_vblank.applies(false),
+    );
+    if schip {
+        let chip8 = run(base, &[0x00FF, 0xA000, 0xD001, 0xD001]);
+        check(
+            4,
+            chip8.program_counter == 0x206,
+            expected.wait_for_vblank.applies(true),
+        );
+    }
+
+    // Draw 8 pixels at x = 60, the last 4 wrap around to x = 0 unless clipped
+    let chip8 = run(base, &[0xA206, 0x603C, 0xD011, 0xFF00]);
+    check(5, !chip8.display.planes[0][0], expected.edge_clipping);
+
+    if scrolling {
+        // Draw a line at y = 0 and scroll down by 2, or 1 with legacy scrolling
+        let chip8 = run(base, &[0xA206, 0xD001, 0x00C2, 0xFF00]);
+        check(6, chip8.display.planes[0][64], expected.lowres_scroll);
+    }
+    if schip {
+        // Draw a line and switch to highres, the line is gone if the display was cleared
+        let chip8 = run(base, &[0xA206, 0xD001, 0x00FF, 0xFF00]);
+        check(7, !chip8.display.planes[0][0], expected.resolution_clear);
+    }
+
+    QuirkTestReport {
+        platform,
+        expected,
+        variant: base.variant,
+        tested: base.quirks,
+        mismatches,
+    }
+}
+
+/// Run every instruction of a test program once on a reset copy of `base`. A data word may follow
+/// the instructions.
+fn run(base: &Chip8, program: &[u16]) -> Chip8 {
+    let mut chip8 = base.clone();
+    chip8.stop_chrome_trace();
+    chip8.stop_frame_dump();
+    chip8.stop_replay();
+    chip8.breakpoints = Breakpoints::default();
+    // The test programs address their data word at 0x200
+    chip8.start_address = 0x200;
+    let rom: Vec<u8> = program.iter().flat_map(|word| word.to_be_bytes()).collect();
+    chip8.reset_and_load(&rom);
+    chip8.start();
+    let instructions = program.iter().take_while(|&&word| word != 0xFF00).count();
+    for _ in 0..instructions {
+        chip8.execute_cycle();
+    }
+    chip8
+}
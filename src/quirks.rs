@@ -1,5 +1,6 @@
 /// The desired quirks of the CHIP-8 interpreter.
 #[derive(Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Quirks {
     /// If `true`, the `8xy1`, `8xy2` and `8xy3` opcodes will set VF to 0.  
     /// If `false`, the `8xy1`, `8xy2` and `8xy3` opcodes will not modify VF.
@@ -7,23 +8,47 @@ pub struct Quirks {
     /// If `true`, the `8xy6` and `8xyE` opcodes will set Vx to Vx >> 1.  
     /// If `false`, the `8xy6` and `8xyE` opcodes will set Vx to Vy >> 1.
     pub direct_shifting: bool,
-    /// If `true`, the `Fx55` and `Fx65` opcodes will not modify I.  
-    /// If `false`, the `Fx55` and `Fx65` opcodes will set I to I + x + 1.
-    pub save_load_increment: bool,
+    /// How much the `Fx55` and `Fx65` opcodes increment I by.
+    pub save_load_increment: IndexIncrement,
     /// If `true`, the `Bnnn` opcode will jump to nnn + V0.  
     /// If `false`, the `Bnnn` opcode will jump to nnn + Vx.
     pub jump_to_x: bool,
-    /// If `true`, the `Dxyn` opcode will wait for a vblank interrupt before drawing.  
-    /// If `false`, the `Dxyn` opcode will draw immediately.
-    pub wait_for_vblank: bool,
+    /// When the `Dxyn` opcode will wait for a vblank interrupt before drawing.
+    pub wait_for_vblank: VblankWait,
     /// If `true`, the `Dxyn` opcode will clip sprites that go off the edge of the screen.  
     /// If `false`, the `Dxyn` opcode will wrap sprites that go off the edge of the screen around.
     pub edge_clipping: bool,
     /// If `true` and emulating SUPER-CHIP, the scroll opcodes (`00Cn`, `00FB`, `00FC`) in lowres
-    /// mode will scroll half the amount pixels.
+    /// mode will scroll half the amount pixels. The legacy SUPER-CHIP 1.1 on the HP48 always
+    /// scrolls by highres pixels, which are half a lowres pixel, so lowres programs written for it
+    /// scroll by half pixels.
     /// If `false` and emulating SUPER-CHIP, the scroll opcodes (`00Cn`, `00FB`, `00FC`) in lowres
-    /// mode will scroll the expected amount of pixels.
+    /// mode will scroll the expected amount of pixels, like Octo and modern SUPER-CHIP
+    /// interpreters.
     pub lowres_scroll: bool,
+    /// If `true` and emulating SUPER-CHIP, the `Dxy0` opcode in lowres mode will draw an 8x16
+    /// sprite of 16 bytes, like SUPER-CHIP 1.0.
+    /// If `false` and emulating SUPER-CHIP, the `Dxy0` opcode will draw a 16x16 sprite of 32 bytes
+    /// in both modes.
+    pub lowres_tall_sprites: bool,
+    /// If `true` and emulating SUPER-CHIP, the `00FE` and `00FF` opcodes will clear the display,
+    /// like Octo.
+    /// If `false` and emulating SUPER-CHIP, the `00FE` and `00FF` opcodes will keep the display
+    /// contents, like the original SUPER-CHIP.
+    pub resolution_clear: bool,
+    /// If `true`, the `Cxnn` opcode will get its numbers from a fixed sequence, which gives the same
+    /// numbers in the same order every run.
+    /// If `false`, the `Cxnn` opcode will use a true random number generator.
+    pub deterministic_random: bool,
+    /// If `true`, the opcodes that access memory at I, and jumps, calls and returns, will wrap
+    /// addresses past the end of memory around to 0x000.
+    /// If `false`, the interpreter will halt when they go past the end.
+    pub memory_wrap: bool,
+    /// If `true`, the `Fx0A` opcode will finish as soon as a key is pressed, like many modern
+    /// interpreters.
+    /// If `false`, the `Fx0A` opcode will wait until the pressed key is released again, like the
+    /// COSMAC-VIP.
+    pub key_wait_on_press: bool,
 }
 
 impl Quirks {
@@ -31,19 +56,30 @@ impl Quirks {
     ///
     /// - bitwise_reset_vf: true
     /// - direct_shifting: false
-    /// - save_load_increment: false
+    /// - save_load_increment: x + 1
     /// - jump_to_x: false
-    /// - wait_for_vblank: true
+    /// - wait_for_vblank: always
     /// - edge_clipping: true
+    /// - lowres_scroll: false
+    /// - lowres_tall_sprites: false
+    /// - resolution_clear: false
+    /// - deterministic_random: false
+    /// - memory_wrap: false
+    /// - key_wait_on_press: false
     pub const fn vip_chip() -> Quirks {
         Quirks {
             bitwise_reset_vf: true,
             direct_shifting: false,
-            save_load_increment: false,
+            save_load_increment: IndexIncrement::XPlusOne,
             jump_to_x: false,
-            wait_for_vblank: true,
+            wait_for_vblank: VblankWait::Always,
             edge_clipping: true,
             lowres_scroll: false,
+            lowres_tall_sprites: false,
+            resolution_clear: false,
+            deterministic_random: false,
+            memory_wrap: false,
+            key_wait_on_press: false,
         }
     }
 
@@ -51,19 +87,84 @@ impl Quirks {
     ///
     /// - bitwise_reset_vf: false
     /// - direct_shifting: false
-    /// - save_load_increment: false
+    /// - save_load_increment: x + 1
     /// - jump_to_x: false
-    /// - wait_for_vblank: false
+    /// - wait_for_vblank: never
     /// - edge_clipping: false
+    /// - lowres_scroll: false
+    /// - lowres_tall_sprites: false
+    /// - resolution_clear: true
+    /// - deterministic_random: false
+    /// - memory_wrap: false
+    /// - key_wait_on_press: false
     pub const fn octo_chip() -> Quirks {
         Quirks {
             bitwise_reset_vf: false,
             direct_shifting: false,
-            save_load_increment: false,
+            save_load_increment: IndexIncrement::XPlusOne,
             jump_to_x: false,
-            wait_for_vblank: false,
+            wait_for_vblank: VblankWait::Never,
             edge_clipping: false,
             lowres_scroll: false,
+            lowres_tall_sprites: false,
+            resolution_clear: true,
+            deterministic_random: false,
+            memory_wrap: false,
+            key_wait_on_press: false,
+        }
+    }
+
+    /// The quirks of the CHIP-48 on the HP48.  
+    ///
+    /// - bitwise_reset_vf: false
+    /// - direct_shifting: true
+    /// - save_load_increment: x
+    /// - jump_to_x: true
+    /// - wait_for_vblank: never
+    /// - edge_clipping: true
+    /// - lowres_scroll: false
+    /// - lowres_tall_sprites: false
+    /// - resolution_clear: false
+    /// - deterministic_random: false
+    /// - memory_wrap: false
+    /// - key_wait_on_press: false
+    pub const fn chip48() -> Quirks {
+        Quirks {
+            bitwise_reset_vf: false,
+            direct_shifting: true,
+            save_load_increment: IndexIncrement::X,
+            jump_to_x: true,
+            wait_for_vblank: VblankWait::Never,
+            edge_clipping: true,
+            lowres_scroll: false,
+            lowres_tall_sprites: false,
+            resolution_clear: false,
+            deterministic_random: false,
+            memory_wrap: false,
+            key_wait_on_press: false,
+        }
+    }
+
+    /// The quirks of the SUPER-CHIP 1.0. It has no scroll instructions, so `lowres_scroll` does not
+    /// matter.  
+    ///
+    /// - bitwise_reset_vf: false
+    /// - direct_shifting: true
+    /// - save_load_increment: x
+    /// - jump_to_x: true
+    /// - wait_for_vblank: lowres only
+    /// - edge_clipping: true
+    /// - lowres_scroll: false
+    /// - lowres_tall_sprites: true
+    /// - resolution_clear: false
+    /// - deterministic_random: false
+    /// - memory_wrap: false
+    /// - key_wait_on_press: false
+    pub const fn super_chip1_0() -> Quirks {
+        Quirks {
+            save_load_increment: IndexIncrement::X,
+            lowres_tall_sprites: true,
+            ..Quirks::super_chip1_1()
         }
     }
 
@@ -71,42 +172,357 @@ impl Quirks {
     ///
     /// - bitwise_reset_vf: false
     /// - direct_shifting: true
-    /// - save_load_increment: true
+    /// - save_load_increment: none
     /// - jump_to_x: true
-    /// - wait_for_vblank: false
+    /// - wait_for_vblank: lowres only
     /// - edge_clipping: true
+    /// - lowres_scroll: false
+    /// - lowres_tall_sprites: false
+    /// - resolution_clear: false
+    /// - deterministic_random: false
+    /// - memory_wrap: false
+    /// - key_wait_on_press: false
     pub const fn super_chip1_1() -> Quirks {
         Quirks {
             bitwise_reset_vf: false,
             direct_shifting: true,
-            save_load_increment: true,
+            save_load_increment: IndexIncrement::Unchanged,
             jump_to_x: true,
-            wait_for_vblank: false,
+            wait_for_vblank: VblankWait::LowresOnly,
             edge_clipping: true,
             lowres_scroll: false,
+            lowres_tall_sprites: false,
+            resolution_clear: false,
+            deterministic_random: false,
+            memory_wrap: false,
+            key_wait_on_press: false,
+        }
+    }
+
+    /// Write the quirks as a small TOML file, one `name = value` line per quirk, for sharing the
+    /// exact quirks a ROM needs. Read it back with [`Quirks::from_toml`].
+    pub fn to_toml(&self) -> String {
+        let flag = |name: &str, value: bool| format!("{name} = {value}\n");
+        let mut toml = String::from("# E-CHIP quirks\n");
+        toml += &flag("bitwise_reset_vf", self.bitwise_reset_vf);
+        toml += &flag("direct_shifting", self.direct_shifting);
+        toml += &format!(
+            "save_load_increment = \"{}\"\n",
+            self.save_load_increment.name()
+        );
+        toml += &flag("jump_to_x", self.jump_to_x);
+        toml += &format!("wait_for_vblank = \"{}\"\n", self.wait_for_vblank.name());
+        toml += &flag("edge_clipping", self.edge_clipping);
+        toml += &flag("lowres_scroll", self.lowres_scroll);
+        toml += &flag("lowres_tall_sprites", self.lowres_tall_sprites);
+        toml += &flag("resolution_clear", self.resolution_clear);
+        toml += &flag("deterministic_random", self.deterministic_random);
+        toml += &flag("memory_wrap", self.memory_wrap);
+        toml += &flag("key_wait_on_press", self.key_wait_on_press);
+        toml
+    }
+
+    /// Read quirks written by [`Quirks::to_toml`]. Quirks that are left out are `false`, `x + 1` and
+    /// `never`, like [`Quirks::default`]. Empty lines, comments and a `[quirks]` table header are
+    /// skipped. Returns a description of the problem and its line if a line cannot be read.
+    pub fn from_toml(text: &str) -> Result<Quirks, String> {
+        let mut quirks = Quirks::default();
+        for (line, text) in text.lines().enumerate() {
+            let text = text.split('#').next().unwrap_or_default().trim();
+            if text.is_empty() || text == "[quirks]" {
+                continue;
+            }
+            let error = |message: String| format!("Line {}: {message}", line + 1);
+            let Some((name, value)) = text.split_once('=') else {
+                return Err(error(format!("Expected `name = value` but found `{text}`")));
+            };
+            let (name, value) = (name.trim(), value.trim());
+            let flag = || match value {
+                "true" => Ok(true),
+                "false" => Ok(false),
+                _ => Err(error(format!(
+                    "Expected true or false for {name} but found {value}"
+                ))),
+            };
+            let choice = || value.trim_matches('"');
+            match name {
+                "bitwise_reset_vf" => quirks.bitwise_reset_vf = flag()?,
+                "direct_shifting" => quirks.direct_shifting = flag()?,
+                "save_load_increment" => {
+                    quirks.save_load_increment = match choice() {
+                        "x+1" => IndexIncrement::XPlusOne,
+                        "x" => IndexIncrement::X,
+                        "none" => IndexIncrement::Unchanged,
+                        _ => {
+                            return Err(error(format!(
+                                "Expected \"x+1\", \"x\" or \"none\" for {name} but found {value}"
+                            )));
+                        }
+                    }
+                }
+                "jump_to_x" => quirks.jump_to_x = flag()?,
+                "wait_for_vblank" => {
+                    quirks.wait_for_vblank = match choice() {
+                        "never" => VblankWait::Never,
+                        "lowres" => VblankWait::LowresOnly,
+                        "always" => VblankWait::Always,
+                        _ => {
+                            return Err(error(format!(
+                                "Expected \"never\", \"lowres\" or \"always\" for {name} but found {value}"
+                            )));
+                        }
+                    }
+                }
+                "edge_clipping" => quirks.edge_clipping = flag()?,
+                "lowres_scroll" => quirks.lowres_scroll = flag()?,
+                "lowres_tall_sprites" => quirks.lowres_tall_sprites = flag()?,
+                "resolution_clear" => quirks.resolution_clear = flag()?,
+                "deterministic_random" => quirks.deterministic_random = flag()?,
+                "memory_wrap" => quirks.memory_wrap = flag()?,
+                "key_wait_on_press" => quirks.key_wait_on_press = flag()?,
+                _ => return Err(error(format!("Unknown quirk `{name}`"))),
+            }
+        }
+        Ok(quirks)
+    }
+}
+
+/// How much the `Fx55` and `Fx65` opcodes increment I by after accessing memory.
+#[derive(Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum IndexIncrement {
+    /// Set I to I + x + 1, like the original COSMAC-VIP.
+    #[default]
+    XPlusOne,
+    /// Set I to I + x, like CHIP-48 and SUPER-CHIP 1.0.
+    X,
+    /// Do not modify I, like SUPER-CHIP 1.1.
+    Unchanged,
+}
+
+impl IndexIncrement {
+    /// The name used in quirk files.
+    const fn name(&self) -> &'static str {
+        match self {
+            IndexIncrement::XPlusOne => "x+1",
+            IndexIncrement::X => "x",
+            IndexIncrement::Unchanged => "none",
+        }
+    }
+
+    /// Get how much I is incremented by after accessing V0 to Vx.
+    #[inline]
+    pub const fn amount(&self, x: u16) -> u16 {
+        match self {
+            IndexIncrement::XPlusOne => x + 1,
+            IndexIncrement::X => x,
+            IndexIncrement::Unchanged => 0,
+        }
+    }
+}
+
+/// When the `Dxyn` opcode waits for a vblank interrupt before drawing.
+#[derive(Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum VblankWait {
+    /// Draw immediately.
+    #[default]
+    Never,
+    /// Wait only in lowres mode, like SUPER-CHIP 1.1 on the HP48.
+    LowresOnly,
+    /// Always wait, like the original COSMAC-VIP.
+    Always,
+}
+
+impl VblankWait {
+    /// The name used in quirk files.
+    const fn name(&self) -> &'static str {
+        match self {
+            VblankWait::Never => "never",
+            VblankWait::LowresOnly => "lowres",
+            VblankWait::Always => "always",
+        }
+    }
+
+    /// Check whether drawing has to wait for a vblank interrupt in the given display mode.
+    #[inline]
+    pub const fn applies(&self, highres: bool) -> bool {
+        match self {
+            VblankWait::Never => false,
+            VblankWait::LowresOnly => !highres,
+            VblankWait::Always => true,
         }
     }
 }
 
 /// Determines what CHIP-8 variant to run as.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Variant {
     /// Run as a CHIP-8 interpreter
     CHIP8,
+    /// Run as a COSMAC VIP CHIP-8 interpreter that can run machine code routines
+    VIPHYBRID,
+    /// Run as a CHIP-8X interpreter
+    CHIP8X,
+    /// Run as a CHIP-48 interpreter
+    CHIP48,
+    /// Run as an ETI-660 interpreter
+    ETI660,
+    /// Run as a SUPER-CHIP 1.0 interpreter
+    SCHIP10,
     /// Run as a SUPER-CHIP 1.1 interpreter
     SCHIP11,
-    /// Run as an XO-CHIP interpreter (not implemented)
+    /// Run as an XO-CHIP interpreter
     XOCHIP,
 }
 
 impl Variant {
-    /// Check whether the variant supports all features introduced by SUPEP-CHIP
+    /// Check whether the variant supports all features introduced by SUPER-CHIP 1.0
     #[inline]
     pub const fn supports_schip(&self) -> bool {
         match self {
             Variant::CHIP8 => false,
+            Variant::VIPHYBRID => false,
+            Variant::CHIP8X => false,
+            Variant::CHIP48 => false,
+            Variant::ETI660 => false,
+            Variant::SCHIP10 => true,
             Variant::SCHIP11 => true,
             Variant::XOCHIP => true,
         }
     }
+
+    /// Check whether the variant supports the `00Cn`, `00FB` and `00FC` scroll instructions, which
+    /// were introduced by SUPER-CHIP 1.1
+    #[inline]
+    pub const fn supports_scrolling(&self) -> bool {
+        match self {
+            Variant::CHIP8 => false,
+            Variant::VIPHYBRID => false,
+            Variant::CHIP8X => false,
+            Variant::CHIP48 => false,
+            Variant::ETI660 => false,
+            Variant::SCHIP10 => false,
+            Variant::SCHIP11 => true,
+            Variant::XOCHIP => true,
+        }
+    }
+
+    /// Check whether the variant supports the features introduced by XO-CHIP
+    #[inline]
+    pub const fn supports_xochip(&self) -> bool {
+        match self {
+            Variant::CHIP8 => false,
+            Variant::VIPHYBRID => false,
+            Variant::CHIP8X => false,
+            Variant::CHIP48 => false,
+            Variant::ETI660 => false,
+            Variant::SCHIP10 => false,
+            Variant::SCHIP11 => false,
+            Variant::XOCHIP => true,
+        }
+    }
+
+    /// Get how many registers the `Fx75` and `Fx85` opcodes can save to persistent storage
+    #[inline]
+    pub const fn persistent_flag_count(&self) -> usize {
+        match self {
+            Variant::CHIP8 => 0,
+            Variant::VIPHYBRID => 0,
+            Variant::CHIP8X => 0,
+            Variant::CHIP48 => 0,
+            Variant::ETI660 => 0,
+            Variant::SCHIP10 => 8,
+            Variant::SCHIP11 => 8,
+            Variant::XOCHIP => 16,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn presets_round_trip() {
+        for quirks in [
+            Quirks::default(),
+            Quirks::vip_chip(),
+            Quirks::octo_chip(),
+            Quirks::chip48(),
+            Quirks::super_chip1_0(),
+            Quirks::super_chip1_1(),
+        ] {
+            assert_eq!(Quirks::from_toml(&quirks.to_toml()), Ok(quirks));
+        }
+        // Every value of the quirks that are not flags
+        let mut quirks = Quirks::default();
+        for save_load_increment in [
+            IndexIncrement::XPlusOne,
+            IndexIncrement::X,
+            IndexIncrement::Unchanged,
+        ] {
+            for wait_for_vblank in [
+                VblankWait::Never,
+                VblankWait::LowresOnly,
+                VblankWait::Always,
+            ] {
+                quirks.save_load_increment = save_load_increment;
+                quirks.wait_for_vblank = wait_for_vblank;
+                assert_eq!(Quirks::from_toml(&quirks.to_toml()), Ok(quirks));
+            }
+        }
+    }
+
+    #[test]
+    fn partial_toml() {
+        let toml =
+            "# For the CHIP-48\n\n[quirks]\nsave_load_increment = \"x\"  # I + x\njump_to_x=true\n";
+        assert_eq!(
+            Quirks::from_toml(toml),
+            Ok(Quirks {
+                save_load_increment: IndexIncrement::X,
+                jump_to_x: true,
+                ..Quirks::default()
+            })
+        );
+    }
+
+    #[test]
+    fn invalid_toml() {
+        assert_eq!(
+            Quirks::from_toml("edge_clipping = true\nwrap_sprites = true"),
+            Err("Line 2: Unknown quirk `wrap_sprites`".to_string())
+        );
+        assert_eq!(
+            Quirks::from_toml("memory_wrap = yes"),
+            Err("Line 1: Expected true or false for memory_wrap but found yes".to_string())
+        );
+        assert_eq!(
+            Quirks::from_toml("save_load_increment = \"x+2\""),
+            Err(
+                "Line 1: Expected \"x+1\", \"x\" or \"none\" for save_load_increment but found \"x+2\""
+                    .to_string()
+            )
+        );
+        assert_eq!(
+            Quirks::from_toml("wait_for_vblank = true"),
+            Err(
+                "Line 1: Expected \"never\", \"lowres\" or \"always\" for wait_for_vblank but found true"
+                    .to_string()
+            )
+        );
+        assert_eq!(
+            Quirks::from_toml("[quirks]\nmemory_wrap"),
+            Err("Line 2: Expected `name = value` but found `memory_wrap`".to_string())
+        );
+    }
+
+    #[test]
+    fn index_increment() {
+        assert_eq!(IndexIncrement::XPlusOne.amount(3), 4);
+        assert_eq!(IndexIncrement::X.amount(3), 3);
+        assert_eq!(IndexIncrement::Unchanged.amount(3), 0);
+    }
 }
@@ -0,0 +1,74 @@
+use std::{fs, io, path::PathBuf};
+
+/// The name of the file the recently loaded ROMs are stored in.
+const RECENT_FILE: &str = "recent.dat";
+/// How many ROMs are remembered.
+const RECENT_COUNT: usize = 10;
+
+/// The paths of the ROM files loaded last, newest first, so they can be loaded again with one
+/// click, even after a restart.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct RecentRoms {
+    paths: Vec<String>,
+}
+
+impl RecentRoms {
+    /// Read the recent ROMs. Every line of the file is the path of a ROM.
+    pub fn load() -> RecentRoms {
+        RecentRoms {
+            paths: recent_file()
+                .and_then(|path| fs::read_to_string(path).ok())
+                .unwrap_or_default()
+                .lines()
+                .filter(|line| !line.is_empty())
+                .take(RECENT_COUNT)
+                .map(str::to_string)
+                .collect(),
+        }
+    }
+
+    /// Get the paths of the recent ROMs, newest first.
+    #[inline]
+    pub fn paths(&self) -> &[String] {
+        &self.paths
+    }
+
+    /// Move a ROM to the top of the list, forgetting the oldest if there are too many. The path is
+    /// made absolute so it still works when the app is started from another folder.
+    pub fn add(&mut self, path: &str) {
+        let path = fs::canonicalize(path)
+            .map(|path| path.display().to_string())
+            .unwrap_or_else(|_| path.to_string());
+        self.paths.retain(|recent| *recent != path);
+        self.paths.insert(0, path);
+        self.paths.truncate(RECENT_COUNT);
+    }
+
+    /// Forget every ROM.
+    #[inline]
+    pub fn clear(&mut self) {
+        self.paths.clear();
+    }
+
+    /// Write the recent ROMs to the file.
+    pub fn save(&self) -> io::Result<()> {
+        let path = recent_file().ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                "the platform has no config directory",
+            )
+        })?;
+        if let Some(folder) = path.parent() {
+            fs::create_dir_all(folder)?;
+        }
+        fs::write(path, self.paths.join("\n"))
+    }
+}
+
+/// Get the file the recent ROMs are kept in: `e-chip/recent.dat` in the platform config directory
+/// (`%APPDATA%` on Windows, `~/Library/Application Support` on macOS and `~/.config` on Linux), so
+/// it is the same wherever the app is started from. `None` if the platform has no config
+/// directory.
+fn recent_file() -> Option<PathBuf> {
+    Some(dirs::config_dir()?.join("e-chip").join(RECENT_FILE))
+}
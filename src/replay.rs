@@ -0,0 +1,466 @@
+use crate::{state::Reader, Chip8, StateError};
+
+/// The first bytes of every replay file.
+const MAGIC: &[u8; 4] = b"ECHR";
+/// The version of the replay file format.
+const VERSION: u8 = 1;
+
+/// The keypad input of a recorded frame and a hash of the machine after it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ReplayFrame {
+    /// The state of the keypad during the frame.
+    pub keys: [bool; 16],
+    /// The key whose release resolved an `Fx0A` instruction before the frame, if any.
+    pub released: Option<u8>,
+    /// The [`Chip8::state_hash`] after the frame. `None` for frames read from a text recording,
+    /// which are not checked.
+    pub hash: Option<u64>,
+}
+
+/// The keypad input of every frame since a starting state, with a hash of the machine after each
+/// frame so playback can tell exactly where it stops reproducing the recording.
+#[derive(Debug, Default, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Replay {
+    /// The save state the recording started from.
+    start_state: Vec<u8>,
+    frames: Vec<ReplayFrame>,
+}
+
+impl Replay {
+    /// Get the recorded frames.
+    #[inline]
+    pub fn frames(&self) -> &[ReplayFrame] {
+        &self.frames
+    }
+
+    /// Serialize the replay for saving to a file.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut w = Vec::with_capacity(self.start_state.len() + self.frames.len() * 11 + 13);
+        w.extend_from_slice(MAGIC);
+        w.push(VERSION);
+        w.extend((self.start_state.len() as u32).to_le_bytes());
+        w.extend(&self.start_state);
+        w.extend((self.frames.len() as u32).to_le_bytes());
+        for frame in &self.frames {
+            let keys = (0..16).fold(0u16, |keys, i| keys | (frame.keys[i] as u16) << i);
+            w.extend(keys.to_le_bytes());
+            w.push(frame.released.unwrap_or(0xFF));
+            // A hash of 0 marks an unchecked frame
+            w.extend(frame.hash.unwrap_or(0).to_le_bytes());
+        }
+        w
+    }
+
+    /// Read a replay made by [`Replay::to_bytes`].
+    pub fn from_bytes(data: &[u8]) -> Result<Replay, StateError> {
+        let mut r = Reader(data);
+        if r.take(4).ok() != Some(MAGIC.as_slice()) {
+            return Err(StateError::NotAState);
+        }
+        let version = r.u8()?;
+        if version != VERSION {
+            return Err(StateError::UnsupportedVersion(version));
+        }
+
+        let len = r.u32()? as usize;
+        let start_state = r.take(len)?.to_vec();
+        let frame_count = r.u32()?;
+        let frames = (0..frame_count)
+            .map(|_| {
+                let keys = r.u16()?;
+                let released = r.u8()?;
+                Ok(ReplayFrame {
+                    keys: std::array::from_fn(|i| keys >> i & 1 == 1),
+                    released: (released < 16).then_some(released),
+                    hash: Some(r.u64()?).filter(|&hash| hash != 0),
+                })
+            })
+            .collect::<Result<_, StateError>>()?;
+        Ok(Replay {
+            start_state,
+            frames,
+        })
+    }
+
+    /// Write the keypad input as text for sharing with other tools: one line per frame with the
+    /// held keys as hex digits, like `5A`, or `-` if no key is held. The starting state and the
+    /// state hashes are left out.
+    pub fn to_text(&self) -> String {
+        let mut text = String::with_capacity(self.frames.len() * 3);
+        for frame in &self.frames {
+            let keys: String = (0..16)
+                .filter(|&key| frame.keys[key])
+                .map(|key| {
+                    char::from_digit(key as u32, 16)
+                        .unwrap_or('0')
+                        .to_ascii_uppercase()
+                })
+                .collect();
+            text += if keys.is_empty() { "-" } else { &keys };
+            text.push('\n');
+        }
+        text
+    }
+
+    /// Read keypad input written by [`Replay::to_text`], to be played back from `start_state`.
+    /// A key that is held in one frame and not in the next resolves `Fx0A`. The frames are not
+    /// checked during playback because the text has no state hashes.
+    ///
+    /// Empty lines and lines starting with `#` are ignored.
+    pub fn from_text(text: &str, start_state: Vec<u8>) -> Result<Replay, String> {
+        let mut frames: Vec<ReplayFrame> = Vec::new();
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let mut keys = [false; 16];
+            if line != "-" {
+                for c in line.chars() {
+                    let key = c
+                        .to_digit(16)
+                        .ok_or_else(|| format!("Line {}: '{c}' is not a key", i + 1))?;
+                    keys[key as usize] = true;
+                }
+            }
+            let held = frames.last().map_or([false; 16], |frame| frame.keys);
+            frames.push(ReplayFrame {
+                keys,
+                released: (0..16).find(|&key| held[key as usize] && !keys[key as usize]),
+                hash: None,
+            });
+        }
+        Ok(Replay {
+            start_state,
+            frames,
+        })
+    }
+}
+
+/// A replay being recorded or played back. See [`Chip8::start_replay_recording`] and
+/// [`Chip8::start_replay_playback`].
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ReplaySession {
+    replay: Replay,
+    /// `false` while recording.
+    playing: bool,
+    /// The number of frames recorded or played back.
+    position: usize,
+    /// The key released since the last recorded frame.
+    released: Option<u8>,
+    /// The first played back frame whose state hash differs from the recording.
+    divergence: Option<usize>,
+}
+
+impl ReplaySession {
+    /// Get the replay, with the frames recorded so far while recording.
+    #[inline]
+    pub const fn replay(&self) -> &Replay {
+        &self.replay
+    }
+    /// Check if the replay is being played back rather than recorded.
+    #[inline]
+    pub const fn is_playing(&self) -> bool {
+        self.playing
+    }
+    /// Check if every frame of the replay was played back.
+    #[inline]
+    pub fn is_finished(&self) -> bool {
+        self.playing && self.position == self.replay.frames.len()
+    }
+    /// Get the number of frames recorded or played back.
+    #[inline]
+    pub const fn position(&self) -> usize {
+        self.position
+    }
+    /// Get the number of frames in the replay.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.replay.frames.len()
+    }
+    /// Check if the replay has no frames.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.replay.frames.is_empty()
+    }
+    /// Get the first played back frame (counted from the start of the replay) after which the
+    /// machine differed from the recording, if any.
+    #[inline]
+    pub const fn divergence(&self) -> Option<usize> {
+        self.divergence
+    }
+}
+
+/// Hash bytes with FNV-1a, which does not change between Rust versions like the standard hasher
+/// may.
+pub(crate) fn fnv1a(bytes: impl Iterator<Item = u8>) -> u64 {
+    bytes.fold(0xCBF29CE484222325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001B3)
+    })
+}
+
+impl Chip8 {
+    /// Compute a cheap hash of the machine state (everything [`Chip8::save_state`] saves), for
+    /// telling whether two machines are in the same state.
+    pub fn state_hash(&self) -> u64 {
+        fnv1a(self.save_state().into_iter())
+    }
+
+    /// Start recording the keypad input of every frame from the current state, replacing any replay
+    /// that is being recorded or played back.
+    pub fn start_replay_recording(&mut self) {
+        self.replay = Some(ReplaySession {
+            replay: Replay {
+                start_state: self.save_state(),
+                frames: Vec::new(),
+            },
+            playing: false,
+            position: 0,
+            released: None,
+            divergence: None,
+        });
+    }
+
+    /// Restore the starting state of `replay` and play back its input, checking after every frame
+    /// that the machine is in the recorded state. Keypad input from [`Chip8::set_keys`] and
+    /// [`Chip8::save_awaited_key`] is ignored until the playback finishes.
+    pub fn start_replay_playback(&mut self, replay: Replay) -> Result<(), StateError> {
+        self.load_state(&replay.start_state)?;
+        let first = replay.frames.first().copied();
+        self.replay = Some(ReplaySession {
+            replay,
+            playing: true,
+            position: 0,
+            released: None,
+            divergence: None,
+        });
+        if let Some(frame) = first {
+            self.apply_replay_frame(frame);
+        }
+        Ok(())
+    }
+
+    /// Stop recording or playing back and get the replay, if there is one.
+    #[inline]
+    pub fn stop_replay(&mut self) -> Option<Replay> {
+        self.replay.take().map(|session| session.replay)
+    }
+
+    /// Get the replay being recorded or played back, if any.
+    #[inline]
+    pub const fn get_replay(&self) -> Option<&ReplaySession> {
+        self.replay.as_ref()
+    }
+
+    /// Check if a replay is feeding the keypad.
+    #[inline]
+    pub(crate) fn is_playing_replay(&self) -> bool {
+        self.replay
+            .as_ref()
+            .is_some_and(|session| session.playing && !session.is_finished())
+    }
+
+    /// Remember a key release that resolved `Fx0A` for the next recorded frame.
+    #[inline]
+    pub(crate) fn record_replay_release(&mut self, key: u8) {
+        if let Some(session) = self.replay.as_mut().filter(|session| !session.playing) {
+            session.released = Some(key);
+        }
+    }
+
+    /// Record the completed frame, or check it against the recording and set up the input of the
+    /// next one.
+    pub(crate) fn replay_frame_completed(&mut self) {
+        if self.replay.is_none() {
+            return;
+        }
+        let hash = self.state_hash();
+        let Some(session) = &mut self.replay else {
+            return;
+        };
+
+        if !session.playing {
+            session.replay.frames.push(ReplayFrame {
+                keys: self.keypad,
+                released: session.released.take(),
+                hash: Some(hash),
+            });
+            session.position += 1;
+            return;
+        }
+        if session.is_finished() {
+            return;
+        }
+        if session.divergence.is_none()
+            && session.replay.frames[session.position]
+                .hash
+                .is_some_and(|recorded| recorded != hash)
+        {
+            session.divergence = Some(session.position);
+        }
+        session.position += 1;
+        if let Some(&frame) = session.replay.frames.get(session.position) {
+            self.apply_replay_frame(frame);
+        }
+    }
+
+    /// Set the keypad to a recorded frame's input.
+    fn apply_replay_frame(&mut self, frame: ReplayFrame) {
+        self.keypad = frame.keys;
+        if let Some(key) = frame.released {
+            if self.awaiting_key {
+                self.V[self.key_destination] = key;
+                self.awaiting_key = false;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Waits for a key in V1, then counts cycles in V2 and mixes random numbers into V1 until key 0
+    /// is held, and starts over.
+    const PROGRAM: &[u8] = &[
+        0xF1, 0x0A, 0xC3, 0xFF, 0x81, 0x34, 0x72, 0x01, 0xE4, 0x9E, 0x12, 0x02, 0x12, 0x00,
+    ];
+    const FRAMES: usize = 30;
+
+    fn machine() -> Chip8 {
+        let mut chip8 = Chip8::chip8();
+        chip8.quirks.deterministic_random = true;
+        chip8.reset_and_load(PROGRAM);
+        chip8.start();
+        chip8
+    }
+
+    /// The keypad during a frame: key 5 tapped, key 0 held, key A tapped.
+    fn keys(frame: usize) -> [bool; 16] {
+        let mut keys = [false; 16];
+        match frame {
+            3..=4 => keys[5] = true,
+            10..=12 => keys[0] = true,
+            20 => keys[0xA] = true,
+            _ => {}
+        }
+        keys
+    }
+
+    /// Run `FRAMES` frames, reporting input like a frontend does.
+    fn run(chip8: &mut Chip8) {
+        for frame in 0..FRAMES {
+            let held = keys(frame.saturating_sub(1));
+            let keys = keys(frame);
+            for key in 0..16 {
+                if held[key] && !keys[key] {
+                    chip8.key_released(key as u8);
+                }
+            }
+            chip8.set_keys(keys);
+            chip8.run_frame();
+        }
+    }
+
+    /// Record the input of a run, and the machine at the end of it.
+    fn record() -> (Replay, Chip8) {
+        let mut chip8 = machine();
+        chip8.start_replay_recording();
+        run(&mut chip8);
+        let replay = chip8.stop_replay().unwrap();
+        (replay, chip8)
+    }
+
+    #[test]
+    fn play_back() {
+        let (replay, recorded) = record();
+        assert_eq!(replay.frames().len(), FRAMES);
+        assert_eq!(replay.frames()[5].released, Some(5));
+        let replay = Replay::from_bytes(&replay.to_bytes()).unwrap();
+
+        // Different input is ignored during the playback
+        let mut chip8 = Chip8::chip8();
+        chip8.start_replay_playback(replay).unwrap();
+        chip8.start();
+        for _ in 0..FRAMES {
+            chip8.set_keys([true; 16]);
+            chip8.save_awaited_key(0xF);
+            chip8.run_frame();
+        }
+        let session = chip8.get_replay().unwrap();
+        assert!(session.is_finished());
+        assert_eq!(session.divergence(), None);
+        assert_eq!(chip8.save_state(), recorded.save_state());
+    }
+
+    #[test]
+    fn divergence() {
+        let (replay, _) = record();
+        // Drop key 0 from frame 10
+        let mut bytes = replay.to_bytes();
+        let frame = bytes.len() - (FRAMES - 10) * 11;
+        bytes[frame..frame + 2].copy_from_slice(&[0, 0]);
+
+        let mut chip8 = Chip8::chip8();
+        chip8
+            .start_replay_playback(Replay::from_bytes(&bytes).unwrap())
+            .unwrap();
+        chip8.start();
+        for _ in 0..FRAMES {
+            chip8.run_frame();
+        }
+        let session = chip8.get_replay().unwrap();
+        assert!(session.is_finished());
+        assert_eq!(session.divergence(), Some(10));
+    }
+
+    #[test]
+    fn text() {
+        let (replay, recorded) = record();
+        let text = replay.to_text();
+        let lines: Vec<_> = text.lines().collect();
+        assert_eq!(lines.len(), FRAMES);
+        assert_eq!(
+            (lines[0], lines[3], lines[10], lines[20]),
+            ("-", "5", "0", "A")
+        );
+
+        let text = format!("# Exported\n\n{text}");
+        let imported = Replay::from_text(&text, replay.start_state.clone()).unwrap();
+        assert_eq!(imported.frames().len(), FRAMES);
+        for (imported, frame) in imported.frames().iter().zip(replay.frames()) {
+            assert_eq!(imported.keys, frame.keys);
+            assert_eq!(imported.hash, None);
+        }
+
+        let mut chip8 = Chip8::chip8();
+        chip8.start_replay_playback(imported).unwrap();
+        chip8.start();
+        for _ in 0..FRAMES {
+            chip8.run_frame();
+        }
+        assert_eq!(chip8.save_state(), recorded.save_state());
+
+        assert_eq!(
+            Replay::from_text("-\n5G\n", Vec::new()),
+            Err("Line 2: 'G' is not a key".to_string())
+        );
+    }
+
+    #[test]
+    fn invalid_replays() {
+        let (replay, _) = record();
+        let bytes = replay.to_bytes();
+        assert_eq!(Replay::from_bytes(b"ECHS"), Err(StateError::NotAState));
+        assert_eq!(
+            Replay::from_bytes(&bytes[..bytes.len() - 1]),
+            Err(StateError::Truncated)
+        );
+        let mut newer = bytes;
+        newer[MAGIC.len()] = VERSION + 1;
+        assert_eq!(
+            Replay::from_bytes(&newer),
+            Err(StateError::UnsupportedVersion(VERSION + 1))
+        );
+    }
+}
@@ -0,0 +1,437 @@
+use std::collections::VecDeque;
+
+use rand::Rng;
+
+use crate::{script::InputScript, Chip8, Expression};
+
+/// How many frames the rewind buffer remembers: ten seconds.
+pub const REWIND_FRAMES: usize = 600;
+
+/// Input given to the interpreter in the middle of a frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+enum RewindInput {
+    /// The keypad changed with [`Chip8::set_keys`].
+    Keys([bool; 16]),
+    /// A key release resolved `Fx0A` with [`Chip8::save_awaited_key`].
+    Release(u8),
+}
+
+/// The machine at the start of a frame and everything that is needed to execute the frame again
+/// exactly as it ran.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+struct RewindFrame {
+    /// The frame count of the machine.
+    frame: u64,
+    /// A [`Chip8::save_state`] of the machine.
+    state: Vec<u8>,
+    /// The parts of the machine that save states leave out.
+    keypad: [bool; 16],
+    input_script: InputScript,
+    persistent_flags: [u8; 16],
+    /// The random numbers drawn by `Cxnn` during the frame, in order.
+    randoms: Vec<u8>,
+    /// The input given during the frame and the cycle of the frame before which it was given.
+    inputs: Vec<(u32, RewindInput)>,
+}
+
+/// The random numbers and input of a frame that is executed again.
+#[derive(Debug, Default, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+struct Playback {
+    randoms: VecDeque<u8>,
+    inputs: VecDeque<(u32, RewindInput)>,
+}
+
+/// The snapshots of the last [`REWIND_FRAMES`] frames, or the input of a frame being executed
+/// again.
+#[derive(Debug, Default, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub(crate) struct RewindBuffer {
+    frames: VecDeque<RewindFrame>,
+    /// The random numbers and input that are played back instead of recorded while a frame is
+    /// executed again.
+    playback: Option<Playback>,
+}
+
+impl RewindBuffer {
+    /// Forget every snapshot.
+    #[inline]
+    pub(crate) fn clear(&mut self) {
+        self.frames.clear();
+    }
+
+    /// Get the snapshot of the frame in progress, if it has one and nothing is played back.
+    #[inline]
+    fn recording(&mut self, frame: u64) -> Option<&mut RewindFrame> {
+        if self.playback.is_some() {
+            return None;
+        }
+        self.frames
+            .back_mut()
+            .filter(|snapshot| snapshot.frame == frame)
+    }
+}
+
+impl Chip8 {
+    /// Get the number of frames that can be rewound.
+    #[inline]
+    pub fn rewind_frames(&self) -> usize {
+        self.rewind.frames.len()
+    }
+
+    /// Take a snapshot for the rewind buffer if the frame in progress does not have one yet.
+    pub(crate) fn record_rewind_frame(&mut self) {
+        if self.rewind.playback.is_some()
+            || self
+                .rewind
+                .frames
+                .back()
+                .is_some_and(|snapshot| snapshot.frame == self.frame_count)
+        {
+            return;
+        }
+        if self.rewind.frames.len() == REWIND_FRAMES {
+            self.rewind.frames.pop_front();
+        }
+        self.rewind.frames.push_back(RewindFrame {
+            frame: self.frame_count,
+            state: self.save_state(),
+            keypad: self.keypad,
+            input_script: self.input_script.clone(),
+            persistent_flags: self.persistent_flags,
+            randoms: Vec::new(),
+            inputs: Vec::new(),
+        });
+    }
+
+    /// Remember input given in the middle of a frame so the frame can be executed again.
+    #[inline]
+    fn record_rewind_input(&mut self, input: RewindInput) {
+        let cycle = self.frame_cycle;
+        if let Some(snapshot) = self.rewind.recording(self.frame_count) {
+            snapshot.inputs.push((cycle, input));
+        }
+    }
+    #[inline]
+    pub(crate) fn record_rewind_keys(&mut self, keys: [bool; 16]) {
+        if keys != self.keypad {
+            self.record_rewind_input(RewindInput::Keys(keys));
+        }
+    }
+    #[inline]
+    pub(crate) fn record_rewind_release(&mut self, key: u8) {
+        self.record_rewind_input(RewindInput::Release(key));
+    }
+
+    /// Draw a random number for `Cxnn`. The numbers are remembered, so executing a frame again
+    /// draws the same ones. The numbers of the `deterministic_random` quirk are part of the machine state and
+    /// need not be remembered.
+    pub(crate) fn random_byte(&mut self) -> u8 {
+        if self.quirks.deterministic_random {
+            return self.deterministic_random_byte();
+        }
+        if let Some(playback) = &mut self.rewind.playback {
+            if let Some(byte) = playback.randoms.pop_front() {
+                return byte;
+            }
+        }
+        let byte = rand::thread_rng().gen();
+        if let Some(snapshot) = self.rewind.recording(self.frame_count) {
+            snapshot.randoms.push(byte);
+        }
+        byte
+    }
+
+    /// Run backwards to the last time `condition` became true (not 0): the interpreter is put in
+    /// the state right after the cycle that made it true, which is where a value first went
+    /// wrong. Newer snapshots are forgotten and the interpreter is stopped.
+    ///
+    /// The rewind buffer only has the machine at the start of each frame, so the frames are
+    /// executed again from their snapshots with the same random numbers and input. Returns the
+    /// frame count and cycle of the new state, or `None` if the condition did not become true in
+    /// the rewind buffer, leaving the interpreter unchanged.
+    pub fn reverse_until(&mut self, condition: &Expression) -> Option<(u64, u32)> {
+        let now = (self.frame_count, self.frame_cycle);
+        let holds = |chip8: &Chip8| condition.evaluate(chip8).is_some_and(|value| value != 0);
+
+        // Execute every frame again up to now and find the last step that made the condition true
+        let mut found = None;
+        let mut chip8 = self.restore_rewind_frame(0)?;
+        let mut held = holds(&chip8);
+        for index in 0..self.rewind.frames.len() {
+            if index > 0 {
+                let snapshot = &self.rewind.frames[index];
+                if chip8.frame_count != snapshot.frame || chip8.save_state() != snapshot.state {
+                    // Something other than the recorded input changed the machine between frames
+                    chip8 = self.restore_rewind_frame(index)?;
+                    held = holds(&chip8);
+                } else {
+                    chip8.keypad = snapshot.keypad;
+                    chip8.input_script = snapshot.input_script.clone();
+                    chip8.rewind.playback = Some(playback(snapshot));
+                }
+            }
+            let frame = chip8.frame_count;
+            let mut steps = 0;
+            while chip8.frame_count == frame && (chip8.frame_count, chip8.frame_cycle) < now {
+                if !chip8.rewind_step() {
+                    break;
+                }
+                steps += 1;
+                let holds_now = holds(&chip8);
+                if holds_now && !held && (chip8.frame_count, chip8.frame_cycle) < now {
+                    found = Some((index, steps));
+                }
+                held = holds_now;
+            }
+        }
+
+        // Go back to that step and forget what came after it
+        let (index, steps) = found?;
+        self.rewind_to(index, steps)
+    }
+
+    /// Go back one cycle while stopped, to the state before the last executed instruction.
+    /// Returns `false` if the rewind buffer does not reach back that far, leaving the interpreter
+    /// unchanged.
+    ///
+    /// Like [`Chip8::reverse_until`], the frame is executed again from its snapshot with the same
+    /// random numbers and input, so changes made to the machine by hand since then are lost.
+    pub fn step_back_cycle(&mut self) -> bool {
+        let now = (self.frame_count, self.frame_cycle);
+        let Some(index) = self.step_back_index() else {
+            return false;
+        };
+        let Some(mut chip8) = self.restore_rewind_frame(index) else {
+            return false;
+        };
+        let mut steps = 0;
+        while (chip8.frame_count, chip8.frame_cycle) < now && chip8.rewind_step() {
+            steps += 1;
+        }
+        steps > 0 && self.rewind_to(index, steps - 1).is_some()
+    }
+
+    /// Go back to the start of the current frame while stopped, or to the start of the previous
+    /// frame if the current one did not execute any cycle yet. Returns `false` if the rewind buffer
+    /// does not reach back that far, leaving the interpreter unchanged.
+    pub fn step_back_frame(&mut self) -> bool {
+        self.step_back_index()
+            .is_some_and(|index| self.rewind_to(index, 0).is_some())
+    }
+
+    /// Get the snapshot a step back starts from: that of the current frame, or of the previous
+    /// frame if the current one did not execute any cycle yet.
+    fn step_back_index(&self) -> Option<usize> {
+        let frame = if self.frame_cycle > 0 {
+            self.frame_count
+        } else {
+            self.frame_count.checked_sub(1)?
+        };
+        self.rewind
+            .frames
+            .iter()
+            .rposition(|snapshot| snapshot.frame == frame)
+    }
+
+    /// Put the interpreter in the state `steps` cycles after the snapshot at `index` and forget
+    /// the snapshots, random numbers and input that came after it. Breakpoints are kept and the
+    /// interpreter is stopped. Returns the frame count and cycle of the new state.
+    fn rewind_to(&mut self, index: usize, steps: usize) -> Option<(u64, u32)> {
+        let mut chip8 = self.restore_rewind_frame(index)?;
+        for _ in 0..steps {
+            chip8.rewind_step();
+        }
+        let left = chip8.rewind.playback.take().unwrap_or_default();
+        let mut rewind = std::mem::take(&mut self.rewind);
+        rewind.frames.truncate(index + 1);
+        if let Some(snapshot) = rewind.frames.back_mut() {
+            snapshot
+                .randoms
+                .truncate(snapshot.randoms.len() - left.randoms.len());
+            snapshot
+                .inputs
+                .truncate(snapshot.inputs.len() - left.inputs.len());
+        }
+        let breakpoints = std::mem::take(&mut self.breakpoints);
+        let symbols = std::mem::take(&mut self.symbols);
+        *self = chip8;
+        self.rewind = rewind;
+        self.breakpoints = breakpoints;
+        self.symbols = symbols;
+        Some((self.frame_count, self.frame_cycle))
+    }
+
+    /// Make a stopped copy of the machine at the start of a frame of the rewind buffer, set up to
+    /// play back the frame's random numbers and input.
+    fn restore_rewind_frame(&self, index: usize) -> Option<Chip8> {
+        let snapshot = self.rewind.frames.get(index)?;
+        let mut chip8 = Chip8::chip8();
+        chip8.sound_on = self.sound_on;
+        chip8.vip_timing = self.vip_timing;
+        chip8.memory.font_set = self.memory.font_set;
+        chip8.memory.custom_font = self.memory.custom_font.clone();
+        chip8.persistent_flags_rom = self.persistent_flags_rom.clone();
+        chip8.persistent_storage = self.persistent_storage.clone();
+        chip8.load_state(&snapshot.state).ok()?;
+        chip8.keypad = snapshot.keypad;
+        chip8.input_script = snapshot.input_script.clone();
+        chip8.persistent_flags = snapshot.persistent_flags;
+        chip8.rewind.playback = Some(playback(snapshot));
+        Some(chip8)
+    }
+
+    /// Give the input recorded before the next cycle and execute it, completing the frame after its
+    /// last cycle. Returns `false` if the program counter left memory and nothing was executed.
+    fn rewind_step(&mut self) -> bool {
+        if let Some(playback) = &mut self.rewind.playback {
+            let mut given = Vec::new();
+            while let Some(&(_, input)) = playback
+                .inputs
+                .front()
+                .filter(|(cycle, _)| *cycle <= self.frame_cycle)
+            {
+                given.push(input);
+                playback.inputs.pop_front();
+            }
+            for input in given {
+                match input {
+                    RewindInput::Keys(keys) => self.keypad = keys,
+                    RewindInput::Release(key) => {
+                        if self.awaiting_key {
+                            self.V[self.key_destination] = key;
+                            self.awaiting_key = false;
+                        }
+                    }
+                }
+            }
+        }
+        let before = (self.frame_count, self.frame_cycle);
+        self.step_cycle();
+        (self.frame_count, self.frame_cycle) != before
+    }
+}
+
+/// Set up playing back the random numbers and input of a frame.
+fn playback(snapshot: &RewindFrame) -> Playback {
+    Playback {
+        randoms: snapshot.randoms.iter().copied().collect(),
+        inputs: snapshot.inputs.iter().copied().collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Draws a random number into V1 and counts V2 up in a loop.
+    const PROGRAM: &[u8] = &[0xC1, 0xFF, 0x72, 0x01, 0x12, 0x00];
+
+    fn machine() -> Chip8 {
+        let mut chip8 = Chip8::chip8();
+        chip8.reset_and_load(PROGRAM);
+        chip8.start();
+        chip8
+    }
+
+    /// Run `frames` frames and get the save state before each of them and after the last.
+    fn run(chip8: &mut Chip8, frames: usize) -> Vec<Vec<u8>> {
+        let mut states = vec![chip8.save_state()];
+        for _ in 0..frames {
+            chip8.run_frame();
+            states.push(chip8.save_state());
+        }
+        chip8.stop();
+        states
+    }
+
+    #[test]
+    fn step_back_frames() {
+        let mut chip8 = machine();
+        let states = run(&mut chip8, 10);
+        assert_eq!(chip8.rewind_frames(), 10);
+        for n in 1..=4 {
+            assert!(chip8.step_back_frame());
+            assert_eq!(chip8.save_state(), states[10 - n]);
+            assert!(!chip8.is_running());
+        }
+        // The frames after the new state are forgotten
+        assert_eq!(chip8.rewind_frames(), 7);
+    }
+
+    #[test]
+    fn step_back_cycles() {
+        let mut chip8 = machine();
+        run(&mut chip8, 2);
+        let mut states = Vec::new();
+        for _ in 0..5 {
+            states.push(chip8.save_state());
+            chip8.step_cycle();
+        }
+        // The frame is executed again with the same random numbers
+        for state in states.iter().rev() {
+            assert!(chip8.step_back_cycle());
+            assert_eq!(&chip8.save_state(), state);
+        }
+    }
+
+    #[test]
+    fn full_buffer() {
+        let extra = 20;
+        let mut chip8 = machine();
+        let states = run(&mut chip8, REWIND_FRAMES + extra);
+        assert_eq!(chip8.rewind_frames(), REWIND_FRAMES);
+        for _ in 0..REWIND_FRAMES {
+            assert!(chip8.step_back_frame());
+        }
+        // The oldest frames were dropped to make room
+        assert_eq!(chip8.save_state(), states[extra]);
+        assert_eq!(chip8.rewind_frames(), 1);
+
+        // Going back past the oldest frame leaves the interpreter unchanged
+        assert!(!chip8.step_back_frame());
+        assert!(!chip8.step_back_cycle());
+        assert_eq!(chip8.save_state(), states[extra]);
+    }
+
+    #[test]
+    fn reverse_until() {
+        let mut chip8 = machine();
+        let mut random = None;
+        for _ in 0..40 * chip8.execution_speed {
+            chip8.step_cycle();
+            if chip8.V[2] == 100 && random.is_none() {
+                random = Some((chip8.V[1], chip8.frame_count, chip8.frame_cycle));
+            }
+        }
+        chip8.stop();
+
+        let condition = Expression::parse("V2 == 100").unwrap();
+        let (random, frame, cycle) = random.unwrap();
+        assert_eq!(chip8.reverse_until(&condition), Some((frame, cycle)));
+        // Right after the instruction that made the condition true, with the same random number
+        assert_eq!(chip8.V[2], 100);
+        assert_eq!(chip8.program_counter, 0x204);
+        assert_eq!(chip8.V[1], random);
+        assert!(!chip8.is_running());
+    }
+
+    #[test]
+    fn reverse_until_outside_buffer() {
+        let mut chip8 = machine();
+        run(&mut chip8, REWIND_FRAMES + 10);
+        let state = chip8.save_state();
+        // True since before the oldest snapshot
+        let condition = Expression::parse("V3 == 0").unwrap();
+        assert_eq!(chip8.reverse_until(&condition), None);
+        assert_eq!(chip8.save_state(), state);
+        assert_eq!(chip8.rewind_frames(), REWIND_FRAMES);
+    }
+
+    #[test]
+    fn empty_buffer() {
+        let mut chip8 = machine();
+        chip8.stop();
+        assert_eq!(chip8.rewind_frames(), 0);
+        assert!(!chip8.step_back_frame());
+        assert!(!chip8.step_back_cycle());
+    }
+}
@@ -0,0 +1,94 @@
+use std::collections::VecDeque;
+
+/// A single timed keypad event of an [`InputScript`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum InputStep {
+    /// Hold a key down for a number of frames, then release it.
+    Hold { key: u8, frames: u32 },
+    /// Press and release a key in one frame. Same as holding it for one frame.
+    Press(u8),
+    /// Do nothing for a number of frames.
+    Wait(u32),
+}
+
+impl InputStep {
+    /// How many frames the step lasts.
+    #[inline]
+    pub const fn frames(&self) -> u32 {
+        match self {
+            InputStep::Hold { frames, .. } => *frames,
+            InputStep::Press(_) => 1,
+            InputStep::Wait(frames) => *frames,
+        }
+    }
+
+    /// The key held down during the step, if any.
+    #[inline]
+    pub const fn key(&self) -> Option<u8> {
+        match self {
+            InputStep::Hold { key, .. } => Some(*key & 0xF),
+            InputStep::Press(key) => Some(*key & 0xF),
+            InputStep::Wait(_) => None,
+        }
+    }
+}
+
+/// A queue of timed keypad events that is played back one frame at a time.
+///
+/// Used for automated gameplay tests and demo playback.
+#[derive(Debug, Default, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct InputScript {
+    /// Steps that have not finished yet. The first one is currently playing.
+    steps: VecDeque<InputStep>,
+    /// How many frames are left of the first step. `None` if it has not started yet.
+    frames_left: Option<u32>,
+}
+
+impl InputScript {
+    /// Add a step to the end of the queue.
+    #[inline]
+    pub fn push(&mut self, step: InputStep) {
+        self.steps.push_back(step);
+    }
+
+    /// Remove all steps.
+    #[inline]
+    pub fn clear(&mut self) {
+        self.steps.clear();
+        self.frames_left = None;
+    }
+
+    /// Get the number of steps left, including the one currently playing.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.steps.len()
+    }
+
+    /// Get the state of the keypad as set by the current step.
+    pub fn keys(&self) -> [bool; 16] {
+        let mut keys = [false; 16];
+        if let Some(key) = self.steps.front().and_then(InputStep::key) {
+            keys[key as usize] = true;
+        }
+        keys
+    }
+
+    /// Complete a frame of the current step.
+    ///
+    /// Returns the key that was released if a step holding a key just finished.
+    pub fn advance(&mut self) -> Option<u8> {
+        let step = *self.steps.front()?;
+        let frames_left = self.frames_left.get_or_insert(step.frames());
+        *frames_left = frames_left.saturating_sub(1);
+
+        if *frames_left == 0 {
+            self.steps.pop_front();
+            self.frames_left = None;
+            step.key()
+        } else {
+            None
+        }
+    }
+}
@@ -0,0 +1,147 @@
+use std::{
+    collections::HashMap,
+    fs,
+    hash::{DefaultHasher, Hash, Hasher},
+    io,
+};
+
+use crate::gui::Windows;
+
+/// The file the per-ROM debugging sessions are stored in.
+const SESSIONS_PATH: &str = "sessions.dat";
+
+/// The debugging setup of a ROM: its watch expressions and which windows are open.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct DebugSession {
+    /// The source of each watch expression and whether it breaks when true.
+    pub watches: Vec<(String, bool)>,
+    pub windows: Windows,
+}
+
+/// Get the name of every window that is remembered in sessions and whether it is open.
+fn windows_by_name(windows: &mut Windows) -> Vec<(&'static str, &mut bool)> {
+    vec![
+        ("rom", &mut windows.rom),
+        ("display_settings", &mut windows.display_settings),
+        ("history", &mut windows.history),
+        ("disassembly", &mut windows.disassembly),
+        ("memory_log", &mut windows.memory_log),
+        ("watches", &mut windows.watches),
+        ("slots", &mut windows.slots),
+        ("sound", &mut windows.sound),
+        ("comparison", &mut windows.comparison),
+        ("bisect", &mut windows.bisect),
+        ("lockstep", &mut windows.lockstep),
+        ("attract", &mut windows.attract),
+        ("pop_out_display", &mut windows.pop_out_display),
+        ("shortcuts", &mut windows.shortcuts),
+        #[cfg(feature = "archive")]
+        ("archive", &mut windows.archive),
+        #[cfg(feature = "gamepad")]
+        ("gamepad", &mut windows.gamepad),
+    ]
+}
+
+/// Get the hash a ROM's session is saved under.
+pub fn rom_hash(rom: &[u8]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    rom.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// The debugging sessions of every ROM, so debugging can go on where it was left when a ROM is
+/// loaded again, even after a restart.
+pub struct Sessions {
+    /// The saved sessions, by ROM hash.
+    sessions: HashMap<u64, DebugSession>,
+    /// The hash of the ROM being debugged.
+    rom_hash: u64,
+}
+
+impl Sessions {
+    /// Read the saved sessions.
+    ///
+    /// Every line of the file is a ROM hash followed by `windows` and the names of the open
+    /// windows, or by `watch`, 0 or 1 for whether it breaks and the expression.
+    pub fn load() -> Sessions {
+        let mut sessions: HashMap<u64, DebugSession> = HashMap::new();
+        for line in fs::read_to_string(SESSIONS_PATH)
+            .unwrap_or_default()
+            .lines()
+        {
+            let mut parts = line.splitn(3, ' ');
+            let (Some(hash), Some(kind), rest) = (parts.next(), parts.next(), parts.next()) else {
+                continue;
+            };
+            let Ok(hash) = u64::from_str_radix(hash, 16) else {
+                continue;
+            };
+            let session = sessions.entry(hash).or_default();
+            match (kind, rest) {
+                ("windows", rest) => {
+                    let open: Vec<&str> = rest.unwrap_or_default().split(' ').collect();
+                    for (name, window) in windows_by_name(&mut session.windows) {
+                        *window = open.contains(&name);
+                    }
+                }
+                ("watch", Some(rest)) => {
+                    if let Some((break_on_true, source)) = rest.split_once(' ') {
+                        session
+                            .watches
+                            .push((source.to_string(), break_on_true == "1"));
+                    }
+                }
+                _ => {}
+            }
+        }
+        Sessions {
+            sessions,
+            rom_hash: 0,
+        }
+    }
+
+    /// Get the hash of the ROM being debugged.
+    #[inline]
+    pub fn rom_hash(&self) -> u64 {
+        self.rom_hash
+    }
+
+    /// Remember `current` for the ROM being debugged and switch to another ROM. Returns the saved
+    /// session of the new ROM, if it has one.
+    pub fn switch(&mut self, rom_hash: u64, current: DebugSession) -> Option<DebugSession> {
+        self.remember(current);
+        self.rom_hash = rom_hash;
+        self.sessions.get(&rom_hash).cloned()
+    }
+
+    /// Remember the session of the ROM being debugged. Sessions with nothing to restore are
+    /// forgotten.
+    pub fn remember(&mut self, session: DebugSession) {
+        if session == DebugSession::default() {
+            self.sessions.remove(&self.rom_hash);
+        } else {
+            self.sessions.insert(self.rom_hash, session);
+        }
+    }
+
+    /// Write every session to the file.
+    pub fn save(&self) -> io::Result<()> {
+        let mut lines = Vec::new();
+        for (hash, session) in &self.sessions {
+            let mut windows = session.windows;
+            let open: Vec<&str> = windows_by_name(&mut windows)
+                .into_iter()
+                .filter(|(_, open)| **open)
+                .map(|(name, _)| name)
+                .collect();
+            lines.push(format!("{hash:016x} windows {}", open.join(" ")));
+            for (source, break_on_true) in &session.watches {
+                lines.push(format!(
+                    "{hash:016x} watch {} {source}",
+                    *break_on_true as u8
+                ));
+            }
+        }
+        fs::write(SESSIONS_PATH, lines.join("\n"))
+    }
+}
@@ -0,0 +1,53 @@
+use e_chip::FramePacing;
+
+use crate::{i18n::Language, keymap::KeypadInput};
+
+/// Emulator settings that are not part of the interpreter.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Settings {
+    /// Keyboard to keypad mapping and input handling options.
+    pub keypad: KeypadInput,
+    /// If false, the interpreter is suspended while the window is minimized.
+    pub run_in_background: bool,
+    /// What advances the interpreter to the next frame.
+    pub frame_pacing: FramePacing,
+    /// If true, the machine is saved when the app is closed and can be resumed on the next launch.
+    pub save_on_exit: bool,
+    /// If true, a crash dump file is written whenever the interpreter halts.
+    pub crash_dumps: bool,
+    /// If true, the execution history opens whenever the interpreter halts.
+    pub history_on_halt: bool,
+    /// If true, picking a quirk preset or variant tests whether the interpreter behaves like it.
+    pub verify_quirks: bool,
+    /// If true, the speed is set in instructions per second rather than cycles per frame.
+    pub speed_in_ips: bool,
+    /// If true, loaded ROMs that are in the CHIP-8 database get the variant, quirks, speed, font
+    /// and colors it has for them.
+    #[cfg(feature = "database")]
+    pub use_database: bool,
+    /// The language of the GUI.
+    pub language: Language,
+    /// If true, the loaded ROM is reset and loaded again whenever its file changes on disk.
+    #[cfg(feature = "hot-reload")]
+    pub reload_on_change: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            keypad: KeypadInput::default(),
+            run_in_background: false,
+            frame_pacing: FramePacing::default(),
+            save_on_exit: true,
+            crash_dumps: false,
+            history_on_halt: true,
+            verify_quirks: true,
+            speed_in_ips: false,
+            #[cfg(feature = "database")]
+            use_database: true,
+            language: Language::default(),
+            #[cfg(feature = "hot-reload")]
+            reload_on_change: true,
+        }
+    }
+}
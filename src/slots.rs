@@ -0,0 +1,153 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use e_chip::Chip8;
+use egui::{Color32, ColorImage};
+use web_time::{SystemTime, UNIX_EPOCH};
+
+/// How many quick save slots there are.
+pub const SLOT_COUNT: usize = 10;
+
+/// A save state in a quick save slot, with a screenshot of the display to recognize it by.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SaveSlot {
+    /// The save state of the interpreter.
+    pub state: Vec<u8>,
+    /// The display pixels when the state was saved, row by row.
+    pub screenshot: Vec<bool>,
+    /// The width of the screenshot: 64 in lowres mode, 128 in highres mode.
+    pub width: usize,
+    /// The frame count of the interpreter when the state was saved.
+    pub frame: u64,
+    /// When the state was saved, if known.
+    pub saved: Option<SystemTime>,
+}
+
+impl SaveSlot {
+    /// Save the interpreter and its display.
+    pub fn capture(interpreter: &Chip8) -> SaveSlot {
+        let observation = interpreter.observe();
+        SaveSlot {
+            state: interpreter.save_state(),
+            screenshot: observation.pixels,
+            width: observation.width,
+            frame: interpreter.get_frame_count(),
+            saved: Some(SystemTime::now()),
+        }
+    }
+
+    /// The file a slot is saved to: next to the ROM and named after it (`game.slot1.dat` for
+    /// `game.ch8`), or in the working directory if the ROM was not loaded from a file.
+    fn path(rom: Option<&str>, slot: usize) -> PathBuf {
+        let name = format!("slot{}.dat", slot + 1);
+        match rom {
+            Some(rom) => Path::new(rom).with_extension(name),
+            None => PathBuf::from(name),
+        }
+    }
+
+    /// Read every slot file of a ROM, with `None` for the empty slots.
+    pub fn load_all(rom: Option<&str>) -> [Option<SaveSlot>; SLOT_COUNT] {
+        std::array::from_fn(|slot| SaveSlot::load(rom, slot))
+    }
+
+    /// Read a slot file of a ROM, if there is one. The time it was saved is that of the file.
+    pub fn load(rom: Option<&str>, slot: usize) -> Option<SaveSlot> {
+        let path = SaveSlot::path(rom, slot);
+        let data = fs::read(&path).ok()?;
+        let width = u16::from_le_bytes(data.get(0..2)?.try_into().unwrap()) as usize;
+        let height = u16::from_le_bytes(data.get(2..4)?.try_into().unwrap()) as usize;
+        let frame = u64::from_le_bytes(data.get(4..12)?.try_into().unwrap());
+        let packed = data.get(12..12 + (width * height).div_ceil(8))?;
+        let screenshot = (0..width * height)
+            .map(|i| packed[i / 8] & (0x80 >> (i % 8)) != 0)
+            .collect();
+        Some(SaveSlot {
+            state: data[12 + packed.len()..].to_vec(),
+            screenshot,
+            width,
+            frame,
+            #[cfg(not(target_arch = "wasm32"))]
+            saved: fs::metadata(&path)
+                .and_then(|metadata| metadata.modified())
+                .ok(),
+            // The browser has no file times
+            #[cfg(target_arch = "wasm32")]
+            saved: None,
+        })
+    }
+
+    /// Write the slot file. The screenshot is stored with one bit per pixel.
+    pub fn save(&self, rom: Option<&str>, slot: usize) -> io::Result<()> {
+        let mut data = Vec::with_capacity(12 + self.screenshot.len() / 8 + self.state.len());
+        data.extend((self.width as u16).to_le_bytes());
+        data.extend((self.height() as u16).to_le_bytes());
+        data.extend(self.frame.to_le_bytes());
+        for byte in self.screenshot.chunks(8) {
+            data.push(
+                byte.iter()
+                    .enumerate()
+                    .fold(0, |packed, (i, &on)| packed | (on as u8) << (7 - i)),
+            );
+        }
+        data.extend(&self.state);
+        fs::write(SaveSlot::path(rom, slot), data)
+    }
+
+    /// Delete a slot file of a ROM.
+    pub fn delete(rom: Option<&str>, slot: usize) {
+        let _ = fs::remove_file(SaveSlot::path(rom, slot));
+    }
+
+    /// Get when the state was saved as a UTC date and time, like `2025-01-31 18:05 UTC`.
+    pub fn timestamp(&self) -> String {
+        let Some(seconds) = self
+            .saved
+            .and_then(|saved| saved.duration_since(UNIX_EPOCH).ok())
+            .map(|since| since.as_secs())
+        else {
+            return "-".to_string();
+        };
+        // Count the days in the proleptic Gregorian calendar from 0000-03-01, so leap days come last
+        let days = seconds / 86400 + 719468;
+        let era = days / 146097;
+        let day_of_era = days % 146097;
+        let year_of_era =
+            (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let month_index = (5 * day_of_year + 2) / 153;
+        let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+        let month = if month_index < 10 {
+            month_index + 3
+        } else {
+            month_index - 9
+        };
+        let year = era * 400 + year_of_era + (month <= 2) as u64;
+        let time = seconds % 86400;
+        format!(
+            "{year}-{month:02}-{day:02} {:02}:{:02} UTC",
+            time / 3600,
+            time / 60 % 60
+        )
+    }
+
+    /// Get the height of the screenshot: 32 in lowres mode (48 on the ETI-660), 64 in highres mode.
+    #[inline]
+    pub fn height(&self) -> usize {
+        self.screenshot.len() / self.width.max(1)
+    }
+
+    /// Render the screenshot in the display colors.
+    pub fn thumbnail(&self, background_color: Color32, fill_color: Color32) -> ColorImage {
+        ColorImage {
+            size: [self.width, self.height()],
+            pixels: self
+                .screenshot
+                .iter()
+                .map(|&on| if on { fill_color } else { background_color })
+                .collect(),
+        }
+    }
+}
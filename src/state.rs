@@ -0,0 +1,514 @@
+use std::fmt;
+
+use crate::{
+    Chip8, HaltReason, IndexIncrement, Quirks, Variant, VblankWait, MAX_EXECUTION_SPEED,
+    MAX_TIMER_FREQUENCY,
+};
+
+/// The first bytes of every save state.
+const MAGIC: &[u8; 4] = b"ECHS";
+/// The version of the save state format, to be increased when the format changes.
+const VERSION: u8 = 1;
+
+/// Why a save state could not be loaded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StateError {
+    /// The data is not an E-CHIP save state.
+    NotAState,
+    /// The save state was made by an incompatible version of E-CHIP.
+    UnsupportedVersion(u8),
+    /// The save state ends too early.
+    Truncated,
+    /// The save state contains a value that does not make sense.
+    Invalid(&'static str),
+}
+
+impl fmt::Display for StateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StateError::NotAState => write!(f, "not an E-CHIP save state"),
+            StateError::UnsupportedVersion(v) => write!(f, "unsupported save state version {v}"),
+            StateError::Truncated => write!(f, "the save state is truncated"),
+            StateError::Invalid(what) => write!(f, "the save state has an invalid {what}"),
+        }
+    }
+}
+
+impl std::error::Error for StateError {}
+
+impl Chip8 {
+    /// Serialize the machine: registers, memory, display, stack, variant, quirks, speed, timer
+    /// frequency, start address and the planes and sound of XO-CHIP.
+    ///
+    /// Inspector data (the trace, timeline and profiling) and the running state are not saved.
+    pub fn save_state(&self) -> Vec<u8> {
+        let mut w = Vec::with_capacity(self.memory.ram.len() + 2 * 128 * 64 / 8 + 128);
+        w.extend_from_slice(MAGIC);
+        w.push(VERSION);
+
+        // Configuration
+        w.push(match self.variant {
+            Variant::CHIP8 => 0,
+            Variant::SCHIP11 => 1,
+            Variant::XOCHIP => 2,
+            Variant::SCHIP10 => 3,
+            Variant::CHIP48 => 4,
+            Variant::ETI660 => 5,
+            Variant::CHIP8X => 6,
+            Variant::VIPHYBRID => 7,
+        });
+        let q = &self.quirks;
+        w.extend([
+            q.bitwise_reset_vf as u8,
+            q.direct_shifting as u8,
+            match q.save_load_increment {
+                IndexIncrement::XPlusOne => 0,
+                IndexIncrement::Unchanged => 1,
+                IndexIncrement::X => 2,
+            },
+            q.jump_to_x as u8,
+            match q.wait_for_vblank {
+                VblankWait::Never => 0,
+                VblankWait::LowresOnly => 1,
+                VblankWait::Always => 2,
+            },
+            q.edge_clipping as u8,
+            q.lowres_scroll as u8,
+            q.resolution_clear as u8,
+            q.deterministic_random as u8,
+            q.memory_wrap as u8,
+            q.lowres_tall_sprites as u8,
+            q.key_wait_on_press as u8,
+        ]);
+        w.extend(self.execution_speed.to_le_bytes());
+        w.push(self.vip_speed as u8);
+
+        // Registers
+        w.extend(self.V);
+        w.extend(self.I.to_le_bytes());
+        w.extend(self.program_counter.to_le_bytes());
+        w.push(self.stack_pointer);
+        w.push(self.delay);
+        w.push(self.sound);
+        w.push(self.stack.len() as u8);
+        for address in &self.stack {
+            w.extend(address.to_le_bytes());
+        }
+
+        // Devices
+        w.extend(&self.memory.ram);
+        w.push(self.highres as u8);
+        w.extend((self.display.planes[0].len() as u32).to_le_bytes());
+        // Only XO-CHIP draws to the second plane
+        let planes = if self.variant.supports_xochip() { 2 } else { 1 };
+        for pixels in &self.display.planes[..planes] {
+            for byte in pixels.chunks(8) {
+                w.push(
+                    byte.iter()
+                        .enumerate()
+                        .fold(0, |b, (i, &p)| b | (p as u8) << i),
+                );
+            }
+        }
+
+        // Execution
+        w.extend(self.frame_cycle.to_le_bytes());
+        w.extend(self.frame_vip_cycles.to_le_bytes());
+        w.extend(self.frame_count.to_le_bytes());
+        w.push(self.vblank as u8);
+        w.push(self.awaiting_key as u8);
+        w.push(self.key_destination as u8);
+        match &self.halt_reason {
+            Some(reason) => {
+                w.push(1);
+                match reason {
+                    HaltReason::IllegalInstruction { opcode } => {
+                        w.push(0);
+                        w.extend(opcode.to_le_bytes());
+                    }
+                    HaltReason::MachineCodeUnsupported { opcode } => {
+                        w.push(1);
+                        w.extend(opcode.to_le_bytes());
+                    }
+                    HaltReason::MachineCodeDidNotReturn { address } => {
+                        w.push(2);
+                        w.extend(address.to_le_bytes());
+                    }
+                    HaltReason::MemoryOutOfBounds { address, opcode } => {
+                        w.push(3);
+                        w.extend(address.to_le_bytes());
+                        w.extend(opcode.to_le_bytes());
+                    }
+                    HaltReason::JumpOutOfBounds {
+                        address,
+                        opcode,
+                        target,
+                    } => {
+                        w.push(4);
+                        w.extend(address.to_le_bytes());
+                        w.extend(opcode.to_le_bytes());
+                        w.extend(target.to_le_bytes());
+                    }
+                    HaltReason::StackOverflow { address, opcode } => {
+                        w.push(5);
+                        w.extend(address.to_le_bytes());
+                        w.extend(opcode.to_le_bytes());
+                    }
+                    HaltReason::StackUnderflow { address, opcode } => {
+                        w.push(6);
+                        w.extend(address.to_le_bytes());
+                        w.extend(opcode.to_le_bytes());
+                    }
+                    HaltReason::RomTooLarge { size, capacity } => {
+                        w.push(7);
+                        w.extend((*size as u32).to_le_bytes());
+                        w.extend((*capacity as u32).to_le_bytes());
+                    }
+                }
+            }
+            None => w.push(0),
+        }
+        w.push(self.halt_context.len() as u8);
+        for (address, opcode) in &self.halt_context {
+            w.extend(address.to_le_bytes());
+            w.extend(opcode.to_le_bytes());
+        }
+        w.extend(self.timer_frequency.to_le_bytes());
+        w.extend(self.timer_phase.to_le_bytes());
+        w.extend(self.start_address.to_le_bytes());
+        w.extend(self.random_state.to_le_bytes());
+        if self.variant.supports_xochip() {
+            w.push(self.selected_planes);
+            w.push(self.pitch);
+            match self.audio_pattern {
+                Some(pattern) => {
+                    w.push(1);
+                    w.extend(pattern);
+                }
+                None => w.push(0),
+            }
+        }
+        if self.variant == Variant::CHIP8X {
+            w.push(self.color_map.background);
+            w.extend(&self.color_map.zones);
+        }
+
+        w
+    }
+
+    /// Restore the machine from a save state made by [`Chip8::save_state`]. The interpreter is
+    /// stopped afterwards.
+    ///
+    /// The interpreter is left unchanged if the save state can not be loaded, including when it is
+    /// longer than the format says, since it would not be read as it was written.
+    pub fn load_state(&mut self, state: &[u8]) -> Result<(), StateError> {
+        let mut r = Reader(state);
+        if r.take(MAGIC.len()).map_err(|_| StateError::NotAState)? != MAGIC {
+            return Err(StateError::NotAState);
+        }
+        let version = r.u8()?;
+        if version != VERSION {
+            return Err(StateError::UnsupportedVersion(version));
+        }
+
+        let mut chip8 = match r.u8()? {
+            0 => Chip8::chip8(),
+            1 => Chip8::super_chip1_1(),
+            2 => Chip8::xo_chip(),
+            3 => Chip8::super_chip1_0(),
+            4 => Chip8::chip48(),
+            5 => Chip8::eti660(),
+            6 => Chip8::chip8x(),
+            7 => Chip8::vip_hybrid(),
+            _ => return Err(StateError::Invalid("variant")),
+        };
+        chip8.quirks = Quirks {
+            bitwise_reset_vf: r.bool()?,
+            direct_shifting: r.bool()?,
+            save_load_increment: match r.u8()? {
+                0 => IndexIncrement::XPlusOne,
+                1 => IndexIncrement::Unchanged,
+                2 => IndexIncrement::X,
+                _ => return Err(StateError::Invalid("memory increment quirk")),
+            },
+            jump_to_x: r.bool()?,
+            wait_for_vblank: match r.u8()? {
+                0 => VblankWait::Never,
+                1 => VblankWait::LowresOnly,
+                2 => VblankWait::Always,
+                _ => return Err(StateError::Invalid("vblank quirk")),
+            },
+            edge_clipping: r.bool()?,
+            lowres_scroll: r.bool()?,
+            resolution_clear: r.bool()?,
+            deterministic_random: r.bool()?,
+            memory_wrap: r.bool()?,
+            lowres_tall_sprites: r.bool()?,
+            key_wait_on_press: r.bool()?,
+        };
+        chip8.execution_speed = r.u32()?;
+        if !(1..=MAX_EXECUTION_SPEED).contains(&chip8.execution_speed) {
+            return Err(StateError::Invalid("speed"));
+        }
+        chip8.vip_speed = r.bool()?;
+
+        chip8.V.copy_from_slice(r.take(16)?);
+        chip8.I = r.u16()?;
+        chip8.program_counter = r.u16()?;
+        chip8.stack_pointer = r.u8()?;
+        chip8.delay = r.u8()?;
+        chip8.sound = r.u8()?;
+        let stack_size = r.u8()? as usize;
+        if stack_size == 0 || chip8.stack_pointer as usize > stack_size {
+            return Err(StateError::Invalid("stack"));
+        }
+        chip8.stack_size = stack_size;
+        chip8.stack = (0..stack_size).map(|_| r.u16()).collect::<Result<_, _>>()?;
+
+        let ram_len = chip8.memory.ram.len();
+        chip8.memory.ram.copy_from_slice(r.take(ram_len)?);
+        chip8.highres = r.bool()?;
+        let pixel_count = r.u32()? as usize;
+        if pixel_count != chip8.display.planes[0].len() {
+            return Err(StateError::Invalid("display size"));
+        }
+        let planes = if chip8.variant.supports_xochip() {
+            2
+        } else {
+            1
+        };
+        for pixels in &mut chip8.display.planes[..planes] {
+            let bytes = r.take(pixel_count.div_ceil(8))?;
+            for (i, pixel) in pixels.iter_mut().enumerate() {
+                *pixel = bytes[i / 8] >> (i % 8) & 1 == 1;
+            }
+        }
+
+        chip8.frame_cycle = r.u32()?;
+        chip8.frame_vip_cycles = r.u32()?;
+        chip8.frame_count = r.u64()?;
+        chip8.vblank = r.bool()?;
+        chip8.awaiting_key = r.bool()?;
+        chip8.key_destination = r.u8()? as usize & 0xF;
+        chip8.halt_reason = match r.bool()? {
+            false => None,
+            true => Some(match r.u8()? {
+                0 => HaltReason::IllegalInstruction { opcode: r.u16()? },
+                1 => HaltReason::MachineCodeUnsupported { opcode: r.u16()? },
+                2 => HaltReason::MachineCodeDidNotReturn { address: r.u16()? },
+                3 => HaltReason::MemoryOutOfBounds {
+                    address: r.u16()?,
+                    opcode: r.u16()?,
+                },
+                4 => HaltReason::JumpOutOfBounds {
+                    address: r.u16()?,
+                    opcode: r.u16()?,
+                    target: r.u16()?,
+                },
+                5 => HaltReason::StackOverflow {
+                    address: r.u16()?,
+                    opcode: r.u16()?,
+                },
+                6 => HaltReason::StackUnderflow {
+                    address: r.u16()?,
+                    opcode: r.u16()?,
+                },
+                7 => HaltReason::RomTooLarge {
+                    size: r.u32()? as usize,
+                    capacity: r.u32()? as usize,
+                },
+                _ => return Err(StateError::Invalid("halt reason")),
+            }),
+        };
+        let len = r.u8()?;
+        chip8.halt_context = (0..len)
+            .map(|_| Ok((r.u16()?, r.u16()?)))
+            .collect::<Result<_, StateError>>()?;
+        chip8.timer_frequency = r.u32()?;
+        if !(1..=MAX_TIMER_FREQUENCY).contains(&chip8.timer_frequency) {
+            return Err(StateError::Invalid("timer frequency"));
+        }
+        chip8.timer_phase = r.u32()?;
+        chip8.start_address = r.u16()?;
+        chip8.random_state = r.u16()?;
+        if chip8.variant.supports_xochip() {
+            chip8.selected_planes = r.u8()? & 0b11;
+            chip8.pitch = r.u8()?;
+            chip8.audio_pattern = if r.bool()? {
+                Some(r.take(16)?.try_into().unwrap())
+            } else {
+                None
+            };
+        }
+        if chip8.variant == Variant::CHIP8X {
+            chip8.color_map.background = r.u8()?;
+            let zones = chip8.color_map.zones.len();
+            chip8.color_map.zones = r.take(zones)?.to_vec();
+        }
+        if !r.0.is_empty() {
+            return Err(StateError::Invalid("length"));
+        }
+
+        // Keep the emulator configuration of this interpreter
+        chip8.sound_on = self.sound_on;
+        chip8.vip_timing = self.vip_timing;
+        chip8.memory.font_set = self.memory.font_set;
+        chip8.memory.custom_font = self.memory.custom_font.clone();
+        // Persistent flags are stored per ROM, not in save states
+        chip8.persistent_flags = self.persistent_flags;
+        chip8.persistent_flags_rom = self.persistent_flags_rom.clone();
+        chip8.persistent_flags_error = self.persistent_flags_error.clone();
+        chip8.persistent_storage = self.persistent_storage.clone();
+        chip8.breakpoints = std::mem::take(&mut self.breakpoints);
+        chip8.symbols = std::mem::take(&mut self.symbols);
+        *self = chip8;
+        Ok(())
+    }
+}
+
+/// Reads little-endian values from the front of a save state.
+pub(crate) struct Reader<'a>(pub(crate) &'a [u8]);
+
+impl<'a> Reader<'a> {
+    pub(crate) fn take(&mut self, len: usize) -> Result<&'a [u8], StateError> {
+        if self.0.len() < len {
+            return Err(StateError::Truncated);
+        }
+        let (taken, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Ok(taken)
+    }
+
+    pub(crate) fn u8(&mut self) -> Result<u8, StateError> {
+        Ok(self.take(1)?[0])
+    }
+
+    pub(crate) fn bool(&mut self) -> Result<bool, StateError> {
+        Ok(self.u8()? != 0)
+    }
+
+    pub(crate) fn u16(&mut self) -> Result<u16, StateError> {
+        Ok(u16::from_le_bytes(self.take(2)?.try_into().unwrap()))
+    }
+
+    pub(crate) fn u32(&mut self) -> Result<u32, StateError> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    pub(crate) fn u64(&mut self) -> Result<u64, StateError> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Draws a digit, changes a register and waits for a key.
+    const PROGRAM: [u8; 12] = [
+        0x61, 0x05, 0x62, 0x0A, 0xF1, 0x29, 0xD1, 0x25, 0x71, 0x01, 0xF0, 0x0A,
+    ];
+
+    fn variants() -> [Chip8; 8] {
+        [
+            Chip8::chip8(),
+            Chip8::super_chip1_1(),
+            Chip8::xo_chip(),
+            Chip8::super_chip1_0(),
+            Chip8::chip48(),
+            Chip8::eti660(),
+            Chip8::chip8x(),
+            Chip8::vip_hybrid(),
+        ]
+    }
+
+    /// Run the program until it waits for a key.
+    fn run(mut chip8: Chip8) -> Chip8 {
+        chip8.reset_and_load(&PROGRAM);
+        chip8.start();
+        for _ in 0..100 {
+            chip8.execute_cycle();
+        }
+        assert!(chip8.awaiting_key, "{:?}", chip8.variant);
+        chip8
+    }
+
+    #[test]
+    fn round_trip() {
+        for chip8 in variants() {
+            let chip8 = run(chip8);
+            let state = chip8.save_state();
+            let mut loaded = Chip8::chip8();
+            loaded.load_state(&state).unwrap();
+            assert_eq!(loaded.variant, chip8.variant);
+            assert_eq!(loaded.quirks, chip8.quirks);
+            assert_eq!(loaded.V, chip8.V);
+            assert_eq!(loaded.display.planes, chip8.display.planes);
+            assert_eq!(loaded.save_state(), state, "{:?}", chip8.variant);
+        }
+    }
+
+    #[test]
+    fn halt_reason_round_trip() {
+        let mut chip8 = Chip8::chip8();
+        chip8.reset_and_load(&[0x60, 0xFF, 0xBF, 0xFF]);
+        chip8.start();
+        chip8.execute_cycle();
+        chip8.execute_cycle();
+        assert_eq!(
+            chip8.halt_reason,
+            Some(HaltReason::JumpOutOfBounds {
+                address: 0x202,
+                opcode: 0xBFFF,
+                target: 0x10FE
+            })
+        );
+        let mut loaded = Chip8::chip8();
+        loaded.load_state(&chip8.save_state()).unwrap();
+        assert_eq!(loaded.halt_reason, chip8.halt_reason);
+    }
+
+    #[test]
+    fn invalid_states() {
+        let mut chip8 = run(Chip8::xo_chip());
+        let state = chip8.save_state();
+        assert_eq!(chip8.load_state(b"ECHO"), Err(StateError::NotAState));
+        let mut newer = state.clone();
+        newer[MAGIC.len()] = VERSION + 1;
+        assert_eq!(
+            chip8.load_state(&newer),
+            Err(StateError::UnsupportedVersion(VERSION + 1))
+        );
+        assert_eq!(
+            chip8.load_state(&state[..state.len() - 1]),
+            Err(StateError::Truncated)
+        );
+        let mut longer = state.clone();
+        longer.push(0);
+        assert_eq!(
+            chip8.load_state(&longer),
+            Err(StateError::Invalid("length"))
+        );
+        // The interpreter is left unchanged
+        assert_eq!(chip8.save_state(), state);
+    }
+
+    #[test]
+    fn invalid_speeds() {
+        let mut chip8 = run(Chip8::chip8());
+        for (speed, frequency, error) in [
+            (0, 60, "speed"),
+            (MAX_EXECUTION_SPEED + 1, 60, "speed"),
+            (15, 0, "timer frequency"),
+            (15, MAX_TIMER_FREQUENCY + 1, "timer frequency"),
+        ] {
+            let mut invalid = chip8.clone();
+            invalid.execution_speed = speed;
+            invalid.timer_frequency = frequency;
+            assert_eq!(
+                chip8.load_state(&invalid.save_state()),
+                Err(StateError::Invalid(error))
+            );
+        }
+    }
+}
@@ -0,0 +1,202 @@
+use std::{
+    collections::HashMap,
+    fmt, io,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
+
+/// Where the persistent flags saved by `Fx75` are kept, so programs can read them back with `Fx85`
+/// after a restart. Flags are kept separately for every ROM, which is identified by its SHA-1 in
+/// hex.
+///
+/// [`FileStorage`] is used by default, or [`LocalStorage`] on the web. Embedders can use
+/// [`MemoryStorage`] or their own storage with
+/// [`Chip8::set_persistent_storage`](crate::Chip8::set_persistent_storage).
+pub trait PersistentStorage: Send + Sync {
+    /// Read the flags saved for a ROM. They are zero if the ROM has not saved any yet.
+    fn load(&self, rom: &str) -> io::Result<[u8; 16]>;
+    /// Save the flags of a ROM, replacing the ones saved before.
+    fn save(&self, rom: &str, flags: &[u8; 16]) -> io::Result<()>;
+    /// Describe where the flags of a ROM are kept, like the path of their file, to show to the
+    /// user. `None` if they are not kept anywhere the user can find them.
+    fn location(&self, _rom: &str) -> Option<String> {
+        None
+    }
+}
+
+/// Get the folder [`FileStorage`] keeps persistent flags in by default: `e-chip/flags` in the
+/// platform data directory (`%APPDATA%` on Windows, `~/Library/Application Support` on macOS and
+/// `~/.local/share` on Linux). `None` if the platform has no data directory.
+pub fn persistent_flags_folder() -> Option<PathBuf> {
+    Some(dirs::data_dir()?.join("e-chip").join("flags"))
+}
+
+/// Keeps the flags of every ROM in a file named after its SHA-1 in a folder.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileStorage {
+    /// The folder the files are in. `None` if the platform has no data directory.
+    folder: Option<PathBuf>,
+}
+
+impl FileStorage {
+    /// Keep flags in `folder`, which is created when flags are first saved.
+    pub fn new(folder: impl Into<PathBuf>) -> FileStorage {
+        FileStorage {
+            folder: Some(folder.into()),
+        }
+    }
+
+    /// Get the file the flags of a ROM are kept in.
+    fn path(&self, rom: &str) -> Option<PathBuf> {
+        Some(self.folder.as_ref()?.join(format!("{rom}.dat")))
+    }
+}
+
+impl Default for FileStorage {
+    /// Keep flags in [`persistent_flags_folder`].
+    fn default() -> Self {
+        FileStorage {
+            folder: persistent_flags_folder(),
+        }
+    }
+}
+
+impl PersistentStorage for FileStorage {
+    fn load(&self, rom: &str) -> io::Result<[u8; 16]> {
+        let mut flags = [0; 16];
+        if let Some(path) = self.path(rom) {
+            match std::fs::read(path) {
+                Ok(bytes) => {
+                    for (flag, byte) in flags.iter_mut().zip(bytes) {
+                        *flag = byte;
+                    }
+                }
+                Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(flags)
+    }
+
+    fn save(&self, rom: &str, flags: &[u8; 16]) -> io::Result<()> {
+        let path = self
+            .path(rom)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "there is no data directory"))?;
+        if let Some(folder) = path.parent() {
+            std::fs::create_dir_all(folder)?;
+        }
+        std::fs::write(path, flags)
+    }
+
+    fn location(&self, rom: &str) -> Option<String> {
+        Some(self.path(rom)?.display().to_string())
+    }
+}
+
+/// Keeps flags in memory only, so they last as long as the storage. Clones of an interpreter share
+/// it.
+#[derive(Debug, Default)]
+pub struct MemoryStorage {
+    flags: Mutex<HashMap<String, [u8; 16]>>,
+}
+
+impl PersistentStorage for MemoryStorage {
+    fn load(&self, rom: &str) -> io::Result<[u8; 16]> {
+        let flags = self.flags.lock().unwrap_or_else(|e| e.into_inner());
+        Ok(flags.get(rom).copied().unwrap_or_default())
+    }
+
+    fn save(&self, rom: &str, flags: &[u8; 16]) -> io::Result<()> {
+        let mut saved = self.flags.lock().unwrap_or_else(|e| e.into_inner());
+        saved.insert(rom.to_string(), *flags);
+        Ok(())
+    }
+}
+
+/// Keeps flags in the `localStorage` of the browser, as 32 hex digits under `e-chip-flags-` and
+/// the SHA-1 of the ROM.
+#[cfg(target_arch = "wasm32")]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct LocalStorage;
+
+#[cfg(target_arch = "wasm32")]
+impl LocalStorage {
+    /// Get the local storage of the page.
+    fn storage() -> io::Result<web_sys::Storage> {
+        web_sys::window()
+            .and_then(|window| window.local_storage().ok().flatten())
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::Unsupported,
+                    "the browser has no local storage",
+                )
+            })
+    }
+
+    /// Get the key the flags of a ROM are kept under.
+    fn key(rom: &str) -> String {
+        format!("e-chip-flags-{rom}")
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+impl PersistentStorage for LocalStorage {
+    fn load(&self, rom: &str) -> io::Result<[u8; 16]> {
+        let mut flags = [0; 16];
+        let value = LocalStorage::storage()?
+            .get_item(&LocalStorage::key(rom))
+            .map_err(|_| io::Error::other("could not read local storage"))?;
+        if let Some(value) = value {
+            for (flag, i) in flags.iter_mut().zip((0..value.len()).step_by(2)) {
+                *flag = value
+                    .get(i..i + 2)
+                    .and_then(|digits| u8::from_str_radix(digits, 16).ok())
+                    .unwrap_or(0);
+            }
+        }
+        Ok(flags)
+    }
+
+    fn save(&self, rom: &str, flags: &[u8; 16]) -> io::Result<()> {
+        let value: String = flags.iter().map(|flag| format!("{flag:02X}")).collect();
+        LocalStorage::storage()?
+            .set_item(&LocalStorage::key(rom), &value)
+            .map_err(|_| io::Error::other("could not write local storage"))
+    }
+
+    fn location(&self, rom: &str) -> Option<String> {
+        Some(format!("localStorage[\"{}\"]", LocalStorage::key(rom)))
+    }
+}
+
+/// The persistent storage of an interpreter. Clones of the interpreter share it, and it is not part
+/// of the machine, so it does not make interpreters differ.
+#[derive(Clone)]
+pub(crate) struct SharedStorage(pub(crate) Arc<dyn PersistentStorage>);
+
+impl Default for SharedStorage {
+    fn default() -> Self {
+        #[cfg(target_arch = "wasm32")]
+        return SharedStorage(Arc::new(LocalStorage));
+        #[cfg(not(target_arch = "wasm32"))]
+        SharedStorage(Arc::new(FileStorage::default()))
+    }
+}
+
+impl fmt::Debug for SharedStorage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("PersistentStorage")
+    }
+}
+
+impl PartialEq for SharedStorage {
+    fn eq(&self, _: &Self) -> bool {
+        true
+    }
+}
+
+impl PartialOrd for SharedStorage {
+    fn partial_cmp(&self, _: &Self) -> Option<std::cmp::Ordering> {
+        Some(std::cmp::Ordering::Equal)
+    }
+}
@@ -0,0 +1,111 @@
+use std::collections::BTreeMap;
+
+/// Names of addresses in memory, like the labels of an assembled program, shown by the debugger
+/// views instead of raw addresses.
+#[derive(Debug, Default, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Symbols {
+    names: BTreeMap<u16, String>,
+}
+
+impl Symbols {
+    /// Make symbols from the labels of an assembled program, see
+    /// [`OctoProgram`](crate::OctoProgram). Of the labels at the same address, the first in
+    /// alphabetical order is kept.
+    pub fn from_labels(labels: &BTreeMap<String, u16>) -> Symbols {
+        let mut symbols = Symbols::default();
+        for (name, &address) in labels {
+            symbols.insert(address, name);
+        }
+        symbols
+    }
+
+    /// Read a symbol file. Every line has a name and an address in hex, in either order and
+    /// optionally separated by `=`, `:` or `,`, like `main 0x202`, `0202 main` or `main = $202`.
+    /// Empty lines and lines starting with `#` or `;` are skipped. Returns a description of the
+    /// problem and its line if a line cannot be read.
+    pub fn parse(text: &str) -> Result<Symbols, String> {
+        let mut symbols = Symbols::default();
+        for (line, text) in text.lines().enumerate() {
+            let text = text.trim();
+            if text.is_empty() || text.starts_with(['#', ';']) {
+                continue;
+            }
+            let tokens: Vec<&str> = text
+                .split(|c: char| c.is_whitespace() || matches!(c, '=' | ':' | ','))
+                .filter(|token| !token.is_empty())
+                .collect();
+            let (name, address) = match tokens[..] {
+                [name, address] if parse_address(address).is_some() => (name, address),
+                [address, name] if parse_address(address).is_some() => (name, address),
+                _ => {
+                    return Err(format!(
+                        "Line {}: Expected a name and an address but found `{text}`",
+                        line + 1
+                    ));
+                }
+            };
+            let Some(Ok(address)) = parse_address(address) else {
+                return Err(format!(
+                    "Line {}: The address {address} is too large",
+                    line + 1
+                ));
+            };
+            symbols.insert(address, name);
+        }
+        Ok(symbols)
+    }
+
+    /// Name `address`, unless it already has a name.
+    #[inline]
+    pub fn insert(&mut self, address: u16, name: &str) {
+        self.names
+            .entry(address)
+            .or_insert_with(|| name.to_string());
+    }
+    /// Remove every symbol.
+    #[inline]
+    pub fn clear(&mut self) {
+        self.names.clear();
+    }
+    /// Check if there are no symbols.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+    /// Get the name of `address`.
+    #[inline]
+    pub fn name(&self, address: u16) -> Option<&str> {
+        self.names.get(&address).map(String::as_str)
+    }
+    /// Get the symbols in ascending order of address.
+    #[inline]
+    pub fn iter(&self) -> impl Iterator<Item = (u16, &str)> + '_ {
+        self.names
+            .iter()
+            .map(|(&address, name)| (address, name.as_str()))
+    }
+
+    /// Describe `address` by the closest symbol at or before it: `draw` for the address of `draw`,
+    /// and `draw+6` six bytes after it. `None` if there is no symbol before it.
+    pub fn describe(&self, address: u16) -> Option<String> {
+        let (&start, name) = self.names.range(..=address).next_back()?;
+        Some(match address - start {
+            0 => name.clone(),
+            offset => format!("{name}+{offset}"),
+        })
+    }
+}
+
+/// Read an address in hex, with an optional `0x` or `$` prefix. Without a prefix, it needs a
+/// decimal digit so names like `add` are not read as addresses. `Some(Err)` if it is too large.
+fn parse_address(token: &str) -> Option<Result<u16, ()>> {
+    let digits = match token.strip_prefix("0x").or_else(|| token.strip_prefix('$')) {
+        Some(digits) => digits,
+        None if token.contains(|c: char| c.is_ascii_digit()) => token,
+        None => return None,
+    };
+    if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    Some(u16::from_str_radix(digits, 16).map_err(|_| ()))
+}
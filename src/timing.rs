@@ -0,0 +1,67 @@
+use crate::Chip8;
+
+/// How many machine cycles the COSMAC VIP executes in one 60 Hz frame (1.76 MHz clock, 8 clock
+/// cycles per machine cycle).
+pub const VIP_CYCLES_PER_FRAME: u32 = 3668;
+/// The highest execution speed in instructions per frame.
+pub const MAX_EXECUTION_SPEED: u32 = 10000;
+/// The highest timer frequency in Hz.
+pub const MAX_TIMER_FREQUENCY: u32 = 240;
+
+/// Machine cycles the VIP interpreter spends fetching and decoding every instruction.
+const FETCH_CYCLES: u32 = 40;
+
+impl Chip8 {
+    /// Estimate how many machine cycles the original COSMAC VIP interpreter needs to execute an
+    /// opcode in the current state. Based on Laurence Scotford's analysis of the VIP interpreter.
+    ///
+    /// The estimate does not include the time spent waiting for a vblank interrupt or a key press.
+    pub fn vip_cycle_cost(&self, opcode: u16) -> u32 {
+        let x = ((opcode & 0x0F00) >> 8) as usize;
+        let y = ((opcode & 0x00F0) >> 4) as usize;
+        let byte = (opcode & 0x00FF) as u8;
+        let nibble = (opcode & 0x000F) as u32;
+
+        let execution = match opcode >> 12 {
+            0x0 => match opcode {
+                0x00E0 => 3078,
+                0x00EE => 10,
+                _ => 0,
+            },
+            0x1 => 12,
+            0x2 => 26,
+            // Skips take longer when they skip
+            0x3 => 10 + if self.V[x] == byte { 4 } else { 0 },
+            0x4 => 10 + if self.V[x] != byte { 4 } else { 0 },
+            0x5 => 14 + if self.V[x] == self.V[y] { 4 } else { 0 },
+            0x6 => 6,
+            0x7 => 10,
+            0x8 => 44,
+            0x9 => 14 + if self.V[x] != self.V[y] { 4 } else { 0 },
+            0xA => 12,
+            0xB => 22,
+            0xC => 36,
+            // Sprites that are not aligned to a byte have to be shifted, which is much slower
+            0xD => 26 + nibble * if self.V[x] & 7 == 0 { 34 } else { 68 },
+            0xE => 14,
+            0xF => match byte {
+                0x07 => 10,
+                0x0A => 20,
+                0x15 => 10,
+                0x18 => 10,
+                0x1E => 16,
+                0x29 => 16,
+                // BCD conversion counts down each digit
+                0x33 => {
+                    let value = self.V[x] as u32;
+                    84 + 16 * (value / 100 + value / 10 % 10 + value % 10)
+                }
+                0x55 | 0x65 => 14 + 14 * (x as u32 + 1),
+                _ => 0,
+            },
+            _ => 0,
+        };
+
+        FETCH_CYCLES + execution
+    }
+}
@@ -0,0 +1,100 @@
+use std::collections::VecDeque;
+
+/// How many executed instructions the trace remembers.
+pub const TRACE_LENGTH: usize = 64;
+
+/// How many instructions leading up to a halt are kept with the halt message, including the one
+/// that halted.
+pub const HALT_CONTEXT_LENGTH: usize = 4;
+
+/// An executed instruction and its effect on the registers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[allow(non_snake_case)]
+pub struct TraceEntry {
+    /// The address of the instruction.
+    pub address: u16,
+    /// The executed opcode.
+    pub opcode: u16,
+    /// The V registers before the instruction was executed.
+    pub V_before: [u8; 16],
+    /// The V registers after the instruction was executed.
+    pub V_after: [u8; 16],
+    /// The I register before the instruction was executed.
+    pub I_before: u16,
+    /// The I register after the instruction was executed.
+    pub I_after: u16,
+    /// The estimated cost of the instruction in COSMAC VIP machine cycles.
+    pub vip_cycles: u32,
+}
+
+impl TraceEntry {
+    /// Get the V registers that were changed by the instruction as `(register, before, after)`.
+    pub fn changed_registers(&self) -> impl Iterator<Item = (usize, u8, u8)> + '_ {
+        (0..16)
+            .filter(|&i| self.V_before[i] != self.V_after[i])
+            .map(|i| (i, self.V_before[i], self.V_after[i]))
+    }
+}
+
+/// A ring buffer of the last executed instructions.
+#[derive(Debug, Default, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Trace {
+    entries: VecDeque<TraceEntry>,
+}
+
+impl Trace {
+    /// Record an executed instruction, forgetting the oldest one if the trace is full.
+    #[inline]
+    pub fn push(&mut self, entry: TraceEntry) {
+        if self.entries.len() == TRACE_LENGTH {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(entry);
+    }
+
+    /// Forget all instructions.
+    #[inline]
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    /// Iterate over the recorded instructions, from the oldest to the most recent.
+    #[inline]
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &TraceEntry> {
+        self.entries.iter()
+    }
+
+    /// Get the number of recorded instructions.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Check if no instructions were recorded.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+/// Something an instruction did that is shown on the frame timeline.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum FrameEventKind {
+    /// The display was changed.
+    Draw,
+    /// The keypad was read.
+    KeyRead,
+    /// The sound timer was set.
+    Sound,
+}
+
+/// An event that happened during a frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FrameEvent {
+    /// The cycle of the frame in which the event happened, starting at 1.
+    pub cycle: u32,
+    /// What happened.
+    pub kind: FrameEventKind,
+}
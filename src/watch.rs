@@ -0,0 +1,460 @@
+use std::{iter::Peekable, str::Chars};
+
+use crate::Chip8;
+
+/// A parsed watch expression, like `V[3] + V[4]`, `mem[I + 1]` or `delay > 0`, that can be
+/// evaluated against the interpreter.
+///
+/// Values are integers. Comparisons and logical operators give 1 for true and 0 for false, like C.
+/// The names are case-insensitive:
+///
+/// - `V0` to `VF` or `V[n]`: the V registers
+/// - `I`, `PC`, `SP`, `delay` (or `DT`), `sound` (or `ST`) and `frame` (the frame count)
+/// - `mem[address]`, `key[n]` (1 if the key is down) and `stack[n]`
+///
+/// The operators are, from the lowest precedence to the highest: `||`, `&&`, `==` `!=` `<` `<=`
+/// `>` `>=`, `|`, `^`, `&`, `<<` `>>`, `+` `-`, `*` `/` `%` and the unary `-` `!` `~`.
+/// Numbers are decimal, or hex and binary with a `0x` and `0b` prefix.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Expression {
+    /// The expression as it was entered.
+    source: String,
+    node: Node,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+enum Node {
+    Number(i64),
+    Value(Value),
+    Indexed(Array, Box<Node>),
+    Unary(char, Box<Node>),
+    Binary(&'static str, Box<Node>, Box<Node>),
+}
+
+/// A single value of the machine.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+enum Value {
+    I,
+    ProgramCounter,
+    StackPointer,
+    Delay,
+    Sound,
+    Frame,
+}
+
+/// Something of the machine that is indexed with `[]`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+enum Array {
+    V,
+    Memory,
+    Key,
+    Stack,
+}
+
+/// The binary operators of each precedence level, from the lowest to the highest.
+const BINARY_OPERATORS: [&[&str]; 9] = [
+    &["||"],
+    &["&&"],
+    &["==", "!=", "<=", ">=", "<", ">"],
+    &["|"],
+    &["^"],
+    &["&"],
+    &["<<", ">>"],
+    &["+", "-"],
+    &["*", "/", "%"],
+];
+/// The most tokens an expression can have, which also bounds how deeply it nests, so parsing and
+/// evaluating it can't overflow the stack.
+const MAX_TOKENS: usize = 256;
+/// Every operator, longer ones first so `<=` is not read as `<`.
+const OPERATORS: [&str; 21] = [
+    "||", "&&", "==", "!=", "<=", ">=", "<<", ">>", "<", ">", "|", "^", "&", "+", "-", "*", "/",
+    "%", "!", "~", "=",
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Number(i64),
+    Name(String),
+    Operator(&'static str),
+    Open(char),
+    Close(char),
+}
+
+impl Expression {
+    /// Parse a watch expression, returning a description of the problem if it is not valid.
+    pub fn parse(source: &str) -> Result<Expression, String> {
+        let tokens = tokenize(source)?;
+        if tokens.len() > MAX_TOKENS {
+            return Err(format!("The expression has more than {MAX_TOKENS} tokens"));
+        }
+        let mut parser = Parser {
+            tokens,
+            position: 0,
+        };
+        let node = parser.binary(0)?;
+        match parser.tokens.get(parser.position) {
+            None => Ok(Expression {
+                source: source.trim().to_string(),
+                node,
+            }),
+            Some(token) => Err(format!(
+                "Unexpected {} after the expression",
+                describe(token)
+            )),
+        }
+    }
+
+    /// Evaluate the expression. Returns `None` if it divides by zero, shifts by an invalid amount
+    /// or indexes outside of a register, memory, the keypad or the stack.
+    pub fn evaluate(&self, chip8: &Chip8) -> Option<i64> {
+        evaluate(&self.node, chip8)
+    }
+
+    /// Get the expression as it was entered.
+    #[inline]
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+}
+
+fn evaluate(node: &Node, chip8: &Chip8) -> Option<i64> {
+    Some(match node {
+        Node::Number(n) => *n,
+        Node::Value(value) => match value {
+            Value::I => chip8.I as i64,
+            Value::ProgramCounter => chip8.program_counter as i64,
+            Value::StackPointer => chip8.stack_pointer as i64,
+            Value::Delay => chip8.delay as i64,
+            Value::Sound => chip8.sound as i64,
+            Value::Frame => chip8.frame_count as i64,
+        },
+        Node::Indexed(array, index) => {
+            let index = usize::try_from(evaluate(index, chip8)?).ok()?;
+            match array {
+                Array::V => *chip8.V.get(index)? as i64,
+                Array::Memory => *chip8.memory.ram.get(index)? as i64,
+                Array::Key => *chip8.keypad.get(index)? as i64,
+                Array::Stack => *chip8.stack.get(index)? as i64,
+            }
+        }
+        Node::Unary(operator, operand) => {
+            let operand = evaluate(operand, chip8)?;
+            match operator {
+                '-' => operand.wrapping_neg(),
+                '!' => (operand == 0) as i64,
+                _ => !operand,
+            }
+        }
+        // Short-circuit like C, so `I < 4096 && mem[I] == 0` does not fail
+        Node::Binary("&&", a, b) => (evaluate(a, chip8)? != 0 && evaluate(b, chip8)? != 0) as i64,
+        Node::Binary("||", a, b) => (evaluate(a, chip8)? != 0 || evaluate(b, chip8)? != 0) as i64,
+        Node::Binary(operator, a, b) => {
+            let (a, b) = (evaluate(a, chip8)?, evaluate(b, chip8)?);
+            match *operator {
+                "==" => (a == b) as i64,
+                "!=" => (a != b) as i64,
+                "<" => (a < b) as i64,
+                "<=" => (a <= b) as i64,
+                ">" => (a > b) as i64,
+                ">=" => (a >= b) as i64,
+                "|" => a | b,
+                "^" => a ^ b,
+                "&" => a & b,
+                "<<" => a.checked_shl(u32::try_from(b).ok()?)?,
+                ">>" => a.checked_shr(u32::try_from(b).ok()?)?,
+                "+" => a.wrapping_add(b),
+                "-" => a.wrapping_sub(b),
+                "*" => a.wrapping_mul(b),
+                "/" => a.checked_div(b)?,
+                _ => a.checked_rem(b)?,
+            }
+        }
+    })
+}
+
+/// Split an expression into tokens.
+fn tokenize(source: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = source.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if c.is_ascii_digit() {
+            tokens.push(Token::Number(number(&mut chars)?));
+        } else if c.is_ascii_alphabetic() || c == '_' {
+            let mut name = String::new();
+            while let Some(&c) = chars
+                .peek()
+                .filter(|c| c.is_ascii_alphanumeric() || **c == '_')
+            {
+                name.push(c.to_ascii_lowercase());
+                chars.next();
+            }
+            tokens.push(Token::Name(name));
+        } else if c == '(' || c == '[' {
+            chars.next();
+            tokens.push(Token::Open(c));
+        } else if c == ')' || c == ']' {
+            chars.next();
+            tokens.push(Token::Close(c));
+        } else {
+            let rest: String = chars.clone().take(2).collect();
+            let Some(&operator) = OPERATORS.iter().find(|&&op| rest.starts_with(op)) else {
+                return Err(format!("Unexpected character '{c}'"));
+            };
+            if operator == "=" {
+                return Err("Use == to compare".to_string());
+            }
+            for _ in 0..operator.len() {
+                chars.next();
+            }
+            tokens.push(Token::Operator(operator));
+        }
+    }
+    Ok(tokens)
+}
+
+/// Read a decimal, `0x` hex or `0b` binary number.
+fn number(chars: &mut Peekable<Chars>) -> Result<i64, String> {
+    let mut digits = String::new();
+    while let Some(&c) = chars.peek().filter(|c| c.is_ascii_alphanumeric()) {
+        digits.push(c);
+        chars.next();
+    }
+    let lower = digits.to_ascii_lowercase();
+    let parsed = if let Some(hex) = lower.strip_prefix("0x") {
+        i64::from_str_radix(hex, 16)
+    } else if let Some(binary) = lower.strip_prefix("0b") {
+        i64::from_str_radix(binary, 2)
+    } else {
+        lower.parse()
+    };
+    parsed.map_err(|_| format!("Invalid number '{digits}'"))
+}
+
+/// Describe a token for error messages.
+fn describe(token: &Token) -> String {
+    match token {
+        Token::Number(n) => format!("number {n}"),
+        Token::Name(name) => format!("'{name}'"),
+        Token::Operator(operator) => format!("'{operator}'"),
+        Token::Open(c) | Token::Close(c) => format!("'{c}'"),
+    }
+}
+
+/// A recursive descent parser over the tokens of an expression.
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl Parser {
+    /// Take the next token if it is one of the operators.
+    fn operator(&mut self, operators: &[&'static str]) -> Option<&'static str> {
+        match self.tokens.get(self.position) {
+            Some(Token::Operator(operator)) if operators.contains(operator) => {
+                self.position += 1;
+                Some(operator)
+            }
+            _ => None,
+        }
+    }
+
+    /// Take the next token, which must be `token`.
+    fn expect(&mut self, token: Token) -> Result<(), String> {
+        match self.tokens.get(self.position) {
+            Some(next) if *next == token => {
+                self.position += 1;
+                Ok(())
+            }
+            Some(next) => Err(format!(
+                "Expected {} instead of {}",
+                describe(&token),
+                describe(next)
+            )),
+            None => Err(format!("Expected {} at the end", describe(&token))),
+        }
+    }
+
+    /// Parse the binary operators of a precedence level of [`BINARY_OPERATORS`] and all higher ones.
+    fn binary(&mut self, level: usize) -> Result<Node, String> {
+        let Some(operators) = BINARY_OPERATORS.get(level) else {
+            return self.unary();
+        };
+        let mut node = self.binary(level + 1)?;
+        while let Some(operator) = self.operator(operators) {
+            let right = self.binary(level + 1)?;
+            node = Node::Binary(operator, Box::new(node), Box::new(right));
+        }
+        Ok(node)
+    }
+
+    /// Parse a value with any unary operators in front of it.
+    fn unary(&mut self) -> Result<Node, String> {
+        match self.operator(&["-", "!", "~"]) {
+            Some(operator) => Ok(Node::Unary(
+                operator.chars().next().unwrap_or('-'),
+                Box::new(self.unary()?),
+            )),
+            None => self.value(),
+        }
+    }
+
+    /// Parse a number, a name or an expression in parentheses.
+    fn value(&mut self) -> Result<Node, String> {
+        let Some(token) = self.tokens.get(self.position).cloned() else {
+            return Err("Expected a value at the end".to_string());
+        };
+        self.position += 1;
+        match token {
+            Token::Number(n) => Ok(Node::Number(n)),
+            Token::Open('(') => {
+                let node = self.binary(0)?;
+                self.expect(Token::Close(')'))?;
+                Ok(node)
+            }
+            Token::Name(name) => self.name(&name),
+            token => Err(format!("Expected a value instead of {}", describe(&token))),
+        }
+    }
+
+    /// Parse a name and its index, if it has one.
+    fn name(&mut self, name: &str) -> Result<Node, String> {
+        let value = match name {
+            "i" => Value::I,
+            "pc" => Value::ProgramCounter,
+            "sp" => Value::StackPointer,
+            "delay" | "dt" => Value::Delay,
+            "sound" | "st" => Value::Sound,
+            "frame" => Value::Frame,
+            _ => {
+                let array = match name {
+                    "v" => Array::V,
+                    "mem" => Array::Memory,
+                    "key" => Array::Key,
+                    "stack" => Array::Stack,
+                    // V0 to VF
+                    _ => {
+                        return match name.strip_prefix('v') {
+                            Some(x) if x.len() == 1 => u8::from_str_radix(x, 16)
+                                .map(|x| Node::Indexed(Array::V, Box::new(Node::Number(x as i64))))
+                                .map_err(|_| format!("Unknown name '{name}'")),
+                            _ => Err(format!("Unknown name '{name}'")),
+                        };
+                    }
+                };
+                self.expect(Token::Open('['))?;
+                let index = self.binary(0)?;
+                self.expect(Token::Close(']'))?;
+                return Ok(Node::Indexed(array, Box::new(index)));
+            }
+        };
+        Ok(Node::Value(value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chip8() -> Chip8 {
+        let mut chip8 = Chip8::chip8();
+        chip8.V[3] = 7;
+        chip8.V[0xF] = 1;
+        chip8.I = 0x300;
+        chip8.memory.ram[0x300] = 0xAB;
+        chip8.memory.ram[0x301] = 0xCD;
+        chip8.delay = 5;
+        chip8
+    }
+
+    fn evaluate(source: &str) -> Option<i64> {
+        Expression::parse(source).unwrap().evaluate(&chip8())
+    }
+
+    #[test]
+    fn precedence() {
+        assert_eq!(evaluate("1 + 2 * 3"), Some(7));
+        assert_eq!(evaluate("(1 + 2) * 3"), Some(9));
+        assert_eq!(evaluate("10 - 4 - 3"), Some(3));
+        assert_eq!(evaluate("1 << 2 + 1"), Some(8));
+        assert_eq!(evaluate("6 & 3 | 8 ^ 1"), Some(11));
+        assert_eq!(evaluate("1 + 1 == 2 && 3 > 2 || 0"), Some(1));
+        assert_eq!(evaluate("-2 * -3"), Some(6));
+        assert_eq!(evaluate("!0 + ~0"), Some(0));
+        assert_eq!(evaluate("17 % 5 / 2"), Some(1));
+    }
+
+    #[test]
+    fn operands() {
+        assert_eq!(evaluate("V3 + v[3] + vf"), Some(15));
+        assert_eq!(evaluate("v[1 + 2]"), Some(7));
+        assert_eq!(evaluate("I"), Some(0x300));
+        assert_eq!(evaluate("mem[I] << 8 | MEM[i + 1]"), Some(0xABCD));
+        assert_eq!(evaluate("delay == DT && sound == 0"), Some(1));
+        assert_eq!(evaluate("pc"), Some(0x200));
+        assert_eq!(evaluate("key[4] + stack[0] + sp + frame"), Some(0));
+        assert_eq!(evaluate("0x10 + 0b11"), Some(19));
+        assert_eq!(Expression::parse("  v0 ").unwrap().source(), "v0");
+    }
+
+    #[test]
+    fn failed_evaluation() {
+        assert_eq!(evaluate("v[16]"), None);
+        assert_eq!(evaluate("mem[-1]"), None);
+        assert_eq!(evaluate("mem[4096]"), None);
+        assert_eq!(evaluate("key[16]"), None);
+        assert_eq!(evaluate("1 / 0"), None);
+        assert_eq!(evaluate("1 % 0"), None);
+        assert_eq!(evaluate("1 << 64"), None);
+        assert_eq!(evaluate("1 >> -1"), None);
+        // The right side is not evaluated when the left side decides
+        assert_eq!(evaluate("0 && 1 / 0"), Some(0));
+        assert_eq!(evaluate("1 || mem[9999]"), Some(1));
+    }
+
+    #[test]
+    fn bad_input() {
+        let error = |source: &str| Expression::parse(source).unwrap_err();
+        assert_eq!(error(""), "Expected a value at the end");
+        assert_eq!(error("1 +"), "Expected a value at the end");
+        assert_eq!(error("v0 = 1"), "Use == to compare");
+        assert_eq!(error("v0 $ 1"), "Unexpected character '$'");
+        assert_eq!(error("(1 + 2"), "Expected ')' at the end");
+        assert_eq!(error("mem[1)"), "Expected ']' instead of ')'");
+        assert_eq!(error("mem 1"), "Expected '[' instead of number 1");
+        assert_eq!(error("vg"), "Unknown name 'vg'");
+        assert_eq!(error("foo"), "Unknown name 'foo'");
+        assert_eq!(error("1 2"), "Unexpected number 2 after the expression");
+        assert_eq!(error(")"), "Expected a value instead of ')'");
+        assert_eq!(error("0xZZ"), "Invalid number '0xZZ'");
+        // Deep nesting is an error instead of a stack overflow
+        assert!(error(&"(".repeat(10000)).contains("tokens"));
+        assert!(error(&"!".repeat(10000)).contains("tokens"));
+        assert!(error(&"1+".repeat(10000)).contains("tokens"));
+        assert_eq!(
+            Expression::parse(&format!("{}1{}", "(".repeat(100), ")".repeat(100)))
+                .unwrap()
+                .evaluate(&chip8()),
+            Some(1)
+        );
+    }
+
+    #[test]
+    fn number_overflow() {
+        assert_eq!(evaluate("9223372036854775807"), Some(i64::MAX));
+        assert_eq!(
+            Expression::parse("9223372036854775808").unwrap_err(),
+            "Invalid number '9223372036854775808'"
+        );
+        assert_eq!(
+            Expression::parse("0x10000000000000000").unwrap_err(),
+            "Invalid number '0x10000000000000000'"
+        );
+        // Arithmetic wraps around instead of panicking
+        assert_eq!(evaluate("9223372036854775807 + 1"), Some(i64::MIN));
+        assert_eq!(evaluate("-(-9223372036854775807 - 1)"), Some(i64::MIN));
+        assert_eq!(evaluate("(-9223372036854775807 - 1) / -1"), None);
+    }
+}